@@ -0,0 +1,119 @@
+//! Conventions for measuring azimuths.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use uom::si::{angle::radian, f64::Angle};
+
+/// Describes the reference direction and handedness of an azimuth.
+///
+/// Azimuths are measured in a horizontal plane spanned by an `x` axis and a `y` axis that is a
+/// quarter turn counterclockwise from it when looking down, e.g., east and north or the right and
+/// top of an image.
+/// Compass bearings such as [`SolarPosition::azimuth`] are measured clockwise from `y`, i.e.,
+/// `atan2(x, y)`, while directions in the camera frame such as [`RayDirection::azimuth`] are
+/// measured counterclockwise from `x`, i.e., `atan2(y, x)`.
+/// Each type that accepts or returns an azimuth names its convention in an `AZIMUTH` constant, so
+/// convert between them with [`AzimuthConvention::convert`] rather than by hand.
+///
+/// [`SolarPosition::azimuth`]: crate::model::ephemeris::SolarPosition::azimuth
+/// [`RayDirection::azimuth`]: crate::optic::RayDirection::azimuth
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AzimuthConvention {
+    /// Clockwise from north, or `y`, as on a compass.
+    #[default]
+    NorthClockwise,
+    /// Counterclockwise from east, or `x`, as in mathematics.
+    EastCounterclockwise,
+}
+
+impl AzimuthConvention {
+    /// Returns the azimuth in this convention of the horizontal direction with components `x`
+    /// and `y`.
+    ///
+    /// The azimuth is on (-180, 180] degrees.
+    #[must_use]
+    pub fn from_components(self, x: f64, y: f64) -> Angle {
+        Angle::new::<radian>(match self {
+            Self::NorthClockwise => x.atan2(y),
+            Self::EastCounterclockwise => y.atan2(x),
+        })
+    }
+
+    /// Returns the `x` and `y` components of the unit horizontal direction at `azimuth` in this
+    /// convention.
+    #[must_use]
+    pub fn components(self, azimuth: Angle) -> (f64, f64) {
+        let (sin, cos) = azimuth.get::<radian>().sin_cos();
+        match self {
+            Self::NorthClockwise => (sin, cos),
+            Self::EastCounterclockwise => (cos, sin),
+        }
+    }
+
+    /// Converts `azimuth` from this convention into `to`.
+    ///
+    /// The result is on (-180, 180] degrees.
+    #[must_use]
+    pub fn convert(self, azimuth: Angle, to: Self) -> Angle {
+        let (x, y) = self.components(azimuth);
+        to.from_components(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    #[rstest]
+    #[case(0.0, 90.0)]
+    #[case(90.0, 0.0)]
+    #[case(180.0, -90.0)]
+    #[case(270.0, 180.0)]
+    #[case(-45.0, 135.0)]
+    fn converts_between_conventions(#[case] compass: f64, #[case] math: f64) {
+        let convert = |azimuth: f64, from: AzimuthConvention, to| {
+            from.convert(Angle::new::<degree>(azimuth), to)
+                .get::<degree>()
+        };
+
+        assert_relative_eq!(
+            convert(
+                compass,
+                AzimuthConvention::NorthClockwise,
+                AzimuthConvention::EastCounterclockwise
+            )
+            .rem_euclid(360.0),
+            math.rem_euclid(360.0),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            convert(
+                math,
+                AzimuthConvention::EastCounterclockwise,
+                AzimuthConvention::NorthClockwise
+            )
+            .rem_euclid(360.0),
+            compass.rem_euclid(360.0),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn components_roundtrip() {
+        // North east is up and to the right in both conventions.
+        let (x, y) = AzimuthConvention::NorthClockwise.components(Angle::new::<degree>(45.0));
+        assert_relative_eq!(x, y);
+        assert!(x > 0.0);
+        assert_relative_eq!(
+            AzimuthConvention::EastCounterclockwise
+                .from_components(x, y)
+                .get::<degree>(),
+            45.0,
+            epsilon = 1e-9
+        );
+    }
+}
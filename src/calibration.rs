@@ -0,0 +1,718 @@
+use crate::{
+    image::{ImageError, StokesImage},
+    light::stokes::StokesVec,
+    optic::{Camera, Optic, PixelCoordinate},
+    ray::SensorFrame,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uom::si::{
+    angle::{degree, radian},
+    f64::Angle,
+};
+
+#[derive(Debug, Error)]
+pub enum CalibrationError {
+    #[error("polarizer channels do not constrain the linear Stokes parameters")]
+    Singular,
+}
+
+/// Describes the response of a single linear polarizer in a micro-polarizer array.
+///
+/// A measured intensity is modeled as:
+/// ```text
+/// I = gain / 2 * (S_0 + efficiency * (S_1 * cos(2 * orientation) + S_2 * sin(2 * orientation)))
+/// ```
+/// An ideal polarizer has unit gain and unit efficiency.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PolarizerChannel {
+    gain: f64,
+    efficiency: f64,
+    orientation: Angle,
+}
+
+impl PolarizerChannel {
+    /// Creates a new `PolarizerChannel` from a transmission `gain`, a polarization `efficiency`,
+    /// and the `orientation` of its transmission axis.
+    #[must_use]
+    pub fn new(gain: f64, efficiency: f64, orientation: Angle) -> Self {
+        Self {
+            gain,
+            efficiency,
+            orientation,
+        }
+    }
+
+    /// Creates an ideal `PolarizerChannel` with its transmission axis at `orientation`.
+    #[must_use]
+    pub fn ideal(orientation: Angle) -> Self {
+        Self::new(1.0, 1.0, orientation)
+    }
+
+    /// Creates a new `PolarizerChannel` from a measured `extinction_ratio`.
+    ///
+    /// The extinction ratio is the ratio of the maximum to the minimum transmitted intensity.
+    #[must_use]
+    pub fn from_extinction_ratio(gain: f64, extinction_ratio: f64, orientation: Angle) -> Self {
+        Self::new(
+            gain,
+            (extinction_ratio - 1.0) / (extinction_ratio + 1.0),
+            orientation,
+        )
+    }
+
+    #[must_use]
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+
+    #[must_use]
+    pub fn efficiency(&self) -> f64 {
+        self.efficiency
+    }
+
+    #[must_use]
+    pub fn orientation(&self) -> Angle {
+        self.orientation
+    }
+
+    /// Returns the row of the measurement matrix for this channel.
+    fn analyzer(&self) -> [f64; 3] {
+        let two_theta = 2.0 * self.orientation;
+        [
+            self.gain / 2.0,
+            self.gain / 2.0 * self.efficiency * two_theta.cos().value,
+            self.gain / 2.0 * self.efficiency * two_theta.sin().value,
+        ]
+    }
+}
+
+/// Describes the four channels of a division of focal plane polarimeter.
+///
+/// Channels are stored in 0, 45, 90, 135 order.
+/// Stokes vectors are recovered from measured intensities with the least squares inverse of the
+/// measurement matrix defined by the channels.
+/// Only the channels are serialized and the inverse is recomputed when they are deserialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "PolarizerCalibrationFields")
+)]
+pub struct PolarizerCalibration {
+    channels: [PolarizerChannel; 4],
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    inverse: [[f64; 4]; 3],
+}
+
+/// Holds the channels of a [`PolarizerCalibration`] before its inverse is computed.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct PolarizerCalibrationFields {
+    channels: [PolarizerChannel; 4],
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PolarizerCalibrationFields> for PolarizerCalibration {
+    type Error = CalibrationError;
+
+    fn try_from(fields: PolarizerCalibrationFields) -> Result<Self, Self::Error> {
+        // Keep the exact inverse of the ideal array.
+        let ideal = Self::ideal();
+        if fields.channels == ideal.channels {
+            return Ok(ideal);
+        }
+
+        Self::try_new(fields.channels)
+    }
+}
+
+impl PolarizerCalibration {
+    /// Creates a new `PolarizerCalibration` from four measured `channels`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the `channels` cannot recover the linear Stokes parameters.
+    pub fn try_new(channels: [PolarizerChannel; 4]) -> Result<Self, CalibrationError> {
+        let rows = channels.map(|channel| channel.analyzer());
+
+        // Normal matrix of the least squares problem.
+        let mut normal = [[0.0; 3]; 3];
+        for row in &rows {
+            for i in 0..3 {
+                for j in 0..3 {
+                    normal[i][j] += row[i] * row[j];
+                }
+            }
+        }
+
+        let normal_inv = invert(&normal).ok_or(CalibrationError::Singular)?;
+        let mut inverse = [[0.0; 4]; 3];
+        for (i, inverse_row) in inverse.iter_mut().enumerate() {
+            for (k, row) in rows.iter().enumerate() {
+                inverse_row[k] = (0..3).map(|j| normal_inv[i][j] * row[j]).sum();
+            }
+        }
+
+        Ok(Self { channels, inverse })
+    }
+
+    /// Creates a `PolarizerCalibration` for an ideal micro-polarizer array.
+    #[must_use]
+    pub fn ideal() -> Self {
+        // The inverse is written out to avoid rounding errors from trigonometry at exact angles.
+        Self {
+            channels: [0.0, 45.0, 90.0, 135.0]
+                .map(|orientation| PolarizerChannel::ideal(Angle::new::<degree>(orientation))),
+            inverse: [
+                [0.5, 0.5, 0.5, 0.5],
+                [1.0, 0.0, -1.0, 0.0],
+                [0.0, 1.0, 0.0, -1.0],
+            ],
+        }
+    }
+
+    #[must_use]
+    pub fn channels(&self) -> &[PolarizerChannel; 4] {
+        &self.channels
+    }
+
+    /// Computes the [`StokesVec`] for `intensities` measured in 0, 45, 90, 135 order.
+    #[must_use]
+    pub fn stokes(&self, intensities: [f64; 4]) -> StokesVec<SensorFrame> {
+        let [s0, s1, s2] = self
+            .inverse
+            .map(|row| row.iter().zip(intensities).map(|(a, i)| a * i).sum());
+        StokesVec::new(s0, s1, s2)
+    }
+
+    /// Computes the intensities measured by each channel for an incident `stokes` vector.
+    ///
+    /// This is the forward model used when rendering simulated intensity images.
+    #[must_use]
+    pub fn intensities<Frame>(&self, stokes: &StokesVec<Frame>) -> [f64; 4] {
+        let s = [stokes.s0(), stokes.s1(), stokes.s2()];
+        self.channels
+            .map(|channel| channel.analyzer().iter().zip(s).map(|(a, s)| a * s).sum())
+    }
+}
+
+impl Default for PolarizerCalibration {
+    fn default() -> Self {
+        Self::ideal()
+    }
+}
+
+/// Selects how the total intensity `S_0` of a metapixel is formed.
+///
+/// `S_0` normalizes the [`Dop`](crate::light::dop::Dop), so the choice trades noise against
+/// robustness to a single bad channel.
+/// Pair estimates use raw intensities and assume the paired channels have equal gain.
+/// Noisy pairs can produce a degree of polarization above one, which invalidates the metapixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum S0Normalization {
+    /// Uses the least squares estimate of the [`PolarizerCalibration`], which averages both
+    /// orthogonal pairs `(I_0 + I_45 + I_90 + I_135) / 2` for an ideal array.
+    #[default]
+    Calibrated,
+
+    /// Uses the orthogonal pair `I_0 + I_90`.
+    Pair0And90,
+
+    /// Uses the orthogonal pair `I_45 + I_135`.
+    Pair45And135,
+}
+
+impl S0Normalization {
+    /// Computes the [`StokesVec`] for `intensities` measured in 0, 45, 90, 135 order.
+    #[must_use]
+    pub fn stokes(
+        self,
+        calibration: &PolarizerCalibration,
+        intensities: [f64; 4],
+    ) -> StokesVec<SensorFrame> {
+        let stokes = calibration.stokes(intensities);
+        let [i000, i045, i090, i135] = intensities;
+        let s0 = match self {
+            S0Normalization::Calibrated => return stokes,
+            S0Normalization::Pair0And90 => i000 + i090,
+            S0Normalization::Pair45And135 => i045 + i135,
+        };
+
+        StokesVec::new(s0, stokes.s1(), stokes.s2())
+    }
+}
+
+/// Describes the polarization a lens adds to light away from its optical axis.
+///
+/// Oblique rays meet the surfaces of a lens at angles where reflection and transmission favor one
+/// polarization, so even unpolarized light leaves the lens partially polarized.
+/// For a rotationally symmetric lens, the added polarization at a field angle `θ` and azimuth
+/// `φ` about the optical axis is radial or tangential:
+/// ```text
+/// S_1 += p(θ) * S_0 * cos(2 * φ)
+/// S_2 += p(θ) * S_0 * sin(2 * φ)
+/// ```
+/// where `p` is positive for radial and negative for tangential polarization.
+/// It is stored at the centers of bins of field angle and interpolated linearly between them.
+///
+/// The model is estimated from an image of an unpolarized, uniform source, e.g., an integrating
+/// sphere, with [`InstrumentalPolarization::estimate`] and removed from measured Stokes vectors
+/// with [`InstrumentalPolarization::correct`] before they are converted to rays.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "InstrumentalPolarizationFields")
+)]
+pub struct InstrumentalPolarization {
+    bin_width: Angle,
+    degrees: Vec<f64>,
+}
+
+/// Holds the fields of an [`InstrumentalPolarization`] before they are validated.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct InstrumentalPolarizationFields {
+    bin_width: Angle,
+    degrees: Vec<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<InstrumentalPolarizationFields> for InstrumentalPolarization {
+    type Error = &'static str;
+
+    fn try_from(fields: InstrumentalPolarizationFields) -> Result<Self, Self::Error> {
+        let bin_width = fields.bin_width.get::<radian>();
+        if !(bin_width > 0.0 && bin_width.is_finite()) {
+            return Err("bin width must be finite and greater than zero");
+        }
+        if fields.degrees.is_empty() {
+            return Err("degrees must not be empty");
+        }
+        if !fields.degrees.iter().all(|p| p.is_finite()) {
+            return Err("degrees must be finite");
+        }
+
+        Ok(Self::new(fields.bin_width, fields.degrees))
+    }
+}
+
+impl InstrumentalPolarization {
+    /// Creates a new `InstrumentalPolarization` from the signed degree of polarization `p` at the
+    /// center of each bin of field angle of `bin_width`.
+    ///
+    /// # Panics
+    /// Will panic if `bin_width` is not greater than zero or `degrees` is empty.
+    #[must_use]
+    pub fn new(bin_width: Angle, degrees: Vec<f64>) -> Self {
+        assert!(
+            bin_width.get::<radian>() > 0.0,
+            "bin width must be greater than zero: {bin_width:#?}"
+        );
+        assert!(!degrees.is_empty(), "degrees must not be empty");
+
+        Self { bin_width, degrees }
+    }
+
+    /// Estimates the model from `flat`, an image of an unpolarized source captured by `camera`.
+    ///
+    /// Each metapixel contributes the component of its normalized Stokes vector along the
+    /// radial direction to the bin of its field angle.
+    /// Bins without metapixels take the value of the closest bin with metapixels towards the
+    /// optical axis, or of the first bin with metapixels if there is none.
+    /// Returns `None` if the extents of `flat` do not match the sensor or no metapixel has a
+    /// positive total intensity.
+    ///
+    /// # Panics
+    /// Will panic if `bin_width` is not greater than zero.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn estimate<O: Optic>(
+        flat: &StokesImage<SensorFrame>,
+        camera: &Camera<O>,
+        bin_width: Angle,
+    ) -> Option<Self> {
+        assert!(
+            bin_width.get::<radian>() > 0.0,
+            "bin width must be greater than zero: {bin_width:#?}"
+        );
+        if (flat.rows(), flat.cols()) != (camera.rows(), camera.cols()) {
+            return None;
+        }
+
+        let mut sums: Vec<(f64, usize)> = Vec::new();
+        for pixel in camera.pixels() {
+            let Some((field_angle, azimuth)) = field(camera, pixel) else {
+                continue;
+            };
+            let stokes = flat.stokes(pixel.row(), pixel.col());
+            if stokes.s0() <= 0.0 {
+                continue;
+            }
+
+            let (sin, cos) = (2.0 * azimuth.get::<radian>()).sin_cos();
+            let radial = (stokes.s1() * cos + stokes.s2() * sin) / stokes.s0();
+            if !radial.is_finite() {
+                continue;
+            }
+
+            let bin = (field_angle / bin_width).value.floor().max(0.0) as usize;
+            if sums.len() <= bin {
+                sums.resize(bin + 1, (0.0, 0));
+            }
+            sums[bin].0 += radial;
+            sums[bin].1 += 1;
+        }
+
+        let mut degrees: Vec<Option<f64>> = sums
+            .iter()
+            .map(|(sum, count)| (*count > 0).then(|| sum / *count as f64))
+            .collect();
+        let first = degrees.iter().flatten().copied().next()?;
+
+        // Fill empty bins forward from the last bin with samples, or from the first one.
+        let mut last = first;
+        for value in &mut degrees {
+            last = *value.get_or_insert(last);
+        }
+
+        Some(Self::new(
+            bin_width,
+            degrees.into_iter().flatten().collect(),
+        ))
+    }
+
+    #[must_use]
+    pub fn bin_width(&self) -> Angle {
+        self.bin_width
+    }
+
+    /// Returns the signed degree of polarization at the center of each bin of field angle.
+    #[must_use]
+    pub fn degrees(&self) -> &[f64] {
+        &self.degrees
+    }
+
+    /// Returns the signed degree of polarization `p` at `field_angle`.
+    ///
+    /// Field angles beyond the outermost bin centers take the value of that bin.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn degree(&self, field_angle: Angle) -> f64 {
+        let position = ((field_angle / self.bin_width).value - 0.5)
+            .clamp(0.0, (self.degrees.len() - 1) as f64);
+        let lower = position.floor() as usize;
+        let upper = (lower + 1).min(self.degrees.len() - 1);
+        let t = position - lower as f64;
+        self.degrees[lower] * (1.0 - t) + self.degrees[upper] * t
+    }
+
+    /// Removes the instrumental polarization from `image` captured by `camera`.
+    ///
+    /// The [`Vignetting`](crate::optic::Vignetting) of `camera` is divided out as well, so the
+    /// total intensity is on the same scale across the sensor.
+    /// Metapixels that receive no light have non-finite Stokes parameters.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of `image` do not match the sensor of `camera`.
+    pub fn correct<O: Optic>(
+        &self,
+        image: &StokesImage<SensorFrame>,
+        camera: &Camera<O>,
+    ) -> Result<StokesImage<SensorFrame>, ImageError> {
+        if (image.rows(), image.cols()) != (camera.rows(), camera.cols()) {
+            return Err(ImageError::ExtentsMismatch {
+                expected_rows: camera.rows(),
+                expected_cols: camera.cols(),
+                rows: image.rows(),
+                cols: image.cols(),
+            });
+        }
+
+        let mut s0 = image.s0().to_vec();
+        let mut s1 = image.s1().to_vec();
+        let mut s2 = image.s2().to_vec();
+        for (index, pixel) in camera.pixels().enumerate() {
+            let Some((field_angle, azimuth)) = field(camera, pixel) else {
+                continue;
+            };
+            let (sin, cos) = (2.0 * azimuth.get::<radian>()).sin_cos();
+            let added = self.degree(field_angle) * image.s0()[index];
+            s1[index] -= added * cos;
+            s2[index] -= added * sin;
+
+            let illumination = camera.vignetting().relative_illumination(field_angle);
+            let scale = if illumination > 0.0 {
+                illumination.recip()
+            } else {
+                f64::NAN
+            };
+            for plane in [&mut s0, &mut s1, &mut s2] {
+                plane[index] *= scale;
+            }
+        }
+
+        Ok(
+            StokesImage::from_planes(s0, s1, s2, image.rows(), image.cols())?
+                .with_meta(*image.meta()),
+        )
+    }
+}
+
+/// Returns the field angle and azimuth of the direction `pixel` is traced to.
+fn field<O: Optic>(camera: &Camera<O>, pixel: PixelCoordinate) -> Option<(Angle, Angle)> {
+    let azimuth = camera.trace_from_pixel(pixel)?.azimuth();
+    Some((camera.field_angle(pixel)?, azimuth))
+}
+
+/// Inverts a 3x3 matrix using its adjugate.
+fn invert(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let cofactor = |r: usize, c: usize| {
+        let (r0, r1) = ((r + 1) % 3, (r + 2) % 3);
+        let (c0, c1) = ((c + 1) % 3, (c + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+
+    let det: f64 = (0..3).map(|c| m[0][c] * cofactor(0, c)).sum();
+    if det.abs() <= f64::EPSILON {
+        return None;
+    }
+
+    let mut inverse = [[0.0; 3]; 3];
+    for (r, row) in inverse.iter_mut().enumerate() {
+        for (c, element) in row.iter_mut().enumerate() {
+            *element = cofactor(c, r) / det;
+        }
+    }
+
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+
+    #[test]
+    fn ideal_matches_closed_form() {
+        let [i000, i045, i090, i135] = [200.0, 120.0, 40.0, 120.0];
+        let stokes = PolarizerCalibration::ideal().stokes([i000, i045, i090, i135]);
+
+        assert_eq!(stokes.s0(), (i000 + i045 + i090 + i135) / 2.);
+        assert_eq!(stokes.s1(), i000 - i090);
+        assert_eq!(stokes.s2(), i045 - i135);
+    }
+
+    #[test]
+    fn ideal_matches_measurement_matrix() {
+        let calibration =
+            PolarizerCalibration::try_new(*PolarizerCalibration::ideal().channels()).unwrap();
+        let stokes = calibration.stokes([200.0, 120.0, 40.0, 120.0]);
+
+        assert_relative_eq!(stokes.s0(), 240.0, epsilon = 1e-9);
+        assert_relative_eq!(stokes.s1(), 160.0, epsilon = 1e-9);
+        assert_relative_eq!(stokes.s2(), 0.0, epsilon = 1e-9);
+    }
+
+    #[rstest]
+    #[case(1.0, 0.5, 0.1)]
+    #[case(1.0, -0.2, 0.7)]
+    fn calibrated_roundtrip(#[case] s0: f64, #[case] s1: f64, #[case] s2: f64) {
+        let calibration = PolarizerCalibration::try_new([
+            PolarizerChannel::from_extinction_ratio(0.95, 100.0, Angle::new::<degree>(0.5)),
+            PolarizerChannel::from_extinction_ratio(1.02, 80.0, Angle::new::<degree>(44.0)),
+            PolarizerChannel::from_extinction_ratio(0.98, 120.0, Angle::new::<degree>(90.3)),
+            PolarizerChannel::from_extinction_ratio(1.01, 90.0, Angle::new::<degree>(135.8)),
+        ])
+        .unwrap();
+
+        let stokes = StokesVec::<SensorFrame>::new(s0, s1, s2);
+        let result = calibration.stokes(calibration.intensities(&stokes));
+
+        assert_relative_eq!(result.s0(), s0, epsilon = 1e-9);
+        assert_relative_eq!(result.s1(), s1, epsilon = 1e-9);
+        assert_relative_eq!(result.s2(), s2, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn zero_efficiency_is_singular() {
+        let channels = [0.0, 45.0, 90.0, 135.0]
+            .map(|orientation| PolarizerChannel::new(1.0, 0.0, Angle::new::<degree>(orientation)));
+        assert!(PolarizerCalibration::try_new(channels).is_err());
+    }
+
+    #[rstest]
+    #[case(S0Normalization::Calibrated, 240.0)]
+    #[case(S0Normalization::Pair0And90, 250.0)]
+    #[case(S0Normalization::Pair45And135, 230.0)]
+    fn s0_normalization(#[case] normalization: S0Normalization, #[case] s0: f64) {
+        let intensities = [200.0, 120.0, 50.0, 110.0];
+        let stokes = normalization.stokes(&PolarizerCalibration::ideal(), intensities);
+
+        assert_eq!(stokes.s0(), s0);
+        assert_eq!(stokes.s1(), 150.0);
+        assert_eq!(stokes.s2(), 10.0);
+    }
+
+    /// A pinhole camera with 200 micron pixels 1 mm behind its pinhole, so pixels beside the
+    /// center are at a field angle of `atan(0.2) = 11.3099` degrees and diagonal ones at
+    /// `atan(0.2 * sqrt(2)) = 15.7932` degrees.
+    fn pinhole(rows: usize, cols: usize) -> Camera<crate::optic::PinholeOptic> {
+        use uom::si::{
+            f64::Length,
+            length::{micron, millimeter},
+        };
+
+        Camera::new(
+            crate::optic::PinholeOptic::from_focal_length(Length::new::<millimeter>(1.0)),
+            Length::new::<micron>(200.0),
+            rows,
+            cols,
+        )
+    }
+
+    #[test]
+    fn estimates_instrumental_polarization() {
+        // Left, center, and right pixels at field angles of 11.31, 0, and 11.31 degrees.
+        let camera = pinhole(1, 3);
+        let flat =
+            StokesImage::from_planes(vec![100.0; 3], vec![-10.0, 0.0, -10.0], vec![0.0; 3], 1, 3)
+                .unwrap();
+
+        // The center falls in the first bin and the sides in the third, so the empty second bin
+        // takes the value of the first.
+        let model =
+            InstrumentalPolarization::estimate(&flat, &camera, Angle::new::<degree>(5.0)).unwrap();
+        assert_eq!(model.degrees(), [0.0, 0.0, -0.1]);
+
+        let small = StokesImage::from_planes(vec![1.0], vec![0.0], vec![0.0], 1, 1).unwrap();
+        assert_eq!(
+            InstrumentalPolarization::estimate(&small, &camera, Angle::new::<degree>(5.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn interpolates_between_bin_centers() {
+        let model = InstrumentalPolarization::new(Angle::new::<degree>(10.0), vec![-0.1, -0.3]);
+        let p = |field_angle: f64| model.degree(Angle::new::<degree>(field_angle));
+
+        assert_relative_eq!(p(0.0), -0.1);
+        assert_relative_eq!(p(5.0), -0.1);
+        assert_relative_eq!(p(10.0), -0.2);
+        assert_relative_eq!(p(12.5), -0.25);
+        assert_relative_eq!(p(40.0), -0.3);
+    }
+
+    #[test]
+    fn removes_instrumental_polarization() {
+        let camera = pinhole(3, 3);
+        let model = InstrumentalPolarization::new(Angle::new::<degree>(10.0), vec![0.0, -0.1]);
+        let flat =
+            StokesImage::from_planes(vec![100.0; 9], vec![0.0; 9], vec![0.0; 9], 3, 3).unwrap();
+
+        let corrected = model.correct(&flat, &camera).unwrap();
+        // Beside the center, p = -0.1 * (1.13099 - 0.5) = -0.063099.
+        // The right pixel is at an azimuth of 0 degrees and the top one at 90 degrees.
+        assert_relative_eq!(corrected.s1()[5], 6.3099, epsilon = 1e-4);
+        assert_relative_eq!(corrected.s1()[1], -6.3099, epsilon = 1e-4);
+        assert_relative_eq!(corrected.s2()[5], 0.0, epsilon = 1e-9);
+        // The top left pixel is past the last bin center, so p = -0.1 at an azimuth of 135
+        // degrees.
+        assert_relative_eq!(corrected.s1()[0], 0.0, epsilon = 1e-9);
+        assert_relative_eq!(corrected.s2()[0], -10.0, epsilon = 1e-9);
+        assert_eq!(corrected.s1()[4], 0.0);
+        assert_eq!(corrected.s0(), flat.s0());
+
+        let small = StokesImage::from_planes(vec![1.0], vec![0.0], vec![0.0], 1, 1).unwrap();
+        assert!(matches!(
+            model.correct(&small, &camera),
+            Err(ImageError::ExtentsMismatch {
+                expected_rows: 3,
+                expected_cols: 3,
+                rows: 1,
+                cols: 1,
+            })
+        ));
+    }
+
+    #[test]
+    fn removes_vignetting() {
+        let camera = pinhole(3, 3).with_vignetting(crate::optic::Vignetting::new([-0.8, 0.0, 0.0]));
+        let s0: Vec<_> = camera
+            .pixels()
+            .map(|pixel| 100.0 * camera.relative_illumination(pixel).unwrap())
+            .collect();
+        let s1: Vec<_> = s0.iter().map(|s0| 0.5 * s0).collect();
+        let flat = StokesImage::from_planes(s0, s1, vec![0.0; 9], 3, 3).unwrap();
+        assert!(flat.s0()[0] < flat.s0()[4]);
+
+        let model = InstrumentalPolarization::new(Angle::new::<degree>(10.0), vec![0.0]);
+        let corrected = model.correct(&flat, &camera).unwrap();
+        for index in 0..9 {
+            assert_relative_eq!(corrected.s0()[index], 100.0, epsilon = 1e-9);
+            assert_relative_eq!(corrected.s1()[index], 50.0, epsilon = 1e-9);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    #[case(f64::NAN, vec![0.0])]
+    #[case(f64::INFINITY, vec![0.0])]
+    #[case(0.0, vec![0.0])]
+    #[case(10.0, vec![])]
+    #[case(10.0, vec![0.0, f64::NAN])]
+    fn rejects_invalid_fields(#[case] bin_width: f64, #[case] degrees: Vec<f64>) {
+        let fields = InstrumentalPolarizationFields {
+            bin_width: Angle::new::<degree>(bin_width),
+            degrees,
+        };
+        assert!(InstrumentalPolarization::try_from(fields).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_channels_only() {
+        let channels = [0.0, 45.0, 90.0, 135.0].map(|orientation| {
+            PolarizerChannel::new(1.1, 0.9, Angle::new::<degree>(orientation + 2.0))
+        });
+        for calibration in [
+            PolarizerCalibration::ideal(),
+            PolarizerCalibration::try_new(channels).unwrap(),
+        ] {
+            let json = serde_json::to_string(&calibration).unwrap();
+            assert!(!json.contains("inverse"));
+            assert_eq!(
+                serde_json::from_str::<PolarizerCalibration>(&json).unwrap(),
+                calibration
+            );
+        }
+
+        // A stale inverse is ignored in favor of the channels.
+        let mut value = serde_json::to_value(PolarizerCalibration::ideal()).unwrap();
+        value["inverse"] = serde_json::to_value([[0.0; 4]; 3]).unwrap();
+        assert_eq!(
+            serde_json::from_value::<PolarizerCalibration>(value).unwrap(),
+            PolarizerCalibration::ideal()
+        );
+
+        let singular = [PolarizerChannel::ideal(Angle::new::<degree>(0.0)); 4];
+        let json = format!(
+            r#"{{"channels":{}}}"#,
+            serde_json::to_string(&singular).unwrap()
+        );
+        assert!(serde_json::from_str::<PolarizerCalibration>(&json).is_err());
+    }
+}
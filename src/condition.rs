@@ -0,0 +1,322 @@
+//! Classification of the sky condition of a frame.
+
+use crate::{image::IntensityImage, light::dop::Dop};
+use uom::si::{angle::degree, f64::Angle};
+
+/// The condition of the sky in a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SkyCondition {
+    /// The polarization pattern is strong and smooth.
+    Clear,
+
+    /// Clouds break up the pattern over part of the sky.
+    PartlyCloudy,
+
+    /// Clouds depolarize the whole sky.
+    Overcast,
+}
+
+/// Labels frames by their [`SkyCondition`] from statistics of their polarization and intensity.
+///
+/// Clouds depolarize the light that passes through them and break the smooth pattern of a clear
+/// sky into patches of unrelated [`Aop`](crate::light::aop::Aop).
+/// A frame is overcast when its median [`Dop`](crate::light::dop::Dop) is below the overcast
+/// threshold.
+/// It is clear when its median DoP reaches the clear threshold, the [`Aop`] of neighboring
+/// metapixels agrees to within the roughness threshold on average, and the coefficient of
+/// variation of the total intensity is below the variation threshold.
+/// Every other frame is partly cloudy.
+///
+/// The confidence of a label is the relative margin of the deciding statistic from its
+/// threshold, so frames near a boundary between conditions score close to zero.
+/// The defaults suit a fisheye camera looking up with the sun well above the horizon.
+///
+/// [`Aop`]: crate::light::aop::Aop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyClassifier {
+    overcast_dop: f64,
+    clear_dop: f64,
+    max_roughness: Angle,
+    max_intensity_variation: f64,
+}
+
+impl Default for SkyClassifier {
+    fn default() -> Self {
+        Self {
+            overcast_dop: 0.08,
+            clear_dop: 0.2,
+            max_roughness: Angle::new::<degree>(5.0),
+            max_intensity_variation: 0.5,
+        }
+    }
+}
+
+impl SkyClassifier {
+    /// Creates a new `SkyClassifier` with the default thresholds.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the median DoP below which a frame is overcast.
+    #[must_use]
+    pub fn with_overcast_threshold(mut self, overcast_dop: Dop) -> Self {
+        self.overcast_dop = overcast_dop.into();
+        self
+    }
+
+    /// Sets the median DoP a clear frame must reach.
+    #[must_use]
+    pub fn with_clear_threshold(mut self, clear_dop: Dop) -> Self {
+        self.clear_dop = clear_dop.into();
+        self
+    }
+
+    /// Sets the median DoP below which a frame is overcast.
+    #[must_use]
+    #[deprecated(note = "use `SkyClassifier::with_overcast_threshold` with a `Dop`")]
+    pub fn with_overcast_dop(self, overcast_dop: f64) -> Self {
+        self.with_overcast_threshold(Dop::clamped(overcast_dop))
+    }
+
+    /// Sets the median DoP a clear frame must reach.
+    #[must_use]
+    #[deprecated(note = "use `SkyClassifier::with_clear_threshold` with a `Dop`")]
+    pub fn with_clear_dop(self, clear_dop: f64) -> Self {
+        self.with_clear_threshold(Dop::clamped(clear_dop))
+    }
+
+    /// Sets the largest mean difference in AoP between neighboring metapixels of a clear frame.
+    #[must_use]
+    pub fn with_max_roughness(mut self, max_roughness: Angle) -> Self {
+        self.max_roughness = max_roughness;
+        self
+    }
+
+    /// Sets the largest coefficient of variation of the total intensity of a clear frame.
+    #[must_use]
+    pub fn with_max_intensity_variation(mut self, max_intensity_variation: f64) -> Self {
+        self.max_intensity_variation = max_intensity_variation;
+        self
+    }
+
+    #[must_use]
+    pub fn overcast_dop(&self) -> f64 {
+        self.overcast_dop
+    }
+
+    #[must_use]
+    pub fn clear_dop(&self) -> f64 {
+        self.clear_dop
+    }
+
+    #[must_use]
+    pub fn max_roughness(&self) -> Angle {
+        self.max_roughness
+    }
+
+    #[must_use]
+    pub fn max_intensity_variation(&self) -> f64 {
+        self.max_intensity_variation
+    }
+
+    /// Classifies the sky in `image`.
+    ///
+    /// Returns `None` if `image` has no metapixels with a defined polarization.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn classify(&self, image: &IntensityImage) -> Option<SkyAssessment> {
+        let stokes = image.stokes_image();
+        let rays = stokes.ray_image();
+        let (rows, cols) = (rays.rows(), rays.cols());
+
+        let mut dops: Vec<f64> = rays.rays().flatten().map(|ray| ray.dop().into()).collect();
+        if dops.is_empty() {
+            return None;
+        }
+        dops.sort_by(f64::total_cmp);
+        let dop = dops[dops.len() / 2];
+
+        // Compare each metapixel with its neighbors to the right and below.
+        let (mut roughness, mut pairs) = (Angle::new::<degree>(0.0), 0usize);
+        for row in 0..rows {
+            for col in 0..cols {
+                let Some(ray) = rays.ray(row, col) else {
+                    continue;
+                };
+                for (r, c) in [(row, col + 1), (row + 1, col)] {
+                    if r >= rows || c >= cols {
+                        continue;
+                    }
+                    if let Some(neighbor) = rays.ray(r, c) {
+                        roughness += ray.aop().angular_distance(&neighbor.aop());
+                        pairs += 1;
+                    }
+                }
+            }
+        }
+        let roughness = if pairs == 0 {
+            Angle::new::<degree>(0.0)
+        } else {
+            roughness / pairs as f64
+        };
+
+        let s0: Vec<f64> = stokes
+            .s0()
+            .iter()
+            .zip(rays.rays())
+            .filter_map(|(s0, ray)| ray.map(|_| *s0))
+            .collect();
+        let mean = s0.iter().sum::<f64>() / s0.len() as f64;
+        let variance = s0.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / s0.len() as f64;
+        let intensity_variation = if mean > 0.0 {
+            variance.sqrt() / mean
+        } else {
+            0.0
+        };
+
+        let (condition, confidence) = self.label(dop, roughness, intensity_variation);
+        Some(SkyAssessment {
+            condition,
+            confidence,
+            dop,
+            roughness,
+            intensity_variation,
+        })
+    }
+
+    /// Labels the statistics of a frame and scores the label.
+    fn label(&self, dop: f64, roughness: Angle, intensity_variation: f64) -> (SkyCondition, f64) {
+        let dop_overcast = margin(dop, self.overcast_dop);
+        if dop < self.overcast_dop {
+            return (SkyCondition::Overcast, dop_overcast);
+        }
+
+        let margins = [
+            margin(dop, self.clear_dop),
+            margin(roughness.value, self.max_roughness.value),
+            margin(intensity_variation, self.max_intensity_variation),
+        ];
+        let clear = [
+            dop >= self.clear_dop,
+            roughness <= self.max_roughness,
+            intensity_variation <= self.max_intensity_variation,
+        ];
+
+        if clear.iter().all(|passes| *passes) {
+            (SkyCondition::Clear, margins.into_iter().fold(1.0, f64::min))
+        } else {
+            // The frame is as far from clear as its worst failing statistic.
+            let failing = margins
+                .into_iter()
+                .zip(clear)
+                .filter(|(_, passes)| !passes)
+                .fold(0.0, |worst, (margin, _)| f64::max(worst, margin));
+            (SkyCondition::PartlyCloudy, failing.min(dop_overcast))
+        }
+    }
+}
+
+/// Returns the distance of `value` from `threshold` relative to the threshold, clamped to one.
+fn margin(value: f64, threshold: f64) -> f64 {
+    if threshold > 0.0 {
+        ((value - threshold).abs() / threshold).min(1.0)
+    } else {
+        1.0
+    }
+}
+
+/// The result of [`SkyClassifier::classify`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyAssessment {
+    condition: SkyCondition,
+    confidence: f64,
+    dop: f64,
+    roughness: Angle,
+    intensity_variation: f64,
+}
+
+impl SkyAssessment {
+    #[must_use]
+    pub fn condition(&self) -> SkyCondition {
+        self.condition
+    }
+
+    /// Returns the confidence in the condition between zero and one.
+    #[must_use]
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// Returns the median DoP of the frame.
+    #[must_use]
+    pub fn dop(&self) -> f64 {
+        self.dop
+    }
+
+    /// Returns the mean difference in AoP between neighboring metapixels.
+    #[must_use]
+    pub fn roughness(&self) -> Angle {
+        self.roughness
+    }
+
+    /// Returns the standard deviation of the total intensity divided by its mean.
+    #[must_use]
+    pub fn intensity_variation(&self) -> f64 {
+        self.intensity_variation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Builds an image whose metapixels have the `aop` in degrees, `dop`, and `s0` returned by
+    /// `pixel` for each row and column.
+    fn image(pixel: impl Fn(usize, usize) -> (f64, f64, f64)) -> IntensityImage {
+        let intensities = (0..64).map(|index| {
+            let (aop, dop, s0) = pixel(index / 8, index % 8);
+            [0.0_f64, 45.0, 90.0, 135.0].map(|polarizer| {
+                s0 / 2.0 * (1.0 + dop * (2.0 * (polarizer - aop)).to_radians().cos())
+            })
+        });
+        IntensityImage::from_intensities(8, 8, intensities).unwrap()
+    }
+
+    #[rstest]
+    #[case::clear(image(|row, _| (row as f64, 0.5, 100.0)), SkyCondition::Clear)]
+    #[case::overcast(image(|row, _| (row as f64 * 20.0, 0.02, 100.0)), SkyCondition::Overcast)]
+    #[case::rough(
+        image(|row, col| (if (row + col) % 2 == 0 { 0.0 } else { 80.0 }, 0.5, 100.0)),
+        SkyCondition::PartlyCloudy
+    )]
+    #[case::patchy(
+        image(|row, _| if row < 4 { (0.0, 0.5, 100.0) } else { (0.0, 0.1, 400.0) }),
+        SkyCondition::PartlyCloudy
+    )]
+    fn classifies_conditions(#[case] image: IntensityImage, #[case] expected: SkyCondition) {
+        let assessment = SkyClassifier::new().classify(&image).unwrap();
+
+        assert_eq!(assessment.condition(), expected);
+        assert!(assessment.confidence() > 0.1);
+        assert!(assessment.confidence() <= 1.0);
+    }
+
+    #[test]
+    fn borderline_frames_have_low_confidence() {
+        let classifier = SkyClassifier::new();
+        let assessment = classifier
+            .classify(&image(|_, _| (0.0, classifier.clear_dop() + 1e-3, 100.0)))
+            .unwrap();
+
+        assert_eq!(assessment.condition(), SkyCondition::Clear);
+        assert!(assessment.confidence() < 0.01);
+    }
+
+    #[test]
+    fn unpolarized_image_is_unclassified() {
+        let image = IntensityImage::from_intensities(1, 1, [[0.0; 4]]).unwrap();
+        assert_eq!(SkyClassifier::new().classify(&image), None);
+    }
+}
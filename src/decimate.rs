@@ -0,0 +1,288 @@
+use crate::{image::RayImage, iter::RayIterator, ray::Ray, rng::SplitMix64};
+
+/// A strategy for keeping a subset of the rays in a [`RayImage`].
+///
+/// Decimating an image trades accuracy for speed when matching against a model.
+/// Every strategy is deterministic given its parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decimation {
+    /// Keeps every `step`th pixel in row-major order.
+    Stride { step: usize },
+
+    /// Keeps each ray independently with probability `fraction`.
+    Random { fraction: f64, seed: u64 },
+
+    /// Splits the image into `strata` concentric rings about its center and keeps up to
+    /// `per_stratum` randomly chosen rays from each.
+    ///
+    /// For a camera looking towards the zenith, distance from the center of the image is a proxy
+    /// for elevation so each ring spans a band of elevations.
+    Stratified {
+        strata: usize,
+        per_stratum: usize,
+        seed: u64,
+    },
+
+    /// Keeps the `count` rays with the largest degree of polarization.
+    TopDop { count: usize },
+}
+
+impl Decimation {
+    /// Returns a row-major mask of the pixels in `image` that are kept.
+    ///
+    /// Pixels without a ray are never kept.
+    ///
+    /// # Panics
+    /// Will panic if `step` or `strata` is zero.
+    #[must_use]
+    pub fn mask<Frame>(&self, image: &RayImage<Frame>) -> Vec<bool> {
+        let rays: Vec<_> = image.rays().collect();
+        let valid = |index: &usize| rays[*index].is_some();
+        let mut mask = vec![false; rays.len()];
+
+        match *self {
+            Decimation::Stride { step } => {
+                assert!(step > 0, "stride step must be non-zero");
+                for index in (0..rays.len()).step_by(step).filter(valid) {
+                    mask[index] = true;
+                }
+            }
+            Decimation::Random { fraction, seed } => {
+                let mut rng = SplitMix64::new(seed);
+                for (index, keep) in mask.iter_mut().enumerate() {
+                    *keep = rng.next_f64() < fraction && valid(&index);
+                }
+            }
+            Decimation::Stratified {
+                strata,
+                per_stratum,
+                seed,
+            } => {
+                assert!(strata > 0, "number of strata must be non-zero");
+                let (rows, cols) = (image.rows(), image.cols());
+
+                #[allow(clippy::cast_precision_loss)]
+                let center = ((rows as f64 - 1.0) / 2.0, (cols as f64 - 1.0) / 2.0);
+                let max_radius = center.0.hypot(center.1).max(f64::EPSILON);
+
+                let mut bins = vec![Vec::new(); strata];
+                for index in (0..rays.len()).filter(valid) {
+                    #[allow(clippy::cast_precision_loss)]
+                    let radius =
+                        ((index / cols) as f64 - center.0).hypot((index % cols) as f64 - center.1);
+
+                    #[allow(
+                        clippy::cast_possible_truncation,
+                        clippy::cast_sign_loss,
+                        clippy::cast_precision_loss
+                    )]
+                    let bin = ((radius / max_radius * strata as f64) as usize).min(strata - 1);
+                    bins[bin].push(index);
+                }
+
+                let mut rng = SplitMix64::new(seed);
+                for mut bin in bins {
+                    // Partial Fisher-Yates shuffle.
+                    let keep = per_stratum.min(bin.len());
+                    for i in 0..keep {
+                        let j = i + rng.next_index(bin.len() - i);
+                        bin.swap(i, j);
+                        mask[bin[i]] = true;
+                    }
+                }
+            }
+            Decimation::TopDop { count } => {
+                let mut indices: Vec<_> = (0..rays.len()).filter(valid).collect();
+                let dop = |index: &usize| rays[*index].map(|ray| f64::from(ray.dop()));
+                if count < indices.len() {
+                    indices.select_nth_unstable_by(count, |a, b| {
+                        dop(b).partial_cmp(&dop(a)).expect("dop is finite")
+                    });
+                    indices.truncate(count);
+                }
+
+                for index in indices {
+                    mask[index] = true;
+                }
+            }
+        }
+
+        mask
+    }
+}
+
+/// An iterator that yields every `step`th ray from `iter`.
+///
+/// See [`RayIterator::ray_stride`].
+pub struct RayStride<I> {
+    iter: std::iter::StepBy<I>,
+}
+
+impl<I: Iterator> RayStride<I> {
+    /// # Panics
+    /// Will panic if `step` is zero.
+    pub fn new(iter: I, step: usize) -> Self {
+        Self {
+            iter: iter.step_by(step),
+        }
+    }
+}
+
+impl<I, Frame> Iterator for RayStride<I>
+where
+    I: Iterator<Item = Ray<Frame>>,
+{
+    type Item = Ray<Frame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+// All of RayIterator's functions are defined using Iterator.
+impl<I, Frame> RayIterator<Frame> for RayStride<I> where I: Iterator<Item = Ray<Frame>> {}
+
+/// An iterator that yields each ray from `iter` with probability `fraction`.
+///
+/// See [`RayIterator::ray_sample`].
+pub struct RaySample<I> {
+    iter: I,
+    fraction: f64,
+    rng: SplitMix64,
+}
+
+impl<I> RaySample<I> {
+    pub fn new(iter: I, fraction: f64, seed: u64) -> Self {
+        Self {
+            iter,
+            fraction,
+            rng: SplitMix64::new(seed),
+        }
+    }
+}
+
+impl<I, Frame> Iterator for RaySample<I>
+where
+    I: Iterator<Item = Ray<Frame>>,
+{
+    type Item = Ray<Frame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let Self {
+            iter,
+            fraction,
+            rng,
+        } = self;
+        iter.find(|_| rng.next_f64() < *fraction)
+    }
+}
+
+// All of RayIterator's functions are defined using Iterator.
+impl<I, Frame> RayIterator<Frame> for RaySample<I> where I: Iterator<Item = Ray<Frame>> {}
+
+/// An iterator over the rays with the largest degree of polarization.
+///
+/// Rays are yielded in descending order of degree of polarization.
+/// See [`RayIterator::ray_top_dop`].
+pub struct RayTopDop<Frame> {
+    rays: std::vec::IntoIter<Ray<Frame>>,
+}
+
+impl<Frame> RayTopDop<Frame> {
+    pub fn new(iter: impl Iterator<Item = Ray<Frame>>, count: usize) -> Self {
+        let mut rays: Vec<_> = iter.collect();
+        let order = |a: &Ray<Frame>, b: &Ray<Frame>| {
+            f64::from(b.dop())
+                .partial_cmp(&f64::from(a.dop()))
+                .expect("dop is finite")
+        };
+
+        if count < rays.len() {
+            rays.select_nth_unstable_by(count, order);
+            rays.truncate(count);
+        }
+        rays.sort_unstable_by(order);
+
+        Self {
+            rays: rays.into_iter(),
+        }
+    }
+}
+
+impl<Frame> Iterator for RayTopDop<Frame> {
+    type Item = Ray<Frame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rays.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rays.size_hint()
+    }
+}
+
+impl<Frame> ExactSizeIterator for RayTopDop<Frame> {}
+
+// All of RayIterator's functions are defined using Iterator.
+impl<Frame> RayIterator<Frame> for RayTopDop<Frame> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::{aop::Aop, dop::Dop},
+        ray::GlobalFrame,
+    };
+    use rstest::rstest;
+    use uom::{ConstZero, si::f64::Angle};
+
+    fn ray(dop: f64) -> Ray<GlobalFrame> {
+        Ray::new(Aop::from_angle_wrapped(Angle::ZERO), Dop::clamped(dop))
+    }
+
+    fn image(rows: usize, cols: usize) -> RayImage<GlobalFrame> {
+        #[allow(clippy::cast_precision_loss)]
+        let rays = (0..rows * cols).map(|i| Some(ray(i as f64 / (rows * cols) as f64)));
+        RayImage::from_rays(rays, rows, cols).unwrap()
+    }
+
+    fn kept(mask: &[bool]) -> usize {
+        mask.iter().filter(|keep| **keep).count()
+    }
+
+    #[rstest]
+    #[case(Decimation::Stride { step: 4 }, 25)]
+    #[case(Decimation::Stratified { strata: 4, per_stratum: 5, seed: 1 }, 20)]
+    #[case(Decimation::TopDop { count: 7 }, 7)]
+    #[case(Decimation::Random { fraction: 0.0, seed: 1 }, 0)]
+    #[case(Decimation::Random { fraction: 1.0, seed: 1 }, 100)]
+    fn mask_keeps_expected_count(#[case] decimation: Decimation, #[case] expected: usize) {
+        assert_eq!(kept(&decimation.mask(&image(10, 10))), expected);
+    }
+
+    #[test]
+    fn mask_skips_missing_rays() {
+        let image =
+            RayImage::<GlobalFrame>::from_rays(vec![None, Some(ray(0.5)), None], 1, 3).unwrap();
+        assert_eq!(
+            Decimation::Stride { step: 1 }.mask(&image),
+            vec![false, true, false]
+        );
+    }
+
+    #[test]
+    fn top_dop_keeps_largest() {
+        let mask = Decimation::TopDop { count: 3 }.mask(&image(4, 4));
+        assert_eq!(&mask[13..], &[true; 3]);
+        assert_eq!(kept(&mask), 3);
+
+        let rays: Vec<_> = RayTopDop::new(image(4, 4).rays().flatten().copied(), 2).collect();
+        assert_eq!(rays, vec![ray(15.0 / 16.0), ray(14.0 / 16.0)]);
+    }
+
+    #[test]
+    fn sample_is_deterministic() {
+        let sample = |seed| -> Vec<_> {
+            RaySample::new(image(10, 10).rays().flatten().copied(), 0.3, seed).collect()
+        };
+        assert_eq!(sample(5), sample(5));
+        assert_ne!(sample(5), sample(6));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::ray::{Ray, SensorFrame};
+use thiserror::Error;
+use uom::{
+    ConstZero,
+    si::{angle::radian, f64::Angle},
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum FusionError {
+    #[error("heading fusion requires at least one estimate")]
+    Empty,
+
+    #[error("estimate {index} has a heading or weight that is not finite")]
+    InvalidEstimate { index: usize },
+
+    #[error("weighted headings cancel out")]
+    Cancelled,
+}
+
+/// A heading estimate with a one standard deviation uncertainty.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeadingEstimate {
+    heading: Angle,
+    uncertainty: Angle,
+}
+
+impl HeadingEstimate {
+    /// Creates a new `HeadingEstimate`.
+    ///
+    /// # Panics
+    /// Will panic if `uncertainty` is not greater than zero.
+    #[must_use]
+    pub fn new(heading: Angle, uncertainty: Angle) -> Self {
+        assert!(
+            uncertainty > Angle::ZERO,
+            "uncertainty must be greater than zero: {uncertainty:#?}"
+        );
+
+        Self {
+            heading,
+            uncertainty,
+        }
+    }
+
+    #[must_use]
+    pub fn heading(&self) -> Angle {
+        self.heading
+    }
+
+    #[must_use]
+    pub fn uncertainty(&self) -> Angle {
+        self.uncertainty
+    }
+
+    fn weight(&self) -> f64 {
+        self.uncertainty.get::<radian>().powi(-2)
+    }
+}
+
+/// Fraction of the total weight below which the resultant of the weighted headings is treated as
+/// cancelled.
+const CANCELLATION_TOLERANCE: f64 = 1e-9;
+
+/// Fuses `estimates` with an inverse variance weighted circular mean.
+///
+/// The fused uncertainty assumes the estimates are independent and their uncertainties are small.
+///
+/// # Errors
+/// Will return `Err` if `estimates` is empty, an estimate has a heading that is not finite or an
+/// uncertainty too small to weight, or the weighted headings cancel out.
+pub fn fuse(
+    estimates: impl IntoIterator<Item = HeadingEstimate>,
+) -> Result<HeadingEstimate, FusionError> {
+    let (mut cos_sum, mut sin_sum, mut weight_sum) = (0.0, 0.0, 0.0);
+    let mut count = 0;
+    for (index, estimate) in estimates.into_iter().enumerate() {
+        let weight = estimate.weight();
+        weight_sum += weight;
+        if !(estimate.heading.is_finite() && weight_sum.is_finite()) {
+            return Err(FusionError::InvalidEstimate { index });
+        }
+        cos_sum += weight * estimate.heading.cos().value;
+        sin_sum += weight * estimate.heading.sin().value;
+        count += 1;
+    }
+
+    if count == 0 {
+        return Err(FusionError::Empty);
+    }
+    // Headings that oppose each other leave a resultant that is only rounding error.
+    if weight_sum <= 0.0 || cos_sum.hypot(sin_sum) <= CANCELLATION_TOLERANCE * weight_sum {
+        return Err(FusionError::Cancelled);
+    }
+
+    Ok(HeadingEstimate::new(
+        Angle::new::<radian>(sin_sum.atan2(cos_sum)),
+        Angle::new::<radian>(weight_sum.sqrt().recip()),
+    ))
+}
+
+/// An estimator that recovers a heading from rays.
+pub trait HeadingEstimator {
+    fn estimate_heading(&self, rays: &[Ray<SensorFrame>]) -> Option<HeadingEstimate>;
+}
+
+impl<F> HeadingEstimator for F
+where
+    F: Fn(&[Ray<SensorFrame>]) -> Option<HeadingEstimate>,
+{
+    fn estimate_heading(&self, rays: &[Ray<SensorFrame>]) -> Option<HeadingEstimate> {
+        self(rays)
+    }
+}
+
+/// The result of a [`HeadingFusion`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FusedHeading {
+    individual: Vec<Option<HeadingEstimate>>,
+    fused: Option<HeadingEstimate>,
+}
+
+impl FusedHeading {
+    /// The estimate of each estimator in the order they were added.
+    #[must_use]
+    pub fn individual(&self) -> &[Option<HeadingEstimate>] {
+        &self.individual
+    }
+
+    /// The fused estimate.
+    #[must_use]
+    pub fn fused(&self) -> Option<HeadingEstimate> {
+        self.fused
+    }
+}
+
+/// Runs several [`HeadingEstimator`]s on the same rays and fuses their estimates.
+///
+/// Each estimate is weighted by its reported uncertainty.
+/// Estimators that return `None` do not contribute to the fused estimate.
+#[derive(Default)]
+pub struct HeadingFusion<'a> {
+    estimators: Vec<Box<dyn HeadingEstimator + 'a>>,
+}
+
+impl<'a> HeadingFusion<'a> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `estimator` to the fusion.
+    #[must_use]
+    pub fn with_estimator(mut self, estimator: impl HeadingEstimator + 'a) -> Self {
+        self.estimators.push(Box::new(estimator));
+        self
+    }
+
+    /// Runs every estimator on `rays` and fuses the results.
+    #[must_use]
+    pub fn estimate(&self, rays: &[Ray<SensorFrame>]) -> FusedHeading {
+        let individual: Vec<_> = self
+            .estimators
+            .iter()
+            .map(|estimator| estimator.estimate_heading(rays))
+            .collect();
+        let fused = fuse(individual.iter().flatten().copied()).ok();
+
+        FusedHeading { individual, fused }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    fn estimate(heading: f64, uncertainty: f64) -> HeadingEstimate {
+        HeadingEstimate::new(
+            Angle::new::<degree>(heading),
+            Angle::new::<degree>(uncertainty),
+        )
+    }
+
+    #[rstest]
+    #[case(estimate(10.0, 1.0), estimate(20.0, 1.0), 15.0)]
+    #[case(estimate(179.0, 1.0), estimate(-179.0, 1.0), 180.0)]
+    #[case(estimate(10.0, 1.0), estimate(40.0, 1000.0), 10.0)]
+    fn fuse_pair(#[case] lhs: HeadingEstimate, #[case] rhs: HeadingEstimate, #[case] fused: f64) {
+        let result = fuse([lhs, rhs]).unwrap();
+        let error = result.heading() - Angle::new::<degree>(fused);
+
+        assert_relative_eq!(error.sin().value, 0.0, epsilon = 1e-4);
+        assert!(error.cos().value > 0.0);
+        assert!(result.uncertainty() <= lhs.uncertainty().min(rhs.uncertainty()));
+    }
+
+    #[test]
+    fn fuse_rejects_invalid_estimates() {
+        assert_eq!(fuse([]), Err(FusionError::Empty));
+        assert_eq!(
+            fuse([estimate(0.0, 1.0), estimate(180.0, 1.0)]),
+            Err(FusionError::Cancelled)
+        );
+        assert_eq!(
+            fuse([estimate(90.0, 1.0), estimate(270.0, 1.0)]),
+            Err(FusionError::Cancelled)
+        );
+        assert_eq!(
+            fuse([estimate(10.0, 1.0), estimate(f64::NAN, 1.0)]),
+            Err(FusionError::InvalidEstimate { index: 1 })
+        );
+
+        // The weight of a vanishing uncertainty overflows.
+        let exact = HeadingEstimate::new(Angle::new::<degree>(10.0), Angle::new::<radian>(1e-200));
+        assert_eq!(
+            fuse([estimate(10.0, 1.0), exact]),
+            Err(FusionError::InvalidEstimate { index: 1 })
+        );
+    }
+
+    #[test]
+    fn fusion_keeps_individual() {
+        let fusion = HeadingFusion::new()
+            .with_estimator(|_: &[Ray<SensorFrame>]| Some(estimate(10.0, 2.0)))
+            .with_estimator(|_: &[Ray<SensorFrame>]| None)
+            .with_estimator(|_: &[Ray<SensorFrame>]| Some(estimate(10.0, 2.0)));
+        let result = fusion.estimate(&[]);
+
+        assert_eq!(
+            result.individual(),
+            &[Some(estimate(10.0, 2.0)), None, Some(estimate(10.0, 2.0))]
+        );
+        assert_relative_eq!(
+            result.fused().unwrap().uncertainty().get::<degree>(),
+            2.0 / 2.0_f64.sqrt(),
+            epsilon = 1e-9
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Estimators that recover orientation cues from measured rays.
+
+pub mod fusion;
+pub mod signature;
+pub mod solar;
+pub mod symmetry;
+pub mod tilt;
+
+use uom::si::{angle::radian, f64::Angle};
+
+/// Returns the unit axis most perpendicular to the unit vectors accumulated in the orientation
+/// `tensor`, i.e., its eigenvector with the smallest eigenvalue, and a one standard deviation
+/// uncertainty of its direction.
+///
+/// The uncertainty assumes the errors of the vectors, whose weights total `weight_sum`, are
+/// independent.
+/// The sign of the axis is arbitrary.
+/// Returns `None` if the vectors do not constrain the axis, e.g., if they are all parallel.
+fn perpendicular_axis(tensor: [[f64; 3]; 3], weight_sum: f64) -> Option<([f64; 3], Angle)> {
+    let (values, vectors) = symmetric_eigen(tensor);
+    let mut order = [0, 1, 2];
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    let (smallest, middle) = (values[order[0]].max(0.0), values[order[1]]);
+    if middle - smallest <= f64::EPSILON * weight_sum {
+        return None;
+    }
+
+    Some((
+        vectors[order[0]],
+        Angle::new::<radian>((smallest / (weight_sum * (middle - smallest))).sqrt()),
+    ))
+}
+
+/// Computes the eigenvalues and unit eigenvectors of a symmetric matrix with cyclic Jacobi
+/// rotations.
+fn symmetric_eigen(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..50 {
+        let off = a[0][1].powi(2) + a[0][2].powi(2) + a[1][2].powi(2);
+        if off < 1e-30 {
+            break;
+        }
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0.0 {
+                continue;
+            }
+
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + theta.hypot(1.0));
+            let t = if theta == 0.0 { 1.0 } else { t };
+            let c = t.hypot(1.0).recip();
+            let s = t * c;
+
+            for row in &mut a {
+                let (akp, akq) = (row[p], row[q]);
+                row[p] = c * akp - s * akq;
+                row[q] = s * akp + c * akq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = [0, 1, 2].map(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = [0, 1, 2].map(|k| s * row_p[k] + c * row_q[k]);
+            for row in &mut v {
+                let (vkp, vkq) = (row[p], row[q]);
+                row[p] = c * vkp - s * vkq;
+                row[q] = s * vkp + c * vkq;
+            }
+        }
+    }
+
+    // Eigenvectors are the columns of `v`.
+    let column = |j: usize| [v[0][j], v[1][j], v[2][j]];
+    (
+        [a[0][0], a[1][1], a[2][2]],
+        [column(0), column(1), column(2)],
+    )
+}
@@ -0,0 +1,255 @@
+use super::fusion::HeadingEstimate;
+use crate::{azimuth::AzimuthConvention, image::RayImage};
+use std::f64::consts::PI;
+use uom::si::{angle::radian, f64::Angle};
+
+/// A one dimensional signature of the AoP against image azimuth.
+///
+/// Each pixel is binned by its azimuth about the center of the image, measured counterclockwise
+/// from the right of the image towards the top as in [`AopSignature::AZIMUTH`].
+/// This matches the [`SensorFrame`](crate::ray::SensorFrame) that the AoP is measured in.
+/// A bin holds the mean of the doubled AoP of its pixels measured from their azimuth, weighted by
+/// their [`crate::light::dop::Dop`].
+/// For a camera pointing at the zenith, a change of yaw rotates the image about its center and
+/// circularly shifts the signature without changing its shape.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AopSignature {
+    bins: Vec<[f64; 2]>,
+}
+
+impl AopSignature {
+    /// The convention of the image azimuth of the bins and of [`AopSignature::rotation_from`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::EastCounterclockwise;
+
+    /// Computes the signature of `image` with `bins` azimuth bins.
+    ///
+    /// # Panics
+    /// Will panic if `bins` is zero.
+    #[must_use]
+    pub fn from_image<Frame: Copy>(image: &RayImage<Frame>, bins: usize) -> Self {
+        assert!(bins > 0, "bins must be non-zero");
+
+        let center_row = (image.rows() as f64 - 1.0) / 2.0;
+        let center_col = (image.cols() as f64 - 1.0) / 2.0;
+        let width = std::f64::consts::TAU / bins as f64;
+
+        let mut sums = vec![[0.0; 2]; bins];
+        let mut counts = vec![0_u32; bins];
+        for row in 0..image.rows() {
+            for col in 0..image.cols() {
+                let Some(ray) = image.ray(row, col) else {
+                    continue;
+                };
+
+                let azimuth = Self::AZIMUTH
+                    .from_components(col as f64 - center_col, center_row - row as f64)
+                    .get::<radian>();
+                let bin = ((azimuth.rem_euclid(std::f64::consts::TAU) / width) as usize) % bins;
+                let relative = 2.0 * (Angle::from(ray.aop()).get::<radian>() - azimuth);
+                let weight = f64::from(ray.dop());
+                sums[bin][0] += weight * relative.cos();
+                sums[bin][1] += weight * relative.sin();
+                counts[bin] += 1;
+            }
+        }
+
+        // Average each bin so the corners of a rectangular sensor are not over weighted.
+        for (sum, count) in sums.iter_mut().zip(counts) {
+            if count > 0 {
+                *sum = sum.map(|component| component / f64::from(count));
+            }
+        }
+
+        Self { bins: sums }
+    }
+
+    /// Returns the number of azimuth bins.
+    #[must_use]
+    pub fn bins(&self) -> usize {
+        self.bins.len()
+    }
+
+    /// Returns the rotation of `self` relative to `reference` about the center of the image on
+    /// [-180, 180).
+    ///
+    /// The rotation is counterclockwise as in [`AopSignature::AZIMUTH`], i.e., a positive rotation
+    /// turns the right of the image towards the top.
+    /// The rotation maximizes the magnitude of the circular cross-correlation of the two
+    /// signatures, so a constant offset between their AoP, e.g., from a different reference
+    /// axis, does not bias it.
+    /// The peak is refined to a fraction of a bin with a parabola through its neighbors.
+    ///
+    /// Returns `None` if the signatures have different numbers of bins or do not overlap.
+    #[must_use]
+    pub fn rotation_from(&self, reference: &Self) -> Option<Angle> {
+        let bins = self.bins();
+        if bins != reference.bins() {
+            return None;
+        }
+
+        let scores: Vec<f64> = (0..bins)
+            .map(|shift| {
+                let (mut re, mut im) = (0.0, 0.0);
+                for (k, [cos, sin]) in self.bins.iter().enumerate() {
+                    let [ref_cos, ref_sin] = reference.bins[(k + bins - shift) % bins];
+                    re += cos * ref_cos + sin * ref_sin;
+                    im += sin * ref_cos - cos * ref_sin;
+                }
+                re.hypot(im)
+            })
+            .collect();
+
+        let (peak, best) = scores
+            .iter()
+            .copied()
+            .enumerate()
+            .reduce(|best, next| if next.1 > best.1 { next } else { best })?;
+        if best <= 0.0 {
+            return None;
+        }
+
+        let before = scores[(peak + bins - 1) % bins];
+        let after = scores[(peak + 1) % bins];
+        let curvature = before - 2.0 * best + after;
+        let offset = if curvature < 0.0 {
+            (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+
+        let width = std::f64::consts::TAU / bins as f64;
+        let rotation = (peak as f64 + offset) * width;
+        Some(Angle::new::<radian>(
+            (rotation + PI).rem_euclid(std::f64::consts::TAU) - PI,
+        ))
+    }
+}
+
+/// Finds a coarse yaw by matching the [`AopSignature`] of a measured image against a simulated
+/// reference.
+///
+/// The reference is usually computed once from a simulated image at zero yaw, e.g., from
+/// [`Simulation::ray_image`](crate::simulation::Simulation::ray_image) in the same frame as the
+/// measured images.
+/// Matching costs a single pass over the image and a cross-correlation of the signatures, so it
+/// is cheap enough to seed an iterative refinement on every frame.
+///
+/// The estimate assumes the camera points near the zenith.
+/// The heading is the counterclockwise rotation of the image as in [`AopSignature::AZIMUTH`].
+/// Whether that is a clockwise or counterclockwise yaw depends on how the sensor is mounted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureMatch {
+    reference: AopSignature,
+}
+
+impl SignatureMatch {
+    /// Creates a new `SignatureMatch` against `reference`.
+    #[must_use]
+    pub fn new(reference: AopSignature) -> Self {
+        Self { reference }
+    }
+
+    #[must_use]
+    pub fn reference(&self) -> &AopSignature {
+        &self.reference
+    }
+
+    /// Estimates the counterclockwise rotation of `image` relative to the reference.
+    ///
+    /// The uncertainty is the resolution of the signature, i.e., the width of a bin over the
+    /// square root of twelve.
+    ///
+    /// Returns `None` if `image` holds no rays that overlap the reference.
+    #[must_use]
+    pub fn estimate<Frame: Copy>(&self, image: &RayImage<Frame>) -> Option<HeadingEstimate> {
+        let bins = self.reference.bins();
+        let rotation = AopSignature::from_image(image, bins).rotation_from(&self.reference)?;
+        let width = std::f64::consts::TAU / bins as f64;
+
+        Some(HeadingEstimate::new(
+            rotation,
+            Angle::new::<radian>(width / 12.0_f64.sqrt()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::{aop::Aop, dop::Dop},
+        ray::{Ray, SensorFrame},
+    };
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    /// An image whose AoP, measured from the image azimuth, varies with the azimuth rotated
+    /// counterclockwise by `rotation` degrees.
+    fn image(rotation: f64) -> RayImage<SensorFrame> {
+        let size = 64;
+        let center = (size as f64 - 1.0) / 2.0;
+        let rays = (0..size * size).map(|index| {
+            let (row, col) = ((index / size) as f64, (index % size) as f64);
+            let azimuth = AopSignature::AZIMUTH
+                .from_components(col - center, center - row)
+                .get::<radian>();
+            let local = azimuth - rotation.to_radians();
+            let pattern = 0.6 * local.sin() + 0.3 * (3.0 * local).cos() + 0.2 * (2.0 * local).sin();
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<radian>(azimuth + pattern + 0.4)),
+                Dop::clamped(0.5 + 0.3 * local.cos()),
+            ))
+        });
+        RayImage::from_rays(rays, size, size).unwrap()
+    }
+
+    #[rstest]
+    #[case(0.0)]
+    #[case(37.0)]
+    #[case(-120.0)]
+    #[case(179.0)]
+    fn recovers_rotation(#[case] rotation: f64) {
+        let matcher = SignatureMatch::new(AopSignature::from_image(&image(0.0), 180));
+        let estimate = matcher.estimate(&image(rotation)).unwrap();
+
+        let error =
+            (estimate.heading().get::<degree>() - rotation + 180.0).rem_euclid(360.0) - 180.0;
+        assert_relative_eq!(error, 0.0, epsilon = 1.0);
+        assert!(estimate.uncertainty().get::<degree>() < 1.0);
+    }
+
+    #[test]
+    fn mismatched_bins_do_not_match() {
+        let reference = AopSignature::from_image(&image(0.0), 36);
+        assert_eq!(reference.bins(), 36);
+        assert_eq!(
+            AopSignature::from_image(&image(10.0), 72).rotation_from(&reference),
+            None
+        );
+
+        let empty = RayImage::<SensorFrame>::from_rays([None, None], 1, 2).unwrap();
+        assert_eq!(SignatureMatch::new(reference).estimate(&empty), None);
+    }
+
+    #[test]
+    fn rotation_is_counterclockwise() {
+        // Turn the scene a quarter turn so the right of the image moves to the top.
+        let reference = image(0.0);
+        let size = reference.rows();
+        let rays = (0..size * size).map(|index| {
+            let (row, col) = (index / size, index % size);
+            reference.ray(col, size - 1 - row).map(|ray| {
+                Ray::new(
+                    Aop::from_angle_wrapped(Angle::from(ray.aop()) + Angle::new::<degree>(90.0)),
+                    ray.dop(),
+                )
+            })
+        });
+        let turned = RayImage::<SensorFrame>::from_rays(rays, size, size).unwrap();
+
+        let matcher = SignatureMatch::new(AopSignature::from_image(&reference, 180));
+        let estimate = matcher.estimate(&turned).unwrap();
+        assert_relative_eq!(estimate.heading().get::<degree>(), 90.0, epsilon = 1.0);
+    }
+}
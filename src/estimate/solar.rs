@@ -0,0 +1,223 @@
+use super::perpendicular_axis;
+use crate::{
+    azimuth::AzimuthConvention,
+    ray::{GlobalFrame, Ray},
+};
+use sguaba::Bearing;
+use uom::si::{angle::radian, f64::Angle};
+
+/// The direction of the sun recovered from measured rays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolarEstimate<In> {
+    bearing: Bearing<In>,
+    uncertainty: Angle,
+}
+
+impl<In> SolarEstimate<In> {
+    /// The bearing of the sun, which is always above the horizon.
+    #[must_use]
+    pub fn bearing(&self) -> Bearing<In>
+    where
+        In: Copy,
+    {
+        self.bearing
+    }
+
+    /// A one standard deviation uncertainty of the direction of the sun.
+    ///
+    /// It is derived from how far the e-vectors stray from the fitted plane and assumes their
+    /// errors are independent.
+    #[must_use]
+    pub fn uncertainty(&self) -> Angle {
+        self.uncertainty
+    }
+}
+
+/// Estimates the bearing of the sun from rays at known bearings.
+///
+/// Under single scattering, the e-vector of every ray is perpendicular to the direction of the
+/// sun.
+/// The sun is therefore the direction most perpendicular to all of the e-vectors, which is the
+/// eigenvector with the smallest eigenvalue of their orientation tensor.
+/// Rays must be in the [`GlobalFrame`] and their bearings must come from a camera with a known
+/// orientation, e.g., from [`Camera`](crate::optic::Camera) and an attitude reference.
+///
+/// The sun and anti-solar point are indistinguishable from the angle of polarization alone so
+/// the estimate is always above the horizon.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SolarBearingFit {
+    dop_weighted: bool,
+}
+
+impl SolarBearingFit {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Weight each ray by its [`crate::light::dop::Dop`] when fitting the sun.
+    #[must_use]
+    pub fn dop_weighted(mut self, dop_weighted: bool) -> Self {
+        self.dop_weighted = dop_weighted;
+        self
+    }
+
+    /// Estimates the bearing of the sun from rays and the bearings they were observed at.
+    ///
+    /// Returns `None` if fewer than two rays carry weight or the e-vectors do not constrain the
+    /// sun, e.g., if they are all parallel.
+    ///
+    /// # Panics
+    /// Will panic if the fitted direction has an invalid elevation.
+    /// This should never occur.
+    pub fn estimate<In>(
+        &self,
+        observations: impl IntoIterator<Item = (Bearing<In>, Ray<GlobalFrame>)>,
+    ) -> Option<SolarEstimate<In>> {
+        let mut tensor = [[0.0; 3]; 3];
+        let (mut weight_sum, mut count) = (0.0, 0);
+        for (bearing, ray) in observations {
+            let weight = if self.dop_weighted {
+                f64::from(ray.dop())
+            } else {
+                1.0
+            };
+            if weight <= 0.0 {
+                continue;
+            }
+
+            let e = e_vector(bearing.azimuth(), bearing.elevation(), ray.aop().into());
+            for (i, row) in tensor.iter_mut().enumerate() {
+                for (j, cell) in row.iter_mut().enumerate() {
+                    *cell += weight * e[i] * e[j];
+                }
+            }
+            weight_sum += weight;
+            count += 1;
+        }
+
+        if count < 2 {
+            return None;
+        }
+
+        let (mut sun, uncertainty) = perpendicular_axis(tensor, weight_sum)?;
+
+        // The sun and anti-solar point share a tensor so pick the one above the horizon.
+        if sun[2] < 0.0 {
+            sun = sun.map(|component| -component);
+        }
+
+        Some(SolarEstimate {
+            bearing: Bearing::<In>::builder()
+                .azimuth(AzimuthConvention::NorthClockwise.from_components(sun[0], sun[1]))
+                .elevation(Angle::new::<radian>(sun[2].clamp(-1.0, 1.0).asin()))
+                .expect("elevation of a unit vector is on the range -90 to 90")
+                .build(),
+            uncertainty,
+        })
+    }
+}
+
+/// Returns the e-vector in east, north, up components at `azimuth` and `elevation` with `aop`
+/// measured from the local meridian.
+fn e_vector(azimuth: Angle, elevation: Angle, aop: Angle) -> [f64; 3] {
+    let (sin_a, cos_a) = azimuth.get::<radian>().sin_cos();
+    let (sin_e, cos_e) = elevation.get::<radian>().sin_cos();
+    let (sin_p, cos_p) = aop.get::<radian>().sin_cos();
+
+    // Unit vectors along the local meridian towards the horizon and along the local parallel.
+    let meridian = [sin_e * sin_a, sin_e * cos_a, -cos_e];
+    let parallel = [cos_a, -sin_a, 0.0];
+    [0, 1, 2].map(|i| cos_p * meridian[i] - sin_p * parallel[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::{aop::Aop, dop::Dop},
+        model::SkyModel,
+    };
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use sguaba::system;
+    use uom::si::angle::degree;
+
+    system!(struct SolarEnu using ENU);
+
+    fn bearing(azimuth: f64, elevation: f64) -> Bearing<SolarEnu> {
+        Bearing::<SolarEnu>::builder()
+            .azimuth(Angle::new::<degree>(azimuth))
+            .elevation(Angle::new::<degree>(elevation))
+            .expect("elevation is on the range -90 to 90")
+            .build()
+    }
+
+    fn observations(model: &SkyModel<SolarEnu>) -> Vec<(Bearing<SolarEnu>, Ray<GlobalFrame>)> {
+        (0..36)
+            .flat_map(|i| (1..9).map(move |j| bearing(f64::from(i) * 10.0, f64::from(j) * 10.0)))
+            .filter_map(|bearing| {
+                Some((bearing, Ray::new(model.aop(bearing)?, model.dop(bearing)?)))
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(30.0, 40.0)]
+    #[case(-120.0, 10.0)]
+    #[case(200.0, 75.0)]
+    fn recovers_sun(#[case] azimuth: f64, #[case] elevation: f64) {
+        let sun = bearing(azimuth, elevation);
+        let model = SkyModel::from_solar_bearing(sun);
+        let estimate = SolarBearingFit::new()
+            .dop_weighted(true)
+            .estimate(observations(&model))
+            .unwrap();
+
+        let azimuth_error = (estimate.bearing().azimuth() - sun.azimuth()).get::<degree>();
+        assert_relative_eq!(
+            (azimuth_error + 180.0).rem_euclid(360.0) - 180.0,
+            0.0,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            estimate.bearing().elevation().get::<degree>(),
+            elevation,
+            epsilon = 1e-6
+        );
+        assert!(estimate.uncertainty().get::<degree>() < 1e-3);
+    }
+
+    #[test]
+    fn noise_increases_uncertainty() {
+        let model = SkyModel::from_solar_bearing(bearing(30.0, 40.0));
+        let noisy: Vec<_> = observations(&model)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (bearing, ray))| {
+                let offset = if i % 2 == 0 { 2.0 } else { -2.0 };
+                let aop = Angle::from(ray.aop()) + Angle::new::<degree>(offset);
+                (bearing, Ray::new(Aop::from_angle_wrapped(aop), ray.dop()))
+            })
+            .collect();
+
+        let exact = SolarBearingFit::new()
+            .estimate(observations(&model))
+            .unwrap();
+        let noisy = SolarBearingFit::new().estimate(noisy).unwrap();
+        assert!(noisy.uncertainty() > exact.uncertainty());
+        assert!(noisy.uncertainty().get::<degree>() < 1.0);
+    }
+
+    #[test]
+    fn needs_two_rays() {
+        let ray = Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(0.0)),
+            Dop::clamped(0.5),
+        );
+        assert_eq!(
+            SolarBearingFit::new().estimate([(bearing(0.0, 45.0), ray)]),
+            None
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{
+    azimuth::AzimuthConvention,
+    image::RayImage,
+    light::aop::Aop,
+    mask::SkyPatch,
+    ray::{Ray, SensorFrame, WeightedRay},
+};
+use uom::si::{angle::radian, f64::Angle};
+
+/// The solar meridian recovered from a measured AoP field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeridianEstimate {
+    azimuth: Angle,
+    coherence: f64,
+}
+
+impl MeridianEstimate {
+    /// The convention of [`MeridianEstimate::azimuth`] about the axes of the [`SensorFrame`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::EastCounterclockwise;
+
+    /// The azimuth of the solar meridian in the [`SensorFrame`] on [-90, 90].
+    ///
+    /// The azimuth is measured counterclockwise from the right of the image towards the top.
+    ///
+    /// The meridian is a line through the zenith, so the sun may lie at either `azimuth` or
+    /// `azimuth + 180`.
+    #[must_use]
+    pub fn azimuth(&self) -> Angle {
+        self.azimuth
+    }
+
+    /// The mean resultant length of the doubled AoP on [0, 1].
+    ///
+    /// Values close to one indicate a strongly aligned field.
+    /// Values close to zero indicate the estimate is unreliable.
+    #[must_use]
+    pub fn coherence(&self) -> f64 {
+        self.coherence
+    }
+}
+
+/// Estimates the solar meridian from the axis of symmetry of a measured AoP field.
+///
+/// For a camera pointing at the zenith, the Rayleigh sky is mirror symmetric about the solar
+/// meridian and the e-vectors are predominantly perpendicular to it.
+/// The principal axis of the orientation tensor of the AoP, which is half the angle of the mean
+/// doubled AoP vector, is therefore perpendicular to the meridian.
+/// This estimate is closed form and requires neither a sky model nor a search.
+///
+/// The estimate degrades when the sun is near the zenith or the camera is tilted far from it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SymmetryAxisFit {
+    dop_weighted: bool,
+}
+
+impl SymmetryAxisFit {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Weight each ray by its [`crate::light::dop::Dop`] when fitting the axis.
+    #[must_use]
+    pub fn dop_weighted(mut self, dop_weighted: bool) -> Self {
+        self.dop_weighted = dop_weighted;
+        self
+    }
+
+    /// Estimates the solar meridian from `rays`.
+    ///
+    /// Returns `None` if `rays` is empty or carries no weight.
+    pub fn estimate(
+        &self,
+        rays: impl IntoIterator<Item = Ray<SensorFrame>>,
+    ) -> Option<MeridianEstimate> {
+        self.estimate_weighted(rays.into_iter().map(WeightedRay::from))
+    }
+
+    /// Estimates the solar meridian from `rays` scaled by their
+    /// [`WeightedRay::effective_weight`].
+    ///
+    /// Invalid rays are skipped.
+    ///
+    /// Returns `None` if `rays` is empty or carries no weight.
+    pub fn estimate_weighted(
+        &self,
+        rays: impl IntoIterator<Item = WeightedRay<SensorFrame>>,
+    ) -> Option<MeridianEstimate> {
+        let (mut cos_sum, mut sin_sum, mut weight_sum) = (0.0, 0.0, 0.0);
+        for weighted in rays.into_iter().filter(WeightedRay::is_valid) {
+            let ray = weighted.ray();
+            let weight = if self.dop_weighted {
+                weighted.weight() * f64::from(ray.dop())
+            } else {
+                weighted.weight()
+            };
+            let (sin, cos) = doubled(&ray);
+            cos_sum += weight * cos;
+            sin_sum += weight * sin;
+            weight_sum += weight;
+        }
+
+        from_resultant(cos_sum, sin_sum, weight_sum)
+    }
+
+    /// Estimates the solar meridian from the rays of `image` inside `patches`.
+    ///
+    /// When only small patches of sky are visible, e.g., in an urban canyon, each patch is fit
+    /// on its own and contributes its mean doubled AoP weighted by the number of rays it holds.
+    /// If the fit is [`SymmetryAxisFit::dop_weighted`], the weight is also scaled by the mean
+    /// [`crate::light::dop::Dop`] of the patch.
+    /// Pixels outside of `image` are ignored.
+    ///
+    /// Returns `None` if the patches hold no rays or carry no weight.
+    pub fn estimate_patches<'a>(
+        &self,
+        image: &RayImage<SensorFrame>,
+        patches: impl IntoIterator<Item = &'a SkyPatch>,
+    ) -> Option<MeridianEstimate> {
+        let (mut cos_sum, mut sin_sum, mut weight_sum) = (0.0, 0.0, 0.0);
+        for patch in patches {
+            let (mut cos_patch, mut sin_patch, mut dop_patch, mut count) = (0.0, 0.0, 0.0, 0);
+            let rays = patch
+                .pixels()
+                .iter()
+                .filter(|pixel| pixel.row() < image.rows() && pixel.col() < image.cols())
+                .filter_map(|pixel| image.ray(pixel.row(), pixel.col()));
+            for ray in rays {
+                let (sin, cos) = doubled(ray);
+                cos_patch += cos;
+                sin_patch += sin;
+                dop_patch += f64::from(ray.dop());
+                count += 1;
+            }
+
+            if count == 0 {
+                continue;
+            }
+
+            let count = f64::from(count);
+            let weight = if self.dop_weighted { dop_patch } else { count };
+            cos_sum += weight * cos_patch / count;
+            sin_sum += weight * sin_patch / count;
+            weight_sum += weight;
+        }
+
+        from_resultant(cos_sum, sin_sum, weight_sum)
+    }
+}
+
+/// Returns the sine and cosine of the doubled AoP of `ray`.
+fn doubled(ray: &Ray<SensorFrame>) -> (f64, f64) {
+    (2.0 * Angle::from(ray.aop()).get::<radian>()).sin_cos()
+}
+
+/// Recovers the meridian from the weighted sum of doubled AoP vectors.
+fn from_resultant(cos_sum: f64, sin_sum: f64, weight_sum: f64) -> Option<MeridianEstimate> {
+    if weight_sum <= 0.0 {
+        return None;
+    }
+
+    let axis = Angle::new::<radian>(sin_sum.atan2(cos_sum) / 2.0);
+    Some(MeridianEstimate {
+        azimuth: Aop::<SensorFrame>::from_angle_wrapped(axis + Angle::HALF_TURN / 2.0).into(),
+        coherence: (cos_sum.powi(2) + sin_sum.powi(2)).sqrt() / weight_sum,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{light::dop::Dop, optic::PixelCoordinate, ray::RayFlags};
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    /// Samples the Rayleigh e-vector field seen by a zenith pointing camera.
+    fn zenith_field(solar_azimuth: f64, solar_elevation: f64) -> Vec<Ray<SensorFrame>> {
+        let (az, el) = (solar_azimuth.to_radians(), solar_elevation.to_radians());
+        let sun = [el.cos() * az.cos(), el.cos() * az.sin(), el.sin()];
+
+        (1..=6)
+            .flat_map(|ring| {
+                (0..360).map(move |step| {
+                    let polar = f64::from(ring) * 10.0_f64.to_radians();
+                    let azimuth = f64::from(step).to_radians();
+                    [
+                        polar.sin() * azimuth.cos(),
+                        polar.sin() * azimuth.sin(),
+                        polar.cos(),
+                    ]
+                })
+            })
+            .map(|view| {
+                // The e-vector is perpendicular to the scattering plane.
+                let e = [
+                    sun[1] * view[2] - sun[2] * view[1],
+                    sun[2] * view[0] - sun[0] * view[2],
+                    sun[0] * view[1] - sun[1] * view[0],
+                ];
+                Ray::new(
+                    Aop::from_angle_wrapped(Angle::new::<radian>(e[1].atan2(e[0]))),
+                    Dop::clamped(0.5),
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(30.0, 40.0)]
+    #[case(-60.0, 20.0)]
+    #[case(170.0, 55.0)]
+    fn recovers_meridian(#[case] solar_azimuth: f64, #[case] solar_elevation: f64) {
+        let estimate = SymmetryAxisFit::new()
+            .dop_weighted(true)
+            .estimate(zenith_field(solar_azimuth, solar_elevation))
+            .unwrap();
+
+        let expected = Aop::<SensorFrame>::from_angle_wrapped(Angle::new::<degree>(solar_azimuth));
+        let result = Aop::<SensorFrame>::from_angle_wrapped(estimate.azimuth());
+        assert_relative_eq!(
+            result.angular_distance(&expected).get::<degree>(),
+            0.0,
+            epsilon = 0.1
+        );
+        assert!(estimate.coherence() > 0.2);
+    }
+
+    #[test]
+    fn coherence_drops_near_zenith() {
+        let fit = SymmetryAxisFit::new();
+        let low = fit.estimate(zenith_field(0.0, 20.0)).unwrap();
+        let high = fit.estimate(zenith_field(0.0, 70.0)).unwrap();
+
+        assert!(low.coherence() > high.coherence());
+    }
+
+    #[test]
+    fn skips_flagged_rays() {
+        let clear = zenith_field(30.0, 40.0).into_iter().map(WeightedRay::from);
+        // Cloud corrupts a second field that would pull the meridian to -60 degrees.
+        let cloud = zenith_field(-60.0, 40.0)
+            .into_iter()
+            .map(|ray| WeightedRay::new(ray).with_flags(RayFlags::CLOUD));
+
+        let estimate = SymmetryAxisFit::new()
+            .estimate_weighted(clear.chain(cloud))
+            .unwrap();
+        let expected = Aop::<SensorFrame>::from_angle_wrapped(Angle::new::<degree>(30.0));
+        let result = Aop::<SensorFrame>::from_angle_wrapped(estimate.azimuth());
+        assert_relative_eq!(
+            result.angular_distance(&expected).get::<degree>(),
+            0.0,
+            epsilon = 0.1
+        );
+    }
+
+    #[test]
+    fn empty_has_no_estimate() {
+        assert_eq!(SymmetryAxisFit::new().estimate(Vec::new()), None);
+    }
+
+    #[test]
+    fn recovers_meridian_from_patches() {
+        let rays = zenith_field(30.0, 40.0);
+        let image = RayImage::from_rays(rays.into_iter().map(Some), 6, 360).unwrap();
+        // Two sectors that are each symmetric about the solar meridian.
+        let sector =
+            |cols: std::ops::Range<usize>| {
+                SkyPatch::new((0..6).flat_map(move |row| {
+                    cols.clone().map(move |col| PixelCoordinate::new(row, col))
+                }))
+            };
+        let patches = [sector(10..51), sector(190..231)];
+
+        let estimate = SymmetryAxisFit::new()
+            .dop_weighted(true)
+            .estimate_patches(&image, &patches)
+            .unwrap();
+        let expected = Aop::<SensorFrame>::from_angle_wrapped(Angle::new::<degree>(30.0));
+        let result = Aop::<SensorFrame>::from_angle_wrapped(estimate.azimuth());
+        assert_relative_eq!(
+            result.angular_distance(&expected).get::<degree>(),
+            0.0,
+            epsilon = 0.1
+        );
+        assert_eq!(
+            SymmetryAxisFit::new().estimate_patches(&image, &[SkyPatch::default()]),
+            None
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use super::perpendicular_axis;
+use crate::{
+    image::S0Image,
+    optic::{Camera, Optic, PixelCoordinate},
+};
+use sguaba::engineering::Orientation;
+use uom::si::{angle::radian, f64::Angle};
+
+/// The roll and pitch of a camera recovered from the horizon.
+///
+/// Angles follow the Tait-Bryan convention of a forward, right, down body whose forward axis is
+/// the optical axis and whose down axis points towards the bottom of the image.
+/// Pitch is positive when the optical axis is above the horizon and roll is positive when the
+/// camera is rotated clockwise about the optical axis, which turns the horizon counterclockwise
+/// in the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TiltEstimate {
+    roll: Angle,
+    pitch: Angle,
+    uncertainty: Angle,
+}
+
+impl TiltEstimate {
+    #[must_use]
+    pub fn roll(&self) -> Angle {
+        self.roll
+    }
+
+    #[must_use]
+    pub fn pitch(&self) -> Angle {
+        self.pitch
+    }
+
+    /// A one standard deviation uncertainty of the direction of the zenith.
+    ///
+    /// It is derived from how far the horizon strays from a great circle and assumes the errors
+    /// of each column are independent.
+    #[must_use]
+    pub fn uncertainty(&self) -> Angle {
+        self.uncertainty
+    }
+
+    /// Combines the tilt with a `yaw`, e.g., the [`HeadingEstimate::heading`] of a polarization
+    /// estimator, into the full orientation of the forward, right, down body of the camera.
+    ///
+    /// `In` should be a north, east, down system for the angles to keep their usual meaning.
+    ///
+    /// [`HeadingEstimate::heading`]: super::fusion::HeadingEstimate::heading
+    #[must_use]
+    pub fn orientation<In>(&self, yaw: Angle) -> Orientation<In> {
+        Orientation::<In>::tait_bryan_builder()
+            .yaw(yaw)
+            .pitch(self.pitch)
+            .roll(self.roll)
+            .build()
+    }
+}
+
+/// Estimates the roll and pitch of a camera from the horizon in its total intensity.
+///
+/// Polarization constrains the heading of a camera well but its tilt poorly.
+/// Ground vehicles often see the horizon, which is the boundary between the bright sky above and
+/// the darker ground below.
+/// In each column, the horizon is taken as the largest drop in total intensity from one row to
+/// the next, and columns whose drop is weaker than a fraction of the strongest are discarded.
+/// Each edge is traced through the [`Camera`], and the zenith is the direction most
+/// perpendicular to all of them, which is the eigenvector with the smallest eigenvalue of their
+/// orientation tensor.
+///
+/// The horizon is assumed to be distant and level, so the dip of the horizon from an elevated
+/// camera and sloped terrain bias the estimate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TiltFit {
+    edge_threshold: f64,
+    min_columns: usize,
+}
+
+impl Default for TiltFit {
+    fn default() -> Self {
+        Self {
+            edge_threshold: 0.5,
+            min_columns: 8,
+        }
+    }
+}
+
+impl TiltFit {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the fraction of the strongest edge that the edge of a column must reach to be used.
+    #[must_use]
+    pub fn with_edge_threshold(mut self, edge_threshold: f64) -> Self {
+        self.edge_threshold = edge_threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the fewest columns that must see the horizon.
+    #[must_use]
+    pub fn with_min_columns(mut self, min_columns: usize) -> Self {
+        self.min_columns = min_columns.max(2);
+        self
+    }
+
+    #[must_use]
+    pub fn edge_threshold(&self) -> f64 {
+        self.edge_threshold
+    }
+
+    #[must_use]
+    pub fn min_columns(&self) -> usize {
+        self.min_columns
+    }
+
+    /// Estimates the tilt of `camera` from `s0` captured by it.
+    ///
+    /// Returns `None` if the extents of `s0` do not match the sensor, fewer than the minimum
+    /// number of columns see the horizon, or the edges do not constrain the zenith, e.g., if
+    /// they all lie in one column.
+    #[must_use]
+    pub fn estimate<O: Optic>(&self, s0: &S0Image, camera: &Camera<O>) -> Option<TiltEstimate> {
+        let (rows, cols) = (s0.rows(), s0.cols());
+        if (rows, cols) != (camera.sensor().rows(), camera.sensor().cols()) || rows < 2 {
+            return None;
+        }
+
+        // The largest drop in intensity from each row to the next in each column.
+        let values = s0.values();
+        let edges: Vec<(usize, usize, f64)> = (0..cols)
+            .filter_map(|col| {
+                (0..rows - 1)
+                    .map(|row| {
+                        (
+                            row,
+                            col,
+                            values[row * cols + col] - values[(row + 1) * cols + col],
+                        )
+                    })
+                    .filter(|(_, _, drop)| drop.is_finite() && *drop > 0.0)
+                    .max_by(|(_, _, lhs), (_, _, rhs)| lhs.total_cmp(rhs))
+            })
+            .collect();
+        let strongest = edges.iter().map(|(_, _, drop)| *drop).fold(0.0, f64::max);
+
+        let mut tensor = [[0.0; 3]; 3];
+        let mut sky = [0.0; 3];
+        let mut count = 0;
+        for &(row, col, drop) in &edges {
+            if drop < self.edge_threshold * strongest {
+                continue;
+            }
+
+            // The edge lies between the centers of the two rows.
+            // Columns whose edge cannot be traced are skipped like weak edges.
+            let (Some(above), Some(below)) = (
+                direction(camera, PixelCoordinate::new(row, col)),
+                direction(camera, PixelCoordinate::new(row + 1, col)),
+            ) else {
+                continue;
+            };
+            let edge = normalize([0, 1, 2].map(|i| above[i] + below[i]));
+            for (i, tensor_row) in tensor.iter_mut().enumerate() {
+                for (j, cell) in tensor_row.iter_mut().enumerate() {
+                    *cell += edge[i] * edge[j];
+                }
+            }
+            for (total, component) in sky.iter_mut().zip(above) {
+                *total += component;
+            }
+            count += 1;
+        }
+
+        if count < self.min_columns {
+            return None;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let (mut up, uncertainty) = perpendicular_axis(tensor, count as f64)?;
+
+        // The zenith and nadir share a tensor so pick the one on the side of the sky.
+        if up.iter().zip(sky).map(|(u, s)| u * s).sum::<f64>() < 0.0 {
+            up = up.map(|component| -component);
+        }
+
+        // The camera frame has X to the right of the image, Y to the top, and Z towards the
+        // viewer, so forward is -Z, right is X, and down is -Y.
+        let [x, y, z] = up;
+        Some(TiltEstimate {
+            roll: Angle::new::<radian>((-x).atan2(y)),
+            pitch: Angle::new::<radian>((-z).clamp(-1.0, 1.0).asin()),
+            uncertainty,
+        })
+    }
+}
+
+/// Returns the unit vector in the camera frame that `pixel` is traced to.
+fn direction<O: Optic>(camera: &Camera<O>, pixel: PixelCoordinate) -> Option<[f64; 3]> {
+    let direction = camera.trace_from_pixel(pixel)?;
+    let (sin_p, cos_p) = direction.polar().get::<radian>().sin_cos();
+    let (sin_a, cos_a) = direction.azimuth().get::<radian>().sin_cos();
+    Some([sin_p * cos_a, sin_p * sin_a, cos_p])
+}
+
+fn normalize(vector: [f64; 3]) -> [f64; 3] {
+    let norm = vector.iter().map(|c| c * c).sum::<f64>().sqrt();
+    vector.map(|c| c / norm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{image::IntensityImage, optic::PinholeOptic};
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use sguaba::system;
+    use uom::si::{
+        angle::degree,
+        f64::Length,
+        length::{micron, millimeter},
+    };
+
+    system!(struct TiltNed using NED);
+
+    fn camera() -> Camera<PinholeOptic> {
+        Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(4.0)),
+            Length::new::<micron>(20.0),
+            120,
+            160,
+        )
+    }
+
+    /// Renders a bright sky above a dark ground for a camera with `roll` and `pitch` in degrees.
+    fn horizon(camera: &Camera<PinholeOptic>, roll: f64, pitch: f64) -> S0Image {
+        let (sin_r, cos_r) = roll.to_radians().sin_cos();
+        let (sin_p, cos_p) = pitch.to_radians().sin_cos();
+        // The zenith in forward, right, down components converted to the camera frame.
+        let up = [-sin_r * cos_p, cos_r * cos_p, -sin_p];
+
+        let intensities: Vec<_> = camera
+            .pixels()
+            .map(|pixel| {
+                let d = direction(camera, pixel).unwrap();
+                let elevation: f64 = d.iter().zip(up).map(|(d, u)| d * u).sum();
+                [if elevation > 0.0 { 100.0 } else { 30.0 }; 4]
+            })
+            .collect();
+        IntensityImage::from_intensities(160, 120, intensities)
+            .unwrap()
+            .s0_image()
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0)]
+    #[case(10.0, 0.0)]
+    #[case(-5.0, 8.0)]
+    #[case(3.0, -12.0)]
+    fn recovers_tilt(#[case] roll: f64, #[case] pitch: f64) {
+        let camera = camera();
+        let estimate = TiltFit::new()
+            .estimate(&horizon(&camera, roll, pitch), &camera)
+            .unwrap();
+
+        assert_relative_eq!(estimate.roll().get::<degree>(), roll, epsilon = 0.5);
+        assert_relative_eq!(estimate.pitch().get::<degree>(), pitch, epsilon = 0.5);
+        assert!(estimate.uncertainty().get::<degree>() < 1.0);
+
+        let (yaw, pitch_out, roll_out) = estimate
+            .orientation::<TiltNed>(Angle::new::<degree>(40.0))
+            .to_tait_bryan_angles();
+        assert_relative_eq!(yaw.get::<degree>(), 40.0, epsilon = 1e-6);
+        assert_relative_eq!(pitch_out.value, estimate.pitch().value, epsilon = 1e-9);
+        assert_relative_eq!(roll_out.value, estimate.roll().value, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn needs_horizon() {
+        let camera = camera();
+        let sky = IntensityImage::from_intensities(160, 120, vec![[100.0; 4]; 160 * 120])
+            .unwrap()
+            .s0_image();
+        assert_eq!(TiltFit::new().estimate(&sky, &camera), None);
+
+        // The horizon is out of view when looking far up.
+        assert_eq!(
+            TiltFit::new().estimate(&horizon(&camera, 0.0, 60.0), &camera),
+            None
+        );
+    }
+}
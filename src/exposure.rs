@@ -0,0 +1,197 @@
+//! Exposure control from the intensities of sky pixels.
+
+use crate::image::IntensityImage;
+
+/// Recommends exposure changes that use the dynamic range of a sensor without saturating it.
+///
+/// Sky pixels are metapixels whose brightest intensity exceeds a fraction of the saturation
+/// level, which excludes the dark border of fisheye lenses and obstructions.
+/// The recommended scale brings a high percentile of sky intensities to a target fraction of the
+/// saturation level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExposureAdvisor {
+    saturation_level: f64,
+    target: f64,
+    percentile: f64,
+    sky_threshold: f64,
+    saturated_scale: f64,
+}
+
+impl ExposureAdvisor {
+    /// Creates a new `ExposureAdvisor` for a sensor that saturates at `saturation_level` e.g., 255
+    /// for an 8 bit sensor.
+    ///
+    /// By default, the 99th percentile of sky intensities is brought to 90% of the saturation
+    /// level and pixels below 5% of the saturation level are not sky.
+    #[must_use]
+    pub fn new(saturation_level: f64) -> Self {
+        Self {
+            saturation_level,
+            target: 0.9,
+            percentile: 99.0,
+            sky_threshold: 0.05,
+            saturated_scale: 0.5,
+        }
+    }
+
+    /// Sets the fraction of the saturation level targeted by the percentile.
+    #[must_use]
+    pub fn with_target(mut self, target: f64) -> Self {
+        self.target = target.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the percentile of sky intensities that is brought to the target.
+    #[must_use]
+    pub fn with_percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile.clamp(0.0, 100.0);
+        self
+    }
+
+    /// Sets the fraction of the saturation level below which metapixels are not sky.
+    #[must_use]
+    pub fn with_sky_threshold(mut self, sky_threshold: f64) -> Self {
+        self.sky_threshold = sky_threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the largest scale recommended when the percentile is saturated.
+    ///
+    /// The true intensity of saturated pixels is unknown so the exposure is reduced by at least
+    /// this factor.
+    #[must_use]
+    pub fn with_saturated_scale(mut self, saturated_scale: f64) -> Self {
+        self.saturated_scale = saturated_scale;
+        self
+    }
+
+    /// Analyzes the sky pixels of `image`.
+    ///
+    /// Returns `None` if `image` has no sky pixels or the percentile of sky intensities is not
+    /// positive, e.g., with a sky threshold of zero, since no scale brings it to the target.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn recommend(&self, image: &IntensityImage) -> Option<ExposureRecommendation> {
+        let total = image.intensities().len();
+        let mut sky: Vec<f64> = image
+            .intensities()
+            .map(|px| px.into_iter().fold(f64::NEG_INFINITY, f64::max))
+            .filter(|max| *max >= self.sky_threshold * self.saturation_level)
+            .collect();
+        if sky.is_empty() {
+            return None;
+        }
+
+        sky.sort_by(f64::total_cmp);
+        let rank = (self.percentile / 100.0 * (sky.len() - 1) as f64).round() as usize;
+        let intensity = sky[rank];
+        if intensity <= 0.0 {
+            return None;
+        }
+        let saturated = sky.partition_point(|i| *i < self.saturation_level);
+
+        let scale = self.target * self.saturation_level / intensity;
+        let scale = if intensity >= self.saturation_level {
+            scale.min(self.saturated_scale)
+        } else {
+            scale
+        };
+
+        Some(ExposureRecommendation {
+            scale,
+            intensity,
+            saturated_fraction: (sky.len() - saturated) as f64 / sky.len() as f64,
+            sky_fraction: sky.len() as f64 / total as f64,
+        })
+    }
+}
+
+/// The result of [`ExposureAdvisor::recommend`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExposureRecommendation {
+    scale: f64,
+    intensity: f64,
+    saturated_fraction: f64,
+    sky_fraction: f64,
+}
+
+impl ExposureRecommendation {
+    /// Returns the factor to multiply the current exposure time or gain by.
+    #[must_use]
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Returns the intensity at the percentile of sky pixels.
+    #[must_use]
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Returns the fraction of sky pixels with a saturated intensity.
+    #[must_use]
+    pub fn saturated_fraction(&self) -> f64 {
+        self.saturated_fraction
+    }
+
+    /// Returns the fraction of metapixels that are sky.
+    #[must_use]
+    pub fn sky_fraction(&self) -> f64 {
+        self.sky_fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+
+    /// Builds an image of metapixels that each have the same intensity on every channel.
+    fn image(levels: &[u8]) -> IntensityImage {
+        let bytes: Vec<u8> = [levels, levels]
+            .concat()
+            .into_iter()
+            .flat_map(|level| [level, level])
+            .collect();
+        IntensityImage::from_bytes(levels.len() * 2, 2, &bytes).unwrap()
+    }
+
+    #[rstest]
+    #[case(&[0, 50, 100, 100], 2.295)]
+    #[case(&[0, 200, 240, 255], 0.5)]
+    fn recommends_scale(#[case] levels: &[u8], #[case] expected: f64) {
+        let recommendation = ExposureAdvisor::new(255.0)
+            .with_percentile(100.0)
+            .recommend(&image(levels))
+            .unwrap();
+
+        assert_relative_eq!(recommendation.scale(), expected, epsilon = 1e-9);
+        assert_relative_eq!(recommendation.sky_fraction(), 0.75);
+    }
+
+    #[test]
+    fn counts_saturated_sky() {
+        let recommendation = ExposureAdvisor::new(255.0)
+            .recommend(&image(&[255, 255, 100, 100]))
+            .unwrap();
+        assert_relative_eq!(recommendation.saturated_fraction(), 0.5);
+        assert!(recommendation.scale() <= 0.5);
+    }
+
+    #[test]
+    fn dark_image_has_no_sky() {
+        assert_eq!(ExposureAdvisor::new(255.0).recommend(&image(&[0, 5])), None);
+    }
+
+    #[test]
+    fn black_sky_has_no_scale() {
+        let advisor = ExposureAdvisor::new(255.0).with_sky_threshold(0.0);
+        assert_eq!(advisor.recommend(&image(&[0, 0])), None);
+        assert!(advisor.recommend(&image(&[0, 10])).is_some());
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
+    error::Error,
     iter::RayIterator,
     light::{aop::Aop, dop::Dop},
-    ray::Ray,
+    ray::{Ray, RayFlags, WeightedRay},
 };
-use uom::si::f64::Angle;
+use uom::si::{angle::radian, f64::Angle};
 
 /// A predicate over a ray.
 ///
@@ -26,6 +27,24 @@ impl<Frame> AopFilter<Frame> {
     pub fn new(center: Aop<Frame>, thres: Angle) -> Self {
         Self { center, thres }
     }
+
+    /// Creates an `AopFilter` that holds on the band of angles from `low` counterclockwise to
+    /// `high`.
+    ///
+    /// The band may wrap across the +/- 90 degree boundary e.g., from 80 to -80 degrees.
+    #[must_use]
+    pub fn from_bounds(low: Aop<Frame>, high: Aop<Frame>) -> Self {
+        let low = Angle::from(low);
+        let width = Angle::new::<radian>(
+            (Angle::from(high) - low)
+                .get::<radian>()
+                .rem_euclid(std::f64::consts::PI),
+        );
+        Self {
+            center: Aop::from_angle_wrapped(low + width / 2.0),
+            thres: width / 2.0,
+        }
+    }
 }
 
 impl<Frame: Copy> RayPredicate<Frame> for AopFilter<Frame> {
@@ -34,23 +53,71 @@ impl<Frame: Copy> RayPredicate<Frame> for AopFilter<Frame> {
     }
 }
 
-/// A predicate that holds on rays with `Dop >= min`.
+/// A predicate that holds on rays with `min <= Dop <= max`.
 pub struct DopFilter {
     min: Dop,
+    max: Dop,
 }
 
 impl DopFilter {
+    /// Creates a `DopFilter` that holds on rays with `Dop >= min`.
     #[must_use]
-    pub fn new(min: f64) -> Self {
+    pub fn from_min(min: Dop) -> Self {
         Self {
-            min: Dop::clamped(min),
+            min,
+            max: Dop::clamped(1.0),
+        }
+    }
+
+    /// Creates a `DopFilter` that holds on rays with `min <= Dop <= max`.
+    ///
+    /// Unusually high degrees of polarization often come from specular reflections or
+    /// saturated pixels.
+    ///
+    /// # Errors
+    /// Will return `Err` if `min` is greater than `max`.
+    pub fn from_bounds(min: Dop, max: Dop) -> Result<Self, Error> {
+        if min > max {
+            return Err(Error::EmptyRange);
+        }
+        Ok(Self { min, max })
+    }
+
+    /// Creates a `DopFilter` that holds on rays with `Dop >= min`.
+    #[must_use]
+    #[deprecated(note = "use `DopFilter::from_min` with a `Dop`")]
+    pub fn new(min: f64) -> Self {
+        Self::from_min(Dop::clamped(min))
+    }
+
+    /// Sets an upper bound on the `Dop` of rays.
+    #[must_use]
+    #[deprecated(note = "use `DopFilter::from_bounds` with a `Dop`")]
+    pub fn with_max(mut self, max: f64) -> Self {
+        self.max = Dop::clamped(max);
+        self
+    }
+
+    /// Raises [`RayFlags::LOW_DOP`] or [`RayFlags::HIGH_DOP`] on `ray` if its `Dop` is outside
+    /// of the bounds of the filter.
+    ///
+    /// Unlike [`RayFilter`], the ray is kept so that estimators can account for it.
+    #[must_use]
+    pub fn flag<Frame: Copy>(&self, ray: WeightedRay<Frame>) -> WeightedRay<Frame> {
+        let dop = ray.ray().dop();
+        if dop < self.min {
+            ray.with_flags(RayFlags::LOW_DOP)
+        } else if dop > self.max {
+            ray.with_flags(RayFlags::HIGH_DOP)
+        } else {
+            ray
         }
     }
 }
 
 impl<Frame> RayPredicate<Frame> for DopFilter {
     fn eval(&self, ray: &Ray<Frame>) -> bool {
-        self.min <= ray.dop()
+        self.min <= ray.dop() && ray.dop() <= self.max
     }
 }
 
@@ -90,3 +157,140 @@ where
     P: RayPredicate<Frame>,
 {
 }
+
+/// An iterator that clamps the `Dop` of rays from `iter` onto `[min, max]`.
+///
+/// See [`RayIterator::clamp_dop`].
+pub struct DopClamp<I> {
+    iter: I,
+    min: Dop,
+    max: Dop,
+}
+
+impl<I> DopClamp<I> {
+    /// Creates a `DopClamp` that clamps the `Dop` of rays from `iter` onto `[min, max]`.
+    ///
+    /// # Errors
+    /// Will return `Err` if `min` is greater than `max`.
+    pub fn from_bounds(iter: I, min: Dop, max: Dop) -> Result<Self, Error> {
+        if min > max {
+            return Err(Error::EmptyRange);
+        }
+        Ok(Self { iter, min, max })
+    }
+
+    /// Creates a `DopClamp` that clamps the `Dop` of rays from `iter` onto `[min, max]`.
+    ///
+    /// Inverted bounds are swapped.
+    #[must_use]
+    #[deprecated(note = "use `DopClamp::from_bounds` with a `Dop`")]
+    pub fn new(iter: I, min: f64, max: f64) -> Self {
+        Self {
+            iter,
+            min: Dop::clamped(min.min(max)),
+            max: Dop::clamped(min.max(max)),
+        }
+    }
+}
+
+impl<I, Frame> Iterator for DopClamp<I>
+where
+    I: Iterator<Item = Ray<Frame>>,
+    Frame: Copy,
+{
+    type Item = Ray<Frame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let ray = self.iter.next()?;
+        let degree =
+            Dop::clamped(f64::from(ray.dop()).clamp(f64::from(self.min), f64::from(self.max)));
+        Some(Ray::new(ray.aop(), degree))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+// All of RayIterator's functions are defined using Iterator.
+impl<I, Frame> RayIterator<Frame> for DopClamp<I>
+where
+    I: Iterator<Item = Ray<Frame>>,
+    Frame: Copy,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::SensorFrame;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    fn ray(aop: f64, dop: f64) -> Ray<SensorFrame> {
+        Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+            Dop::clamped(dop),
+        )
+    }
+
+    #[rstest]
+    #[case(0.1, false)]
+    #[case(0.2, true)]
+    #[case(0.5, true)]
+    #[case(0.8, true)]
+    #[case(0.9, false)]
+    fn dop_range(#[case] dop: f64, #[case] expected: bool) {
+        let filter = DopFilter::from_bounds(Dop::clamped(0.2), Dop::clamped(0.8)).unwrap();
+        assert_eq!(filter.eval(&ray(0.0, dop)), expected);
+    }
+
+    #[test]
+    fn flags_dop_range() {
+        let filter = DopFilter::from_bounds(Dop::clamped(0.2), Dop::clamped(0.8)).unwrap();
+        let flags = |dop| filter.flag(WeightedRay::new(ray(0.0, dop))).flags();
+        assert_eq!(flags(0.1), RayFlags::LOW_DOP);
+        assert_eq!(flags(0.5), RayFlags::NONE);
+        assert_eq!(flags(0.9), RayFlags::HIGH_DOP);
+    }
+
+    #[test]
+    fn rejects_inverted_dop_range() {
+        assert!(matches!(
+            DopFilter::from_bounds(Dop::clamped(0.8), Dop::clamped(0.2)),
+            Err(Error::EmptyRange)
+        ));
+        assert!(DopFilter::from_bounds(Dop::clamped(0.5), Dop::clamped(0.5)).is_ok());
+    }
+
+    #[rstest]
+    #[case(-10.0, 10.0, 0.0, true)]
+    #[case(-10.0, 10.0, 20.0, false)]
+    #[case(80.0, -80.0, 89.0, true)]
+    #[case(80.0, -80.0, -85.0, true)]
+    #[case(80.0, -80.0, 0.0, false)]
+    fn aop_band(#[case] low: f64, #[case] high: f64, #[case] aop: f64, #[case] expected: bool) {
+        let filter = AopFilter::from_bounds(
+            Aop::from_angle_wrapped(Angle::new::<degree>(low)),
+            Aop::from_angle_wrapped(Angle::new::<degree>(high)),
+        );
+        assert_eq!(filter.eval(&ray(aop, 0.5)), expected);
+    }
+
+    #[test]
+    fn dop_clamp() {
+        let rays = [ray(10.0, 0.05), ray(20.0, 0.5), ray(30.0, 0.95)];
+        let result: Vec<_> =
+            DopClamp::from_bounds(rays.into_iter(), Dop::clamped(0.1), Dop::clamped(0.9))
+                .unwrap()
+                .collect();
+        assert_eq!(result, [ray(10.0, 0.1), ray(20.0, 0.5), ray(30.0, 0.9)]);
+
+        assert!(matches!(
+            DopClamp::from_bounds(rays.into_iter(), Dop::clamped(0.9), Dop::clamped(0.1)),
+            Err(Error::EmptyRange)
+        ));
+        #[allow(deprecated)]
+        let result: Vec<_> = DopClamp::new(rays.into_iter(), 0.9, 0.1).collect();
+        assert_eq!(result, [ray(10.0, 0.1), ray(20.0, 0.5), ray(30.0, 0.9)]);
+    }
+}
@@ -0,0 +1,252 @@
+//! High dynamic range fusion of bracketed exposures.
+
+use crate::image::{ImageError, IntensityImage};
+use thiserror::Error;
+use uom::si::time::second;
+
+#[derive(Debug, Error)]
+pub enum HdrError {
+    #[error("exposure fusion requires at least one image")]
+    Empty,
+
+    #[error("image {index} has no exposure time")]
+    MissingExposure { index: usize },
+
+    #[error("image {index} has a relative exposure of {exposure} which is not positive")]
+    InvalidExposure { index: usize, exposure: f64 },
+
+    #[error(
+        "image {index} has extents {height}x{width} but the bracket has {expected_height}x{expected_width}"
+    )]
+    ExtentsMismatch {
+        index: usize,
+        expected_width: usize,
+        expected_height: usize,
+        width: usize,
+        height: usize,
+    },
+
+    #[error(transparent)]
+    Image(#[from] ImageError),
+}
+
+/// Merges bracketed [`IntensityImage`]s of the same scene into one high dynamic range image.
+///
+/// The circumsolar sky saturates long exposures while the horizon is lost in the noise of short
+/// ones.
+/// Each of the four intensities of a metapixel is merged separately as a weighted mean of the
+/// intensities of every exposure, divided by their relative exposures.
+/// Weights fall off linearly from the middle of the range of the sensor towards both ends and
+/// saturated intensities are discarded.
+/// Merging before the Stokes vectors are computed keeps the channels of a metapixel on the same
+/// radiometric scale, so the fused image yields rays like any other [`IntensityImage`].
+///
+/// The relative exposure of each image is its exposure time multiplied by its gain, taken from
+/// its [`FrameMeta`](crate::meta::FrameMeta).
+/// A missing gain is treated as one.
+/// Images must be registered, e.g., from a camera that does not move between exposures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExposureFusion {
+    saturation_level: f64,
+    min_weight: f64,
+}
+
+impl ExposureFusion {
+    /// Creates a new `ExposureFusion` for a sensor that saturates at `saturation_level` e.g., 255
+    /// for an 8 bit sensor.
+    #[must_use]
+    pub fn new(saturation_level: f64) -> Self {
+        Self {
+            saturation_level,
+            min_weight: 1e-3,
+        }
+    }
+
+    /// Sets the weight of unsaturated intensities at either end of the range of the sensor.
+    ///
+    /// A small positive weight keeps dark intensities from being discarded when no exposure
+    /// places them near the middle of the range.
+    #[must_use]
+    pub fn with_min_weight(mut self, min_weight: f64) -> Self {
+        self.min_weight = min_weight.clamp(0.0, 1.0);
+        self
+    }
+
+    #[must_use]
+    pub fn saturation_level(&self) -> f64 {
+        self.saturation_level
+    }
+
+    #[must_use]
+    pub fn min_weight(&self) -> f64 {
+        self.min_weight
+    }
+
+    /// Fuses `images` into one image on the scale of the shortest exposure.
+    ///
+    /// Fused intensities may exceed the saturation level.
+    /// If an intensity is saturated in every exposure, the shortest exposure is kept as a lower
+    /// bound.
+    /// The calibration, normalization, and metadata are taken from the shortest exposure.
+    ///
+    /// # Errors
+    /// Will return `Err` if `images` is empty, an image has no exposure time, the relative
+    /// exposure of an image is not positive, or the extents of the images do not match.
+    pub fn fuse<'a>(
+        &self,
+        images: impl IntoIterator<Item = &'a IntensityImage>,
+    ) -> Result<IntensityImage, HdrError> {
+        let bracket = images
+            .into_iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let meta = image.meta();
+                let exposure = meta.exposure().ok_or(HdrError::MissingExposure { index })?;
+                let exposure = exposure.get::<second>() * meta.gain().unwrap_or(1.0);
+                if !(exposure.is_finite() && exposure > 0.0) {
+                    return Err(HdrError::InvalidExposure { index, exposure });
+                }
+                Ok((image, exposure))
+            })
+            .collect::<Result<Vec<_>, HdrError>>()?;
+
+        let (reference, reference_exposure) = *bracket
+            .iter()
+            .min_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs))
+            .ok_or(HdrError::Empty)?;
+        let (width, height) = (reference.width(), reference.height());
+        for (index, (image, _)) in bracket.iter().enumerate() {
+            if (image.width(), image.height()) != (width, height) {
+                return Err(HdrError::ExtentsMismatch {
+                    index,
+                    expected_width: width,
+                    expected_height: height,
+                    width: image.width(),
+                    height: image.height(),
+                });
+            }
+        }
+
+        let mut sums = vec![[0.0; 4]; width * height];
+        let mut weights = vec![[0.0; 4]; width * height];
+        for (image, exposure) in &bracket {
+            let scale = reference_exposure / exposure;
+            for ((sum, weight), intensities) in
+                sums.iter_mut().zip(&mut weights).zip(image.intensities())
+            {
+                for channel in 0..4 {
+                    let w = self.weight(intensities[channel]);
+                    sum[channel] += w * intensities[channel] * scale;
+                    weight[channel] += w;
+                }
+            }
+        }
+
+        let fused = sums
+            .into_iter()
+            .zip(weights)
+            .zip(reference.intensities())
+            .map(|((sum, weight), fallback)| {
+                std::array::from_fn(|channel| {
+                    if weight[channel] > 0.0 {
+                        sum[channel] / weight[channel]
+                    } else {
+                        fallback[channel]
+                    }
+                })
+            });
+
+        Ok(IntensityImage::from_intensities(width, height, fused)?
+            .with_calibration(*reference.calibration())
+            .with_normalization(reference.normalization())
+            .with_meta(*reference.meta()))
+    }
+
+    /// Weights an intensity by how far it is from either end of the range of the sensor.
+    fn weight(&self, intensity: f64) -> f64 {
+        if !intensity.is_finite() || intensity >= self.saturation_level {
+            return 0.0;
+        }
+
+        let position = (intensity / self.saturation_level).clamp(0.0, 1.0);
+        (1.0 - (2.0 * position - 1.0).abs()).max(self.min_weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::FrameMeta;
+    use approx::assert_relative_eq;
+    use uom::si::{f64::Time, time::millisecond};
+
+    /// Builds a single metapixel image exposed for `exposure` milliseconds.
+    fn image(intensities: [f64; 4], exposure: f64) -> IntensityImage {
+        IntensityImage::from_intensities(1, 1, [intensities])
+            .unwrap()
+            .with_meta(FrameMeta::new().with_exposure(Time::new::<millisecond>(exposure)))
+    }
+
+    #[test]
+    fn replaces_saturated_channels() {
+        // The 0 degree channel saturates the long exposure.
+        let short = image([100.0, 60.0, 20.0, 60.0], 1.0);
+        let long = image([255.0, 240.0, 80.0, 240.0], 4.0);
+
+        let fused = ExposureFusion::new(255.0).fuse([&long, &short]).unwrap();
+        let [i000, i045, i090, i135] = fused.intensities().next().unwrap();
+
+        assert_relative_eq!(i000, 100.0);
+        assert_relative_eq!(i090, 20.0);
+        // Both exposures agree, so the weighted mean is exact.
+        assert_relative_eq!(i045, 60.0);
+        assert_relative_eq!(i135, 60.0);
+        assert_eq!(fused.meta().exposure(), short.meta().exposure());
+    }
+
+    #[test]
+    fn prefers_well_exposed_intensities() {
+        // The short exposure reads 10 but the long exposure places the same sky at 128.
+        let short = image([10.0; 4], 1.0);
+        let long = image([128.0; 4], 10.0);
+        let fused = ExposureFusion::new(255.0).fuse([&short, &long]).unwrap();
+
+        let [i000, ..] = fused.intensities().next().unwrap();
+        assert!((i000 - 12.8).abs() < (i000 - 10.0).abs());
+    }
+
+    #[test]
+    fn rejects_invalid_brackets() {
+        let fusion = ExposureFusion::new(255.0);
+        assert!(matches!(fusion.fuse([]), Err(HdrError::Empty)));
+
+        let unexposed = IntensityImage::from_intensities(1, 1, [[1.0; 4]]).unwrap();
+        assert!(matches!(
+            fusion.fuse([&image([1.0; 4], 1.0), &unexposed]),
+            Err(HdrError::MissingExposure { index: 1 })
+        ));
+
+        let dark = image([1.0; 4], 0.0);
+        assert!(matches!(
+            fusion.fuse([&image([1.0; 4], 1.0), &dark]),
+            Err(HdrError::InvalidExposure { index: 1, .. })
+        ));
+        let inverted = image([1.0; 4], 1.0).with_meta(
+            FrameMeta::new()
+                .with_exposure(Time::new::<millisecond>(1.0))
+                .with_gain(-1.0),
+        );
+        assert!(matches!(
+            fusion.fuse([&inverted]),
+            Err(HdrError::InvalidExposure { index: 0, .. })
+        ));
+
+        let wide = IntensityImage::from_intensities(2, 1, [[1.0; 4]; 2])
+            .unwrap()
+            .with_meta(*image([1.0; 4], 2.0).meta());
+        assert!(matches!(
+            fusion.fuse([&image([1.0; 4], 1.0), &wide]),
+            Err(HdrError::ExtentsMismatch { index: 1, .. })
+        ));
+    }
+}
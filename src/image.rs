@@ -1,11 +1,17 @@
 use crate::{
+    calibration::{PolarizerCalibration, S0Normalization},
+    decimate::Decimation,
     iter::RayIterator,
-    light::stokes::StokesVec,
-    ray::{Ray, SensorFrame},
+    light::{LightError, aop::AopConvention, stokes::StokesVec},
+    meta::FrameMeta,
+    optic::{Camera, MetapixelLayout, Optic, PixelCoordinate},
+    ray::{FrameTransform, GlobalFrame, Ray, RayError, RayFlags, SensorFrame, WeightedRay},
+    render::legend::Legend,
 };
 use rayon::prelude::*;
+use std::{cmp::Reverse, collections::BinaryHeap};
 use thiserror::Error;
-use uom::si::{angle::degree, f64::Angle};
+use uom::si::f64::Angle;
 
 #[derive(Debug, Error)]
 pub enum ImageError {
@@ -16,12 +22,30 @@ pub enum ImageError {
         len: usize,
     },
 
+    #[error(
+        "image extents do not match: expected {expected_rows}x{expected_cols} found {rows}x{cols}"
+    )]
+    ExtentsMismatch {
+        expected_rows: usize,
+        expected_cols: usize,
+        rows: usize,
+        cols: usize,
+    },
+
     #[error(
         "intensity image reader requires even numbered image dimensions: found {}x{}",
         width,
         height
     )]
     InvalidDimensions { width: usize, height: usize },
+
+    #[error("metapixel at row {row} and column {col} does not encode a valid ray")]
+    InvalidPixel {
+        row: usize,
+        col: usize,
+        #[source]
+        source: LightError,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -120,6 +144,66 @@ impl<'a, T> Iterator for Cells<'a, T> {
     }
 }
 
+/// A rectangular view into a row-major buffer of `stride` columns.
+#[derive(Clone, Debug, PartialEq)]
+struct Block<'a, T> {
+    elements: &'a [T],
+    stride: usize,
+    row: usize,
+    col: usize,
+    rows: usize,
+    cols: usize,
+}
+
+impl<'a, T> Block<'a, T> {
+    fn iter(&self) -> impl Iterator<Item = &'a T> + use<'a, T> {
+        let Self {
+            elements,
+            stride,
+            row,
+            col,
+            rows,
+            cols,
+        } = *self;
+
+        (row..row + rows).flat_map(move |r| elements[r * stride + col..][..cols].iter())
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Option<&'a T> {
+        if row < self.rows && col < self.cols {
+            Some(&self.elements[(self.row + row) * self.stride + self.col + col])
+        } else {
+            None
+        }
+    }
+}
+
+/// Splits a row-major buffer of `rows` by `cols` into blocks of at most `block_rows` by
+/// `block_cols` in row-major order.
+fn blocks<T>(
+    elements: &[T],
+    rows: usize,
+    cols: usize,
+    block_rows: usize,
+    block_cols: usize,
+) -> impl Iterator<Item = Block<'_, T>> {
+    assert!(
+        block_rows > 0 && block_cols > 0,
+        "tile extents must be greater than zero: {block_rows}x{block_cols}"
+    );
+
+    (0..rows).step_by(block_rows).flat_map(move |row| {
+        (0..cols).step_by(block_cols).map(move |col| Block {
+            elements,
+            stride: cols,
+            row,
+            col,
+            rows: block_rows.min(rows - row),
+            cols: block_cols.min(cols - col),
+        })
+    })
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct IntensityPixel {
     /// A metapixel is a group of four intensity pixels that have two sets of orthogonal linear polarizing filters.
@@ -128,18 +212,64 @@ pub struct IntensityPixel {
 }
 
 impl IntensityPixel {
-    /// The Stokes vectors are computed by:
+    /// For an ideal micro-polarizer array the Stokes vectors are computed by:
     /// ```text
     /// S_0 = (I_0 + I_45 + I_90 + I_135) / 2
     /// S_1 = I_0 - I_90
     /// S_2 = I_45 - I_135
     /// ```
-    fn stokes(&self) -> StokesVec<SensorFrame> {
-        StokesVec::new(
-            (self.inner[0] + self.inner[1] + self.inner[2] + self.inner[3]) / 2.,
-            self.inner[0] - self.inner[2],
-            self.inner[1] - self.inner[3],
-        )
+    /// See [`PolarizerCalibration`] for non-ideal arrays and [`S0Normalization`] for other
+    /// estimates of `S_0`.
+    fn stokes(
+        &self,
+        calibration: &PolarizerCalibration,
+        normalization: S0Normalization,
+    ) -> StokesVec<SensorFrame> {
+        normalization.stokes(calibration, self.inner)
+    }
+
+    /// Reads the metapixel at column `x` and row `y` from a raw image that is `width` pixels
+    /// wide.
+    ///
+    /// See [`IntensityImage::from_bytes`] for the layout of the micro-polarizer array.
+    fn from_raw(bytes: &[u8], width: usize, x: usize, y: usize) -> Self {
+        let i000 = (x * 2 + 1) + (y * 2 + 1) * width;
+        let i045 = (x * 2) + (y * 2 + 1) * width;
+        let i090 = (x * 2) + (y * 2) * width;
+        let i135 = (x * 2 + 1) + (y * 2) * width;
+
+        IntensityPixel {
+            inner: [
+                f64::from(bytes[i000]),
+                f64::from(bytes[i045]),
+                f64::from(bytes[i090]),
+                f64::from(bytes[i135]),
+            ],
+        }
+    }
+
+    /// Reads the metapixel at column `x` and row `y` from a raw image that is `width` pixels
+    /// wide where each polarizer covers `cell` by `cell` pixels.
+    ///
+    /// Each intensity is the mean of the pixels under its polarizer.
+    #[allow(clippy::cast_precision_loss)]
+    fn from_raw_cells(bytes: &[u8], width: usize, x: usize, y: usize, cell: usize) -> Self {
+        let (row, col) = (y * 2 * cell, x * 2 * cell);
+        let mean = |row: usize, col: usize| {
+            let sum: f64 = (row..row + cell)
+                .flat_map(|r| (col..col + cell).map(move |c| f64::from(bytes[c + r * width])))
+                .sum();
+            sum / (cell * cell) as f64
+        };
+
+        IntensityPixel {
+            inner: [
+                mean(row + cell, col + cell),
+                mean(row + cell, col),
+                mean(row, col),
+                mean(row, col + cell),
+            ],
+        }
     }
 }
 
@@ -154,6 +284,9 @@ pub struct IntensityImage {
     metapixels: Vec<IntensityPixel>,
     width: usize,
     height: usize,
+    calibration: PolarizerCalibration,
+    normalization: S0Normalization,
+    meta: FrameMeta,
 }
 
 impl IntensityImage {
@@ -204,21 +337,58 @@ impl IntensityImage {
 
         let metapixels: Vec<IntensityPixel> = coords
             .into_par_iter()
-            .map(|(x, y)| {
-                let i000 = (x * 2 + 1) + (y * 2 + 1) * width;
-                let i045 = (x * 2) + (y * 2 + 1) * width;
-                let i090 = (x * 2) + (y * 2) * width;
-                let i135 = (x * 2 + 1) + (y * 2) * width;
+            // FIXME: Catch problems with the size of `bytes`.
+            .map(|(x, y)| IntensityPixel::from_raw(bytes, width, x, y))
+            .collect();
 
-                // FIXME: Catch problems with the size of `bytes`.
-                IntensityPixel {
-                    inner: [
-                        f64::from(bytes[i000]),
-                        f64::from(bytes[i045]),
-                        f64::from(bytes[i090]),
-                        f64::from(bytes[i135]),
-                    ],
-                }
+        Ok(Self {
+            metapixels,
+            width: meta_width,
+            height: meta_height,
+            calibration: PolarizerCalibration::ideal(),
+            normalization: S0Normalization::default(),
+            meta: FrameMeta::default(),
+        })
+    }
+
+    /// Create an [`IntensityImage`] from an array of bytes read out with `layout`.
+    ///
+    /// In binned or superpixel modes each polarizer covers a block of pixels, which is averaged
+    /// into one intensity of the metapixel.
+    /// Trailing rows and columns that do not fill a metapixel are dropped.
+    /// See [`IntensityImage::from_bytes`] for the layout of the micro-polarizer array and
+    /// [`ImageSensor::metapixel_sensor_with_layout`] for the matching sensor geometry.
+    ///
+    /// [`ImageSensor::metapixel_sensor_with_layout`]: crate::optic::ImageSensor::metapixel_sensor_with_layout
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of `bytes` does not match `width` by `height`.
+    pub fn from_bytes_with_layout(
+        width: usize,
+        height: usize,
+        bytes: &[u8],
+        layout: MetapixelLayout,
+    ) -> Result<Self, ImageError> {
+        if bytes.len() != width * height {
+            return Err(ImageError::SizeMismatch {
+                rows: height,
+                cols: width,
+                len: bytes.len(),
+            });
+        }
+
+        let cell = layout.cell();
+        let (meta_width, meta_height) = (width / layout.span(), height / layout.span());
+        let metapixels = (0..meta_height * meta_width)
+            .into_par_iter()
+            .map(|index| {
+                IntensityPixel::from_raw_cells(
+                    bytes,
+                    width,
+                    index % meta_width,
+                    index / meta_width,
+                    cell,
+                )
             })
             .collect();
 
@@ -226,9 +396,193 @@ impl IntensityImage {
             metapixels,
             width: meta_width,
             height: meta_height,
+            calibration: PolarizerCalibration::ideal(),
+            normalization: S0Normalization::default(),
+            meta: FrameMeta::default(),
+        })
+    }
+
+    /// Create an [`IntensityImage`] from an array of bytes that may be truncated.
+    ///
+    /// Field data often contains frames with truncated payloads.
+    /// Instead of failing, every complete row of metapixels is recovered and metapixels that are
+    /// missing any of their four intensities are marked invalid with non-finite intensities.
+    /// Invalid metapixels yield no ray and are reported by [`IntensityImage::try_rays`].
+    /// Bytes beyond `width` by `height` are ignored.
+    /// See [`IntensityImage::from_bytes`] for the layout of `bytes`.
+    #[must_use]
+    pub fn from_bytes_lenient(width: usize, height: usize, bytes: &[u8]) -> (Self, DecodeReport) {
+        let (meta_width, meta_height) = (width / 2, height / 2);
+
+        // A metapixel row spans two rows of the raw image.
+        let complete_rows = (bytes.len() / (2 * width).max(1)).min(meta_height);
+        let metapixels = (0..meta_height)
+            .flat_map(|y| (0..meta_width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if y < complete_rows {
+                    IntensityPixel::from_raw(bytes, width, x, y)
+                } else {
+                    IntensityPixel {
+                        inner: [f64::NAN; 4],
+                    }
+                }
+            })
+            .collect();
+
+        let expected = width * height;
+        let report = DecodeReport {
+            complete_rows,
+            missing_rows: meta_height - complete_rows,
+            missing_bytes: expected.saturating_sub(bytes.len()),
+            extra_bytes: bytes.len().saturating_sub(expected),
+        };
+
+        let image = Self {
+            metapixels,
+            width: meta_width,
+            height: meta_height,
+            calibration: PolarizerCalibration::ideal(),
+            normalization: S0Normalization::default(),
+            meta: FrameMeta::default(),
+        };
+
+        (image, report)
+    }
+
+    /// Create an [`IntensityImage`] from the four intensities of each metapixel in 0, 45, 90, 135
+    /// order.
+    ///
+    /// Metapixels are given in row-major order and `width` by `height` are the extents in
+    /// metapixels.
+    /// This is the inverse of [`IntensityImage::intensities`] and accepts intensities that do
+    /// not fit in a byte, e.g., from high bit depth sensors or fused exposures.
+    ///
+    /// # Errors
+    /// Will return `Err` if the number of metapixels does not match `width` by `height`.
+    pub fn from_intensities(
+        width: usize,
+        height: usize,
+        intensities: impl IntoIterator<Item = [f64; 4]>,
+    ) -> Result<Self, ImageError> {
+        let metapixels: Vec<_> = intensities
+            .into_iter()
+            .map(|inner| IntensityPixel { inner })
+            .collect();
+        if metapixels.len() != width * height {
+            return Err(ImageError::SizeMismatch {
+                rows: height,
+                cols: width,
+                len: metapixels.len(),
+            });
+        }
+
+        Ok(Self {
+            metapixels,
+            width,
+            height,
+            calibration: PolarizerCalibration::ideal(),
+            normalization: S0Normalization::default(),
+            meta: FrameMeta::default(),
         })
     }
 
+    /// Create an [`IntensityImage`] from separate planes of intensities in 0, 45, 90, 135 order.
+    ///
+    /// Some cameras demosaic on board and deliver one plane per polarizer instead of the raw
+    /// micro-polarizer array.
+    /// Each plane is row-major and `width` by `height` are its extents, which become the extents
+    /// of the image in metapixels.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of any plane does not match `width` by `height`.
+    pub fn from_planes(
+        width: usize,
+        height: usize,
+        planes: &[&[u8]; 4],
+    ) -> Result<Self, ImageError> {
+        Self::from_plane_values(width, height, planes)
+    }
+
+    /// Create an [`IntensityImage`] from separate planes of 16 bit intensities.
+    ///
+    /// See [`IntensityImage::from_planes`] for the layout of `planes`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of any plane does not match `width` by `height`.
+    pub fn from_planes16(
+        width: usize,
+        height: usize,
+        planes: &[&[u16]; 4],
+    ) -> Result<Self, ImageError> {
+        Self::from_plane_values(width, height, planes)
+    }
+
+    fn from_plane_values<T: Copy + Into<f64>>(
+        width: usize,
+        height: usize,
+        planes: &[&[T]; 4],
+    ) -> Result<Self, ImageError> {
+        if let Some(len) = planes
+            .iter()
+            .map(|plane| plane.len())
+            .find(|len| *len != width * height)
+        {
+            return Err(ImageError::SizeMismatch {
+                rows: height,
+                cols: width,
+                len,
+            });
+        }
+
+        Self::from_intensities(
+            width,
+            height,
+            (0..width * height).map(|index| planes.map(|plane| plane[index].into())),
+        )
+    }
+
+    /// Use `calibration` to compute the Stokes vectors of this image.
+    ///
+    /// By default, an [`IntensityImage`] assumes an ideal micro-polarizer array.
+    #[must_use]
+    pub fn with_calibration(mut self, calibration: PolarizerCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    #[must_use]
+    pub fn calibration(&self) -> &PolarizerCalibration {
+        &self.calibration
+    }
+
+    /// Use `normalization` to form the total intensity `S_0` of each metapixel.
+    ///
+    /// By default, an [`IntensityImage`] uses [`S0Normalization::Calibrated`].
+    #[must_use]
+    pub fn with_normalization(mut self, normalization: S0Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    #[must_use]
+    pub fn normalization(&self) -> S0Normalization {
+        self.normalization
+    }
+
+    /// Attaches `meta` to this image.
+    ///
+    /// The metadata is carried into the [`StokesImage`] and [`RayImage`] computed from it.
+    #[must_use]
+    pub fn with_meta(mut self, meta: FrameMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    #[must_use]
+    pub fn meta(&self) -> &FrameMeta {
+        &self.meta
+    }
+
     #[must_use]
     pub fn width(&self) -> usize {
         self.width
@@ -239,143 +593,1563 @@ impl IntensityImage {
         self.height
     }
 
+    /// Returns an iterator over tiles of at most `tile_rows` by `tile_cols` metapixels.
+    ///
+    /// Tiles are yielded in row-major order.
+    /// Tiles on the bottom and right edges are truncated to fit the image.
+    ///
+    /// # Panics
+    /// Will panic if `tile_rows` or `tile_cols` is zero.
+    pub fn tiles(
+        &self,
+        tile_rows: usize,
+        tile_cols: usize,
+    ) -> impl Iterator<Item = IntensityTile<'_>> {
+        blocks(
+            &self.metapixels,
+            self.height,
+            self.width,
+            tile_rows,
+            tile_cols,
+        )
+        .map(|block| IntensityTile {
+            block,
+            calibration: &self.calibration,
+            normalization: self.normalization,
+        })
+    }
+
+    /// Returns an iterator over the rays of each metapixel.
+    ///
+    /// Metapixels that do not encode a valid ray (e.g., a zero or non-finite intensity) are
+    /// dropped.
+    /// See [`IntensityImage::try_rays`] to detect them instead.
     #[must_use]
     pub fn rays(&self) -> Rays<'_> {
         Rays {
-            inner: self.metapixels.iter(),
+            inner: self.try_rays(),
         }
     }
-}
-
-/// An iterator over rays.
-#[derive(Clone, Debug)]
-pub struct Rays<'a> {
-    inner: std::slice::Iter<'a, IntensityPixel>,
-}
 
-impl Iterator for Rays<'_> {
-    type Item = Ray<SensorFrame>;
-    fn next(&mut self) -> Option<Self::Item> {
-        let px = self.inner.next()?;
-        // TODO: Might want to propagate this error..
-        Ray::try_from(px.stokes()).ok()
+    /// Returns an iterator over the rays of each metapixel that reports invalid metapixels.
+    #[must_use]
+    pub fn try_rays(&self) -> TryRays<'_> {
+        TryRays {
+            metapixels: &self.metapixels,
+            width: self.width,
+            front: 0,
+            back: self.metapixels.len(),
+            calibration: &self.calibration,
+            normalization: self.normalization,
+        }
     }
-}
-
-// All of RayIterator's functions are defined using Iterator.
-impl RayIterator<SensorFrame> for Rays<'_> {}
-
-#[derive(Clone, Debug, PartialEq)]
-pub struct RayImage<Frame> {
-    inner: Matrix<Option<Ray<Frame>>>,
-    _phan: std::marker::PhantomData<Frame>,
-}
 
-impl<Frame> RayImage<Frame> {
-    fn from_matrix(matrix: Matrix<Option<Ray<Frame>>>) -> Self {
-        Self {
-            inner: matrix,
-            _phan: std::marker::PhantomData,
-        }
+    /// Returns an iterator over the four intensities of each metapixel in 0, 45, 90, 135 order.
+    pub fn intensities(&self) -> impl ExactSizeIterator<Item = [f64; 4]> {
+        self.metapixels.iter().map(|px| px.inner)
     }
 
+    /// Converts the image into a [`RayImage`] that carries its [`FrameMeta`].
     ///
-    /// # Errors
-    pub fn from_rays(
-        rays: impl IntoIterator<Item = Option<Ray<Frame>>>,
-        rows: usize,
-        cols: usize,
-    ) -> Result<Self, ImageError> {
-        let matrix = Matrix::from_elements(rays, rows, cols)?;
-        Ok(Self::from_matrix(matrix))
+    /// Metapixels that do not encode a valid ray hold `None`.
+    ///
+    /// # Panics
+    /// Will panic if the number of rays does not match the extents of the image.
+    /// This should never occur.
+    #[must_use]
+    pub fn ray_image(&self) -> RayImage<SensorFrame> {
+        RayImage::from_rays(self.try_rays().map(Result::ok), self.height, self.width)
+            .expect("image yields one ray per metapixel")
+            .with_meta(self.meta)
     }
 
+    /// Computes the total intensity `S_0` of each metapixel.
     #[must_use]
-    pub fn rows(&self) -> usize {
-        self.inner.rows()
+    pub fn s0_image(&self) -> S0Image {
+        S0Image {
+            values: self
+                .metapixels
+                .par_iter()
+                .map(|px| px.stokes(&self.calibration, self.normalization).s0())
+                .collect(),
+            rows: self.height,
+            cols: self.width,
+        }
     }
 
+    /// Summarizes the total intensity of the image.
+    ///
+    /// A metapixel is saturated if any of its four intensities is at least `saturation_level`
+    /// e.g., 255 for an 8 bit sensor.
+    /// Metapixels with a non-finite total intensity, such as the missing rows of an image from
+    /// [`IntensityImage::from_bytes_lenient`], are ignored.
+    /// Returns `None` if the image has no finite metapixels.
     #[must_use]
-    pub fn cols(&self) -> usize {
-        self.inner.cols()
+    pub fn radiometric_stats(&self, saturation_level: f64) -> Option<RadiometricStats> {
+        let saturated = self
+            .metapixels
+            .iter()
+            .filter(|px| px.inner.iter().any(|i| *i >= saturation_level))
+            .count();
+
+        let mut values = self.s0_image().values;
+        values.retain(|value| value.is_finite());
+        RadiometricStats::from_values(values, saturated)
     }
 
-    #[must_use]
-    pub fn ray(&self, row: usize, col: usize) -> Option<&Ray<Frame>> {
-        self.inner.cell(row, col).as_ref()
+    /// Returns an iterator over the ray of each metapixel with [`RayFlags::SATURATED`] raised on
+    /// metapixels with any intensity at least `saturation_level`.
+    ///
+    /// Saturation is judged as in [`IntensityImage::radiometric_stats`].
+    /// Yields `None` for metapixels that do not encode a valid ray.
+    pub fn weighted_rays(
+        &self,
+        saturation_level: f64,
+    ) -> impl ExactSizeIterator<Item = Option<WeightedRay<SensorFrame>>> {
+        self.try_rays().zip(&self.metapixels).map(move |(ray, px)| {
+            let ray = WeightedRay::new(ray.ok()?);
+            Some(if px.inner.iter().any(|i| *i >= saturation_level) {
+                ray.with_flags(RayFlags::SATURATED)
+            } else {
+                ray
+            })
+        })
     }
 
-    pub fn rays(&self) -> impl Iterator<Item = Option<&Ray<Frame>>> {
-        self.inner.iter().map(|elem| elem.as_ref())
+    /// Computes the Stokes vector of each metapixel in parallel.
+    #[must_use]
+    pub fn stokes_image(&self) -> StokesImage<SensorFrame> {
+        let stokes: Vec<_> = self
+            .metapixels
+            .par_iter()
+            .map(|px| px.stokes(&self.calibration, self.normalization))
+            .collect();
+
+        StokesImage {
+            s0: stokes.iter().map(StokesVec::s0).collect(),
+            s1: stokes.iter().map(StokesVec::s1).collect(),
+            s2: stokes.iter().map(StokesVec::s2).collect(),
+            rows: self.height,
+            cols: self.width,
+            meta: self.meta,
+            _phan: std::marker::PhantomData,
+        }
     }
 
-    pub fn pixels(&self) -> impl Iterator<Item = RayPixel<'_, Frame>> {
-        self.inner.cells().map(|cell| RayPixel {
-            ray: cell.element.as_ref(),
-            row: cell.row,
-            col: cell.col,
+    /// Divides the intensities of each metapixel by the relative illumination of `camera`.
+    ///
+    /// This removes the darkening of the lens described by its
+    /// [`Vignetting`](crate::optic::Vignetting) before the total intensity is used, e.g., by
+    /// [`crate::exposure::ExposureAdvisor`].
+    /// Metapixels that receive no light have non-finite intensities and encode no ray.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the image do not match the sensor of `camera`.
+    pub fn correct_vignetting<O: Optic>(&self, camera: &Camera<O>) -> Result<Self, ImageError> {
+        self.scale_by_illumination(camera, |intensity, illumination| {
+            if illumination > 0.0 {
+                intensity / illumination
+            } else {
+                f64::NAN
+            }
         })
     }
 
-    pub fn aop_bytes<M>(&self, color_map: &M) -> Vec<u8>
-    where
-        Frame: Copy,
-        M: RayMap,
-        M::Output: IntoIterator<Item = u8>,
-    {
-        self.rays()
-            .map(|pixel| pixel.map_or(f64::NAN, |ray| Angle::from(ray.aop()).get::<degree>()))
-            .flat_map(|value| color_map.map(value, -90.0, 90.0))
-            .collect()
+    /// Multiplies the intensities of each metapixel by the relative illumination of `camera`.
+    ///
+    /// This is the forward model of [`IntensityImage::correct_vignetting`] used by
+    /// [`StokesImage::render`].
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the image do not match the sensor of `camera`.
+    pub fn apply_vignetting<O: Optic>(&self, camera: &Camera<O>) -> Result<Self, ImageError> {
+        self.scale_by_illumination(camera, |intensity, illumination| intensity * illumination)
     }
 
-    pub fn dop_bytes<M>(&self, color_map: &M) -> Vec<u8>
-    where
-        M: RayMap,
-        M::Output: IntoIterator<Item = u8>,
-    {
-        self.rays()
-            .map(|pixel| pixel.map_or(f64::NAN, |ray| f64::from(ray.dop())))
-            .flat_map(|value| color_map.map(value, 0.0, 1.0))
-            .collect()
+    fn scale_by_illumination<O: Optic>(
+        &self,
+        camera: &Camera<O>,
+        scale: impl Fn(f64, f64) -> f64,
+    ) -> Result<Self, ImageError> {
+        if (camera.rows(), camera.cols()) != (self.height, self.width) {
+            return Err(ImageError::ExtentsMismatch {
+                expected_rows: camera.rows(),
+                expected_cols: camera.cols(),
+                rows: self.height,
+                cols: self.width,
+            });
+        }
+
+        let metapixels = self
+            .metapixels
+            .iter()
+            .zip(camera.pixels())
+            .map(|(px, pixel)| {
+                let illumination = camera.relative_illumination(pixel).unwrap_or(1.0);
+                IntensityPixel {
+                    inner: px.inner.map(|intensity| scale(intensity, illumination)),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            metapixels,
+            width: self.width,
+            height: self.height,
+            calibration: self.calibration,
+            normalization: self.normalization,
+            meta: self.meta,
+        })
     }
 }
 
-pub struct RayPixel<'a, Frame> {
-    ray: Option<&'a Ray<Frame>>,
-    row: usize,
-    col: usize,
+/// Describes how much of a frame was recovered by [`IntensityImage::from_bytes_lenient`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodeReport {
+    complete_rows: usize,
+    missing_rows: usize,
+    missing_bytes: usize,
+    extra_bytes: usize,
 }
 
-impl<'a, Frame> RayPixel<'a, Frame> {
+impl DecodeReport {
+    /// The number of rows of metapixels that were fully decoded.
     #[must_use]
-    pub fn ray(&self) -> Option<&'a Ray<Frame>> {
-        self.ray
+    pub fn complete_rows(&self) -> usize {
+        self.complete_rows
     }
 
+    /// The number of rows of metapixels that were marked invalid.
     #[must_use]
-    pub fn row(&self) -> usize {
-        self.row
+    pub fn missing_rows(&self) -> usize {
+        self.missing_rows
     }
 
+    /// The number of bytes the payload was short by.
     #[must_use]
-    pub fn col(&self) -> usize {
-        self.col
+    pub fn missing_bytes(&self) -> usize {
+        self.missing_bytes
     }
-}
 
-pub trait RayMap {
-    type Output;
+    /// The number of bytes beyond the extents of the frame that were ignored.
+    #[must_use]
+    pub fn extra_bytes(&self) -> usize {
+        self.extra_bytes
+    }
 
-    fn map(&self, value: f64, min: f64, max: f64) -> Self::Output;
+    /// Returns `true` if every metapixel was decoded.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.missing_rows == 0
+    }
 }
 
-pub struct Jet;
-impl RayMap for Jet {
-    type Output = [u8; 3];
-
-    fn map(&self, value: f64, min: f64, max: f64) -> Self::Output {
-        if value < min || value > max {
+/// A borrowed view of a raw polarized intensity image.
+///
+/// Unlike [`IntensityImage`], a view does not copy the metapixels out of the raw bytes.
+/// Intensities and rays are computed lazily in row-major order, which suits a single streaming
+/// pass over large frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntensityView<'a> {
+    bytes: &'a [u8],
+    raw_width: usize,
+    width: usize,
+    height: usize,
+    calibration: PolarizerCalibration,
+    normalization: S0Normalization,
+    meta: FrameMeta,
+}
+
+impl<'a> IntensityView<'a> {
+    /// Creates an [`IntensityView`] of `bytes` organized by row.
+    ///
+    /// See [`IntensityImage::from_bytes`] for the layout of the micro-polarizer array.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of `bytes` does not match `width` by `height`.
+    pub fn new(width: usize, height: usize, bytes: &'a [u8]) -> Result<Self, ImageError> {
+        if bytes.len() != width * height {
+            return Err(ImageError::SizeMismatch {
+                rows: height,
+                cols: width,
+                len: bytes.len(),
+            });
+        }
+
+        Ok(Self {
+            bytes,
+            raw_width: width,
+            width: width / 2,
+            height: height / 2,
+            calibration: PolarizerCalibration::ideal(),
+            normalization: S0Normalization::default(),
+            meta: FrameMeta::default(),
+        })
+    }
+
+    /// Use `calibration` to compute the Stokes vectors of this view.
+    #[must_use]
+    pub fn with_calibration(mut self, calibration: PolarizerCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Use `normalization` to form the total intensity `S_0` of each metapixel.
+    #[must_use]
+    pub fn with_normalization(mut self, normalization: S0Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Attaches `meta` to this view.
+    ///
+    /// The metadata is carried into the [`RayImage`] and [`IntensityImage`] made from it.
+    #[must_use]
+    pub fn with_meta(mut self, meta: FrameMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    #[must_use]
+    pub fn meta(&self) -> &FrameMeta {
+        &self.meta
+    }
+
+    /// The number of metapixels in each row.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of rows of metapixels.
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn metapixels(&self) -> impl ExactSizeIterator<Item = IntensityPixel> + use<'a> {
+        let (bytes, raw_width, width) = (self.bytes, self.raw_width, self.width);
+        (0..self.width * self.height).map(move |index| {
+            IntensityPixel::from_raw(bytes, raw_width, index % width, index / width)
+        })
+    }
+
+    /// Returns an iterator over the four intensities of each metapixel in 0, 45, 90, 135 order.
+    pub fn intensities(&self) -> impl ExactSizeIterator<Item = [f64; 4]> + use<'a> {
+        self.metapixels().map(|px| px.inner)
+    }
+
+    /// Returns an iterator over the Stokes vector of each metapixel.
+    pub fn stokes(&self) -> impl ExactSizeIterator<Item = StokesVec<SensorFrame>> + use<'a> {
+        let (calibration, normalization) = (self.calibration, self.normalization);
+        self.metapixels()
+            .map(move |px| px.stokes(&calibration, normalization))
+    }
+
+    /// Returns an iterator over the ray of each metapixel or `None` if it does not encode a
+    /// valid ray.
+    pub fn rays(&self) -> impl ExactSizeIterator<Item = Option<Ray<SensorFrame>>> + use<'a> {
+        self.stokes().map(|stokes| Ray::try_from(stokes).ok())
+    }
+
+    /// Converts the view into a [`RayImage`] in a single pass.
+    ///
+    /// # Panics
+    /// Will panic if the number of rays does not match the extents of the view.
+    /// This should never occur.
+    #[must_use]
+    pub fn ray_image(&self) -> RayImage<SensorFrame> {
+        RayImage::from_rays(self.rays(), self.height, self.width)
+            .expect("view yields one ray per metapixel")
+            .with_meta(self.meta)
+    }
+
+    /// Copies the metapixels into an owned [`IntensityImage`].
+    #[must_use]
+    pub fn to_image(&self) -> IntensityImage {
+        IntensityImage {
+            metapixels: self.metapixels().collect(),
+            width: self.width,
+            height: self.height,
+            calibration: self.calibration,
+            normalization: self.normalization,
+            meta: self.meta,
+        }
+    }
+}
+
+/// An image of the total intensity `S_0` of each metapixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct S0Image {
+    values: Vec<f64>,
+    rows: usize,
+    cols: usize,
+}
+
+impl S0Image {
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the row-major total intensities.
+    #[must_use]
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Scales total intensities on `[0, max]` onto 16 bit grayscale samples.
+    ///
+    /// Intensities outside of the range are clamped.
+    /// The samples can be encoded as a 16 bit grayscale PNG.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn gray16(&self, max: f64) -> Vec<u16> {
+        self.values
+            .iter()
+            .map(|value| ((value / max).clamp(0.0, 1.0) * f64::from(u16::MAX)).round() as u16)
+            .collect()
+    }
+}
+
+/// Summarizes the total intensity of an [`IntensityImage`].
+///
+/// See [`IntensityImage::radiometric_stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadiometricStats {
+    sorted: Vec<f64>,
+    mean: f64,
+    saturated_fraction: f64,
+}
+
+impl RadiometricStats {
+    #[allow(clippy::cast_precision_loss)]
+    fn from_values(mut values: Vec<f64>, saturated: usize) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        values.sort_by(f64::total_cmp);
+        let count = values.len() as f64;
+        Some(Self {
+            mean: values.iter().sum::<f64>() / count,
+            saturated_fraction: saturated as f64 / count,
+            sorted: values,
+        })
+    }
+
+    #[must_use]
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    #[must_use]
+    pub fn min(&self) -> f64 {
+        self.sorted[0]
+    }
+
+    #[must_use]
+    pub fn max(&self) -> f64 {
+        self.sorted[self.sorted.len() - 1]
+    }
+
+    #[must_use]
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+
+    /// Returns the `percentile` of total intensity by linear interpolation between ranks.
+    ///
+    /// `percentile` is clamped onto `[0, 100]`.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        let rank = percentile.clamp(0.0, 100.0) / 100.0 * (self.sorted.len() - 1) as f64;
+        let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+        let t = rank - rank.floor();
+        self.sorted[lower] + t * (self.sorted[upper] - self.sorted[lower])
+    }
+
+    /// Returns the fraction of metapixels with at least one saturated intensity.
+    #[must_use]
+    pub fn saturated_fraction(&self) -> f64 {
+        self.saturated_fraction
+    }
+}
+
+/// An image of linear Stokes parameters in `Frame`.
+///
+/// This is the intermediate stage between an [`IntensityImage`] and a [`RayImage`].
+/// Each parameter is stored as a row-major plane.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StokesImage<Frame> {
+    s0: Vec<f64>,
+    s1: Vec<f64>,
+    s2: Vec<f64>,
+    rows: usize,
+    cols: usize,
+    meta: FrameMeta,
+    _phan: std::marker::PhantomData<Frame>,
+}
+
+impl<Frame> StokesImage<Frame> {
+    /// Creates a `StokesImage` from row-major planes of each Stokes parameter.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of any plane does not match `rows` by `cols`.
+    pub fn from_planes(
+        s0: Vec<f64>,
+        s1: Vec<f64>,
+        s2: Vec<f64>,
+        rows: usize,
+        cols: usize,
+    ) -> Result<Self, ImageError> {
+        if let Some(len) = [s0.len(), s1.len(), s2.len()]
+            .into_iter()
+            .find(|len| *len != rows * cols)
+        {
+            return Err(ImageError::SizeMismatch { rows, cols, len });
+        }
+
+        Ok(Self {
+            s0,
+            s1,
+            s2,
+            rows,
+            cols,
+            meta: FrameMeta::default(),
+            _phan: std::marker::PhantomData,
+        })
+    }
+
+    /// Attaches `meta` to this image.
+    #[must_use]
+    pub fn with_meta(mut self, meta: FrameMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    #[must_use]
+    pub fn meta(&self) -> &FrameMeta {
+        &self.meta
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the plane of total intensities.
+    #[must_use]
+    pub fn s0(&self) -> &[f64] {
+        &self.s0
+    }
+
+    /// Returns the plane of `S_1` parameters.
+    #[must_use]
+    pub fn s1(&self) -> &[f64] {
+        &self.s1
+    }
+
+    /// Returns the plane of `S_2` parameters.
+    #[must_use]
+    pub fn s2(&self) -> &[f64] {
+        &self.s2
+    }
+
+    /// Returns the Stokes vector at `row` and `col`.
+    ///
+    /// # Panics
+    /// Will panic if `row` or `col` is outside of the image.
+    #[must_use]
+    pub fn stokes(&self, row: usize, col: usize) -> StokesVec<Frame> {
+        assert!(
+            row < self.rows && col < self.cols,
+            "pixel is outside of image"
+        );
+        let index = row * self.cols + col;
+        StokesVec::new(self.s0[index], self.s1[index], self.s2[index])
+    }
+
+    /// Converts each Stokes vector into a ray.
+    ///
+    /// Pixels that do not encode a valid ray have no ray in the result.
+    #[must_use]
+    pub fn ray_image(&self) -> RayImage<Frame>
+    where
+        Frame: Send + Sync,
+    {
+        let rays: Vec<_> = (0..self.s0.len())
+            .into_par_iter()
+            .map(|index| {
+                Ray::try_from(StokesVec::<Frame>::new(
+                    self.s0[index],
+                    self.s1[index],
+                    self.s2[index],
+                ))
+                .ok()
+            })
+            .collect();
+
+        RayImage::from_matrix(Matrix {
+            elements: rays,
+            rows: self.rows,
+            cols: self.cols,
+        })
+        .with_meta(self.meta)
+    }
+
+    /// Rotates the reference axis of each pixel by the angle returned from `shift` for its row
+    /// and column.
+    fn par_rotate<Into>(self, shift: impl Fn(usize, usize) -> Angle + Sync) -> StokesImage<Into> {
+        let cols = self.cols;
+        let (s1, s2) = self
+            .s1
+            .par_iter()
+            .zip(&self.s2)
+            .enumerate()
+            .map(|(index, (s1, s2))| {
+                let rotated = StokesVec::<Frame>::new(0.0, *s1, *s2)
+                    .rotate::<Into>(shift(index / cols, index % cols));
+                (rotated.s1(), rotated.s2())
+            })
+            .unzip();
+
+        StokesImage {
+            s0: self.s0,
+            s1,
+            s2,
+            rows: self.rows,
+            cols: self.cols,
+            meta: self.meta,
+            _phan: std::marker::PhantomData,
+        }
+    }
+}
+
+impl StokesImage<SensorFrame> {
+    /// Transforms every pixel into the `GlobalFrame` in parallel.
+    ///
+    /// `shift` returns the angle between frames for the pixel at a row and column.
+    /// Use `|_, _| shift` when every pixel shares the same angle.
+    /// See [`StokesVec::into_global_frame`].
+    #[must_use]
+    pub fn par_into_global_frame(
+        self,
+        shift: impl Fn(usize, usize) -> Angle + Sync,
+    ) -> StokesImage<GlobalFrame> {
+        self.par_rotate(shift)
+    }
+
+    /// Renders the [`IntensityImage`] that `camera` measures through `calibration`.
+    ///
+    /// This is the forward model of [`IntensityImage::stokes_image`].
+    /// Each Stokes vector is passed through [`PolarizerCalibration::intensities`] and darkened by
+    /// the [`Vignetting`](crate::optic::Vignetting) of `camera`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the image do not match the sensor of `camera`.
+    pub fn render<O: Optic>(
+        &self,
+        calibration: &PolarizerCalibration,
+        camera: &Camera<O>,
+    ) -> Result<IntensityImage, ImageError> {
+        let intensities = (0..self.rows * self.cols).map(|index| {
+            calibration.intensities(&StokesVec::<SensorFrame>::new(
+                self.s0[index],
+                self.s1[index],
+                self.s2[index],
+            ))
+        });
+
+        IntensityImage::from_intensities(self.cols, self.rows, intensities)?
+            .with_calibration(*calibration)
+            .with_meta(self.meta)
+            .apply_vignetting(camera)
+    }
+}
+
+impl StokesImage<GlobalFrame> {
+    /// Transforms every pixel into the `SensorFrame` in parallel.
+    ///
+    /// See [`StokesImage::par_into_global_frame`].
+    #[must_use]
+    pub fn par_into_sensor_frame(
+        self,
+        shift: impl Fn(usize, usize) -> Angle + Sync,
+    ) -> StokesImage<SensorFrame> {
+        self.par_rotate(|row, col| -shift(row, col))
+    }
+}
+
+/// An iterator over rays.
+///
+/// Since invalid metapixels are dropped, the number of remaining rays is not known exactly.
+/// See [`TryRays`] for an [`ExactSizeIterator`].
+#[derive(Clone, Debug)]
+pub struct Rays<'a> {
+    inner: TryRays<'a>,
+}
+
+impl Rays<'_> {
+    /// Advances the iterator to the metapixel at `row` and `col`.
+    ///
+    /// See [`TryRays::skip_to`].
+    #[must_use]
+    pub fn skip_to(self, row: usize, col: usize) -> Self {
+        Self {
+            inner: self.inner.skip_to(row, col),
+        }
+    }
+}
+
+impl Iterator for Rays<'_> {
+    type Item = Ray<SensorFrame>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find_map(Result::ok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.inner.len()))
+    }
+}
+
+impl DoubleEndedIterator for Rays<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(ray) = self.inner.next_back() {
+            if let Ok(ray) = ray {
+                return Some(ray);
+            }
+        }
+
+        None
+    }
+}
+
+/// An iterator over rays that yields an [`ImageError::InvalidPixel`] for each metapixel that
+/// does not encode a valid ray.
+#[derive(Clone, Debug)]
+pub struct TryRays<'a> {
+    metapixels: &'a [IntensityPixel],
+    width: usize,
+    /// Index of the next metapixel yielded from the front.
+    front: usize,
+    /// One past the index of the next metapixel yielded from the back.
+    back: usize,
+    calibration: &'a PolarizerCalibration,
+    normalization: S0Normalization,
+}
+
+impl TryRays<'_> {
+    /// Advances the iterator to the metapixel at `row` and `col`.
+    ///
+    /// Metapixels are yielded in row-major order, so the next item is the metapixel at `row`
+    /// and `col` unless the iterator has already passed it.
+    /// The iterator is exhausted if `row` and `col` are outside of the image.
+    #[must_use]
+    pub fn skip_to(mut self, row: usize, col: usize) -> Self {
+        let height = self.metapixels.len().checked_div(self.width).unwrap_or(0);
+        if row < height && col < self.width {
+            self.front = self.front.max(row * self.width + col).min(self.back);
+        } else {
+            self.front = self.back;
+        }
+
+        self
+    }
+
+    fn ray(&self, index: usize) -> Result<Ray<SensorFrame>, ImageError> {
+        Ray::try_from(self.metapixels[index].stokes(self.calibration, self.normalization)).map_err(
+            |RayError::InvalidStokes(source)| ImageError::InvalidPixel {
+                row: index / self.width,
+                col: index % self.width,
+                source,
+            },
+        )
+    }
+}
+
+impl Iterator for TryRays<'_> {
+    type Item = Result<Ray<SensorFrame>, ImageError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.front += 1;
+        Some(self.ray(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for TryRays<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        Some(self.ray(self.back))
+    }
+}
+
+impl ExactSizeIterator for TryRays<'_> {}
+
+// All of RayIterator's functions are defined using Iterator.
+impl RayIterator<SensorFrame> for Rays<'_> {}
+
+/// A rectangular block of metapixels in an [`IntensityImage`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntensityTile<'a> {
+    block: Block<'a, IntensityPixel>,
+    calibration: &'a PolarizerCalibration,
+    normalization: S0Normalization,
+}
+
+impl IntensityTile<'_> {
+    /// The row of the top left metapixel of the tile in the image.
+    #[must_use]
+    pub fn row(&self) -> usize {
+        self.block.row
+    }
+
+    /// The column of the top left metapixel of the tile in the image.
+    #[must_use]
+    pub fn col(&self) -> usize {
+        self.block.col
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.block.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.block.cols
+    }
+
+    /// Returns an iterator over the valid rays of the tile in row-major order.
+    pub fn rays(&self) -> impl Iterator<Item = Ray<SensorFrame>> {
+        self.block
+            .iter()
+            .filter_map(|px| Ray::try_from(px.stokes(self.calibration, self.normalization)).ok())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RayImage<Frame> {
+    inner: Matrix<Option<Ray<Frame>>>,
+    meta: FrameMeta,
+    _phan: std::marker::PhantomData<Frame>,
+}
+
+impl<Frame> RayImage<Frame> {
+    fn from_matrix(matrix: Matrix<Option<Ray<Frame>>>) -> Self {
+        Self {
+            inner: matrix,
+            meta: FrameMeta::default(),
+            _phan: std::marker::PhantomData,
+        }
+    }
+
+    ///
+    /// # Errors
+    pub fn from_rays(
+        rays: impl IntoIterator<Item = Option<Ray<Frame>>>,
+        rows: usize,
+        cols: usize,
+    ) -> Result<Self, ImageError> {
+        let matrix = Matrix::from_elements(rays, rows, cols)?;
+        Ok(Self::from_matrix(matrix))
+    }
+
+    /// Builds an image from rays scattered over pixels in parallel.
+    ///
+    /// Unlike [`RayImage::from_rays`], `rays` may arrive in any order, may miss pixels, and may
+    /// hit a pixel more than once, e.g., when reprojecting rays from several cameras.
+    /// Rays are binned into bands of rows in parallel, and each band is then filled on its own
+    /// thread, so no locking is needed for tens of millions of rays.
+    /// Pixels hit more than once are resolved by `policy` and rays outside of the extents are
+    /// dropped, both of which are counted in the returned [`AssemblyReport`].
+    #[must_use]
+    pub fn par_from_pixel_rays(
+        rays: impl IntoParallelIterator<Item = (PixelCoordinate, Ray<Frame>)>,
+        rows: usize,
+        cols: usize,
+        policy: ConflictPolicy,
+    ) -> (Self, AssemblyReport)
+    where
+        Frame: Copy + Send + Sync,
+    {
+        const BAND_ROWS: usize = 16;
+
+        let bands = rows.div_ceil(BAND_ROWS);
+        let empty = || (vec![Vec::new(); bands], 0, 0);
+        let (buckets, received, out_of_bounds) = rays
+            .into_par_iter()
+            .fold(
+                empty,
+                |(mut buckets, received, out_of_bounds), (pixel, ray)| {
+                    if pixel.row() < rows && pixel.col() < cols {
+                        buckets[pixel.row() / BAND_ROWS]
+                            .push((pixel.row() * cols + pixel.col(), ray));
+                        (buckets, received + 1, out_of_bounds)
+                    } else {
+                        (buckets, received + 1, out_of_bounds + 1)
+                    }
+                },
+            )
+            .reduce(
+                empty,
+                |(mut lhs, lhs_received, lhs_out), (rhs, rhs_received, rhs_out)| {
+                    // Rayon reduces neighboring splits, so appending keeps the order of the input.
+                    for (lhs, rhs) in lhs.iter_mut().zip(rhs) {
+                        lhs.extend(rhs);
+                    }
+                    (lhs, lhs_received + rhs_received, lhs_out + rhs_out)
+                },
+            );
+
+        let mut elements = vec![None; rows * cols];
+        let conflicts = elements
+            .par_chunks_mut((BAND_ROWS * cols).max(1))
+            .zip(buckets)
+            .enumerate()
+            .map(|(band, (cells, mut bucket))| {
+                let offset = band * BAND_ROWS * cols;
+                // The sort is stable so the rays of each pixel stay in the order they arrived.
+                bucket.sort_by_key(|(index, _)| *index);
+
+                let mut conflicts = 0;
+                for group in bucket.chunk_by(|lhs, rhs| lhs.0 == rhs.0) {
+                    cells[group[0].0 - offset] = if let [(_, ray)] = group {
+                        Some(*ray)
+                    } else {
+                        conflicts += 1;
+                        policy.resolve(group.iter().map(|(_, ray)| ray))
+                    };
+                }
+                conflicts
+            })
+            .sum();
+
+        let filled = elements.iter().filter(|ray| ray.is_some()).count();
+        let report = AssemblyReport {
+            received,
+            conflicts,
+            out_of_bounds,
+            filled,
+            pixels: rows * cols,
+        };
+        (
+            Self::from_matrix(Matrix {
+                elements,
+                rows,
+                cols,
+            }),
+            report,
+        )
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.inner.rows()
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.inner.cols()
+    }
+
+    #[must_use]
+    pub fn ray(&self, row: usize, col: usize) -> Option<&Ray<Frame>> {
+        self.inner.cell(row, col).as_ref()
+    }
+
+    /// Attaches `meta` to this image.
+    ///
+    /// The metadata is carried into images derived from this one.
+    #[must_use]
+    pub fn with_meta(mut self, meta: FrameMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    #[must_use]
+    pub fn meta(&self) -> &FrameMeta {
+        &self.meta
+    }
+
+    pub fn rays(&self) -> impl Iterator<Item = Option<&Ray<Frame>>> {
+        self.inner.iter().map(|elem| elem.as_ref())
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = RayPixel<'_, Frame>> {
+        self.inner.cells().map(|cell| RayPixel {
+            ray: cell.element.as_ref(),
+            row: cell.row,
+            col: cell.col,
+        })
+    }
+
+    /// Transforms every ray from `Frame` into `To`.
+    #[must_use]
+    pub fn transform<To>(self, transform: &impl FrameTransform<Frame, To>) -> RayImage<To> {
+        let rows = self.rows();
+        let cols = self.cols();
+        RayImage::from_matrix(Matrix {
+            elements: self
+                .inner
+                .elements
+                .into_iter()
+                .map(|ray| ray.map(|ray| ray.transform(transform)))
+                .collect(),
+            rows,
+            cols,
+        })
+        .with_meta(self.meta)
+    }
+
+    /// Applies `f` to every ray in parallel.
+    ///
+    /// Pixels without a ray stay empty and the geometry and metadata of the image are preserved.
+    #[must_use]
+    pub fn map<To, F>(&self, f: F) -> RayImage<To>
+    where
+        Frame: Sync,
+        To: Send,
+        F: Fn(&Ray<Frame>) -> Ray<To> + Sync,
+    {
+        self.filter_map_pixels(|_, _, ray| Some(f(ray)))
+    }
+
+    /// Applies `f` to the row, column, and ray of every pixel with a ray in parallel.
+    ///
+    /// Pixels where `f` returns `None` are left empty, e.g., to mask pixels by their position or
+    /// their weight.
+    /// The geometry and metadata of the image are preserved.
+    #[must_use]
+    pub fn filter_map_pixels<To, F>(&self, f: F) -> RayImage<To>
+    where
+        Frame: Sync,
+        To: Send,
+        F: Fn(usize, usize, &Ray<Frame>) -> Option<Ray<To>> + Sync,
+    {
+        let cols = self.cols();
+        RayImage::from_matrix(Matrix {
+            elements: self
+                .inner
+                .elements
+                .par_iter()
+                .enumerate()
+                .map(|(index, ray)| f(index / cols, index % cols, ray.as_ref()?))
+                .collect(),
+            rows: self.rows(),
+            cols,
+        })
+        .with_meta(self.meta)
+    }
+
+    /// Combines the rays of this image and `other` at each pixel with `f` in parallel.
+    ///
+    /// `f` is only called where both images have a ray, so a pixel missing from either image is
+    /// empty in the result.
+    /// The metadata of this image is preserved.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of `other` do not match this image.
+    pub fn zip_with<Other, To, F>(
+        &self,
+        other: &RayImage<Other>,
+        f: F,
+    ) -> Result<RayImage<To>, ImageError>
+    where
+        Frame: Sync,
+        Other: Sync,
+        To: Send,
+        F: Fn(&Ray<Frame>, &Ray<Other>) -> Option<Ray<To>> + Sync,
+    {
+        if (self.rows(), self.cols()) != (other.rows(), other.cols()) {
+            return Err(ImageError::ExtentsMismatch {
+                expected_rows: self.rows(),
+                expected_cols: self.cols(),
+                rows: other.rows(),
+                cols: other.cols(),
+            });
+        }
+
+        Ok(RayImage::from_matrix(Matrix {
+            elements: self
+                .inner
+                .elements
+                .par_iter()
+                .zip(&other.inner.elements)
+                .map(|(ray, other)| f(ray.as_ref()?, other.as_ref()?))
+                .collect(),
+            rows: self.rows(),
+            cols: self.cols(),
+        })
+        .with_meta(self.meta))
+    }
+
+    /// Unwraps the [`Aop`] of every pixel into a continuous angle field.
+    ///
+    /// Since AoP is axial, angles jump by 180 degrees at the +/- 90 degree boundary.
+    /// Unwrapping grows regions from the pixel with the largest [`Dop`] and visits neighbors in
+    /// order of decreasing [`Dop`], so unreliable pixels are unwrapped last.
+    /// Each connected region of pixels with rays starts from its own most polarized pixel.
+    ///
+    /// [`Aop`]: crate::light::aop::Aop
+    /// [`Dop`]: crate::light::dop::Dop
+    #[must_use]
+    pub fn unwrap_aop(&self) -> UnwrappedAop
+    where
+        Frame: Copy,
+    {
+        let (rows, cols) = (self.rows(), self.cols());
+        let rays = &self.inner.elements;
+        let quality = |index: usize| rays[index].map(|ray| f64::from(ray.dop()).to_bits());
+
+        let mut seeds: Vec<_> = (0..rays.len()).filter(|i| rays[*i].is_some()).collect();
+        seeds.sort_by_key(|index| (Reverse(quality(*index)), *index));
+
+        let mut angles: Vec<Option<Angle>> = vec![None; rays.len()];
+        let mut frontier = BinaryHeap::new();
+        let push_neighbors =
+            |index: usize, angles: &[Option<Angle>], frontier: &mut BinaryHeap<_>| {
+                let (row, col) = (index / cols, index % cols);
+                let neighbors = [
+                    (row > 0).then(|| index - cols),
+                    (row + 1 < rows).then(|| index + cols),
+                    (col > 0).then(|| index - 1),
+                    (col + 1 < cols).then(|| index + 1),
+                ];
+                for neighbor in neighbors.into_iter().flatten() {
+                    if let (Some(quality), None) = (quality(neighbor), angles[neighbor]) {
+                        // Ties are broken by index so the result is deterministic.
+                        frontier.push((quality, Reverse(neighbor), index));
+                    }
+                }
+            };
+
+        for seed in seeds {
+            if angles[seed].is_some() {
+                continue;
+            }
+
+            angles[seed] = rays[seed].map(|ray| Angle::from(ray.aop()));
+            push_neighbors(seed, &angles, &mut frontier);
+
+            while let Some((_, Reverse(index), from)) = frontier.pop() {
+                if angles[index].is_some() {
+                    continue;
+                }
+
+                let (Some(ray), Some(base), Some(from_ray)) =
+                    (rays[index], angles[from], rays[from])
+                else {
+                    continue;
+                };
+                angles[index] = Some(base + ray.aop().wrapped_difference(&from_ray.aop()));
+                push_neighbors(index, &angles, &mut frontier);
+            }
+        }
+
+        UnwrappedAop { angles, rows, cols }
+    }
+
+    /// Returns a copy of the image that only keeps the rays selected by `decimation`.
+    ///
+    /// Pixels that are not kept have no ray so the geometry of the image is preserved.
+    ///
+    /// # Panics
+    /// Will panic if `decimation` has a zero step or zero strata.
+    #[must_use]
+    pub fn decimate(&self, decimation: Decimation) -> Self
+    where
+        Frame: Clone,
+    {
+        let mask = decimation.mask(self);
+        Self::from_matrix(Matrix {
+            elements: self
+                .inner
+                .iter()
+                .zip(mask)
+                .map(|(ray, keep)| ray.clone().filter(|_| keep))
+                .collect(),
+            rows: self.rows(),
+            cols: self.cols(),
+        })
+        .with_meta(self.meta)
+    }
+
+    /// Reduces each block of `row_factor` by `col_factor` pixels to their [`Ray::mean`].
+    ///
+    /// Blocks on the bottom and right edges are truncated to fit the image, so the result has
+    /// `rows / row_factor` rows rounded up.
+    /// Blocks without any rays have no ray.
+    ///
+    /// # Panics
+    /// Will panic if `row_factor` or `col_factor` is zero.
+    #[must_use]
+    pub fn downsample(&self, row_factor: usize, col_factor: usize) -> Self
+    where
+        Frame: Copy,
+    {
+        Self::from_matrix(Matrix {
+            elements: self
+                .tiles(row_factor, col_factor)
+                .map(|tile| tile.mean())
+                .collect(),
+            rows: self.rows().div_ceil(row_factor),
+            cols: self.cols().div_ceil(col_factor),
+        })
+        .with_meta(self.meta)
+    }
+
+    /// Returns an iterator over tiles of at most `tile_rows` by `tile_cols` pixels.
+    ///
+    /// Tiles are yielded in row-major order.
+    /// Tiles on the bottom and right edges are truncated to fit the image.
+    ///
+    /// # Panics
+    /// Will panic if `tile_rows` or `tile_cols` is zero.
+    pub fn tiles(
+        &self,
+        tile_rows: usize,
+        tile_cols: usize,
+    ) -> impl Iterator<Item = RayTile<'_, Frame>> {
+        blocks(
+            &self.inner.elements,
+            self.rows(),
+            self.cols(),
+            tile_rows,
+            tile_cols,
+        )
+        .map(|block| RayTile { block })
+    }
+
+    pub fn aop_bytes<M>(&self, color_map: &M) -> Vec<u8>
+    where
+        Frame: Copy,
+        M: RayMap,
+        M::Output: IntoIterator<Item = u8>,
+    {
+        self.aop_bytes_with_convention(color_map, AopConvention::SignedDegrees)
+    }
+
+    /// Maps the [`Aop`] of each pixel to bytes with values represented in `convention`.
+    ///
+    /// [`Aop`]: crate::light::aop::Aop
+    pub fn aop_bytes_with_convention<M>(&self, color_map: &M, convention: AopConvention) -> Vec<u8>
+    where
+        Frame: Copy,
+        M: RayMap,
+        M::Output: IntoIterator<Item = u8>,
+    {
+        let (min, max) = convention.bounds();
+        self.rays()
+            .map(|pixel| pixel.map_or(f64::NAN, |ray| convention.value(ray.aop())))
+            .flat_map(|value| color_map.map(value, min, max))
+            .collect()
+    }
+
+    /// Maps the [`Aop`] of each pixel to bytes like [`RayImage::aop_bytes_with_convention`] and
+    /// composites `legend` to the right of the image.
+    ///
+    /// The result has [`Legend::cols`] more columns than the image.
+    ///
+    /// [`Aop`]: crate::light::aop::Aop
+    pub fn aop_bytes_with_legend<M>(
+        &self,
+        color_map: &M,
+        convention: AopConvention,
+        legend: &Legend,
+    ) -> Vec<u8>
+    where
+        Frame: Copy,
+        M: RayMap,
+        M::Output: IntoIterator<Item = u8> + AsRef<[u8]>,
+    {
+        let unit = match convention {
+            AopConvention::SignedDegrees | AopConvention::AxialDegrees => "deg",
+            AopConvention::SignedRadians | AopConvention::AxialRadians => "rad",
+        };
+
+        legend.compose(
+            &self.aop_bytes_with_convention(color_map, convention),
+            self.rows(),
+            self.cols(),
+            color_map,
+            convention.bounds(),
+            unit,
+        )
+    }
+
+    /// Maps the [`Dop`] of each pixel to bytes like [`RayImage::dop_bytes`] and composites
+    /// `legend` to the right of the image.
+    ///
+    /// The result has [`Legend::cols`] more columns than the image.
+    ///
+    /// [`Dop`]: crate::light::dop::Dop
+    pub fn dop_bytes_with_legend<M>(&self, color_map: &M, legend: &Legend) -> Vec<u8>
+    where
+        M: RayMap,
+        M::Output: IntoIterator<Item = u8> + AsRef<[u8]>,
+    {
+        legend.compose(
+            &self.dop_bytes(color_map),
+            self.rows(),
+            self.cols(),
+            color_map,
+            (0.0, 1.0),
+            "DoP",
+        )
+    }
+
+    pub fn dop_bytes<M>(&self, color_map: &M) -> Vec<u8>
+    where
+        M: RayMap,
+        M::Output: IntoIterator<Item = u8>,
+    {
+        self.rays()
+            .map(|pixel| pixel.map_or(f64::NAN, |ray| f64::from(ray.dop())))
+            .flat_map(|value| color_map.map(value, 0.0, 1.0))
+            .collect()
+    }
+}
+
+/// Resolves pixels that receive more than one ray in [`RayImage::par_from_pixel_rays`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConflictPolicy {
+    /// Keeps the ray with the highest DoP, or the first to arrive among equals.
+    #[default]
+    Strongest,
+
+    /// Keeps the [`Ray::mean`] of the rays.
+    Mean,
+
+    /// Leaves the pixel without a ray.
+    Discard,
+}
+
+impl ConflictPolicy {
+    fn resolve<'a, Frame>(self, rays: impl Iterator<Item = &'a Ray<Frame>>) -> Option<Ray<Frame>>
+    where
+        Frame: Copy + 'a,
+    {
+        match self {
+            Self::Strongest => rays
+                .reduce(|best, ray| {
+                    if f64::from(ray.dop()) > f64::from(best.dop()) {
+                        ray
+                    } else {
+                        best
+                    }
+                })
+                .copied(),
+            Self::Mean => Ray::mean(rays),
+            Self::Discard => None,
+        }
+    }
+}
+
+/// Describes how the rays passed to [`RayImage::par_from_pixel_rays`] were assembled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssemblyReport {
+    received: usize,
+    conflicts: usize,
+    out_of_bounds: usize,
+    filled: usize,
+    pixels: usize,
+}
+
+impl AssemblyReport {
+    /// The number of rays that were passed in.
+    #[must_use]
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// The number of pixels that received more than one ray.
+    #[must_use]
+    pub fn conflicts(&self) -> usize {
+        self.conflicts
+    }
+
+    /// The number of rays outside of the extents of the image that were dropped.
+    #[must_use]
+    pub fn out_of_bounds(&self) -> usize {
+        self.out_of_bounds
+    }
+
+    /// The number of pixels that have a ray.
+    #[must_use]
+    pub fn filled(&self) -> usize {
+        self.filled
+    }
+
+    /// Returns the fraction of pixels that have a ray.
+    ///
+    /// An image without pixels has a fill fraction of zero.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fill_fraction(&self) -> f64 {
+        if self.pixels == 0 {
+            0.0
+        } else {
+            self.filled as f64 / self.pixels as f64
+        }
+    }
+}
+
+/// A continuous field of angles of polarization.
+///
+/// See [`RayImage::unwrap_aop`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnwrappedAop {
+    angles: Vec<Option<Angle>>,
+    rows: usize,
+    cols: usize,
+}
+
+impl UnwrappedAop {
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the unwrapped angle at `row` and `col` if the pixel has a ray.
+    ///
+    /// # Panics
+    /// Will panic if `row` or `col` is outside of the field.
+    #[must_use]
+    pub fn angle(&self, row: usize, col: usize) -> Option<Angle> {
+        assert!(
+            row < self.rows && col < self.cols,
+            "pixel is outside of field"
+        );
+        self.angles[row * self.cols + col]
+    }
+
+    /// Returns the row-major unwrapped angles.
+    #[must_use]
+    pub fn angles(&self) -> &[Option<Angle>] {
+        &self.angles
+    }
+}
+
+pub struct RayPixel<'a, Frame> {
+    ray: Option<&'a Ray<Frame>>,
+    row: usize,
+    col: usize,
+}
+
+impl<'a, Frame> RayPixel<'a, Frame> {
+    #[must_use]
+    pub fn ray(&self) -> Option<&'a Ray<Frame>> {
+        self.ray
+    }
+
+    #[must_use]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    #[must_use]
+    pub fn col(&self) -> usize {
+        self.col
+    }
+}
+
+/// A rectangular block of pixels in a [`RayImage`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RayTile<'a, Frame> {
+    block: Block<'a, Option<Ray<Frame>>>,
+}
+
+impl<'a, Frame> RayTile<'a, Frame> {
+    /// The row of the top left pixel of the tile in the image.
+    #[must_use]
+    pub fn row(&self) -> usize {
+        self.block.row
+    }
+
+    /// The column of the top left pixel of the tile in the image.
+    #[must_use]
+    pub fn col(&self) -> usize {
+        self.block.col
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.block.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.block.cols
+    }
+
+    /// Returns the ray at `row` and `col` relative to the top left pixel of the tile.
+    #[must_use]
+    pub fn ray(&self, row: usize, col: usize) -> Option<&'a Ray<Frame>> {
+        self.block.cell(row, col)?.as_ref()
+    }
+
+    /// Returns an iterator over the pixels of the tile in row-major order.
+    pub fn rays(&self) -> impl Iterator<Item = Option<&'a Ray<Frame>>> + use<'a, Frame> {
+        self.block.iter().map(Option::as_ref)
+    }
+
+    /// Returns the [`Ray::mean`] of the rays in the tile.
+    ///
+    /// Returns `None` if the tile has no rays.
+    #[must_use]
+    pub fn mean(&self) -> Option<Ray<Frame>>
+    where
+        Frame: Copy,
+    {
+        Ray::mean(self.rays().flatten())
+    }
+}
+
+pub trait RayMap {
+    type Output;
+
+    fn map(&self, value: f64, min: f64, max: f64) -> Self::Output;
+}
+
+pub struct Jet;
+impl RayMap for Jet {
+    type Output = [u8; 3];
+
+    fn map(&self, value: f64, min: f64, max: f64) -> Self::Output {
+        if value < min || value > max {
             return [255, 255, 255];
         }
 
@@ -449,6 +2223,12 @@ impl RayMap for Binary {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        light::{aop::Aop, dop::Dop},
+        ray::FrameShift,
+    };
+    use approx::assert_relative_eq;
+    use uom::{ConstZero, si::angle::degree};
 
     #[test]
     fn matrix_cells() {
@@ -468,4 +2248,559 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn superpixel_layout_averages_cells() {
+        // Each polarizer of the standard metapixel covers a 2x2 block.
+        let standard = [100, 145, 55, 10];
+        let bytes: Vec<u8> = (0..8)
+            .flat_map(|row| {
+                (0..8).map(move |col| {
+                    let channel = standard[(row / 2) % 2 * 2 + (col / 2) % 2];
+                    // Vary pixels within a cell while keeping their mean.
+                    if (row + col) % 2 == 0 {
+                        channel + 2
+                    } else {
+                        channel - 2
+                    }
+                })
+            })
+            .collect();
+        let image =
+            IntensityImage::from_bytes_with_layout(8, 8, &bytes, MetapixelLayout::SUPERPIXEL_4X4)
+                .unwrap();
+
+        assert_eq!((image.width(), image.height()), (2, 2));
+        for intensities in image.intensities() {
+            assert_eq!(intensities, [10.0, 55.0, 100.0, 145.0]);
+        }
+        assert!(matches!(
+            IntensityImage::from_bytes_with_layout(8, 8, &bytes[1..], MetapixelLayout::STANDARD),
+            Err(ImageError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn standard_layout_matches_from_bytes() {
+        let bytes: Vec<u8> = (0..48).map(|value| value * 5).collect();
+        let layout =
+            IntensityImage::from_bytes_with_layout(8, 6, &bytes, MetapixelLayout::STANDARD)
+                .unwrap();
+
+        assert_eq!(layout, IntensityImage::from_bytes(8, 6, &bytes).unwrap());
+    }
+
+    #[test]
+    fn combinators_preserve_geometry() {
+        let ray = |aop: f64| {
+            Some(Ray::<SensorFrame>::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+                Dop::clamped(0.5),
+            ))
+        };
+        let meta = FrameMeta::new().with_sequence(7);
+        let measured = RayImage::from_rays([ray(10.0), None, ray(30.0), ray(40.0)], 2, 2)
+            .unwrap()
+            .with_meta(meta);
+        let expected = RayImage::from_rays([ray(15.0), ray(20.0), None, ray(35.0)], 2, 2).unwrap();
+
+        let halved =
+            measured.map(|ray| Ray::new(ray.aop(), Dop::clamped(f64::from(ray.dop()) / 2.0)));
+        assert_eq!(halved.meta().sequence(), Some(7));
+        assert!(halved.ray(0, 1).is_none());
+        assert_relative_eq!(f64::from(halved.ray(1, 1).unwrap().dop()), 0.25);
+
+        let residuals = measured
+            .zip_with(&expected, |measured, expected| {
+                let residual = measured.aop().wrapped_difference(&expected.aop());
+                Some(Ray::<SensorFrame>::new(
+                    Aop::from_angle_wrapped(residual),
+                    measured.dop(),
+                ))
+            })
+            .unwrap();
+        let residual =
+            |row, col| Angle::from(residuals.ray(row, col).unwrap().aop()).get::<degree>();
+        assert_eq!(residuals.rays().flatten().count(), 2);
+        assert_relative_eq!(residual(0, 0), -5.0, epsilon = 1e-9);
+        assert_relative_eq!(residual(1, 1), 5.0, epsilon = 1e-9);
+
+        let masked = measured.filter_map_pixels(|row, _, ray| (row == 0).then_some(*ray));
+        assert_eq!(masked.rays().flatten().count(), 1);
+        assert!(matches!(
+            measured.zip_with(
+                &RayImage::<SensorFrame>::from_rays([None], 1, 1).unwrap(),
+                |a, _| Some(*a)
+            ),
+            Err(ImageError::ExtentsMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn radiometric_stats() {
+        // Metapixels have total intensities of 20, 40, 60, and 255 * 2.
+        let bytes = [
+            10, 10, 20, 20, 30, 30, 255, 255, //
+            10, 10, 20, 20, 30, 30, 255, 255,
+        ];
+        let image = IntensityImage::from_bytes(8, 2, &bytes).unwrap();
+        let stats = image.radiometric_stats(255.0).unwrap();
+
+        assert_eq!(image.s0_image().values(), &[20.0, 40.0, 60.0, 510.0]);
+        assert_relative_eq!(stats.mean(), 157.5);
+        assert_relative_eq!(stats.min(), 20.0);
+        assert_relative_eq!(stats.max(), 510.0);
+        assert_relative_eq!(stats.median(), 50.0);
+        assert_relative_eq!(stats.percentile(100.0 / 3.0), 40.0, epsilon = 1e-9);
+        assert_relative_eq!(stats.saturated_fraction(), 0.25);
+        assert_eq!(
+            image.s0_image().gray16(40.0),
+            vec![32768, 65535, 65535, 65535]
+        );
+    }
+
+    #[test]
+    fn flags_saturated_rays() {
+        // The 90 degree channel of the first metapixel saturates.
+        let image =
+            IntensityImage::from_bytes(4, 2, &[255, 200, 40, 60, 200, 150, 60, 100]).unwrap();
+        let rays: Vec<_> = image.weighted_rays(255.0).collect();
+        assert_eq!(rays.len(), 2);
+        assert!(rays[0].unwrap().flags().contains(RayFlags::SATURATED));
+        assert!(rays[1].unwrap().is_valid());
+        assert!(
+            image
+                .weighted_rays(256.0)
+                .all(|ray| ray.unwrap().flags().is_empty())
+        );
+    }
+
+    #[test]
+    fn normalization_changes_dop() {
+        // A single metapixel with I_0 = 100, I_45 = 60, I_90 = 40, I_135 = 60.
+        let image = IntensityImage::from_bytes(2, 2, &[40, 60, 60, 100]).unwrap();
+        let dop = |normalization| {
+            let image = image.clone().with_normalization(normalization);
+            f64::from(image.rays().next().unwrap().dop())
+        };
+
+        assert_relative_eq!(dop(S0Normalization::Calibrated), 60.0 / 130.0);
+        assert_relative_eq!(dop(S0Normalization::Pair0And90), 60.0 / 140.0);
+        assert_relative_eq!(dop(S0Normalization::Pair45And135), 60.0 / 120.0);
+    }
+
+    #[test]
+    fn meta_propagates() {
+        let meta = FrameMeta::new().with_gain(2.0).with_sequence(7);
+        let image = IntensityImage::from_bytes(2, 2, &[40, 60, 60, 100])
+            .unwrap()
+            .with_meta(meta);
+        let rays = image
+            .stokes_image()
+            .ray_image()
+            .decimate(Decimation::Stride { step: 1 })
+            .transform(&FrameShift::<SensorFrame, GlobalFrame>::new(Angle::ZERO));
+
+        assert_eq!(rays.meta(), &meta);
+        assert_eq!(image.ray_image().meta(), &meta);
+
+        let bytes = [40, 60, 60, 100];
+        let view = IntensityView::new(2, 2, &bytes).unwrap().with_meta(meta);
+        assert_eq!(view.ray_image().meta(), &meta);
+        assert_eq!(view.to_image().meta(), &meta);
+    }
+
+    #[test]
+    fn view_matches_image() {
+        let bytes: Vec<u8> = (0..48).map(|i| 10 + i * 3).collect();
+        let view = IntensityView::new(8, 6, &bytes)
+            .unwrap()
+            .with_normalization(S0Normalization::Pair0And90);
+        let image = IntensityImage::from_bytes(8, 6, &bytes)
+            .unwrap()
+            .with_normalization(S0Normalization::Pair0And90);
+
+        assert_eq!((view.width(), view.height()), (4, 3));
+        assert!(view.intensities().eq(image.intensities()));
+        assert!(view.rays().flatten().eq(image.rays()));
+        assert_eq!(view.ray_image(), image.ray_image());
+        assert_eq!(view.to_image(), image);
+        assert!(IntensityView::new(8, 5, &bytes).is_err());
+    }
+
+    #[test]
+    fn lenient_decode_recovers_complete_rows() {
+        let bytes: Vec<u8> = (0..48).map(|i| 10 + i * 3).collect();
+
+        // Drop the last raw row and part of the one before it.
+        let (image, report) = IntensityImage::from_bytes_lenient(8, 6, &bytes[..37]);
+        let complete = IntensityImage::from_bytes(8, 6, &bytes).unwrap();
+
+        assert_eq!(report.complete_rows(), 2);
+        assert_eq!(report.missing_rows(), 1);
+        assert_eq!(report.missing_bytes(), 11);
+        assert!(!report.is_complete());
+        assert!(image.rays().eq(complete.rays().take(8)));
+        assert_eq!(image.try_rays().filter(Result::is_err).count(), 4);
+        assert!(image.radiometric_stats(255.0).is_some());
+    }
+
+    #[test]
+    fn unwrap_aop_removes_jumps() {
+        // A ramp of angles that wraps from 90 to -90 degrees along each row.
+        let rays = (0..3).flat_map(|row| {
+            (0..8).map(move |col| {
+                let angle = Angle::new::<degree>(60.0 + 10.0 * f64::from(col));
+                let dop = if row == 1 && col == 0 { 0.9 } else { 0.5 };
+                Some(Ray::<SensorFrame>::new(
+                    Aop::from_angle_wrapped(angle),
+                    Dop::clamped(dop),
+                ))
+            })
+        });
+        let image = RayImage::from_rays(rays, 3, 8).unwrap();
+        let unwrapped = image.unwrap_aop();
+
+        for (row, col) in (0..3).flat_map(|row| (0..8).map(move |col| (row, col))) {
+            assert_relative_eq!(
+                unwrapped.angle(row, col).unwrap().get::<degree>(),
+                60.0 + 10.0 * col as f64,
+                epsilon = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn unwrap_aop_seeds_each_region() {
+        let ray = |angle: f64| {
+            Some(Ray::<SensorFrame>::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(angle)),
+                Dop::clamped(0.5),
+            ))
+        };
+        let image =
+            RayImage::from_rays(vec![ray(80.0), None, ray(-85.0), ray(-75.0)], 1, 4).unwrap();
+        let unwrapped = image.unwrap_aop();
+
+        assert_eq!(unwrapped.angle(0, 1), None);
+        assert_relative_eq!(
+            unwrapped.angle(0, 0).unwrap().get::<degree>(),
+            80.0,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            unwrapped.angle(0, 3).unwrap().get::<degree>()
+                - unwrapped.angle(0, 2).unwrap().get::<degree>(),
+            10.0,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn stokes_image_matches_rays() {
+        let bytes: Vec<u8> = (100..148).collect();
+        let image = IntensityImage::from_bytes(8, 6, &bytes).unwrap();
+        let stokes = image.stokes_image();
+
+        assert_eq!((stokes.rows(), stokes.cols()), (3, 4));
+        let rays: Vec<_> = stokes.ray_image().rays().map(|ray| ray.copied()).collect();
+        let expected: Vec<_> = image.try_rays().map(Result::ok).collect();
+        assert_eq!(rays, expected);
+    }
+
+    #[test]
+    fn stokes_image_frame_roundtrip() {
+        let bytes: Vec<u8> = (100..148).collect();
+        let stokes = IntensityImage::from_bytes(8, 6, &bytes)
+            .unwrap()
+            .stokes_image();
+        let shift = |row: usize, col: usize| Angle::new::<degree>((row * 10 + col) as f64);
+
+        let result = stokes.clone().par_into_global_frame(shift);
+        let expected = stokes.stokes(2, 3).into_global_frame(shift(2, 3));
+        assert_relative_eq!(result.stokes(2, 3).s1(), expected.s1(), epsilon = 1e-9);
+        assert_relative_eq!(result.stokes(2, 3).s2(), expected.s2(), epsilon = 1e-9);
+
+        let roundtrip = result.par_into_sensor_frame(shift);
+        for (a, b) in roundtrip.s2().iter().zip(stokes.s2()) {
+            assert_relative_eq!(a, b, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn invalid_pixels() {
+        // The second metapixel has no intensity.
+        let bytes = [10, 20, 0, 0, 30, 40, 0, 0];
+        let image = IntensityImage::from_bytes(4, 2, &bytes).unwrap();
+
+        assert_eq!(image.rays().count(), 1);
+        assert!(matches!(
+            image.try_rays().nth(1),
+            Some(Err(ImageError::InvalidPixel {
+                row: 0,
+                col: 1,
+                source: LightError::NonFinite { .. },
+            }))
+        ));
+    }
+
+    #[test]
+    fn skip_to_pixel() {
+        let bytes: Vec<u8> = (100..148).collect();
+        let image = IntensityImage::from_bytes(8, 6, &bytes).unwrap();
+        let rays: Vec<_> = image.try_rays().map(Result::unwrap).collect();
+
+        let mut iter = image.try_rays().skip_to(1, 2);
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.next().unwrap().unwrap(), rays[6]);
+        assert_eq!(iter.next_back().unwrap().unwrap(), rays[11]);
+        assert_eq!(iter.len(), 4);
+
+        assert_eq!(image.rays().skip_to(2, 3).collect::<Vec<_>>(), rays[11..]);
+        assert_eq!(image.rays().skip_to(3, 0).count(), 0);
+        assert_eq!(
+            image.rays().rev().collect::<Vec<_>>(),
+            rays.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn ray_tiles() {
+        let rays = (0..15).map(|i| {
+            (i % 2 == 0).then(|| {
+                Ray::<SensorFrame>::new(
+                    Aop::from_angle_wrapped(Angle::ZERO),
+                    Dop::clamped(f64::from(i) / 15.),
+                )
+            })
+        });
+        let image = RayImage::from_rays(rays, 3, 5).unwrap();
+        let tiles: Vec<_> = image.tiles(2, 2).collect();
+
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(
+            tiles
+                .iter()
+                .map(|tile| (tile.row(), tile.col(), tile.rows(), tile.cols()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 0, 2, 2),
+                (0, 2, 2, 2),
+                (0, 4, 2, 1),
+                (2, 0, 1, 2),
+                (2, 2, 1, 2),
+                (2, 4, 1, 1),
+            ]
+        );
+        for tile in &tiles {
+            for (i, ray) in tile.rays().enumerate() {
+                let (row, col) = (i / tile.cols(), i % tile.cols());
+                assert_eq!(ray, image.ray(tile.row() + row, tile.col() + col));
+                assert_eq!(ray, tile.ray(row, col));
+            }
+        }
+        assert_eq!(tiles[0].ray(2, 0), None);
+    }
+
+    #[test]
+    fn downsample_averages_blocks() {
+        let ray = |aop: f64| {
+            Some(Ray::<SensorFrame>::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+                Dop::clamped(0.5),
+            ))
+        };
+        let rays = [ray(80.0), ray(-80.0), ray(10.0), None, None, None];
+        let image = RayImage::from_rays(rays, 2, 3)
+            .unwrap()
+            .with_meta(FrameMeta::new().with_sequence(3));
+        let downsampled = image.downsample(2, 2);
+
+        assert_eq!((downsampled.rows(), downsampled.cols()), (1, 2));
+        let mean = downsampled.ray(0, 0).unwrap();
+        assert_relative_eq!(
+            Angle::from(mean.aop()).get::<degree>().abs(),
+            90.0,
+            epsilon = 1e-9
+        );
+        assert_eq!(downsampled.ray(0, 1), image.ray(0, 2));
+        assert_eq!(downsampled.meta(), image.meta());
+        assert_eq!(image.downsample(1, 1), image);
+    }
+
+    #[test]
+    fn vignetting_roundtrip() {
+        use crate::optic::{PinholeOptic, Vignetting};
+        use uom::si::{
+            f64::Length,
+            length::{micron, millimeter},
+        };
+
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(1.0)),
+            Length::new::<micron>(200.0),
+            3,
+            3,
+        )
+        .with_vignetting(Vignetting::new([-0.8, 0.0, 0.0]));
+        let image = IntensityImage::from_intensities(3, 3, vec![[100.0, 50.0, 0.0, 50.0]; 9])
+            .unwrap()
+            .with_meta(FrameMeta::new().with_sequence(7));
+
+        let vignetted = image.apply_vignetting(&camera).unwrap();
+        let intensities: Vec<_> = vignetted.intensities().collect();
+        assert_relative_eq!(intensities[4][0], 100.0);
+        assert!(intensities[0][0] < intensities[1][0]);
+        assert_eq!(vignetted.meta(), image.meta());
+
+        // Vignetting scales every channel equally, so the rays are unchanged.
+        for (lhs, rhs) in vignetted.rays().zip(image.rays()) {
+            assert_relative_eq!(f64::from(lhs.dop()), f64::from(rhs.dop()), epsilon = 1e-12);
+        }
+
+        let corrected = vignetted.correct_vignetting(&camera).unwrap();
+        for (lhs, rhs) in corrected.intensities().zip(image.intensities()) {
+            for (lhs, rhs) in lhs.iter().zip(rhs) {
+                assert_relative_eq!(*lhs, rhs, epsilon = 1e-9);
+            }
+        }
+
+        let small = IntensityImage::from_intensities(2, 3, vec![[1.0; 4]; 6]).unwrap();
+        assert!(matches!(
+            small.correct_vignetting(&camera),
+            Err(ImageError::ExtentsMismatch {
+                expected_rows: 3,
+                expected_cols: 3,
+                rows: 3,
+                cols: 2,
+            })
+        ));
+
+        // Rendering applies the same vignetting to the intensities of the Stokes vectors.
+        let rendered = image
+            .stokes_image()
+            .render(image.calibration(), &camera)
+            .unwrap();
+        assert_eq!(rendered.meta(), image.meta());
+        for (lhs, rhs) in rendered.intensities().zip(vignetted.intensities()) {
+            for (lhs, rhs) in lhs.iter().zip(rhs) {
+                assert_relative_eq!(*lhs, rhs, epsilon = 1e-9);
+            }
+        }
+        assert!(
+            small
+                .stokes_image()
+                .render(small.calibration(), &camera)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn assembles_scattered_rays() {
+        let ray = |aop: f64, dop: f64| {
+            Ray::<SensorFrame>::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+                Dop::clamped(dop),
+            )
+        };
+        let (rows, cols) = (40, 7);
+        let expected: Vec<_> = (0..rows * cols)
+            .map(|index| (index % 3 != 0).then(|| ray(index as f64 % 180.0 - 90.0, 0.4)))
+            .collect();
+
+        // Scatter the rays in reverse with a conflict in the last band and two strays.
+        let mut rays: Vec<_> = expected
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(index, ray)| {
+                Some((PixelCoordinate::new(index / cols, index % cols), (*ray)?))
+            })
+            .collect();
+        rays.push((PixelCoordinate::new(39, 1), ray(0.0, 0.9)));
+        rays.push((PixelCoordinate::new(rows, 0), ray(0.0, 0.9)));
+        rays.push((PixelCoordinate::new(0, cols), ray(0.0, 0.9)));
+
+        let (image, report) =
+            RayImage::par_from_pixel_rays(rays.clone(), rows, cols, ConflictPolicy::Strongest);
+        assert_eq!(image.ray(39, 1), Some(&ray(0.0, 0.9)));
+        assert_eq!(image.ray(39, 0), None);
+        assert_eq!(image.ray(12, 4), expected[12 * cols + 4].as_ref());
+        assert_eq!(report.received(), rays.len());
+        assert_eq!(report.conflicts(), 1);
+        assert_eq!(report.out_of_bounds(), 2);
+        assert_eq!(report.filled(), expected.iter().flatten().count());
+        assert_relative_eq!(
+            report.fill_fraction(),
+            report.filled() as f64 / (rows * cols) as f64
+        );
+
+        let (image, _) =
+            RayImage::par_from_pixel_rays(rays.clone(), rows, cols, ConflictPolicy::Discard);
+        assert_eq!(image.ray(39, 1), None);
+        assert_eq!(image.ray(39, 2), expected[39 * cols + 2].as_ref());
+
+        let (image, _) = RayImage::par_from_pixel_rays(rays, rows, cols, ConflictPolicy::Mean);
+        assert_eq!(
+            image.ray(39, 1).copied(),
+            Ray::mean([&expected[39 * cols + 1].unwrap(), &ray(0.0, 0.9)])
+        );
+    }
+
+    #[test]
+    fn intensity_tiles() {
+        let bytes: Vec<u8> = (100..148).collect();
+        let image = IntensityImage::from_bytes(8, 6, &bytes).unwrap();
+        let rays: Vec<_> = image.rays().collect();
+
+        let tiled: Vec<_> = image
+            .tiles(3, 3)
+            .flat_map(|tile| tile.rays().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(tiled.len(), rays.len());
+        assert_eq!(
+            image.tiles(3, 3).nth(1).unwrap().rays().next(),
+            Some(rays[3])
+        );
+    }
+
+    #[test]
+    fn from_intensities_roundtrip() {
+        let bytes: Vec<u8> = (100..148).collect();
+        let image = IntensityImage::from_bytes(8, 6, &bytes).unwrap();
+
+        let rebuilt = IntensityImage::from_intensities(4, 3, image.intensities()).unwrap();
+        assert_eq!(rebuilt, image);
+        assert!(matches!(
+            IntensityImage::from_intensities(4, 2, image.intensities()),
+            Err(ImageError::SizeMismatch { len: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn from_planes_matches_mosaic() {
+        let bytes: Vec<u8> = (100..148).collect();
+        let image = IntensityImage::from_bytes(8, 6, &bytes).unwrap();
+
+        let planes: [Vec<u8>; 4] = std::array::from_fn(|k| {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            image.intensities().map(|i| i[k] as u8).collect()
+        });
+        let planes = [&planes[0][..], &planes[1], &planes[2], &planes[3]];
+        assert_eq!(IntensityImage::from_planes(4, 3, &planes).unwrap(), image);
+
+        let wide = planes.map(|plane| plane.iter().map(|i| u16::from(*i) << 4).collect::<Vec<_>>());
+        let wide =
+            IntensityImage::from_planes16(4, 3, &[&wide[0], &wide[1], &wide[2], &wide[3]]).unwrap();
+        assert!(
+            wide.intensities()
+                .zip(image.intensities())
+                .all(|(wide, narrow)| wide == narrow.map(|i| i * 16.0))
+        );
+
+        assert!(matches!(
+            IntensityImage::from_planes(4, 3, &[&planes[0][..11], planes[1], planes[2], planes[3]]),
+            Err(ImageError::SizeMismatch { len: 11, .. })
+        ));
+    }
 }
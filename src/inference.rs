@@ -0,0 +1,294 @@
+//! Hooks for learned models that complement the analytic estimators.
+//!
+//! Rumpus does not bundle an inference runtime.
+//! Implement [`InferenceModel`] for a session of your runtime, e.g., an ONNX runtime session,
+//! and wrap it in a [`LearnedMask`] to filter rays or a [`LearnedHeading`] to seed estimators.
+
+use crate::{
+    estimate::fusion::HeadingEstimate,
+    image::StokesImage,
+    mask::{MaskError, PixelMask},
+};
+use thiserror::Error;
+use uom::{
+    ConstZero,
+    si::{angle::radian, f64::Angle},
+};
+
+#[derive(Debug, Error)]
+pub enum InferenceError {
+    #[error("model failed to run")]
+    Model(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("model output has the wrong length: expected {expected} found {len}")]
+    OutputSize { expected: usize, len: usize },
+
+    #[error(transparent)]
+    Mask(#[from] MaskError),
+}
+
+/// The input tensor of an [`InferenceModel`].
+///
+/// Holds the total intensity, the angle of polarization in radians, and the degree of
+/// polarization of each pixel as three planes of 32 bit floats in `[1, 3, rows, cols]` layout,
+/// which is the batch, channel, height, width order expected by most vision models.
+/// Pixels without a ray have an AoP and DoP of zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channels {
+    values: Vec<f32>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Channels {
+    /// The number of channels in the tensor.
+    pub const COUNT: usize = 3;
+
+    /// Builds the channels from the Stokes vectors of `image`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_stokes<Frame: Copy + Send + Sync>(image: &StokesImage<Frame>) -> Self {
+        let rays = image.ray_image();
+        let (aop, dop): (Vec<f32>, Vec<f32>) = rays
+            .rays()
+            .map(|ray| {
+                ray.map_or((0.0, 0.0), |ray| {
+                    (
+                        Angle::from(ray.aop()).get::<radian>() as f32,
+                        f64::from(ray.dop()) as f32,
+                    )
+                })
+            })
+            .unzip();
+
+        Self {
+            values: image
+                .s0()
+                .iter()
+                .map(|s0| *s0 as f32)
+                .chain(aop)
+                .chain(dop)
+                .collect(),
+            rows: image.rows(),
+            cols: image.cols(),
+        }
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the shape of the tensor.
+    #[must_use]
+    pub fn shape(&self) -> [usize; 4] {
+        [1, Self::COUNT, self.rows, self.cols]
+    }
+
+    /// Returns the values of the tensor in row-major order.
+    #[must_use]
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+}
+
+/// A learned model that maps [`Channels`] to an output tensor.
+pub trait InferenceModel {
+    /// Runs the model and returns its output flattened in row-major order.
+    ///
+    /// # Errors
+    /// Will return `Err` if the model fails to run.
+    fn infer(&self, input: &Channels) -> Result<Vec<f32>, InferenceError>;
+}
+
+impl<F> InferenceModel for F
+where
+    F: Fn(&Channels) -> Result<Vec<f32>, InferenceError>,
+{
+    fn infer(&self, input: &Channels) -> Result<Vec<f32>, InferenceError> {
+        self(input)
+    }
+}
+
+/// Masks pixels with a model that outputs the probability that each pixel is occluded, e.g., by
+/// clouds.
+///
+/// The model must output one probability per pixel.
+/// Apply the resulting [`PixelMask`] to a [`crate::image::RayImage`] before estimating.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LearnedMask<M> {
+    model: M,
+    threshold: f32,
+}
+
+impl<M: InferenceModel> LearnedMask<M> {
+    /// Creates a new `LearnedMask` that drops pixels with a probability of at least one half.
+    #[must_use]
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            threshold: 0.5,
+        }
+    }
+
+    /// Sets the probability at which pixels are dropped.
+    #[must_use]
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    #[must_use]
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Runs the model on `input` and keeps the pixels below the threshold.
+    ///
+    /// # Errors
+    /// Will return `Err` if the model fails or does not output one value per pixel.
+    pub fn mask(&self, input: &Channels) -> Result<PixelMask, InferenceError> {
+        let output = self.model.infer(input)?;
+        let expected = input.rows() * input.cols();
+        if output.len() != expected {
+            return Err(InferenceError::OutputSize {
+                expected,
+                len: output.len(),
+            });
+        }
+
+        let keep = output.iter().map(|p| *p < self.threshold).collect();
+        Ok(PixelMask::new(keep, input.rows(), input.cols())?)
+    }
+}
+
+/// Produces a coarse heading prior with a model that outputs the cosine and sine of the heading.
+///
+/// Use the prior to initialize or gate the analytic estimators, or fuse it with them through
+/// [`crate::estimate::fusion::fuse`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LearnedHeading<M> {
+    model: M,
+    uncertainty: Angle,
+}
+
+impl<M: InferenceModel> LearnedHeading<M> {
+    /// Creates a new `LearnedHeading` whose estimates carry `uncertainty`, e.g., the error of the
+    /// model on a validation set.
+    ///
+    /// # Panics
+    /// Will panic if `uncertainty` is not greater than zero.
+    #[must_use]
+    pub fn new(model: M, uncertainty: Angle) -> Self {
+        assert!(
+            uncertainty > Angle::ZERO,
+            "uncertainty must be greater than zero: {uncertainty:#?}"
+        );
+
+        Self { model, uncertainty }
+    }
+
+    #[must_use]
+    pub fn uncertainty(&self) -> Angle {
+        self.uncertainty
+    }
+
+    /// Runs the model on `input`.
+    ///
+    /// Returns `Ok(None)` if the cosine and sine are both zero.
+    ///
+    /// # Errors
+    /// Will return `Err` if the model fails or does not output exactly two values.
+    pub fn estimate(&self, input: &Channels) -> Result<Option<HeadingEstimate>, InferenceError> {
+        let output = self.model.infer(input)?;
+        let [cos, sin] = output[..] else {
+            return Err(InferenceError::OutputSize {
+                expected: 2,
+                len: output.len(),
+            });
+        };
+
+        if cos == 0.0 && sin == 0.0 {
+            return Ok(None);
+        }
+
+        Ok(Some(HeadingEstimate::new(
+            Angle::new::<radian>(f64::from(sin).atan2(f64::from(cos))),
+            self.uncertainty,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::IntensityImage;
+    use approx::assert_relative_eq;
+    use uom::si::angle::degree;
+
+    fn channels() -> Channels {
+        // A polarized metapixel beside an unpolarized one.
+        let image =
+            IntensityImage::from_intensities(2, 1, [[100.0, 50.0, 0.0, 50.0], [50.0; 4]]).unwrap();
+        Channels::from_stokes(&image.stokes_image())
+    }
+
+    #[test]
+    fn lays_out_planes() {
+        let channels = channels();
+        assert_eq!(channels.shape(), [1, 3, 1, 2]);
+
+        let [s0_a, s0_b, aop_a, _, dop_a, dop_b] = channels.values()[..] else {
+            panic!("expected six values");
+        };
+        assert!(s0_a > 0.0 && s0_b > 0.0);
+        assert_relative_eq!(aop_a, 0.0);
+        assert_relative_eq!(dop_a, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(dop_b, 0.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn masks_probable_occlusions() {
+        // Treat unpolarized pixels as cloud.
+        let model = |input: &Channels| -> Result<Vec<f32>, InferenceError> {
+            let dop = &input.values()[2 * input.rows() * input.cols()..];
+            Ok(dop.iter().map(|dop| 1.0 - dop).collect())
+        };
+        let mask = LearnedMask::new(model).mask(&channels()).unwrap();
+        assert!(mask.keeps(0, 0));
+        assert!(!mask.keeps(0, 1));
+
+        let short = |_: &Channels| -> Result<Vec<f32>, InferenceError> { Ok(vec![0.0]) };
+        assert!(matches!(
+            LearnedMask::new(short).mask(&channels()),
+            Err(InferenceError::OutputSize {
+                expected: 2,
+                len: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn decodes_heading_prior() {
+        let model = |_: &Channels| -> Result<Vec<f32>, InferenceError> { Ok(vec![0.0, 2.0]) };
+        let prior = LearnedHeading::new(model, Angle::new::<degree>(15.0))
+            .estimate(&channels())
+            .unwrap()
+            .unwrap();
+        assert_relative_eq!(prior.heading().get::<degree>(), 90.0, epsilon = 1e-9);
+        assert_relative_eq!(prior.uncertainty().get::<degree>(), 15.0);
+
+        let zero = |_: &Channels| -> Result<Vec<f32>, InferenceError> { Ok(vec![0.0, 0.0]) };
+        assert_eq!(
+            LearnedHeading::new(zero, Angle::new::<degree>(15.0))
+                .estimate(&channels())
+                .unwrap(),
+            None
+        );
+    }
+}
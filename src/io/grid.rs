@@ -0,0 +1,413 @@
+//! A plain text format for single [`RayImage`]s, e.g., simulated outputs kept for later
+//! comparison.
+//!
+//! A grid starts with a version line and the extents of the image, followed by optional
+//! metadata lines and two blocks of whitespace separated values in row-major order.
+//! Angles are in radians, as in [`crate::io::sequence`], and empty pixels are written as `NaN`
+//! in both blocks.
+//! Floats are written with the shortest representation that reads back to the same value, so the
+//! pixels of a grid reload exactly.
+//!
+//! ```text
+//! rumpus-grid 1
+//! extents <rows> <cols>
+//! time <RFC 3339>                           (optional)
+//! exposure <seconds>                        (optional)
+//! gain <gain>                               (optional)
+//! sequence <number>                         (optional)
+//! position <latitude> <longitude> <meters>  (optional)
+//! solar <azimuth> <elevation>               (optional)
+//! aop
+//! <cols AoP values>
+//! ...                                       (rows lines)
+//! dop
+//! <cols DoP values>
+//! ...                                       (rows lines)
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored, so a grid can be stamped with the
+//! comments of a [`Provenance`] using [`write_grid_with_provenance`].
+
+use crate::{
+    image::{ImageError, RayImage},
+    light::{LightError, aop::Aop, dop::Dop},
+    meta::FrameMeta,
+    model::ephemeris::SolarPosition,
+    provenance::Provenance,
+    ray::Ray,
+};
+use chrono::{DateTime, Utc};
+use sguaba::systems::Wgs84;
+use std::io::{BufRead, Write};
+use thiserror::Error;
+use uom::si::{
+    angle::radian,
+    f64::{Angle, Length, Time},
+    length::meter,
+    time::second,
+};
+
+const HEADER: &str = "rumpus-grid";
+const VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum GridError {
+    #[error("failed to access grid")]
+    Io(#[from] std::io::Error),
+
+    #[error("stream is not a ray grid")]
+    BadHeader,
+
+    #[error("unsupported grid version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("malformed grid at line {line}: {reason}")]
+    Malformed { line: usize, reason: &'static str },
+
+    #[error("grid contains an invalid ray")]
+    InvalidRay(#[from] LightError),
+
+    #[error("failed to assemble grid")]
+    Image(#[from] ImageError),
+}
+
+/// Writes `image` and its metadata as a grid.
+///
+/// # Errors
+/// Will return `Err` if writing to `writer` fails.
+pub fn write_grid<Frame: Copy>(
+    mut writer: impl Write,
+    image: &RayImage<Frame>,
+) -> Result<(), GridError> {
+    writeln!(writer, "{HEADER} {VERSION}")?;
+    writeln!(writer, "extents {} {}", image.rows(), image.cols())?;
+
+    let meta = image.meta();
+    if let Some(time) = meta.time() {
+        writeln!(writer, "time {}", time.to_rfc3339())?;
+    }
+    if let Some(exposure) = meta.exposure() {
+        writeln!(writer, "exposure {}", exposure.get::<second>())?;
+    }
+    if let Some(gain) = meta.gain() {
+        writeln!(writer, "gain {gain}")?;
+    }
+    if let Some(sequence) = meta.sequence() {
+        writeln!(writer, "sequence {sequence}")?;
+    }
+    if let Some(position) = meta.position() {
+        writeln!(
+            writer,
+            "position {} {} {}",
+            position.latitude().get::<radian>(),
+            position.longitude().get::<radian>(),
+            position.altitude().get::<meter>()
+        )?;
+    }
+
+    if let Some(solar) = meta.solar_position() {
+        writeln!(
+            writer,
+            "solar {} {}",
+            solar.azimuth().get::<radian>(),
+            solar.elevation().get::<radian>()
+        )?;
+    }
+
+    write_block(&mut writer, image, "aop", |ray| {
+        Angle::from(ray.aop()).get::<radian>()
+    })?;
+    write_block(&mut writer, image, "dop", |ray| f64::from(ray.dop()))?;
+
+    Ok(())
+}
+
+/// Writes `image` as a grid preceded by the comments of `provenance`.
+///
+/// See [`Provenance::write_comments`].
+///
+/// # Errors
+/// Will return `Err` if writing to `writer` fails.
+pub fn write_grid_with_provenance<Frame: Copy>(
+    mut writer: impl Write,
+    image: &RayImage<Frame>,
+    provenance: &Provenance,
+) -> Result<(), GridError> {
+    provenance.write_comments(&mut writer)?;
+    write_grid(writer, image)
+}
+
+/// Reads a grid written by [`write_grid`].
+///
+/// # Errors
+/// Will return `Err` if `reader` fails or does not hold a valid grid.
+pub fn read_grid<Frame>(reader: impl BufRead) -> Result<RayImage<Frame>, GridError> {
+    let mut lines = reader
+        .lines()
+        .enumerate()
+        .map(|(index, line)| line.map(|line| (index + 1, line)))
+        .filter(|line| {
+            line.as_ref().map_or(true, |(_, line)| {
+                !line.trim().is_empty() && !line.starts_with('#')
+            })
+        });
+    let mut next = |reason| match lines.next() {
+        Some(line) => Ok(line?),
+        None => Err(GridError::Malformed { line: 0, reason }),
+    };
+
+    let (_, header) = next("missing header")?;
+    let version = match header.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [HEADER, version] => version.parse().map_err(|_| GridError::BadHeader)?,
+        _ => return Err(GridError::BadHeader),
+    };
+    if version != VERSION {
+        return Err(GridError::UnsupportedVersion(version));
+    }
+
+    let (line, extents) = next("missing extents")?;
+    let malformed = |line, reason| GridError::Malformed { line, reason };
+    let (rows, cols): (usize, usize) =
+        match extents.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["extents", rows, cols] => (
+                rows.parse().map_err(|_| malformed(line, "invalid rows"))?,
+                cols.parse().map_err(|_| malformed(line, "invalid cols"))?,
+            ),
+            _ => return Err(malformed(line, "expected extents")),
+        };
+    if rows.checked_mul(cols).is_none() {
+        return Err(malformed(line, "extents are too large"));
+    }
+
+    let mut meta = FrameMeta::new();
+    let float = |line, value: &str| {
+        value
+            .parse::<f64>()
+            .map_err(|_| malformed(line, "invalid number"))
+    };
+    loop {
+        let (line, text) = next("missing aop block")?;
+        let fields: Vec<_> = text.split_whitespace().collect();
+        meta = match fields.as_slice() {
+            ["aop"] => break,
+            ["time", time] => meta.with_time(
+                time.parse::<DateTime<Utc>>()
+                    .map_err(|_| malformed(line, "invalid time"))?,
+            ),
+            ["exposure", exposure] => {
+                meta.with_exposure(Time::new::<second>(float(line, exposure)?))
+            }
+            ["gain", gain] => meta.with_gain(float(line, gain)?),
+            ["sequence", sequence] => meta.with_sequence(
+                sequence
+                    .parse()
+                    .map_err(|_| malformed(line, "invalid sequence"))?,
+            ),
+            ["position", latitude, longitude, altitude] => meta.with_position(
+                Wgs84::builder()
+                    .latitude(Angle::new::<radian>(float(line, latitude)?))
+                    .ok_or(malformed(line, "invalid latitude"))?
+                    .longitude(Angle::new::<radian>(float(line, longitude)?))
+                    .altitude(Length::new::<meter>(float(line, altitude)?))
+                    .build(),
+            ),
+            ["solar", azimuth, elevation] => meta.with_solar_position(SolarPosition::new(
+                Angle::new::<radian>(float(line, azimuth)?),
+                Angle::new::<radian>(float(line, elevation)?),
+            )),
+            _ => return Err(malformed(line, "unknown metadata")),
+        };
+    }
+
+    let aop = read_block(&mut next, rows, cols, "missing aop row")?;
+    let (line, dop_header) = next("missing dop block")?;
+    if dop_header.trim() != "dop" {
+        return Err(malformed(line, "expected dop block"));
+    }
+    let dop = read_block(&mut next, rows, cols, "missing dop row")?;
+
+    let rays = aop
+        .into_iter()
+        .zip(dop)
+        .map(|(aop, dop)| {
+            if aop.is_nan() || dop.is_nan() {
+                return Ok(None);
+            }
+            Ok(Some(Ray::new(
+                Aop::try_from_angle(Angle::new::<radian>(aop))?,
+                Dop::try_new(dop)?,
+            )))
+        })
+        .collect::<Result<Vec<_>, LightError>>()?;
+
+    Ok(RayImage::from_rays(rays, rows, cols)?.with_meta(meta))
+}
+
+/// Writes the `name` block with one line of values for each row of `image`.
+fn write_block<Frame>(
+    writer: &mut impl Write,
+    image: &RayImage<Frame>,
+    name: &str,
+    value: impl Fn(&Ray<Frame>) -> f64,
+) -> Result<(), GridError> {
+    writeln!(writer, "{name}")?;
+    for row in 0..image.rows() {
+        let line: Vec<String> = (0..image.cols())
+            .map(|col| image.ray(row, col).map_or(f64::NAN, &value).to_string())
+            .collect();
+        writeln!(writer, "{}", line.join(" "))?;
+    }
+
+    Ok(())
+}
+
+/// Reads `rows` lines of `cols` values each.
+fn read_block(
+    next: &mut impl FnMut(&'static str) -> Result<(usize, String), GridError>,
+    rows: usize,
+    cols: usize,
+    reason: &'static str,
+) -> Result<Vec<f64>, GridError> {
+    // Values are not reserved up front since the extents are not trusted.
+    let mut values = Vec::new();
+    for _ in 0..rows {
+        let (line, text) = next(reason)?;
+        let row = text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| GridError::Malformed {
+                line,
+                reason: "invalid number",
+            })?;
+        if row.len() != cols {
+            return Err(GridError::Malformed {
+                line,
+                reason: "row does not match extents",
+            });
+        }
+        values.extend(row);
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::GlobalFrame;
+    use approx::assert_relative_eq;
+    use uom::si::angle::degree;
+
+    fn image() -> RayImage<GlobalFrame> {
+        let ray = |aop: f64, dop: f64| {
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+                Dop::clamped(dop),
+            ))
+        };
+        let position = Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2187))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.4747))
+            .altitude(Length::new::<meter>(93.5))
+            .build();
+        let meta = FrameMeta::new()
+            .with_time("2025-06-13T16:26:47.25+00:00".parse().unwrap())
+            .with_exposure(Time::new::<second>(0.002))
+            .with_gain(1.5)
+            .with_sequence(12)
+            .with_position(position)
+            .with_solar_position(SolarPosition::new(
+                Angle::new::<degree>(143.5),
+                Angle::new::<degree>(61.25),
+            ));
+
+        RayImage::from_rays(
+            [
+                ray(12.345, 0.1),
+                None,
+                ray(-89.9, 0.99),
+                ray(1.0 / 3.0, 0.3),
+            ],
+            2,
+            2,
+        )
+        .unwrap()
+        .with_meta(meta)
+    }
+
+    #[test]
+    fn roundtrip_preserves_pixels_and_meta() {
+        let mut bytes = Vec::new();
+        write_grid(&mut bytes, &image()).unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.starts_with("rumpus-grid 1\nextents 2 2\n"));
+        assert!(text.contains(" NaN\n"));
+
+        let reloaded: RayImage<GlobalFrame> = read_grid(bytes.as_slice()).unwrap();
+        let (meta, expected) = (*reloaded.meta(), *image().meta());
+        assert_eq!(meta.time(), expected.time());
+        assert_eq!(meta.exposure(), expected.exposure());
+        assert_eq!(meta.gain(), expected.gain());
+        assert_eq!(meta.sequence(), expected.sequence());
+        assert_eq!(meta.solar_position(), expected.solar_position());
+
+        // Wgs84 normalizes the longitude it hands out, so only the pixels reload bit for bit.
+        let (position, expected_position) =
+            (meta.position().unwrap(), expected.position().unwrap());
+        assert_relative_eq!(
+            position.longitude().get::<radian>(),
+            expected_position.longitude().get::<radian>()
+        );
+        assert_eq!(position.latitude(), expected_position.latitude());
+        assert_eq!(reloaded.with_meta(expected), image());
+
+        let provenance = Provenance::new("simulation").with_seed(3);
+        let mut stamped = Vec::new();
+        write_grid_with_provenance(&mut stamped, &image(), &provenance).unwrap();
+        assert!(
+            String::from_utf8(stamped.clone())
+                .unwrap()
+                .contains("# seed: 3\n")
+        );
+        assert_eq!(
+            read_grid::<GlobalFrame>(stamped.as_slice()).unwrap(),
+            read_grid::<GlobalFrame>(bytes.as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_grids() {
+        let read = |text: &str| read_grid::<GlobalFrame>(text.as_bytes());
+
+        assert!(matches!(
+            read("rumpus-sequence 1\n"),
+            Err(GridError::BadHeader)
+        ));
+        assert!(matches!(
+            read("rumpus-grid 2\n"),
+            Err(GridError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            read("rumpus-grid 1\nextents 1 2\naop\n1 2\ndop\n0.5\n"),
+            Err(GridError::Malformed { line: 6, .. })
+        ));
+        assert!(matches!(
+            read("rumpus-grid 1\nextents 1 1\n# comment\n\naop\n1\ndop\n2\n"),
+            Err(GridError::InvalidRay(_))
+        ));
+        assert!(matches!(
+            read(&format!("rumpus-grid 1\nextents {0} {0}\n", usize::MAX)),
+            Err(GridError::Malformed { line: 2, .. })
+        ));
+        // Huge extents fail on the missing rows rather than on allocation.
+        assert!(matches!(
+            read("rumpus-grid 1\nextents 4294967295 4294967295\naop\n"),
+            Err(GridError::Malformed {
+                reason: "missing aop row",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+//! An encoder for MAVLink 2 attitude messages.
+//!
+//! Orientation estimates are written as `ATTITUDE` (#30) and `ATTITUDE_QUATERNION` (#31)
+//! messages so flight controllers can consume polarization derived heading.
+//! Frames are written to any [`Write`], such as a serial port.
+//! For UDP, write each frame into a buffer and send it as one datagram.
+
+use std::{io::Write, time::Duration};
+use uom::si::{
+    angle::radian,
+    angular_velocity::radian_per_second,
+    f64::{Angle, AngularVelocity},
+};
+
+const STX: u8 = 0xFD;
+const ATTITUDE: (u32, u8) = (30, 39);
+const ATTITUDE_QUATERNION: (u32, u8) = (31, 246);
+
+/// An attitude in the local north, east, down frame with body rates.
+///
+/// Angles follow the yaw, pitch, roll convention used by MAVLink.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attitude {
+    roll: Angle,
+    pitch: Angle,
+    yaw: Angle,
+    roll_rate: AngularVelocity,
+    pitch_rate: AngularVelocity,
+    yaw_rate: AngularVelocity,
+}
+
+impl Attitude {
+    /// Creates a new `Attitude` with zero body rates.
+    #[must_use]
+    pub fn new(roll: Angle, pitch: Angle, yaw: Angle) -> Self {
+        let zero = AngularVelocity::new::<radian_per_second>(0.0);
+        Self {
+            roll,
+            pitch,
+            yaw,
+            roll_rate: zero,
+            pitch_rate: zero,
+            yaw_rate: zero,
+        }
+    }
+
+    /// Sets the body rates about the roll, pitch, and yaw axes.
+    #[must_use]
+    pub fn with_rates(
+        mut self,
+        roll_rate: AngularVelocity,
+        pitch_rate: AngularVelocity,
+        yaw_rate: AngularVelocity,
+    ) -> Self {
+        self.roll_rate = roll_rate;
+        self.pitch_rate = pitch_rate;
+        self.yaw_rate = yaw_rate;
+        self
+    }
+
+    /// Returns the attitude as a `[w, x, y, z]` quaternion.
+    #[must_use]
+    pub fn quaternion(&self) -> [f64; 4] {
+        let half = |angle: Angle| (angle.get::<radian>() / 2.0).sin_cos();
+        let (sr, cr) = half(self.roll);
+        let (sp, cp) = half(self.pitch);
+        let (sy, cy) = half(self.yaw);
+
+        [
+            cr * cp * cy + sr * sp * sy,
+            sr * cp * cy - cr * sp * sy,
+            cr * sp * cy + sr * cp * sy,
+            cr * cp * sy - sr * sp * cy,
+        ]
+    }
+
+    fn rates(&self) -> [f32; 3] {
+        [self.roll_rate, self.pitch_rate, self.yaw_rate]
+            .map(|rate| rate.get::<radian_per_second>() as f32)
+    }
+}
+
+/// Writes [`Attitude`]s as MAVLink 2 frames.
+pub struct MavlinkWriter<W> {
+    inner: W,
+    system_id: u8,
+    component_id: u8,
+    sequence: u8,
+}
+
+impl<W: Write> MavlinkWriter<W> {
+    /// Creates a new `MavlinkWriter` that sends from `system_id` and `component_id`.
+    pub fn new(inner: W, system_id: u8, component_id: u8) -> Self {
+        Self {
+            inner,
+            system_id,
+            component_id,
+            sequence: 0,
+        }
+    }
+
+    /// Writes an `ATTITUDE` message for `attitude` at `time_boot` since the system started.
+    ///
+    /// # Errors
+    /// Will return `Err` if the frame cannot be written to the underlying writer.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_attitude(
+        &mut self,
+        time_boot: Duration,
+        attitude: &Attitude,
+    ) -> std::io::Result<()> {
+        let mut payload = Vec::with_capacity(28);
+        payload.extend_from_slice(&(time_boot.as_millis() as u32).to_le_bytes());
+        for angle in [attitude.roll, attitude.pitch, attitude.yaw] {
+            payload.extend_from_slice(&(angle.get::<radian>() as f32).to_le_bytes());
+        }
+        for rate in attitude.rates() {
+            payload.extend_from_slice(&rate.to_le_bytes());
+        }
+
+        self.write_frame(ATTITUDE, payload)
+    }
+
+    /// Writes an `ATTITUDE_QUATERNION` message for `attitude` at `time_boot` since the system
+    /// started.
+    ///
+    /// # Errors
+    /// Will return `Err` if the frame cannot be written to the underlying writer.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_attitude_quaternion(
+        &mut self,
+        time_boot: Duration,
+        attitude: &Attitude,
+    ) -> std::io::Result<()> {
+        let mut payload = Vec::with_capacity(32);
+        payload.extend_from_slice(&(time_boot.as_millis() as u32).to_le_bytes());
+        for component in attitude.quaternion() {
+            payload.extend_from_slice(&(component as f32).to_le_bytes());
+        }
+        for rate in attitude.rates() {
+            payload.extend_from_slice(&rate.to_le_bytes());
+        }
+
+        self.write_frame(ATTITUDE_QUATERNION, payload)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_frame(
+        &mut self,
+        (id, crc_extra): (u32, u8),
+        mut payload: Vec<u8>,
+    ) -> std::io::Result<()> {
+        // MAVLink 2 truncates trailing zeros but always sends at least one byte.
+        while payload.len() > 1 && payload.last() == Some(&0) {
+            payload.pop();
+        }
+
+        let mut frame = vec![
+            STX,
+            payload.len() as u8,
+            0,
+            0,
+            self.sequence,
+            self.system_id,
+            self.component_id,
+        ];
+        frame.extend_from_slice(&id.to_le_bytes()[..3]);
+        frame.extend_from_slice(&payload);
+
+        let checksum = crc(&frame[1..], crc_extra);
+        frame.extend_from_slice(&checksum.to_le_bytes());
+
+        self.sequence = self.sequence.wrapping_add(1);
+        self.inner.write_all(&frame)
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Will return `Err` if the underlying writer cannot be flushed.
+    pub fn into_inner(mut self) -> std::io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Computes the CRC-16/MCRF4XX checksum used by MAVLink seeded with the message's `crc_extra`.
+fn crc(bytes: &[u8], crc_extra: u8) -> u16 {
+    bytes
+        .iter()
+        .chain(std::iter::once(&crc_extra))
+        .fold(0xFFFF, |crc, byte| accumulate(crc, *byte))
+}
+
+fn accumulate(crc: u16, byte: u8) -> u16 {
+    #[allow(clippy::cast_possible_truncation)]
+    let mut tmp = byte ^ (crc & 0xFF) as u8;
+    tmp ^= tmp << 4;
+    let tmp = u16::from(tmp);
+    (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use uom::si::angle::degree;
+
+    #[test]
+    fn crc_matches_reference() {
+        let checksum = b"123456789"
+            .iter()
+            .fold(0xFFFF, |crc, byte| accumulate(crc, *byte));
+        assert_eq!(checksum, 0x6F91);
+    }
+
+    #[test]
+    fn attitude_frame_layout() {
+        let attitude = Attitude::new(
+            Angle::new::<degree>(0.0),
+            Angle::new::<degree>(0.0),
+            Angle::new::<degree>(90.0),
+        );
+        let mut writer = MavlinkWriter::new(Vec::new(), 1, 200);
+        writer
+            .write_attitude(Duration::from_millis(1500), &attitude)
+            .unwrap();
+        writer
+            .write_attitude(Duration::from_millis(1600), &attitude)
+            .unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        // Trailing zero rates are truncated so the payload ends at yaw.
+        let len = usize::from(bytes[1]);
+        assert_eq!(len, 16);
+        assert_eq!(&bytes[..10], &[STX, 16, 0, 0, 0, 1, 200, 30, 0, 0]);
+        assert_eq!(&bytes[10..14], &1500u32.to_le_bytes());
+        assert_eq!(
+            &bytes[22..26],
+            &(std::f64::consts::FRAC_PI_2 as f32).to_le_bytes()
+        );
+
+        let frame_len = 10 + len + 2;
+        let checksum = crc(&bytes[1..10 + len], ATTITUDE.1);
+        assert_eq!(&bytes[10 + len..frame_len], &checksum.to_le_bytes());
+        // The sequence number increments for each frame.
+        assert_eq!(bytes[frame_len + 4], 1);
+    }
+
+    #[test]
+    fn quaternion_of_yaw() {
+        let attitude = Attitude::new(
+            Angle::new::<degree>(0.0),
+            Angle::new::<degree>(0.0),
+            Angle::new::<degree>(90.0),
+        );
+        let [w, x, y, z] = attitude.quaternion();
+        assert_relative_eq!(w, std::f64::consts::FRAC_1_SQRT_2, epsilon = 1e-12);
+        assert_relative_eq!(x, 0.0);
+        assert_relative_eq!(y, 0.0);
+        assert_relative_eq!(z, std::f64::consts::FRAC_1_SQRT_2, epsilon = 1e-12);
+    }
+}
@@ -0,0 +1,8 @@
+//! Readers and writers for persisting processed polarization data.
+
+pub mod grid;
+#[cfg(feature = "mavlink")]
+pub mod mavlink;
+#[cfg(feature = "estimation")]
+pub mod nmea;
+pub mod sequence;
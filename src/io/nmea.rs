@@ -0,0 +1,154 @@
+//! An encoder for NMEA 0183 heading sentences.
+//!
+//! Each [`HeadingEstimate`] is written as an `HDT` (true heading) sentence followed by a `ROT`
+//! (rate of turn) sentence computed from the previous estimate.
+//!
+//! ```text
+//! $HEHDT,123.4,T*2B
+//! $HEROT,-12.0,A*35
+//! ```
+
+use crate::estimate::fusion::HeadingEstimate;
+use chrono::{DateTime, Utc};
+use std::io::Write;
+use uom::si::{angle::degree, f64::Angle};
+
+/// Writes heading estimates as NMEA sentences to a serial port, file, or any other [`Write`].
+///
+/// Headings are expected clockwise from true north.
+pub struct NmeaWriter<W> {
+    inner: W,
+    talker: [u8; 2],
+    max_uncertainty: Option<Angle>,
+    previous: Option<(DateTime<Utc>, Angle)>,
+}
+
+impl<W: Write> NmeaWriter<W> {
+    /// Creates a new `NmeaWriter` with the `HE` (north seeking gyro) talker identifier.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            talker: *b"HE",
+            max_uncertainty: None,
+            previous: None,
+        }
+    }
+
+    /// Sets the two character talker identifier that prefixes each sentence.
+    #[must_use]
+    pub fn with_talker(mut self, talker: [u8; 2]) -> Self {
+        self.talker = talker;
+        self
+    }
+
+    /// Marks estimates with an uncertainty above `max_uncertainty` as invalid.
+    ///
+    /// Invalid estimates produce no `HDT` sentence and a `ROT` sentence with a void status.
+    #[must_use]
+    pub fn with_max_uncertainty(mut self, max_uncertainty: Angle) -> Self {
+        self.max_uncertainty = Some(max_uncertainty);
+        self
+    }
+
+    /// Writes the sentences for an `estimate` made at `time`.
+    ///
+    /// The rate of turn of the first valid estimate is zero.
+    ///
+    /// # Errors
+    /// Will return `Err` if the sentences cannot be written to the underlying writer.
+    pub fn write_heading(
+        &mut self,
+        time: DateTime<Utc>,
+        estimate: &HeadingEstimate,
+    ) -> std::io::Result<()> {
+        let valid = self
+            .max_uncertainty
+            .is_none_or(|max| estimate.uncertainty() <= max);
+        if !valid {
+            return self.write_sentence("ROT", &format!("{:.1},V", 0.0));
+        }
+
+        let heading = estimate.heading().get::<degree>().rem_euclid(360.0);
+        let rate = match self.previous {
+            Some((previous_time, previous_heading)) if time > previous_time => {
+                // Turn through the smallest angle between headings.
+                let delta =
+                    (heading - previous_heading.get::<degree>() + 180.0).rem_euclid(360.0) - 180.0;
+
+                #[allow(clippy::cast_precision_loss)]
+                let minutes = (time - previous_time)
+                    .num_microseconds()
+                    .unwrap_or(i64::MAX) as f64
+                    / 60e6;
+                delta / minutes
+            }
+            _ => 0.0,
+        };
+        self.previous = Some((time, Angle::new::<degree>(heading)));
+
+        self.write_sentence("HDT", &format!("{heading:.1},T"))?;
+        self.write_sentence("ROT", &format!("{rate:.1},A"))
+    }
+
+    fn write_sentence(&mut self, kind: &str, fields: &str) -> std::io::Result<()> {
+        let talker = String::from_utf8_lossy(&self.talker);
+        let body = format!("{talker}{kind},{fields}");
+        let checksum = body.bytes().fold(0, |checksum, byte| checksum ^ byte);
+        write!(self.inner, "${body}*{checksum:02X}\r\n")
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Will return `Err` if the underlying writer cannot be flushed.
+    pub fn into_inner(mut self) -> std::io::Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn estimate(heading: f64, uncertainty: f64) -> HeadingEstimate {
+        HeadingEstimate::new(
+            Angle::new::<degree>(heading),
+            Angle::new::<degree>(uncertainty),
+        )
+    }
+
+    #[test]
+    fn writes_sentences() {
+        let time = DateTime::UNIX_EPOCH;
+        let mut writer =
+            NmeaWriter::new(Vec::new()).with_max_uncertainty(Angle::new::<degree>(5.0));
+
+        writer.write_heading(time, &estimate(-10.0, 1.0)).unwrap();
+        writer
+            .write_heading(time + TimeDelta::seconds(30), &estimate(5.0, 1.0))
+            .unwrap();
+        writer
+            .write_heading(time + TimeDelta::seconds(60), &estimate(5.0, 10.0))
+            .unwrap();
+
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            output,
+            "$HEHDT,350.0,T*29\r\n\
+             $HEROT,0.0,A*2B\r\n\
+             $HEHDT,5.0,T*2A\r\n\
+             $HEROT,30.0,A*18\r\n\
+             $HEROT,0.0,V*3C\r\n"
+        );
+    }
+
+    #[test]
+    fn checksums_match_reference() {
+        // Reference sentence from the NMEA 0183 standard.
+        let mut writer = NmeaWriter::new(Vec::new()).with_talker(*b"GP");
+        writer.write_sentence("HDT", "274.07,T").unwrap();
+        assert_eq!(writer.into_inner().unwrap(), b"$GPHDT,274.07,T*03\r\n");
+    }
+}
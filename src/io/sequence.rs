@@ -0,0 +1,924 @@
+//! A chunked binary container for sequences of [`RayImage`]s.
+//!
+//! All integers and floats are little endian.
+//!
+//! ```text
+//! +-------------+-------------+--------------+--------------+
+//! | magic (4)   | version (2) | rows (4)     | cols (4)     |
+//! +-------------+-------------+--------------+--------------+
+//! | frame chunk | frame chunk | ...          |
+//! +-------------+-------------+--------------+
+//! ```
+//!
+//! Every chunk starts with a tag and the length in bytes of the rest of the chunk.
+//! Readers skip chunks with tags they do not know.
+//! A `PROV` chunk holds the JSON object of a [`Provenance`] and applies to the frames after it.
+//! The pixel planes only store values for pixels that hold a [`Ray`].
+//!
+//! ```text
+//! +-----------+------------+-----------------+---------------+
+//! | tag (4)   | length (8) | seconds (8)     | nanos (4)     |
+//! +-----------+------------+-----------------+---------------+
+//! | validity bitmap (ceil(rows * cols / 8)) |
+//! +-----------------------------------------+
+//! | AoP length (8) | compressed AoP plane in radians |
+//! +----------------+---------------------------------+
+//! | DoP length (8) | compressed DoP plane            |
+//! +----------------+---------------------------------+
+//! | metadata flags (1) | metadata fields              |
+//! +--------------------+------------------------------+
+//! ```
+//!
+//! The timestamp is the time of the frame.
+//! Each bit of the metadata flags marks one of the optional [`FrameMeta`] fields that follow in
+//! this order:
+//!
+//! | bit | field                                               | bytes |
+//! |-----|-----------------------------------------------------|-------|
+//! | 0   | exposure in seconds                                 | 8     |
+//! | 1   | gain                                                | 8     |
+//! | 2   | sequence number                                     | 8     |
+//! | 3   | latitude and longitude in radians, altitude in meters | 24  |
+//! | 4   | solar azimuth and elevation in radians              | 16    |
+//!
+//! Planes are compressed losslessly.
+//! Each value is XORed with the value of the previous valid pixel, so the bytes that
+//! neighboring pixels share become zero, the bytes are grouped by significance, and the result
+//! is run length encoded.
+//! A control byte below 128 is followed by that many plus one literal bytes, otherwise the next
+//! byte repeats the control byte minus 125 times.
+//!
+//! Version 1 sequences store the planes uncompressed without their lengths or metadata and can
+//! still be read.
+
+use crate::{
+    image::{ImageError, RayImage},
+    light::{LightError, aop::Aop, dop::Dop},
+    meta::FrameMeta,
+    model::ephemeris::SolarPosition,
+    provenance::Provenance,
+    ray::Ray,
+    temporal::{TemporalError, TemporalMedian},
+    trajectory::Trajectory,
+};
+use chrono::{DateTime, Utc};
+use sguaba::systems::Wgs84;
+use std::io::{Read, Write};
+use thiserror::Error;
+use uom::si::{
+    angle::radian,
+    f64::{Angle, Length, Time},
+    length::meter,
+    time::second,
+};
+
+const MAGIC: [u8; 4] = *b"RMPS";
+const FRAME_TAG: [u8; 4] = *b"FRME";
+const PROVENANCE_TAG: [u8; 4] = *b"PROV";
+const VERSION: u16 = 2;
+const MIN_RUN: usize = 3;
+const MAX_RUN: usize = MIN_RUN + 127;
+const MAX_LITERALS: usize = 128;
+const EXPOSURE_FLAG: u8 = 1 << 0;
+const GAIN_FLAG: u8 = 1 << 1;
+const SEQUENCE_FLAG: u8 = 1 << 2;
+const POSITION_FLAG: u8 = 1 << 3;
+const SOLAR_FLAG: u8 = 1 << 4;
+
+#[derive(Debug, Error)]
+pub enum SequenceError {
+    #[error("failed to access sequence")]
+    Io(#[from] std::io::Error),
+
+    #[error("stream is not a ray sequence")]
+    BadMagic,
+
+    #[error("unsupported sequence version: {0}")]
+    UnsupportedVersion(u16),
+
+    #[error("frame has extents {rows}x{cols} but sequence has {seq_rows}x{seq_cols}")]
+    GeometryMismatch {
+        rows: usize,
+        cols: usize,
+        seq_rows: usize,
+        seq_cols: usize,
+    },
+
+    #[error("extents {rows}x{cols} do not fit in the sequence header")]
+    ExtentsTooLarge { rows: usize, cols: usize },
+
+    #[error("frame timestamp is out of range")]
+    InvalidTimestamp,
+
+    #[error("frame plane is corrupt")]
+    CorruptPlane,
+
+    #[error("frame metadata is malformed")]
+    MalformedMeta,
+
+    #[error("provenance is not valid UTF-8")]
+    InvalidProvenance,
+
+    #[error("frame contains an invalid ray")]
+    InvalidRay(#[from] LightError),
+
+    #[error("failed to assemble frame")]
+    Image(#[from] ImageError),
+
+    #[error("failed to filter frame")]
+    Temporal(#[from] TemporalError),
+}
+
+/// A [`RayImage`] captured at a point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceFrame<Frame> {
+    time: DateTime<Utc>,
+    image: RayImage<Frame>,
+}
+
+impl<Frame> SequenceFrame<Frame> {
+    #[must_use]
+    pub fn new(time: DateTime<Utc>, image: RayImage<Frame>) -> Self {
+        Self { time, image }
+    }
+
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    #[must_use]
+    pub fn image(&self) -> &RayImage<Frame> {
+        &self.image
+    }
+
+    #[must_use]
+    pub fn into_image(self) -> RayImage<Frame> {
+        self.image
+    }
+}
+
+/// Writes [`RayImage`]s with a shared geometry to a sequence.
+pub struct SequenceWriter<W> {
+    inner: W,
+    rows: usize,
+    cols: usize,
+}
+
+impl<W: Write> SequenceWriter<W> {
+    /// Creates a new `SequenceWriter` and writes the sequence header.
+    ///
+    /// # Errors
+    /// Will return `Err` if the header cannot be written to `inner` or the extents do not fit in
+    /// the header.
+    pub fn new(mut inner: W, rows: usize, cols: usize) -> Result<Self, SequenceError> {
+        let to_u32 = |extent: usize| {
+            u32::try_from(extent).map_err(|_| SequenceError::ExtentsTooLarge { rows, cols })
+        };
+
+        let (rows_u32, cols_u32) = (to_u32(rows)?, to_u32(cols)?);
+        pixel_count(rows, cols)?;
+
+        inner.write_all(&MAGIC)?;
+        inner.write_all(&VERSION.to_le_bytes())?;
+        inner.write_all(&rows_u32.to_le_bytes())?;
+        inner.write_all(&cols_u32.to_le_bytes())?;
+
+        Ok(Self { inner, rows, cols })
+    }
+
+    /// Appends `image` captured at `time` to the sequence.
+    ///
+    /// Every other field of the [`FrameMeta`] of `image` is stored with the frame.
+    ///
+    /// # Errors
+    /// Will return `Err` if `image` does not match the geometry of the sequence or it cannot be
+    /// written.
+    pub fn write_frame<Frame: Copy>(
+        &mut self,
+        time: DateTime<Utc>,
+        image: &RayImage<Frame>,
+    ) -> Result<(), SequenceError> {
+        if image.rows() != self.rows || image.cols() != self.cols {
+            return Err(SequenceError::GeometryMismatch {
+                rows: image.rows(),
+                cols: image.cols(),
+                seq_rows: self.rows,
+                seq_cols: self.cols,
+            });
+        }
+
+        let mut validity = vec![0u8; (self.rows * self.cols).div_ceil(8)];
+        let mut aops = Vec::new();
+        let mut dops = Vec::new();
+        for (i, ray) in image.rays().enumerate() {
+            if let Some(ray) = ray {
+                validity[i / 8] |= 1 << (i % 8);
+                aops.push(Angle::from(ray.aop()).get::<radian>());
+                dops.push(f64::from(ray.dop()));
+            }
+        }
+        let aop_plane = encode_plane(&aops);
+        let dop_plane = encode_plane(&dops);
+
+        let meta = encode_meta(image.meta());
+
+        let length =
+            8 + 4 + validity.len() + 8 + aop_plane.len() + 8 + dop_plane.len() + meta.len();
+        self.inner.write_all(&FRAME_TAG)?;
+        self.inner.write_all(&(length as u64).to_le_bytes())?;
+        self.inner.write_all(&time.timestamp().to_le_bytes())?;
+        self.inner
+            .write_all(&time.timestamp_subsec_nanos().to_le_bytes())?;
+        self.inner.write_all(&validity)?;
+        self.inner
+            .write_all(&(aop_plane.len() as u64).to_le_bytes())?;
+        self.inner.write_all(&aop_plane)?;
+        self.inner
+            .write_all(&(dop_plane.len() as u64).to_le_bytes())?;
+        self.inner.write_all(&dop_plane)?;
+        self.inner.write_all(&meta)?;
+
+        Ok(())
+    }
+
+    /// Stamps the frames written after this call with `provenance`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the provenance cannot be written.
+    pub fn write_provenance(&mut self, provenance: &Provenance) -> Result<(), SequenceError> {
+        let json = provenance.to_json();
+        self.inner.write_all(&PROVENANCE_TAG)?;
+        self.inner.write_all(&(json.len() as u64).to_le_bytes())?;
+        self.inner.write_all(json.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Will return `Err` if the underlying writer cannot be flushed.
+    pub fn into_inner(mut self) -> Result<W, SequenceError> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads [`RayImage`]s from a sequence.
+///
+/// This type is an [`Iterator`] over the frames of the sequence.
+pub struct SequenceReader<R, Frame> {
+    inner: R,
+    version: u16,
+    rows: usize,
+    cols: usize,
+    /// Index of the next frame in the sequence.
+    sequence: u64,
+    trajectory: Option<Trajectory>,
+    provenance: Option<String>,
+    _phan: std::marker::PhantomData<Frame>,
+}
+
+impl<R: Read, Frame> SequenceReader<R, Frame> {
+    /// Creates a new `SequenceReader` and reads the sequence header.
+    ///
+    /// # Errors
+    /// Will return `Err` if `inner` does not start with a valid sequence header.
+    pub fn new(mut inner: R) -> Result<Self, SequenceError> {
+        let magic: [u8; 4] = read_array(&mut inner)?;
+        if magic != MAGIC {
+            return Err(SequenceError::BadMagic);
+        }
+
+        let version = u16::from_le_bytes(read_array(&mut inner)?);
+        if !(1..=VERSION).contains(&version) {
+            return Err(SequenceError::UnsupportedVersion(version));
+        }
+
+        let rows = u32::from_le_bytes(read_array(&mut inner)?) as usize;
+        let cols = u32::from_le_bytes(read_array(&mut inner)?) as usize;
+        pixel_count(rows, cols)?;
+
+        Ok(Self {
+            inner,
+            version,
+            rows,
+            cols,
+            sequence: 0,
+            trajectory: None,
+            provenance: None,
+            _phan: std::marker::PhantomData,
+        })
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the JSON object of the last [`Provenance`] read, which applies to the frames read
+    /// after it.
+    #[must_use]
+    pub fn provenance(&self) -> Option<&str> {
+        self.provenance.as_deref()
+    }
+
+    /// Sets the position of each frame from `trajectory` at the time it was captured.
+    #[must_use]
+    pub fn with_trajectory(mut self, trajectory: Trajectory) -> Self {
+        self.trajectory = Some(trajectory);
+        self
+    }
+
+    /// Reads the next frame from the sequence.
+    ///
+    /// Returns `Ok(None)` at the end of the sequence.
+    /// The [`FrameMeta`] of the image holds the time of the frame and the metadata stored with it.
+    /// Frames stored without a sequence number take their index in the sequence.
+    /// The position of the frame is taken from the reader's [`Trajectory`] if it spans the frame.
+    ///
+    /// # Errors
+    /// Will return `Err` if the frame is malformed or cannot be read.
+    pub fn read_frame(&mut self) -> Result<Option<SequenceFrame<Frame>>, SequenceError> {
+        let body = loop {
+            let mut tag = [0u8; 4];
+            match self.inner.read_exact(&mut tag) {
+                Ok(()) => (),
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+
+            let length = u64::from_le_bytes(read_array(&mut self.inner)?);
+            let mut chunk = (&mut self.inner).take(length);
+            if tag != FRAME_TAG && tag != PROVENANCE_TAG {
+                if std::io::copy(&mut chunk, &mut std::io::sink())? != length {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                }
+                continue;
+            }
+
+            // The body grows as it is read so a corrupt length cannot exhaust memory.
+            let mut body = Vec::new();
+            if chunk.read_to_end(&mut body)? as u64 != length {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            if tag == PROVENANCE_TAG {
+                self.provenance =
+                    Some(String::from_utf8(body).map_err(|_| SequenceError::InvalidProvenance)?);
+                continue;
+            }
+            break body;
+        };
+        let mut body = body.as_slice();
+
+        let seconds = i64::from_le_bytes(read_array(&mut body)?);
+        let nanos = u32::from_le_bytes(read_array(&mut body)?);
+        let time =
+            DateTime::from_timestamp(seconds, nanos).ok_or(SequenceError::InvalidTimestamp)?;
+
+        let pixel_count = pixel_count(self.rows, self.cols)?;
+        let validity = split(&mut body, pixel_count.div_ceil(8))?;
+
+        let is_valid = |i: usize| validity[i / 8] & (1 << (i % 8)) != 0;
+        let valid_count = (0..pixel_count).filter(|&i| is_valid(i)).count();
+
+        let (aops, dops) = if self.version == 1 {
+            (
+                read_plane(&mut body, valid_count)?,
+                read_plane(&mut body, valid_count)?,
+            )
+        } else {
+            let mut plane = || {
+                let length = u64::from_le_bytes(read_array(&mut body)?);
+                let length = usize::try_from(length).map_err(|_| SequenceError::CorruptPlane)?;
+                decode_plane(split(&mut body, length)?, valid_count)
+            };
+            (plane()?, plane()?)
+        };
+
+        let mut values = aops.into_iter().zip(dops).map(|(aop, dop)| {
+            Ok::<_, LightError>(Ray::new(
+                Aop::try_from_angle(Angle::new::<radian>(aop))?,
+                Dop::try_new(dop)?,
+            ))
+        });
+
+        let rays = (0..pixel_count)
+            .map(|i| {
+                if is_valid(i) {
+                    values.next().transpose()
+                } else {
+                    Ok(None)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut meta = decode_meta(&mut body, FrameMeta::new().with_time(time))?;
+        if meta.sequence().is_none() {
+            meta = meta.with_sequence(self.sequence);
+        }
+        if let Some(trajectory) = &self.trajectory {
+            meta = trajectory.annotate(meta);
+        }
+        self.sequence += 1;
+
+        Ok(Some(SequenceFrame::new(
+            time,
+            RayImage::from_rays(rays, self.rows, self.cols)?.with_meta(meta),
+        )))
+    }
+}
+
+impl<R: Read, Frame> Iterator for SequenceReader<R, Frame> {
+    type Item = Result<SequenceFrame<Frame>, SequenceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+impl<R: Read, Frame: Copy> SequenceReader<R, Frame> {
+    /// Filters each frame with `median` before it is yielded.
+    ///
+    /// See [`TemporalMedian`].
+    #[must_use]
+    pub fn temporal_median(self, median: TemporalMedian<Frame>) -> MedianFrames<R, Frame> {
+        MedianFrames {
+            reader: self,
+            median,
+        }
+    }
+}
+
+/// An [`Iterator`] over the frames of a [`SequenceReader`] filtered by a [`TemporalMedian`].
+///
+/// See [`SequenceReader::temporal_median`].
+pub struct MedianFrames<R, Frame> {
+    reader: SequenceReader<R, Frame>,
+    median: TemporalMedian<Frame>,
+}
+
+impl<R: Read, Frame: Copy> Iterator for MedianFrames<R, Frame> {
+    type Item = Result<SequenceFrame<Frame>, SequenceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = match self.reader.read_frame().transpose()? {
+            Ok(frame) => frame,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let time = frame.time();
+        Some(
+            self.median
+                .push(frame.into_image())
+                .map(|image| SequenceFrame::new(time, image))
+                .map_err(SequenceError::from),
+        )
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], std::io::Error> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Returns the number of pixels in a frame with `rows` and `cols`.
+fn pixel_count(rows: usize, cols: usize) -> Result<usize, SequenceError> {
+    rows.checked_mul(cols)
+        .ok_or(SequenceError::ExtentsTooLarge { rows, cols })
+}
+
+/// Removes and returns the first `len` bytes of `body`.
+fn split<'a>(body: &mut &'a [u8], len: usize) -> Result<&'a [u8], SequenceError> {
+    if body.len() < len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    let (head, tail) = body.split_at(len);
+    *body = tail;
+    Ok(head)
+}
+
+fn encode_meta(meta: &FrameMeta) -> Vec<u8> {
+    let mut flags = 0;
+    let mut fields = Vec::new();
+    if let Some(exposure) = meta.exposure() {
+        flags |= EXPOSURE_FLAG;
+        fields.extend_from_slice(&exposure.get::<second>().to_le_bytes());
+    }
+    if let Some(gain) = meta.gain() {
+        flags |= GAIN_FLAG;
+        fields.extend_from_slice(&gain.to_le_bytes());
+    }
+    if let Some(sequence) = meta.sequence() {
+        flags |= SEQUENCE_FLAG;
+        fields.extend_from_slice(&sequence.to_le_bytes());
+    }
+    if let Some(position) = meta.position() {
+        flags |= POSITION_FLAG;
+        fields.extend_from_slice(&position.latitude().get::<radian>().to_le_bytes());
+        fields.extend_from_slice(&position.longitude().get::<radian>().to_le_bytes());
+        fields.extend_from_slice(&position.altitude().get::<meter>().to_le_bytes());
+    }
+    if let Some(solar) = meta.solar_position() {
+        flags |= SOLAR_FLAG;
+        fields.extend_from_slice(&solar.azimuth().get::<radian>().to_le_bytes());
+        fields.extend_from_slice(&solar.elevation().get::<radian>().to_le_bytes());
+    }
+
+    let mut encoded = vec![flags];
+    encoded.append(&mut fields);
+    encoded
+}
+
+/// Adds the metadata fields at the start of `body` to `meta`.
+///
+/// Frames without metadata leave `meta` unchanged.
+fn decode_meta(body: &mut &[u8], mut meta: FrameMeta) -> Result<FrameMeta, SequenceError> {
+    let Some((&flags, rest)) = body.split_first() else {
+        return Ok(meta);
+    };
+    *body = rest;
+
+    let word = |body: &mut &[u8]| {
+        read_array(body)
+            .map(u64::from_le_bytes)
+            .map_err(|_| SequenceError::MalformedMeta)
+    };
+    let float = |body: &mut &[u8]| word(body).map(f64::from_bits);
+    if flags & EXPOSURE_FLAG != 0 {
+        meta = meta.with_exposure(Time::new::<second>(float(body)?));
+    }
+    if flags & GAIN_FLAG != 0 {
+        meta = meta.with_gain(float(body)?);
+    }
+    if flags & SEQUENCE_FLAG != 0 {
+        meta = meta.with_sequence(word(body)?);
+    }
+    if flags & POSITION_FLAG != 0 {
+        let (latitude, longitude, altitude) = (float(body)?, float(body)?, float(body)?);
+        meta = meta.with_position(
+            Wgs84::builder()
+                .latitude(Angle::new::<radian>(latitude))
+                .ok_or(SequenceError::MalformedMeta)?
+                .longitude(Angle::new::<radian>(longitude))
+                .altitude(Length::new::<meter>(altitude))
+                .build(),
+        );
+    }
+    if flags & SOLAR_FLAG != 0 {
+        let (azimuth, elevation) = (float(body)?, float(body)?);
+        meta = meta.with_solar_position(SolarPosition::new(
+            Angle::new::<radian>(azimuth),
+            Angle::new::<radian>(elevation),
+        ));
+    }
+
+    Ok(meta)
+}
+
+/// Reads `count` uncompressed values from `body`.
+fn read_plane(body: &mut &[u8], count: usize) -> Result<Vec<f64>, SequenceError> {
+    let len = count.checked_mul(8).ok_or(SequenceError::CorruptPlane)?;
+    Ok(split(body, len)?
+        .chunks_exact(8)
+        .map(|value| f64::from_le_bytes(value.try_into().expect("chunk has 8 bytes")))
+        .collect())
+}
+
+fn encode_plane(values: &[f64]) -> Vec<u8> {
+    let mut previous = 0;
+    let words: Vec<u64> = values
+        .iter()
+        .map(|value| {
+            let bits = value.to_bits();
+            let word = bits ^ previous;
+            previous = bits;
+            word
+        })
+        .collect();
+    let shuffled: Vec<u8> = (0..8)
+        .flat_map(|byte| words.iter().map(move |word| word.to_le_bytes()[byte]))
+        .collect();
+
+    let mut encoded = Vec::new();
+    let mut literals: Vec<u8> = Vec::new();
+    let flush = |encoded: &mut Vec<u8>, literals: &mut Vec<u8>| {
+        if !literals.is_empty() {
+            #[allow(clippy::cast_possible_truncation)]
+            encoded.push((literals.len() - 1) as u8);
+            encoded.append(literals);
+        }
+    };
+
+    let mut i = 0;
+    while i < shuffled.len() {
+        let run = shuffled[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|byte| **byte == shuffled[i])
+            .count();
+        if run >= MIN_RUN {
+            flush(&mut encoded, &mut literals);
+            #[allow(clippy::cast_possible_truncation)]
+            encoded.extend([(run - MIN_RUN + 128) as u8, shuffled[i]]);
+            i += run;
+        } else {
+            literals.push(shuffled[i]);
+            if literals.len() == MAX_LITERALS {
+                flush(&mut encoded, &mut literals);
+            }
+            i += 1;
+        }
+    }
+    flush(&mut encoded, &mut literals);
+
+    encoded
+}
+
+fn decode_plane(mut encoded: &[u8], count: usize) -> Result<Vec<f64>, SequenceError> {
+    let len = count.checked_mul(8).ok_or(SequenceError::CorruptPlane)?;
+    let mut shuffled = Vec::new();
+    while let Some((&control, rest)) = encoded.split_first() {
+        encoded = rest;
+        if control < 128 {
+            shuffled.extend_from_slice(
+                split(&mut encoded, usize::from(control) + 1)
+                    .map_err(|_| SequenceError::CorruptPlane)?,
+            );
+        } else {
+            let (&byte, rest) = encoded.split_first().ok_or(SequenceError::CorruptPlane)?;
+            encoded = rest;
+            let run = usize::from(control) - 128 + MIN_RUN;
+            shuffled.resize(shuffled.len() + run, byte);
+        }
+        if shuffled.len() > len {
+            return Err(SequenceError::CorruptPlane);
+        }
+    }
+    if shuffled.len() != len {
+        return Err(SequenceError::CorruptPlane);
+    }
+
+    let mut previous = 0;
+    Ok((0..count)
+        .map(|i| {
+            let word = u64::from_le_bytes(std::array::from_fn(|byte| shuffled[byte * count + i]));
+            previous ^= word;
+            f64::from_bits(previous)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::SensorFrame;
+    use approx::assert_relative_eq;
+    use std::io::Cursor;
+    use uom::si::angle::degree;
+
+    fn frame(offset: f64) -> RayImage<SensorFrame> {
+        let rays = (0..6).map(|i| {
+            (i % 4 != 0).then(|| {
+                Ray::new(
+                    Aop::from_angle_wrapped(Angle::new::<degree>(offset + f64::from(i) * 20.0)),
+                    Dop::clamped(f64::from(i) / 10.0),
+                )
+            })
+        });
+        RayImage::from_rays(rays, 2, 3).unwrap()
+    }
+
+    #[test]
+    fn sequence_roundtrip() {
+        let t0: DateTime<Utc> = "2025-06-13T16:26:47.125+00:00".parse().unwrap();
+        let t1: DateTime<Utc> = "2025-06-13T16:26:48+00:00".parse().unwrap();
+
+        let mut writer = SequenceWriter::new(Vec::new(), 2, 3).unwrap();
+        writer.write_frame(t0, &frame(0.0)).unwrap();
+        writer.write_frame(t1, &frame(45.0)).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let frames: Vec<SequenceFrame<SensorFrame>> = SequenceReader::new(Cursor::new(bytes))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            frames,
+            vec![
+                SequenceFrame::new(
+                    t0,
+                    frame(0.0).with_meta(FrameMeta::new().with_time(t0).with_sequence(0))
+                ),
+                SequenceFrame::new(
+                    t1,
+                    frame(45.0).with_meta(FrameMeta::new().with_time(t1).with_sequence(1))
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn stores_frame_meta() {
+        let t0: DateTime<Utc> = "2025-06-13T16:26:47.25+00:00".parse().unwrap();
+        let position = Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2187))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.4747))
+            .altitude(Length::new::<meter>(93.5))
+            .build();
+        let meta = FrameMeta::new()
+            .with_time(t0)
+            .with_exposure(Time::new::<second>(0.002))
+            .with_gain(1.5)
+            .with_sequence(12)
+            .with_position(position)
+            .with_solar_position(SolarPosition::new(
+                Angle::new::<degree>(143.5),
+                Angle::new::<degree>(61.25),
+            ));
+
+        let mut writer = SequenceWriter::new(Vec::new(), 2, 3).unwrap();
+        writer.write_frame(t0, &frame(0.0).with_meta(meta)).unwrap();
+        writer.write_frame(t0, &frame(0.0)).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let frames: Vec<SequenceFrame<SensorFrame>> = SequenceReader::new(Cursor::new(bytes))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // Wgs84 normalizes the longitude it hands out, so compare it separately.
+        let read = *frames[0].image().meta();
+        assert_relative_eq!(
+            read.position().unwrap().longitude().get::<radian>(),
+            position.longitude().get::<radian>()
+        );
+        assert_eq!(read.with_position(position), meta);
+        assert_eq!(
+            frames[1].image().meta(),
+            &FrameMeta::new().with_time(t0).with_sequence(1)
+        );
+    }
+
+    #[test]
+    fn stores_provenance() {
+        let provenance = Provenance::new("simulation").with_seed(3);
+        let mut writer = SequenceWriter::new(Vec::new(), 2, 3).unwrap();
+        writer.write_provenance(&provenance).unwrap();
+        writer.write_frame(Utc::now(), &frame(0.0)).unwrap();
+        let bytes = writer.into_inner().unwrap();
+
+        let mut reader = SequenceReader::<_, SensorFrame>::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.provenance(), None);
+        assert!(reader.read_frame().unwrap().is_some());
+        assert_eq!(reader.provenance(), Some(provenance.to_json().as_str()));
+    }
+
+    #[test]
+    fn temporal_median_filters_frames() {
+        let t0: DateTime<Utc> = "2025-06-13T16:26:47+00:00".parse().unwrap();
+        let mut writer = SequenceWriter::new(Vec::new(), 2, 3).unwrap();
+        for (i, offset) in [0.0, 0.0, 45.0, 0.0].into_iter().enumerate() {
+            let time = t0 + chrono::TimeDelta::seconds(i64::try_from(i).unwrap());
+            writer.write_frame(time, &frame(offset)).unwrap();
+        }
+        let bytes = writer.into_inner().unwrap();
+
+        let frames: Vec<SequenceFrame<SensorFrame>> = SequenceReader::new(Cursor::new(bytes))
+            .unwrap()
+            .temporal_median(TemporalMedian::new(3))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        // The transient third frame is rejected by the median.
+        assert_eq!(frames.len(), 4);
+        assert_eq!(
+            frames[2].image().rays().collect::<Vec<_>>(),
+            frame(0.0).rays().collect::<Vec<_>>()
+        );
+        assert_eq!(frames[2].image().meta().sequence(), Some(2));
+    }
+
+    #[test]
+    fn rejects_mismatched_geometry() {
+        let mut writer = SequenceWriter::new(Vec::new(), 3, 2).unwrap();
+        assert!(matches!(
+            writer.write_frame(Utc::now(), &frame(0.0)),
+            Err(SequenceError::GeometryMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn skips_unknown_chunks() {
+        let t0: DateTime<Utc> = "2025-06-13T16:26:47+00:00".parse().unwrap();
+        let mut writer = SequenceWriter::new(Vec::new(), 2, 3).unwrap();
+        writer.write_frame(t0, &frame(0.0)).unwrap();
+        let mut bytes = writer.into_inner().unwrap();
+        bytes.extend_from_slice(b"NOTE");
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+        bytes.extend_from_slice(b"hello");
+        let mut writer = SequenceWriter::new(Vec::new(), 2, 3).unwrap();
+        writer.write_frame(t0, &frame(45.0)).unwrap();
+        bytes.extend_from_slice(&writer.into_inner().unwrap()[14..]);
+
+        let frames: Vec<SequenceFrame<SensorFrame>> = SequenceReader::new(Cursor::new(bytes))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[1].image().rays().collect::<Vec<_>>(),
+            frame(45.0).rays().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn compresses_planes() {
+        let (rows, cols) = (32, 32);
+        let rays = (0..32u32 * 32).map(|i| {
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(f64::from(i % 4))),
+                Dop::clamped(0.25),
+            ))
+        });
+        let image: RayImage<SensorFrame> = RayImage::from_rays(rays, rows, cols).unwrap();
+
+        let mut writer = SequenceWriter::new(Vec::new(), rows, cols).unwrap();
+        writer.write_frame(Utc::now(), &image).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        // The uniform DoP plane collapses to a few runs.
+        assert!(bytes.len() < rows * cols * 16 * 3 / 4);
+
+        let read = SequenceReader::<_, SensorFrame>::new(Cursor::new(bytes))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            read.image().rays().collect::<Vec<_>>(),
+            image.rays().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reads_uncompressed_version() {
+        let ray = |aop: f64, dop: f64| {
+            Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<radian>(aop)),
+                Dop::clamped(dop),
+            )
+        };
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&FRAME_TAG);
+        bytes.extend_from_slice(&29u64.to_le_bytes());
+        bytes.extend_from_slice(&0i64.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.push(0b10);
+        bytes.extend_from_slice(&0.5f64.to_le_bytes());
+        bytes.extend_from_slice(&0.25f64.to_le_bytes());
+
+        let read = SequenceReader::<_, SensorFrame>::new(Cursor::new(bytes))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            read.image().rays().collect::<Vec<_>>(),
+            vec![None, Some(&ray(0.5, 0.25))]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        // A frame of a huge sensor that holds no pixels.
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&FRAME_TAG);
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+
+        let mut reader = SequenceReader::<_, SensorFrame>::new(Cursor::new(bytes)).unwrap();
+        assert!(matches!(reader.read_frame(), Err(SequenceError::Io(_))));
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        assert!(matches!(
+            SequenceReader::<_, SensorFrame>::new(Cursor::new(b"JUNKJUNKJUNKJUNK".to_vec())),
+            Err(SequenceError::BadMagic)
+        ));
+    }
+}
@@ -1,5 +1,8 @@
 use super::{
-    filter::{RayFilter, RayPredicate},
+    decimate::{RaySample, RayStride, RayTopDop},
+    error::Error,
+    filter::{DopClamp, RayFilter, RayPredicate},
+    light::dop::Dop,
     ray::Ray,
 };
 
@@ -12,4 +15,146 @@ pub trait RayIterator<Frame>: Iterator<Item = Ray<Frame>> {
     {
         RayFilter::new(self, pred)
     }
+
+    /// Clamps the degree of polarization of each ray onto `[min, max]`.
+    ///
+    /// # Errors
+    /// Will return `Err` if `min` is greater than `max`.
+    fn clamp_dop(self, min: Dop, max: Dop) -> Result<DopClamp<Self>, Error>
+    where
+        Self: Sized,
+    {
+        DopClamp::from_bounds(self, min, max)
+    }
+
+    /// Clamps the degree of polarization of each ray onto `[min, max]`.
+    ///
+    /// Inverted bounds are swapped.
+    #[deprecated(note = "use `RayIterator::clamp_dop` with a `Dop`")]
+    fn dop_clamp(self, min: f64, max: f64) -> DopClamp<Self>
+    where
+        Self: Sized,
+    {
+        #[allow(deprecated)]
+        DopClamp::new(self, min, max)
+    }
+
+    /// Yields every `step`th ray.
+    ///
+    /// # Panics
+    /// Will panic if `step` is zero.
+    fn ray_stride(self, step: usize) -> RayStride<Self>
+    where
+        Self: Sized,
+    {
+        RayStride::new(self, step)
+    }
+
+    /// Yields each ray with probability `fraction` using a generator seeded with `seed`.
+    fn ray_sample(self, fraction: f64, seed: u64) -> RaySample<Self>
+    where
+        Self: Sized,
+    {
+        RaySample::new(self, fraction, seed)
+    }
+
+    /// Yields the `count` rays with the largest degree of polarization.
+    ///
+    /// This consumes the iterator before yielding any rays.
+    fn ray_top_dop(self, count: usize) -> RayTopDop<Frame>
+    where
+        Self: Sized,
+    {
+        RayTopDop::new(self, count)
+    }
+
+    /// Splits the rays between two sinks in a single pass.
+    ///
+    /// Rays that satisfy `pred` extend `matched` and the rest extend `unmatched`, e.g., to feed
+    /// high and low DoP rays to different estimators without collecting the stream twice.
+    /// Sinks may be any [`Extend`], such as a `Vec` or a running accumulator.
+    fn partition_by<P, A, B>(self, pred: P, mut matched: A, mut unmatched: B) -> (A, B)
+    where
+        Self: Sized,
+        P: RayPredicate<Frame>,
+        A: Extend<Ray<Frame>>,
+        B: Extend<Ray<Frame>>,
+    {
+        for ray in self {
+            if pred.eval(&ray) {
+                matched.extend(Some(ray));
+            } else {
+                unmatched.extend(Some(ray));
+            }
+        }
+
+        (matched, unmatched)
+    }
+
+    /// Splits the rays between any number of `sinks` in a single pass.
+    ///
+    /// Each ray extends the sink at the index returned by `classify`.
+    /// Rays classified as `None` or past the last sink are dropped.
+    fn partition_into<S, F>(self, mut classify: F, sinks: &mut [S])
+    where
+        Self: Sized,
+        S: Extend<Ray<Frame>>,
+        F: FnMut(&Ray<Frame>) -> Option<usize>,
+    {
+        for ray in self {
+            if let Some(sink) = classify(&ray).and_then(|index| sinks.get_mut(index)) {
+                sink.extend(Some(ray));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter::DopFilter, light::aop::Aop, ray::SensorFrame};
+    use uom::si::{angle::degree, f64::Angle};
+
+    fn rays() -> impl RayIterator<SensorFrame> {
+        let rays = [0.1, 0.6, 0.3, 0.9]
+            .map(|dop| {
+                Ray::new(
+                    Aop::from_angle_wrapped(Angle::new::<degree>(10.0)),
+                    Dop::clamped(dop),
+                )
+            })
+            .into_iter();
+        DopClamp::from_bounds(rays, Dop::zero(), Dop::clamped(1.0)).unwrap()
+    }
+
+    fn dops(rays: &[Ray<SensorFrame>]) -> Vec<f64> {
+        rays.iter().map(|ray| ray.dop().into()).collect()
+    }
+
+    #[test]
+    fn partitions_by_predicate() {
+        let (high, low) = rays().partition_by(
+            DopFilter::from_min(Dop::clamped(0.5)),
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(dops(&high), [0.6, 0.9]);
+        assert_eq!(dops(&low), [0.1, 0.3]);
+    }
+
+    #[test]
+    fn partitions_into_sinks() {
+        let mut sinks = vec![Vec::new(); 2];
+        rays().partition_into(
+            |ray| match f64::from(ray.dop()) {
+                dop if dop < 0.2 => None,
+                dop if dop < 0.5 => Some(0),
+                dop if dop < 0.8 => Some(1),
+                _ => Some(2),
+            },
+            &mut sinks,
+        );
+        assert_eq!(dops(&sinks[0]), [0.3]);
+        assert_eq!(dops(&sinks[1]), [0.6]);
+    }
 }
@@ -2,22 +2,78 @@
 
 //! Skylight Polarization Utilities
 
+pub mod azimuth;
+pub mod calibration;
+pub mod condition;
+pub mod decimate;
 pub mod error;
+#[cfg(feature = "estimation")]
+pub mod estimate;
+pub mod exposure;
 pub mod filter;
+pub mod hdr;
 pub mod image;
+#[cfg(feature = "inference")]
+pub mod inference;
+#[cfg(feature = "io")]
+pub mod io;
 pub mod iter;
 pub mod light;
+pub mod mask;
+pub mod meta;
+#[cfg(feature = "estimation")]
+pub mod metrics;
 pub mod model;
+pub mod neutral;
 pub mod optic;
+pub mod provenance;
+pub mod raw;
 pub mod ray;
+pub mod render;
+mod rng;
+pub mod sequential;
+#[cfg(feature = "simulation")]
 pub mod simulation;
+pub mod temporal;
+pub mod testing;
+pub mod trajectory;
 
+/// Imports the types of a complete pipeline from intensities or a simulated sky to a heading.
 pub mod prelude {
+    pub use crate::azimuth::AzimuthConvention;
+    pub use crate::calibration::{
+        InstrumentalPolarization, PolarizerCalibration, PolarizerChannel, S0Normalization,
+    };
+    pub use crate::decimate::Decimation;
     pub use crate::error::Error;
+    #[cfg(feature = "estimation")]
+    pub use crate::estimate::{
+        fusion::{HeadingEstimate, HeadingEstimator, HeadingFusion},
+        signature::{AopSignature, SignatureMatch},
+        solar::SolarBearingFit,
+        symmetry::SymmetryAxisFit,
+        tilt::TiltFit,
+    };
     pub use crate::filter::{AopFilter, DopFilter, RayFilter};
-    pub use crate::image::{IntensityImage, RayImage};
+    pub use crate::image::{IntensityImage, IntensityView, RayImage, StokesImage};
     pub use crate::iter::RayIterator;
-    pub use crate::light::{aop::Aop, dop::Dop};
-    pub use crate::model::SkyModel;
-    pub use crate::ray::{GlobalFrame, Ray, SensorFrame};
+    pub use crate::light::{
+        aop::{Aop, AopConvention},
+        dop::Dop,
+    };
+    pub use crate::mask::{PixelMask, SkyPatch};
+    pub use crate::meta::FrameMeta;
+    pub use crate::model::{
+        ElevationGate, SkyModel,
+        ephemeris::{RecordedEphemeris, SolarEphemeris},
+        refraction::Refraction,
+    };
+    pub use crate::optic::{
+        Camera, MetapixelLayout, Optic, PinholeOptic, PixelCoordinate, Vignetting,
+    };
+    pub use crate::ray::{
+        FrameShift, FrameTransform, GlobalFrame, Ray, RayFlags, SensorFrame, WeightedRay,
+    };
+    #[cfg(feature = "simulation")]
+    pub use crate::simulation::Simulation;
 }
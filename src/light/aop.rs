@@ -1,10 +1,17 @@
 use crate::{
     light::LightError,
-    ray::{GlobalFrame, SensorFrame},
+    ray::{FrameTransform, GlobalFrame, SensorFrame},
 };
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use uom::si::f64::Angle;
+use std::f64::consts::{FRAC_PI_2, PI};
+use uom::{
+    ConstZero,
+    si::{
+        angle::{degree, radian},
+        f64::Angle,
+    },
+};
 
 /// Describes the e-vector orientation of a ray.
 ///
@@ -43,9 +50,13 @@ impl<Frame> Aop<Frame> {
     /// Creates a new `Aop` from `angle`.
     ///
     /// # Errors
-    /// Will return `Err` if `angle` is outside of [-PI, PI].
+    /// Will return `Err` if `angle` is not finite or is outside of [-PI, PI].
     pub fn try_from_angle(angle: Angle) -> Result<Self, LightError> {
-        if Self::is_valid(angle) {
+        if !angle.is_finite() {
+            Err(LightError::NonFinite {
+                value: angle.get::<radian>(),
+            })
+        } else if Self::is_valid(angle) {
             Ok(Self {
                 inner: angle,
                 _phan: std::marker::PhantomData,
@@ -56,8 +67,13 @@ impl<Frame> Aop<Frame> {
     }
 
     /// Creates a new `Aop` from `angle` wrapping into -90.0 and 90.0 to be wrapped.
+    ///
+    /// # Panics
+    /// Will panic if `angle` is not finite.
     #[must_use]
     pub fn from_angle_wrapped(mut angle: Angle) -> Self {
+        assert!(angle.is_finite(), "angle must be finite: {angle:#?}");
+
         while angle > Angle::HALF_TURN / 2. {
             angle -= Angle::HALF_TURN;
         }
@@ -67,18 +83,92 @@ impl<Frame> Aop<Frame> {
         }
 
         // Expect is enforced by the while loops above.
-        #[allow(clippy::missing_panics_doc)]
         Self::try_from_angle(angle).expect("angle is within range -90 to 90")
     }
 
+    /// Returns the signed difference `self - other` wrapped into -90.0 and 90.0.
+    ///
+    /// Since AoP is axial, the difference is the smallest rotation that takes `other` to `self`.
+    #[must_use]
+    pub fn wrapped_difference(&self, other: &Aop<Frame>) -> Angle {
+        Self::from_angle_wrapped(self.inner - other.inner).inner
+    }
+
+    /// Returns the unsigned angle between `self` and `other` on [0, 90].
+    #[must_use]
+    pub fn angular_distance(&self, other: &Aop<Frame>) -> Angle {
+        self.wrapped_difference(other).abs()
+    }
+
     /// Returns true if `other` is within `thres` of `self` inclusive and
     /// handling wrapping.
     #[must_use]
-    pub fn in_thres(self, other: Aop<Frame>, thres: Angle) -> bool
-    where
-        Frame: Copy,
-    {
-        (self - other).inner.abs() <= thres
+    pub fn in_thres(self, other: Aop<Frame>, thres: Angle) -> bool {
+        self.angular_distance(&other) <= thres
+    }
+}
+
+impl<Frame> Aop<Frame> {
+    /// Returns the angle of the `Aop` using the axial convention on [0, 180).
+    ///
+    /// Angles on [-90, 0) are shifted by a half turn.
+    /// Since -90 and 90 describe the same e-vector, both map to 90.
+    #[must_use]
+    pub fn to_axial_0_180(self) -> Angle {
+        if self.inner < Angle::ZERO {
+            self.inner + Angle::HALF_TURN
+        } else {
+            self.inner
+        }
+    }
+}
+
+/// Describes the range and units used to represent an [`Aop`] as a bare `f64`.
+///
+/// Use this type when exchanging [`Aop`]s with tools that do not share the crate's convention.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AopConvention {
+    /// Degrees on [-90, 90].
+    #[default]
+    SignedDegrees,
+    /// Degrees on [0, 180).
+    AxialDegrees,
+    /// Radians on [-PI/2, PI/2].
+    SignedRadians,
+    /// Radians on [0, PI).
+    AxialRadians,
+}
+
+impl AopConvention {
+    /// Returns the value of `aop` in this convention.
+    #[must_use]
+    pub fn value<Frame>(self, aop: Aop<Frame>) -> f64 {
+        match self {
+            Self::SignedDegrees => aop.inner.get::<degree>(),
+            Self::AxialDegrees => aop.to_axial_0_180().get::<degree>(),
+            Self::SignedRadians => aop.inner.get::<radian>(),
+            Self::AxialRadians => aop.to_axial_0_180().get::<radian>(),
+        }
+    }
+
+    /// Returns the lower and upper bound of values in this convention.
+    #[must_use]
+    pub fn bounds(self) -> (f64, f64) {
+        match self {
+            Self::SignedDegrees => (-90.0, 90.0),
+            Self::AxialDegrees => (0.0, 180.0),
+            Self::SignedRadians => (-FRAC_PI_2, FRAC_PI_2),
+            Self::AxialRadians => (0.0, PI),
+        }
+    }
+}
+
+impl<From> Aop<From> {
+    /// Transforms the `Aop` from `From` into `To`.
+    #[must_use]
+    pub fn transform<To>(self, transform: &impl FrameTransform<From, To>) -> Aop<To> {
+        Aop::from_angle_wrapped(self.inner - transform.shift())
     }
 }
 
@@ -139,7 +229,6 @@ mod tests {
     use approx::assert_relative_eq;
     use quickcheck::quickcheck;
     use rstest::rstest;
-    use uom::si::angle::{degree, radian};
 
     fn a(angle: f64) -> Angle {
         Angle::new::<degree>(angle)
@@ -149,23 +238,40 @@ mod tests {
         fn aop_from_wrapped(angle: i8) -> bool {
             // Will panic if it tries to create an invalid Aop.
             // Should never panic due to wrapping.
-            Aop::<GlobalFrame>::from_angle_wrapped(a(angle as f64));
+            let _ = Aop::<GlobalFrame>::from_angle_wrapped(a(angle as f64));
 
             // If we didn't panic, call this test a success.
             true
         }
     }
 
+    #[test]
+    fn non_finite_aop() {
+        assert!(matches!(
+            Aop::<GlobalFrame>::try_from_angle(a(f64::NAN)),
+            Err(LightError::NonFinite { .. })
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn non_finite_wrapped_aop() {
+        let _ = Aop::<GlobalFrame>::from_angle_wrapped(a(f64::INFINITY));
+    }
+
     #[rstest]
     #[case(a(180.0))]
     #[case(a(91.0))]
     fn invalid_aop(#[case] angle: Angle) {
-        assert_eq!(Aop::<GlobalFrame>::from_angle(angle), None,);
+        #[allow(deprecated)]
+        let aop = Aop::<GlobalFrame>::from_angle(angle);
+        assert_eq!(aop, None,);
     }
 
     #[rstest]
     #[case(a(90.0), a(-89.0), a(1.0))]
     fn add_aop(#[case] lhs: Angle, #[case] rhs: Angle, #[case] sum: Angle) {
+        #[allow(deprecated)]
         let result = Aop::<GlobalFrame>::from_angle(lhs).unwrap() + Aop::from_angle(rhs).unwrap();
         assert_relative_eq!(result.inner.get::<radian>(), sum.get::<radian>(),);
     }
@@ -175,6 +281,7 @@ mod tests {
     #[case(a(-90.0), a(90.0), a(0.0))]
     #[case(a(-90.0), a(-90.0), a(0.0))]
     fn sub_aop(#[case] lhs: Angle, #[case] rhs: Angle, #[case] dif: Angle) {
+        #[allow(deprecated)]
         let result = Aop::<GlobalFrame>::from_angle(lhs).unwrap() - Aop::from_angle(rhs).unwrap();
         assert_relative_eq!(result.inner.get::<radian>(), dif.get::<radian>());
     }
@@ -208,11 +315,81 @@ mod tests {
     fn frame_reversible(#[case] angle: Angle, #[case] offset: Angle) {
         assert_relative_eq!(
             Aop::<SensorFrame>::from_angle_wrapped(angle)
-                .into_global_frame(offset.clone())
+                .into_global_frame(offset)
                 .into_sensor_frame(offset)
                 .inner
                 .get::<radian>(),
             angle.get::<radian>(),
         );
     }
+
+    #[rstest]
+    #[case(a(-90.0), a(90.0))]
+    #[case(a(-45.0), a(135.0))]
+    #[case(a(0.0), a(0.0))]
+    #[case(a(90.0), a(90.0))]
+    fn axial_aop(#[case] angle: Angle, #[case] axial: Angle) {
+        assert_relative_eq!(
+            Aop::<GlobalFrame>::try_from_angle(angle)
+                .unwrap()
+                .to_axial_0_180()
+                .get::<degree>(),
+            axial.get::<degree>(),
+        );
+    }
+
+    #[rstest]
+    #[case(AopConvention::SignedDegrees, -30.0)]
+    #[case(AopConvention::AxialDegrees, 150.0)]
+    #[case(AopConvention::SignedRadians, -30.0_f64.to_radians())]
+    #[case(AopConvention::AxialRadians, 150.0_f64.to_radians())]
+    fn aop_convention(#[case] convention: AopConvention, #[case] value: f64) {
+        let aop = Aop::<GlobalFrame>::try_from_angle(a(-30.0)).unwrap();
+        let result = convention.value(aop);
+        let (min, max) = convention.bounds();
+
+        assert_relative_eq!(result, value);
+        assert!((min..=max).contains(&result));
+    }
+
+    #[rstest]
+    #[case(a(90.0), a(-90.0), a(0.0))]
+    #[case(a(-90.0), a(90.0), a(0.0))]
+    #[case(a(89.0), a(-89.0), a(-2.0))]
+    #[case(a(-89.0), a(89.0), a(2.0))]
+    #[case(a(90.0), a(0.0), a(90.0))]
+    #[case(a(0.0), a(90.0), a(-90.0))]
+    #[case(a(45.0), a(-45.0), a(90.0))]
+    #[case(a(46.0), a(-45.0), a(-89.0))]
+    #[case(a(10.0), a(30.0), a(-20.0))]
+    fn wrapped_difference_aop(#[case] lhs: Angle, #[case] rhs: Angle, #[case] dif: Angle) {
+        let lhs = Aop::<GlobalFrame>::try_from_angle(lhs).unwrap();
+        let rhs = Aop::<GlobalFrame>::try_from_angle(rhs).unwrap();
+
+        // A difference of exactly 90 degrees is ambiguous in sign.
+        let result = lhs.wrapped_difference(&rhs);
+        if dif.abs() == a(90.0) {
+            assert_relative_eq!(result.abs().get::<degree>(), 90.0);
+        } else {
+            assert_relative_eq!(result.get::<degree>(), dif.get::<degree>(), epsilon = 1e-9);
+        }
+
+        assert_relative_eq!(
+            lhs.angular_distance(&rhs).get::<degree>(),
+            dif.abs().get::<degree>(),
+            epsilon = 1e-9
+        );
+    }
+
+    quickcheck! {
+        fn angular_distance_symmetric(lhs: i16, rhs: i16) -> bool {
+            let lhs = Aop::<GlobalFrame>::from_angle_wrapped(a(f64::from(lhs) / 100.0));
+            let rhs = Aop::<GlobalFrame>::from_angle_wrapped(a(f64::from(rhs) / 100.0));
+            let distance = lhs.angular_distance(&rhs);
+
+            distance == rhs.angular_distance(&lhs)
+                && distance >= Angle::ZERO
+                && distance <= a(90.0)
+        }
+    }
 }
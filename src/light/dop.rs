@@ -27,9 +27,11 @@ impl Dop {
     /// Create a new `Dop` from `degree`.
     ///
     /// # Errors
-    /// Will return `Err` if `degree` is outside of [0, 1].
+    /// Will return `Err` if `degree` is not finite or is outside of [0, 1].
     pub fn try_new(degree: f64) -> Result<Self, LightError> {
-        if (0.0..=1.0).contains(&degree) {
+        if !degree.is_finite() {
+            Err(LightError::NonFinite { value: degree })
+        } else if (0.0..=1.0).contains(&degree) {
             Ok(Self { inner: degree })
         } else {
             Err(LightError::DegreeOutOfBounds { degree })
@@ -171,6 +173,7 @@ mod tests {
 
     #[test]
     #[should_panic]
+    #[allow(deprecated)]
     fn create_invalid_dop() {
         Dop::new(-1.0).unwrap();
     }
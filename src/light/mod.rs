@@ -11,4 +11,6 @@ pub enum LightError {
     AngleOutOfBounds { angle: Angle },
     #[error("expected degree in range [0, 1] but got: {degree}")]
     DegreeOutOfBounds { degree: f64 },
+    #[error("expected a finite value but got: {value}")]
+    NonFinite { value: f64 },
 }
@@ -1,4 +1,7 @@
-use crate::light::{LightError, aop::Aop, dop::Dop};
+use crate::{
+    light::{LightError, aop::Aop, dop::Dop},
+    ray::{FrameTransform, GlobalFrame, SensorFrame},
+};
 use uom::si::{angle::radian, f64::Angle};
 
 /// Describes the linear polarization of a ray.
@@ -17,11 +20,28 @@ impl<Frame> StokesVec<Frame> {
         }
     }
 
+    #[must_use]
+    pub fn s0(&self) -> f64 {
+        self.inner[0]
+    }
+
+    #[must_use]
+    pub fn s1(&self) -> f64 {
+        self.inner[1]
+    }
+
+    #[must_use]
+    pub fn s2(&self) -> f64 {
+        self.inner[2]
+    }
+
     /// Compute the `AoP` of the ray.
     ///
     /// # Errors
-    /// Will return an `Err` if the Stokes vector encodes an [`Aop`] outside of [-90, 90].
+    /// Will return an `Err` if the Stokes vector is not finite or encodes an [`Aop`] outside of
+    /// [-90, 90].
     pub fn aop(&self) -> Result<Aop<Frame>, LightError> {
+        self.check_finite()?;
         let angle = Angle::new::<radian>(self.inner[2].atan2(self.inner[1]) / 2.);
         Aop::try_from_angle(angle)
     }
@@ -29,8 +49,119 @@ impl<Frame> StokesVec<Frame> {
     /// Compute the `DoP` of the ray.
     ///
     /// # Errors
-    /// Will return `Err` if the Stokes vector encodes a [`Dop`] outside of [0, 1].
+    /// Will return `Err` if the Stokes vector is not finite or encodes a [`Dop`] outside of
+    /// [0, 1].
+    /// A zero `S_0` encodes a [`Dop`] that is not finite.
     pub fn dop(&self) -> Result<Dop, LightError> {
+        self.check_finite()?;
         Dop::try_new((self.inner[1].powf(2.) + self.inner[2].powf(2.)).sqrt() / self.inner[0])
     }
+
+    fn check_finite(&self) -> Result<(), LightError> {
+        match self.inner.iter().find(|value| !value.is_finite()) {
+            Some(&value) => Err(LightError::NonFinite { value }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<Frame> StokesVec<Frame> {
+    /// Rotates the reference axis of the linear Stokes parameters so the [`Aop`] changes by
+    /// `-shift`.
+    pub(crate) fn rotate<Into>(&self, shift: Angle) -> StokesVec<Into> {
+        let (sin, cos) = (2.0 * shift).get::<radian>().sin_cos();
+        let [s0, s1, s2] = self.inner;
+        StokesVec::new(s0, cos * s1 + sin * s2, cos * s2 - sin * s1)
+    }
+}
+
+impl<From> StokesVec<From> {
+    /// Transforms the `StokesVec` from `From` into `To`.
+    #[must_use]
+    pub fn transform<To>(self, transform: &impl FrameTransform<From, To>) -> StokesVec<To> {
+        self.rotate(transform.shift())
+    }
+}
+
+impl StokesVec<GlobalFrame> {
+    /// Transforms the `StokesVec` from the `GlobalFrame` into the `SensorFrame`.
+    ///
+    /// This matches [`Aop::into_sensor_frame`].
+    #[must_use]
+    pub fn into_sensor_frame(self, shift: Angle) -> StokesVec<SensorFrame> {
+        self.rotate(-shift)
+    }
+}
+
+impl StokesVec<SensorFrame> {
+    /// Transforms the `StokesVec` from the `SensorFrame` into the `GlobalFrame`.
+    ///
+    /// This matches [`Aop::into_global_frame`].
+    #[must_use]
+    pub fn into_global_frame(self, shift: Angle) -> StokesVec<GlobalFrame> {
+        self.rotate(shift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    #[rstest]
+    #[case(0.0, 0.0, 0.0)]
+    #[case(f64::NAN, 1.0, 0.0)]
+    #[case(1.0, f64::INFINITY, 0.0)]
+    fn non_finite_dop(#[case] s0: f64, #[case] s1: f64, #[case] s2: f64) {
+        assert!(matches!(
+            StokesVec::<SensorFrame>::new(s0, s1, s2).dop(),
+            Err(LightError::NonFinite { .. })
+        ));
+    }
+
+    #[test]
+    fn non_finite_aop() {
+        assert!(matches!(
+            StokesVec::<SensorFrame>::new(1.0, f64::NAN, 0.0).aop(),
+            Err(LightError::NonFinite { .. })
+        ));
+    }
+
+    #[rstest]
+    #[case(30.0, 20.0)]
+    #[case(80.0, -25.0)]
+    #[case(-60.0, 45.0)]
+    fn frame_transform_matches_aop(#[case] aop: f64, #[case] shift: f64) {
+        let (aop, shift) = (Angle::new::<degree>(aop), Angle::new::<degree>(shift));
+        let stokes = StokesVec::<SensorFrame>::new(
+            1.0,
+            0.5 * (2.0 * aop).get::<radian>().cos(),
+            0.5 * (2.0 * aop).get::<radian>().sin(),
+        );
+        let expected = stokes.aop().unwrap().into_global_frame(shift);
+        let result = stokes.into_global_frame(shift);
+
+        assert_relative_eq!(
+            result
+                .aop()
+                .unwrap()
+                .angular_distance(&expected)
+                .get::<degree>(),
+            0.0,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(f64::from(result.dop().unwrap()), 0.5, epsilon = 1e-12);
+        assert_relative_eq!(
+            result
+                .into_sensor_frame(shift)
+                .aop()
+                .unwrap()
+                .angular_distance(&Aop::from_angle_wrapped(aop))
+                .get::<degree>(),
+            0.0,
+            epsilon = 1e-9
+        );
+    }
 }
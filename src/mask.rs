@@ -0,0 +1,277 @@
+//! Fixed occlusions of the field of view.
+
+use crate::{
+    image::{ImageError, RayImage},
+    optic::{Camera, PixelCoordinate},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MaskError {
+    #[error("length of mask does not match size of extents: expected {} found {len}", rows * cols)]
+    SizeMismatch {
+        rows: usize,
+        cols: usize,
+        len: usize,
+    },
+
+    #[error(
+        "pixel mask extents do not match image: expected {expected_rows}x{expected_cols} found {rows}x{cols}"
+    )]
+    ExtentsMismatch {
+        expected_rows: usize,
+        expected_cols: usize,
+        rows: usize,
+        cols: usize,
+    },
+
+    #[error(transparent)]
+    Image(#[from] ImageError),
+}
+
+/// Marks the pixels of a sensor that see the sky.
+///
+/// Rigs often have fixed occlusions, e.g., antennas or a roll cage, that cover the same pixels
+/// in every frame.
+/// Applying a `PixelMask` to a [`RayImage`] removes the rays at those pixels so they never reach
+/// an estimator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PixelMask {
+    keep: Vec<bool>,
+    rows: usize,
+    cols: usize,
+}
+
+impl PixelMask {
+    /// Creates a new `PixelMask` from row-major flags that are `true` where the sky is visible.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of `keep` does not match `rows` by `cols`.
+    pub fn new(keep: Vec<bool>, rows: usize, cols: usize) -> Result<Self, MaskError> {
+        let len = keep.len();
+        if rows * cols != len {
+            return Err(MaskError::SizeMismatch { rows, cols, len });
+        }
+
+        Ok(Self { keep, rows, cols })
+    }
+
+    /// Creates a new `PixelMask` from a row-major 8-bit grayscale image.
+    ///
+    /// Black pixels are occluded and every other pixel sees the sky.
+    /// Masks are usually drawn as PNG files and decoded by the caller, e.g., with the `image`
+    /// crate.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of `bytes` does not match `rows` by `cols`.
+    pub fn from_gray8(bytes: &[u8], rows: usize, cols: usize) -> Result<Self, MaskError> {
+        Self::new(bytes.iter().map(|value| *value != 0).collect(), rows, cols)
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns `true` if the pixel sees the sky.
+    ///
+    /// Pixels outside of the mask are occluded.
+    #[must_use]
+    pub fn keeps(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols && self.keep[row * self.cols + col]
+    }
+
+    /// Returns the number of pixels that see the sky.
+    #[must_use]
+    pub fn kept(&self) -> usize {
+        self.keep.iter().filter(|keep| **keep).count()
+    }
+
+    /// Checks that the mask covers every pixel of the sensor of `camera`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the mask do not match the sensor.
+    pub fn validate<O>(&self, camera: &Camera<O>) -> Result<(), MaskError> {
+        self.check_extents(camera.rows(), camera.cols())
+    }
+
+    /// Returns a copy of `image` without rays at occluded pixels.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the mask do not match `image`.
+    pub fn apply<Frame: Copy>(
+        &self,
+        image: &RayImage<Frame>,
+    ) -> Result<RayImage<Frame>, MaskError> {
+        let (rows, cols) = (image.rows(), image.cols());
+        self.check_extents(rows, cols)?;
+
+        let rays = image
+            .rays()
+            .zip(&self.keep)
+            .map(|(ray, keep)| ray.copied().filter(|_| *keep));
+
+        Ok(RayImage::from_rays(rays, rows, cols)?.with_meta(*image.meta()))
+    }
+
+    /// Segments the visible pixels into 4-connected [`SkyPatch`]es of at least `min_area`
+    /// pixels.
+    ///
+    /// Patches are ordered by their first pixel in row-major order.
+    #[must_use]
+    pub fn patches(&self, min_area: usize) -> Vec<SkyPatch> {
+        let (rows, cols) = (self.rows, self.cols);
+        let mut visited = vec![false; self.keep.len()];
+        let mut patches = Vec::new();
+        for seed in 0..self.keep.len() {
+            if !self.keep[seed] || visited[seed] {
+                continue;
+            }
+
+            visited[seed] = true;
+            let mut stack = vec![seed];
+            let mut pixels = Vec::new();
+            while let Some(index) = stack.pop() {
+                let (row, col) = (index / cols, index % cols);
+                pixels.push(PixelCoordinate::new(row, col));
+
+                let neighbors = [
+                    (row > 0).then(|| index - cols),
+                    (row + 1 < rows).then(|| index + cols),
+                    (col > 0).then(|| index - 1),
+                    (col + 1 < cols).then(|| index + 1),
+                ];
+                for neighbor in neighbors.into_iter().flatten() {
+                    if self.keep[neighbor] && !visited[neighbor] {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            if pixels.len() >= min_area {
+                pixels.sort_by_key(|pixel| (pixel.row(), pixel.col()));
+                patches.push(SkyPatch { pixels });
+            }
+        }
+
+        patches
+    }
+
+    fn check_extents(&self, rows: usize, cols: usize) -> Result<(), MaskError> {
+        if (self.rows, self.cols) != (rows, cols) {
+            return Err(MaskError::ExtentsMismatch {
+                expected_rows: self.rows,
+                expected_cols: self.cols,
+                rows,
+                cols,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// A region of an image where the sky is visible, e.g., a gap between buildings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkyPatch {
+    pixels: Vec<PixelCoordinate>,
+}
+
+impl SkyPatch {
+    /// Creates a new `SkyPatch` from the pixels it covers.
+    #[must_use]
+    pub fn new(pixels: impl IntoIterator<Item = PixelCoordinate>) -> Self {
+        Self {
+            pixels: pixels.into_iter().collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn pixels(&self) -> &[PixelCoordinate] {
+        &self.pixels
+    }
+
+    /// Returns the number of pixels in the patch.
+    #[must_use]
+    pub fn area(&self) -> usize {
+        self.pixels.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::{aop::Aop, dop::Dop},
+        ray::{Ray, SensorFrame},
+    };
+    use uom::si::{angle::degree, f64::Angle};
+
+    fn image(rows: usize, cols: usize) -> RayImage<SensorFrame> {
+        let rays = (0..rows * cols).map(|_| {
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(10.0)),
+                Dop::clamped(0.5),
+            ))
+        });
+        RayImage::from_rays(rays, rows, cols).unwrap()
+    }
+
+    #[test]
+    fn removes_occluded_rays() {
+        let mask = PixelMask::from_gray8(&[255, 0, 255, 0, 0, 255], 2, 3).unwrap();
+        let masked = mask.apply(&image(2, 3)).unwrap();
+
+        assert_eq!(mask.kept(), 3);
+        assert_eq!(masked.rays().flatten().count(), 3);
+        for (row, col) in [(0, 0), (0, 2), (1, 2)] {
+            assert!(mask.keeps(row, col));
+            assert!(masked.ray(row, col).is_some());
+        }
+        assert!(masked.ray(0, 1).is_none());
+        assert!(!mask.keeps(2, 0));
+    }
+
+    #[test]
+    fn rejects_mismatched_extents() {
+        let mask = PixelMask::new(vec![true; 6], 2, 3).unwrap();
+
+        assert!(matches!(
+            mask.apply(&image(3, 2)),
+            Err(MaskError::ExtentsMismatch { .. })
+        ));
+        assert!(matches!(
+            PixelMask::from_gray8(&[0; 5], 2, 3),
+            Err(MaskError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn segments_patches() {
+        #[rustfmt::skip]
+        let mask = PixelMask::from_gray8(&[
+            1, 1, 0, 0,
+            0, 1, 0, 1,
+            0, 0, 0, 1,
+            1, 0, 0, 0,
+        ], 4, 4).unwrap();
+
+        let areas: Vec<_> = mask.patches(1).iter().map(SkyPatch::area).collect();
+        assert_eq!(areas, [3, 2, 1]);
+        assert_eq!(mask.patches(2).len(), 2);
+        assert_eq!(
+            mask.patches(3)[0].pixels(),
+            [
+                PixelCoordinate::new(0, 0),
+                PixelCoordinate::new(0, 1),
+                PixelCoordinate::new(1, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Acquisition metadata that travels with images through the pipeline.
+
+use crate::model::ephemeris::SolarPosition;
+use chrono::{DateTime, Utc};
+use sguaba::systems::Wgs84;
+use uom::si::f64::Time;
+
+/// Describes how and when a frame was captured.
+///
+/// Every field is optional so sources only fill in what they know.
+/// Images carry their `FrameMeta` through Stokes formation, frame transforms, decimation, and
+/// cloud simulation, so outputs can report it without passing it out-of-band.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameMeta {
+    time: Option<DateTime<Utc>>,
+    exposure: Option<Time>,
+    gain: Option<f64>,
+    sequence: Option<u64>,
+    position: Option<Wgs84>,
+    solar_position: Option<SolarPosition>,
+}
+
+impl FrameMeta {
+    /// Creates an empty `FrameMeta`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the UTC time at which the frame was captured.
+    #[must_use]
+    pub fn with_time(mut self, time: DateTime<Utc>) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Sets the exposure time of the frame.
+    #[must_use]
+    pub fn with_exposure(mut self, exposure: Time) -> Self {
+        self.exposure = Some(exposure);
+        self
+    }
+
+    /// Sets the analog gain of the sensor.
+    #[must_use]
+    pub fn with_gain(mut self, gain: f64) -> Self {
+        self.gain = Some(gain);
+        self
+    }
+
+    /// Sets the position of the frame in its sequence.
+    #[must_use]
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Sets the position of the camera when the frame was captured.
+    #[must_use]
+    pub fn with_position(mut self, position: Wgs84) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Sets the position of the sun assumed for the frame, e.g., by the simulation that rendered
+    /// it.
+    ///
+    /// Recording it lets a heading error be traced back to the sun position behind it.
+    #[must_use]
+    pub fn with_solar_position(mut self, solar_position: SolarPosition) -> Self {
+        self.solar_position = Some(solar_position);
+        self
+    }
+
+    #[must_use]
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        self.time
+    }
+
+    #[must_use]
+    pub fn exposure(&self) -> Option<Time> {
+        self.exposure
+    }
+
+    #[must_use]
+    pub fn gain(&self) -> Option<f64> {
+        self.gain
+    }
+
+    #[must_use]
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    #[must_use]
+    pub fn position(&self) -> Option<Wgs84> {
+        self.position
+    }
+
+    #[must_use]
+    pub fn solar_position(&self) -> Option<SolarPosition> {
+        self.solar_position
+    }
+}
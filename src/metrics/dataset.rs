@@ -0,0 +1,570 @@
+//! Datasets of captured frames with ground truth for batch evaluation.
+
+use super::pose::{ErrorSummary, wrap};
+use crate::{
+    calibration::PolarizerCalibration,
+    estimate::fusion::{HeadingEstimate, HeadingEstimator},
+    image::{ImageError, IntensityImage},
+    meta::FrameMeta,
+    optic::{Camera, PinholeOptic},
+    provenance::Provenance,
+    ray::{Ray, SensorFrame},
+};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sguaba::{engineering::Orientation, system, systems::Wgs84};
+use std::{
+    borrow::Cow,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use uom::si::{angle::degree, f64::Angle};
+
+#[derive(Debug, Error)]
+pub enum DatasetError {
+    #[error("failed to read dataset image {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("failed to decode dataset image {}", path.display())]
+    Image {
+        path: PathBuf,
+        #[source]
+        source: ImageError,
+    },
+}
+
+system!(
+    /// Local frame of a [`DatasetEntry`].
+    ///
+    /// Axes are aligned with north, east, and down at the position of the camera, so the yaw of
+    /// an orientation in this frame is a heading clockwise from north.
+    pub struct DatasetNed using NED
+);
+
+/// A frame of a [`Manifest`] with its ground truth.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DatasetEntry {
+    image: PathBuf,
+    time: DateTime<Utc>,
+    heading: Angle,
+    #[cfg_attr(feature = "serde", serde(default))]
+    position: Option<Wgs84>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    orientation: Option<Orientation<DatasetNed>>,
+}
+
+impl DatasetEntry {
+    /// Creates a new `DatasetEntry` for the frame stored at `image` that was captured at `time`
+    /// with a reference `heading`.
+    #[must_use]
+    pub fn new(image: impl Into<PathBuf>, time: DateTime<Utc>, heading: Angle) -> Self {
+        Self {
+            image: image.into(),
+            time,
+            heading,
+            position: None,
+            orientation: None,
+        }
+    }
+
+    /// Sets the position of the camera when the frame was captured.
+    #[must_use]
+    pub fn with_position(mut self, position: Wgs84) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Sets the ground truth orientation of the camera body when the frame was captured.
+    ///
+    /// The heading of the entry remains the reference that estimates are compared against.
+    #[must_use]
+    pub fn with_orientation(mut self, orientation: Orientation<DatasetNed>) -> Self {
+        self.orientation = Some(orientation);
+        self
+    }
+
+    /// The path of the frame relative to the root of its [`Manifest`].
+    #[must_use]
+    pub fn image(&self) -> &Path {
+        &self.image
+    }
+
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// The ground truth heading of the camera.
+    #[must_use]
+    pub fn heading(&self) -> Angle {
+        self.heading
+    }
+
+    #[must_use]
+    pub fn position(&self) -> Option<Wgs84> {
+        self.position
+    }
+
+    /// The ground truth orientation of the camera body.
+    #[must_use]
+    pub fn orientation(&self) -> Option<Orientation<DatasetNed>> {
+        self.orientation
+    }
+}
+
+/// Describes a dataset of frames captured by one camera.
+///
+/// Each frame is a raw 8-bit division of focal plane mosaic as read by
+/// [`IntensityImage::from_bytes`].
+/// With the `serde` feature, a `Manifest` can be stored in any format supported by serde, e.g.,
+/// JSON or TOML.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Manifest {
+    #[cfg_attr(feature = "serde", serde(default))]
+    root: PathBuf,
+    width: usize,
+    height: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    calibration: PolarizerCalibration,
+    #[cfg_attr(feature = "serde", serde(default))]
+    camera: Option<Camera<PinholeOptic>>,
+    entries: Vec<DatasetEntry>,
+}
+
+impl Manifest {
+    /// Creates an empty `Manifest` for frames of `width` by `height` pixels.
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            root: PathBuf::new(),
+            width,
+            height,
+            calibration: PolarizerCalibration::default(),
+            camera: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Sets the directory that image paths are relative to.
+    #[must_use]
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Sets the calibration applied to every frame.
+    #[must_use]
+    pub fn with_calibration(mut self, calibration: PolarizerCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Sets the camera that captured every frame.
+    ///
+    /// The camera describes the metapixels of the frames, e.g., from
+    /// [`ImageSensor::metapixel_sensor`](crate::optic::ImageSensor::metapixel_sensor).
+    #[must_use]
+    pub fn with_camera(mut self, camera: Camera<PinholeOptic>) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// Appends `entry` to the dataset.
+    #[must_use]
+    pub fn with_entry(mut self, entry: DatasetEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn calibration(&self) -> &PolarizerCalibration {
+        &self.calibration
+    }
+
+    #[must_use]
+    pub fn camera(&self) -> Option<&Camera<PinholeOptic>> {
+        self.camera.as_ref()
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[DatasetEntry] {
+        &self.entries
+    }
+
+    /// Reads and decodes the frame of `entry`.
+    ///
+    /// The image carries the time and position of `entry` in its [`FrameMeta`].
+    ///
+    /// # Errors
+    /// Will return `Err` if the frame cannot be read or does not match the extents of the
+    /// manifest.
+    pub fn load(&self, entry: &DatasetEntry) -> Result<IntensityImage, DatasetError> {
+        let path = self.root.join(&entry.image);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(source) => return Err(DatasetError::Io { path, source }),
+        };
+        let image = match IntensityImage::from_bytes(self.width, self.height, &bytes) {
+            Ok(image) => image,
+            Err(source) => return Err(DatasetError::Image { path, source }),
+        };
+
+        let mut meta = FrameMeta::new().with_time(entry.time);
+        if let Some(position) = entry.position {
+            meta = meta.with_position(position);
+        }
+
+        Ok(image.with_calibration(self.calibration).with_meta(meta))
+    }
+
+    /// Runs `estimator` on every frame and compares its estimates to the ground truth.
+    ///
+    /// # Errors
+    /// Will return `Err` if any frame cannot be loaded.
+    pub fn evaluate(&self, estimator: &impl HeadingEstimator) -> Result<Evaluation, DatasetError> {
+        let mut rows = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let rays: Vec<Ray<SensorFrame>> = self.load(entry)?.rays().collect();
+            let estimate = estimator.estimate_heading(&rays);
+            rows.push(EvaluationRow {
+                image: entry.image.clone(),
+                time: entry.time,
+                reference: entry.heading,
+                estimate,
+                error: estimate.map(|estimate| wrap(estimate.heading() - entry.heading)),
+            });
+        }
+
+        Ok(Evaluation {
+            rows,
+            provenance: None,
+        })
+    }
+}
+
+/// The result of running an estimator on one frame of a [`Manifest`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationRow {
+    image: PathBuf,
+    time: DateTime<Utc>,
+    reference: Angle,
+    estimate: Option<HeadingEstimate>,
+    error: Option<Angle>,
+}
+
+impl EvaluationRow {
+    #[must_use]
+    pub fn image(&self) -> &Path {
+        &self.image
+    }
+
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// The ground truth heading.
+    #[must_use]
+    pub fn reference(&self) -> Angle {
+        self.reference
+    }
+
+    /// The estimate, or `None` if the estimator failed on this frame.
+    #[must_use]
+    pub fn estimate(&self) -> Option<HeadingEstimate> {
+        self.estimate
+    }
+
+    /// The signed heading error wrapped into [-180, 180).
+    #[must_use]
+    pub fn error(&self) -> Option<Angle> {
+        self.error
+    }
+}
+
+/// A table of results from [`Manifest::evaluate`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Evaluation {
+    rows: Vec<EvaluationRow>,
+    provenance: Option<Provenance>,
+}
+
+impl Evaluation {
+    /// Records how the estimates were produced.
+    ///
+    /// [`Evaluation::write_csv`] writes it as comments above the table.
+    #[must_use]
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    #[must_use]
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Returns one row for each entry in the order of the manifest.
+    #[must_use]
+    pub fn rows(&self) -> &[EvaluationRow] {
+        &self.rows
+    }
+
+    /// Returns the number of frames without an estimate.
+    #[must_use]
+    pub fn failures(&self) -> usize {
+        self.rows.iter().filter(|row| row.error.is_none()).count()
+    }
+
+    /// Summarizes the heading errors of every frame with an estimate.
+    ///
+    /// Returns `None` if no frame has an estimate.
+    #[must_use]
+    pub fn summary(&self) -> Option<ErrorSummary> {
+        ErrorSummary::from_errors(self.rows.iter().filter_map(|row| row.error))
+    }
+
+    /// Writes the table as CSV with angles in degrees.
+    ///
+    /// Frames without an estimate leave the estimate, uncertainty, and error columns empty.
+    /// Image paths that contain a comma, quote, or line break are quoted.
+    /// The [`Provenance`] of the evaluation, if any, is written first as `#` comment lines.
+    ///
+    /// # Errors
+    /// Will return `Err` if writing to `writer` fails.
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        if let Some(provenance) = &self.provenance {
+            provenance.write_comments(&mut writer)?;
+        }
+        writeln!(writer, "image,time,reference,estimate,uncertainty,error")?;
+        for row in &self.rows {
+            let (estimate, uncertainty, error) = match (row.estimate, row.error) {
+                (Some(estimate), Some(error)) => (
+                    estimate.heading().get::<degree>().to_string(),
+                    estimate.uncertainty().get::<degree>().to_string(),
+                    error.get::<degree>().to_string(),
+                ),
+                _ => Default::default(),
+            };
+
+            writeln!(
+                writer,
+                "{},{},{},{estimate},{uncertainty},{error}",
+                csv_field(&row.image.to_string_lossy()),
+                row.time.to_rfc3339(),
+                row.reference.get::<degree>(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Quotes `text` as a CSV field if it contains a delimiter, doubling any quotes.
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        "2025-06-13T16:26:47+00:00"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            + chrono::TimeDelta::seconds(seconds)
+    }
+
+    fn manifest(name: &str) -> Manifest {
+        let root =
+            std::env::temp_dir().join(format!("rumpus-dataset-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        // A 4x4 mosaic of metapixels polarized along 0 degrees.
+        let metapixel = [[200, 100], [0, 100]];
+        let bytes: Vec<u8> = (0..4)
+            .flat_map(|y| (0..4).map(move |x| metapixel[y % 2][x % 2]))
+            .collect();
+        fs::write(root.join("0.raw"), &bytes).unwrap();
+        fs::write(root.join("1.raw"), &bytes).unwrap();
+
+        let deg = Angle::new::<degree>;
+        Manifest::new(4, 4)
+            .with_root(root)
+            .with_entry(DatasetEntry::new("0.raw", time(0), deg(10.0)))
+            .with_entry(DatasetEntry::new("1.raw", time(1), deg(-170.0)))
+    }
+
+    #[test]
+    fn evaluates_estimator() {
+        let manifest = manifest("evaluate");
+        let estimator = |rays: &[Ray<SensorFrame>]| {
+            (!rays.is_empty()).then(|| {
+                HeadingEstimate::new(Angle::new::<degree>(175.0), Angle::new::<degree>(1.0))
+            })
+        };
+        let evaluation = manifest.evaluate(&estimator).unwrap();
+
+        let errors: Vec<f64> = evaluation
+            .rows()
+            .iter()
+            .map(|row| row.error().unwrap().get::<degree>())
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert!((errors[0] - 165.0).abs() < 1e-9);
+        assert!((errors[1] + 15.0).abs() < 1e-9);
+        assert_eq!(evaluation.failures(), 0);
+        assert_eq!(evaluation.summary().unwrap().count(), 2);
+
+        let mut csv = Vec::new();
+        evaluation.write_csv(&mut csv).unwrap();
+        let csv_text = String::from_utf8(csv).unwrap();
+        assert_eq!(csv_text.lines().count(), 3);
+        assert!(
+            csv_text
+                .lines()
+                .nth(1)
+                .unwrap()
+                .starts_with("0.raw,2025-06-13T16:26:47+00:00,10,175,1,")
+        );
+
+        let provenance = Provenance::new("fixed").with_parameter("heading", 185);
+        let mut csv = Vec::new();
+        evaluation
+            .with_provenance(provenance.clone())
+            .write_csv(&mut csv)
+            .unwrap();
+        let mut comments = Vec::new();
+        provenance.write_comments(&mut comments).unwrap();
+        assert!(csv.starts_with(&comments));
+        assert!(String::from_utf8(csv).unwrap().ends_with(&csv_text));
+
+        fs::remove_dir_all(manifest.root()).unwrap();
+    }
+
+    #[test]
+    fn load_reports_missing_image() {
+        let manifest = manifest("missing").with_entry(DatasetEntry::new(
+            "2.raw",
+            time(2),
+            Angle::new::<degree>(0.0),
+        ));
+
+        let image = manifest.load(&manifest.entries()[0]).unwrap();
+        assert_eq!(image.meta().time(), Some(time(0)));
+        assert!(matches!(
+            manifest.load(&manifest.entries()[2]),
+            Err(DatasetError::Io { .. })
+        ));
+
+        fs::remove_dir_all(manifest.root()).unwrap();
+    }
+
+    #[test]
+    fn quotes_image_paths() {
+        assert_eq!(csv_field("0.raw"), "0.raw");
+        assert_eq!(csv_field("a,b.raw"), "\"a,b.raw\"");
+        assert_eq!(csv_field("say \"hi\".raw"), "\"say \"\"hi\"\".raw\"");
+
+        let manifest = manifest("quotes");
+        fs::copy(
+            manifest.root().join("0.raw"),
+            manifest.root().join("a,b.raw"),
+        )
+        .unwrap();
+        let manifest =
+            Manifest::new(4, 4)
+                .with_root(manifest.root())
+                .with_entry(DatasetEntry::new(
+                    "a,b.raw",
+                    time(0),
+                    Angle::new::<degree>(0.0),
+                ));
+        let evaluation = manifest.evaluate(&|_: &[Ray<SensorFrame>]| None).unwrap();
+
+        let mut csv = Vec::new();
+        evaluation.write_csv(&mut csv).unwrap();
+        let row = String::from_utf8(csv)
+            .unwrap()
+            .lines()
+            .nth(1)
+            .unwrap()
+            .to_owned();
+        assert_eq!(row, "\"a,b.raw\",2025-06-13T16:26:47+00:00,0,,,");
+
+        fs::remove_dir_all(manifest.root()).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn manifest_roundtrips_ground_truth() {
+        use uom::si::{
+            f64::Length,
+            length::{micron, millimeter},
+        };
+
+        let deg = Angle::new::<degree>;
+        let orientation = Orientation::<DatasetNed>::tait_bryan_builder()
+            .yaw(deg(10.0))
+            .pitch(deg(2.0))
+            .roll(deg(-1.0))
+            .build();
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(8.0)),
+            Length::new::<micron>(6.9),
+            1024,
+            1224,
+        );
+        let manifest = Manifest::new(4, 4).with_camera(camera).with_entry(
+            DatasetEntry::new("0.raw", time(0), deg(10.0)).with_orientation(orientation),
+        );
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        let loaded: Manifest = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.camera(), Some(&camera));
+        let (yaw, pitch, roll) = loaded.entries()[0]
+            .orientation()
+            .unwrap()
+            .to_tait_bryan_angles();
+        assert!((yaw.get::<degree>() - 10.0).abs() < 1e-9);
+        assert!((pitch.get::<degree>() - 2.0).abs() < 1e-9);
+        assert!((roll.get::<degree>() + 1.0).abs() < 1e-9);
+
+        // Both are optional.
+        let bare = r#"{"width":4,"height":4,"entries":[]}"#;
+        assert_eq!(
+            serde_json::from_str::<Manifest>(bare).unwrap().camera(),
+            None
+        );
+    }
+}
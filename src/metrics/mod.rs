@@ -0,0 +1,4 @@
+//! Metrics for evaluating estimates against references.
+
+pub mod dataset;
+pub mod pose;
@@ -0,0 +1,349 @@
+use sguaba::engineering::Orientation;
+use uom::si::{angle::radian, f64::Angle};
+
+/// The error between an estimated and a reference [`Orientation`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrientationError {
+    geodesic: Angle,
+    yaw: Angle,
+    pitch: Angle,
+    roll: Angle,
+}
+
+impl OrientationError {
+    /// Computes the error of `estimate` with respect to `reference`.
+    #[must_use]
+    pub fn new<In>(estimate: &Orientation<In>, reference: &Orientation<In>) -> Self {
+        let (est_yaw, est_pitch, est_roll) = estimate.to_tait_bryan_angles();
+        let (ref_yaw, ref_pitch, ref_roll) = reference.to_tait_bryan_angles();
+
+        Self {
+            geodesic: geodesic_distance(estimate, reference),
+            yaw: wrap(est_yaw - ref_yaw),
+            pitch: wrap(est_pitch - ref_pitch),
+            roll: wrap(est_roll - ref_roll),
+        }
+    }
+
+    /// The angle of the smallest rotation between the two orientations on [0, 180].
+    #[must_use]
+    pub fn geodesic(&self) -> Angle {
+        self.geodesic
+    }
+
+    /// The signed yaw error wrapped into [-180, 180).
+    #[must_use]
+    pub fn yaw(&self) -> Angle {
+        self.yaw
+    }
+
+    /// The signed pitch error wrapped into [-180, 180).
+    #[must_use]
+    pub fn pitch(&self) -> Angle {
+        self.pitch
+    }
+
+    /// The signed roll error wrapped into [-180, 180).
+    #[must_use]
+    pub fn roll(&self) -> Angle {
+        self.roll
+    }
+}
+
+/// Returns the angle of the smallest rotation that takes `lhs` to `rhs` on [0, 180].
+#[must_use]
+pub fn geodesic_distance<In>(lhs: &Orientation<In>, rhs: &Orientation<In>) -> Angle {
+    let lhs = quaternion(lhs);
+    let rhs = quaternion(rhs);
+    let dot: f64 = lhs.iter().zip(rhs).map(|(l, r)| l * r).sum();
+
+    2.0 * Angle::new::<radian>(dot.abs().min(1.0).acos())
+}
+
+/// Spherically interpolates from `from` at `t = 0` to `to` at `t = 1`.
+///
+/// The result rotates at a constant rate along the shorter of the two great arcs between the
+/// orientations, so `geodesic_distance(from, &slerp(from, to, t))` is `t` times the distance
+/// between them.
+#[must_use]
+pub fn slerp<In>(from: &Orientation<In>, to: &Orientation<In>, t: f64) -> Orientation<In> {
+    let from = quaternion(from);
+    let mut to = quaternion(to);
+    let mut dot: f64 = from.iter().zip(to).map(|(f, t)| f * t).sum();
+    // q and -q are the same rotation so take the shorter arc.
+    if dot < 0.0 {
+        to = to.map(|component| -component);
+        dot = -dot;
+    }
+
+    let angle = dot.min(1.0).acos();
+    let (from_weight, to_weight) = if angle.sin() < 1e-9 {
+        (1.0 - t, t)
+    } else {
+        (
+            ((1.0 - t) * angle).sin() / angle.sin(),
+            (t * angle).sin() / angle.sin(),
+        )
+    };
+
+    let mut q = [0.0; 4];
+    for (i, component) in q.iter_mut().enumerate() {
+        *component = from_weight * from[i] + to_weight * to[i];
+    }
+    from_quaternion(q)
+}
+
+/// Converts `orientation` into a nalgebra [`UnitQuaternion`](nalgebra::UnitQuaternion).
+///
+/// The quaternion rotates by the intrinsic yaw, pitch, and roll of `orientation` in that order,
+/// which is the convention of
+/// [`UnitQuaternion::from_euler_angles`](nalgebra::UnitQuaternion::from_euler_angles).
+/// Note that nalgebra takes and returns the angles as roll, pitch, and yaw.
+#[cfg(feature = "nalgebra")]
+#[must_use]
+pub fn to_unit_quaternion<In>(orientation: &Orientation<In>) -> nalgebra::UnitQuaternion<f64> {
+    let [w, x, y, z] = quaternion(orientation);
+    nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(w, x, y, z))
+}
+
+/// Converts a nalgebra [`UnitQuaternion`](nalgebra::UnitQuaternion) into an [`Orientation`].
+///
+/// This is the inverse of [`to_unit_quaternion`].
+#[cfg(feature = "nalgebra")]
+#[must_use]
+pub fn from_unit_quaternion<In>(quaternion: &nalgebra::UnitQuaternion<f64>) -> Orientation<In> {
+    from_quaternion([quaternion.w, quaternion.i, quaternion.j, quaternion.k])
+}
+
+/// Summary statistics over a set of angular errors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorSummary {
+    count: usize,
+    rmse: Angle,
+    median: Angle,
+    max: Angle,
+}
+
+impl ErrorSummary {
+    /// Summarizes the magnitudes of `errors`.
+    ///
+    /// Returns `None` if `errors` is empty.
+    pub fn from_errors(errors: impl IntoIterator<Item = Angle>) -> Option<Self> {
+        let mut errors: Vec<Angle> = errors.into_iter().map(Angle::abs).collect();
+        if errors.is_empty() {
+            return None;
+        }
+
+        errors.sort_by(|lhs, rhs| lhs.value.total_cmp(&rhs.value));
+        let count = errors.len();
+        let median = if count.is_multiple_of(2) {
+            (errors[count / 2 - 1] + errors[count / 2]) / 2.0
+        } else {
+            errors[count / 2]
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        let mean_square = errors
+            .iter()
+            .map(|error| error.get::<radian>().powi(2))
+            .sum::<f64>()
+            / count as f64;
+
+        Some(Self {
+            count,
+            rmse: Angle::new::<radian>(mean_square.sqrt()),
+            median,
+            max: errors[count - 1],
+        })
+    }
+
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    #[must_use]
+    pub fn rmse(&self) -> Angle {
+        self.rmse
+    }
+
+    #[must_use]
+    pub fn median(&self) -> Angle {
+        self.median
+    }
+
+    #[must_use]
+    pub fn max(&self) -> Angle {
+        self.max
+    }
+}
+
+/// Wraps `angle` into [-180, 180).
+pub(super) fn wrap(angle: Angle) -> Angle {
+    let turns = ((angle + Angle::HALF_TURN) / Angle::FULL_TURN)
+        .value
+        .floor();
+    angle - Angle::FULL_TURN * turns
+}
+
+/// Computes a unit quaternion in `[w, x, y, z]` order from the intrinsic yaw-pitch-roll angles of
+/// `orientation`.
+fn quaternion<In>(orientation: &Orientation<In>) -> [f64; 4] {
+    let (yaw, pitch, roll) = orientation.to_tait_bryan_angles();
+    let half = |angle: Angle| {
+        let half = angle / 2.0;
+        (half.cos().value, half.sin().value)
+    };
+    let (cy, sy) = half(yaw);
+    let (cp, sp) = half(pitch);
+    let (cr, sr) = half(roll);
+
+    [
+        cr * cp * cy + sr * sp * sy,
+        sr * cp * cy - cr * sp * sy,
+        cr * sp * cy + sr * cp * sy,
+        cr * cp * sy - sr * sp * cy,
+    ]
+}
+
+/// Builds an [`Orientation`] from a unit quaternion in `[w, x, y, z]` order.
+///
+/// This is the inverse of [`quaternion`].
+fn from_quaternion<In>(q: [f64; 4]) -> Orientation<In> {
+    let norm = q
+        .iter()
+        .map(|component| component * component)
+        .sum::<f64>()
+        .sqrt();
+    let [w, x, y, z] = q.map(|component| component / norm);
+
+    Orientation::<In>::tait_bryan_builder()
+        .yaw(Angle::new::<radian>(
+            (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z)),
+        ))
+        .pitch(Angle::new::<radian>(
+            (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin(),
+        ))
+        .roll(Angle::new::<radian>(
+            (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y)),
+        ))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use sguaba::system;
+    use uom::si::angle::degree;
+
+    system!(struct PoseEnu using ENU);
+
+    fn orientation(yaw: f64, pitch: f64, roll: f64) -> Orientation<PoseEnu> {
+        Orientation::<PoseEnu>::tait_bryan_builder()
+            .yaw(Angle::new::<degree>(yaw))
+            .pitch(Angle::new::<degree>(pitch))
+            .roll(Angle::new::<degree>(roll))
+            .build()
+    }
+
+    #[rstest]
+    #[case(orientation(10.0, 0.0, 0.0), orientation(10.0, 0.0, 0.0), 0.0)]
+    #[case(orientation(10.0, 0.0, 0.0), orientation(20.0, 0.0, 0.0), 10.0)]
+    #[case(orientation(350.0, 0.0, 0.0), orientation(10.0, 0.0, 0.0), 20.0)]
+    #[case(orientation(0.0, 0.0, 0.0), orientation(0.0, 0.0, 180.0), 180.0)]
+    #[case(orientation(30.0, 20.0, 10.0), orientation(30.0, 20.0, 15.0), 5.0)]
+    fn geodesic(
+        #[case] estimate: Orientation<PoseEnu>,
+        #[case] reference: Orientation<PoseEnu>,
+        #[case] distance: f64,
+    ) {
+        assert_relative_eq!(
+            geodesic_distance(&estimate, &reference).get::<degree>(),
+            distance,
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn per_axis_error_wraps() {
+        let error = OrientationError::new(
+            &orientation(-175.0, 0.0, 0.0),
+            &orientation(175.0, 0.0, 0.0),
+        );
+
+        assert_relative_eq!(error.yaw().get::<degree>(), 10.0, epsilon = 1e-6);
+        assert_relative_eq!(error.pitch().get::<degree>(), 0.0, epsilon = 1e-6);
+        assert_relative_eq!(error.geodesic().get::<degree>(), 10.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn summary() {
+        let summary =
+            ErrorSummary::from_errors([3.0, -4.0, 1.0, 2.0].map(Angle::new::<degree>)).unwrap();
+
+        assert_eq!(summary.count(), 4);
+        assert_relative_eq!(summary.median().get::<degree>(), 2.5, epsilon = 1e-9);
+        assert_relative_eq!(summary.max().get::<degree>(), 4.0, epsilon = 1e-9);
+        assert_relative_eq!(
+            summary.rmse().get::<degree>(),
+            (30.0_f64 / 4.0).sqrt(),
+            epsilon = 1e-9
+        );
+        assert_eq!(ErrorSummary::from_errors([]), None);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[rstest]
+    #[case(0.0, 0.0, 0.0)]
+    #[case(90.0, 0.0, 180.0)]
+    #[case(30.0, 20.0, 10.0)]
+    #[case(-120.0, -45.0, 75.0)]
+    fn unit_quaternion_follows_nalgebra_convention(
+        #[case] yaw: f64,
+        #[case] pitch: f64,
+        #[case] roll: f64,
+    ) {
+        let orientation = orientation(yaw, pitch, roll);
+        let expected = nalgebra::UnitQuaternion::from_euler_angles(
+            roll.to_radians(),
+            pitch.to_radians(),
+            yaw.to_radians(),
+        );
+
+        let quaternion = to_unit_quaternion(&orientation);
+        assert_relative_eq!(quaternion.angle_to(&expected), 0.0, epsilon = 1e-9);
+
+        let roundtrip: Orientation<PoseEnu> = from_unit_quaternion(&quaternion);
+        assert_relative_eq!(
+            geodesic_distance(&orientation, &roundtrip).get::<degree>(),
+            0.0,
+            epsilon = 1e-5
+        );
+    }
+
+    #[rstest]
+    #[case(orientation(10.0, 0.0, 0.0), orientation(50.0, 0.0, 0.0))]
+    #[case(orientation(350.0, 5.0, 0.0), orientation(10.0, -5.0, 0.0))]
+    #[case(orientation(30.0, 20.0, 10.0), orientation(-60.0, -10.0, 40.0))]
+    fn slerp_moves_along_geodesic(
+        #[case] from: Orientation<PoseEnu>,
+        #[case] to: Orientation<PoseEnu>,
+    ) {
+        let distance = geodesic_distance(&from, &to).get::<degree>();
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let between = slerp(&from, &to, t);
+            assert_relative_eq!(
+                geodesic_distance(&from, &between).get::<degree>(),
+                t * distance,
+                epsilon = 1e-5
+            );
+            assert_relative_eq!(
+                geodesic_distance(&between, &to).get::<degree>(),
+                (1.0 - t) * distance,
+                epsilon = 1e-5
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Sources of the position of the sun.
+
+use crate::azimuth::AzimuthConvention;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sguaba::systems::Wgs84;
+use uom::si::{angle::degree, f64::Angle};
+
+/// The direction of the sun seen by an observer on the ground.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SolarPosition {
+    azimuth: Angle,
+    elevation: Angle,
+}
+
+impl SolarPosition {
+    /// The convention of [`SolarPosition::azimuth`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    /// Creates a new `SolarPosition` from an `azimuth` clockwise from north and an `elevation`
+    /// above the horizon.
+    #[must_use]
+    pub fn new(azimuth: Angle, elevation: Angle) -> Self {
+        Self { azimuth, elevation }
+    }
+
+    #[must_use]
+    pub fn azimuth(&self) -> Angle {
+        self.azimuth
+    }
+
+    #[must_use]
+    pub fn elevation(&self) -> Angle {
+        self.elevation
+    }
+}
+
+/// Computes the [`SolarPosition`] for an observer and a time.
+///
+/// Implement this to drive a [`SkyModel`](super::SkyModel) or a
+/// [`Simulation`](crate::simulation::Simulation) from a source other than the solar position
+/// algorithm, e.g., a log of a previous run.
+pub trait SolarEphemeris {
+    /// Returns the position of the sun seen from `position` at `time` or `None` if it is not
+    /// known.
+    fn solar_position(&self, position: Wgs84, time: DateTime<Utc>) -> Option<SolarPosition>;
+}
+
+/// The solar position algorithm of the `spa` crate.
+#[cfg(feature = "solar")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spa;
+
+#[cfg(feature = "solar")]
+impl SolarEphemeris for Spa {
+    fn solar_position(&self, position: Wgs84, time: DateTime<Utc>) -> Option<SolarPosition> {
+        let solar_pos = spa::solar_position::<spa::StdFloatOps>(
+            time,
+            position.latitude().get::<degree>(),
+            position.longitude().get::<degree>(),
+        )
+        .ok()?;
+
+        // Convert the zenith angle into an elevation angle.
+        Some(SolarPosition::new(
+            Angle::new::<degree>(solar_pos.azimuth),
+            Angle::HALF_TURN / 2. - Angle::new::<degree>(solar_pos.zenith_angle),
+        ))
+    }
+}
+
+/// Replays a recorded time series of solar positions.
+///
+/// Positions between samples are linearly interpolated in time, taking the shorter way around
+/// in azimuth.
+/// A time that matches a sample returns it exactly, so replays reproduce the recorded run.
+/// The position of the observer is ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "RecordedEphemerisFields")
+)]
+pub struct RecordedEphemeris {
+    samples: Vec<(DateTime<Utc>, SolarPosition)>,
+}
+
+/// Holds the samples of a [`RecordedEphemeris`] before they are sorted.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RecordedEphemerisFields {
+    samples: Vec<(DateTime<Utc>, SolarPosition)>,
+}
+
+#[cfg(feature = "serde")]
+impl From<RecordedEphemerisFields> for RecordedEphemeris {
+    fn from(fields: RecordedEphemerisFields) -> Self {
+        Self::new(fields.samples)
+    }
+}
+
+impl RecordedEphemeris {
+    /// Creates a new `RecordedEphemeris` from samples in any order.
+    #[must_use]
+    pub fn new(samples: impl IntoIterator<Item = (DateTime<Utc>, SolarPosition)>) -> Self {
+        let mut samples: Vec<_> = samples.into_iter().collect();
+        samples.sort_by_key(|(time, _)| *time);
+        Self { samples }
+    }
+
+    /// Returns the samples ordered by time.
+    #[must_use]
+    pub fn samples(&self) -> &[(DateTime<Utc>, SolarPosition)] {
+        &self.samples
+    }
+}
+
+impl SolarEphemeris for RecordedEphemeris {
+    /// Returns `None` if `time` is outside of the recorded samples.
+    #[allow(clippy::cast_precision_loss)]
+    fn solar_position(&self, _position: Wgs84, time: DateTime<Utc>) -> Option<SolarPosition> {
+        let after = self.samples.partition_point(|(sample, _)| *sample < time);
+        let (next_time, next) = *self.samples.get(after)?;
+        if next_time == time {
+            return Some(next);
+        }
+
+        let (previous_time, previous) = *self.samples.get(after.checked_sub(1)?)?;
+        let span = (next_time - previous_time).num_nanoseconds()? as f64;
+        let t = (time - previous_time).num_nanoseconds()? as f64 / span;
+
+        let turn = Angle::new::<degree>(360.0);
+        let mut azimuth_change = (next.azimuth - previous.azimuth) % turn;
+        if azimuth_change > turn / 2.0 {
+            azimuth_change -= turn;
+        } else if azimuth_change < -turn / 2.0 {
+            azimuth_change += turn;
+        }
+
+        Some(SolarPosition::new(
+            previous.azimuth + azimuth_change * t,
+            previous.elevation + (next.elevation - previous.elevation) * t,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use uom::si::{f64::Length, length::meter};
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        "2025-06-13T16:26:47+00:00"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            + chrono::TimeDelta::seconds(seconds)
+    }
+
+    fn position() -> Wgs84 {
+        Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2187))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.4747))
+            .altitude(Length::new::<meter>(0.0))
+            .build()
+    }
+
+    #[test]
+    fn recorded_interpolates_between_samples() {
+        let deg = Angle::new::<degree>;
+        let ephemeris = RecordedEphemeris::new([
+            (time(10), SolarPosition::new(deg(2.0), deg(40.0))),
+            (time(0), SolarPosition::new(deg(356.0), deg(30.0))),
+        ]);
+
+        let exact = ephemeris.solar_position(position(), time(0)).unwrap();
+        assert_eq!(exact, SolarPosition::new(deg(356.0), deg(30.0)));
+
+        // The azimuth crosses north rather than sweeping back through south.
+        let halfway = ephemeris.solar_position(position(), time(5)).unwrap();
+        assert_relative_eq!(halfway.azimuth().get::<degree>(), 359.0, epsilon = 1e-9);
+        assert_relative_eq!(halfway.elevation().get::<degree>(), 35.0, epsilon = 1e-9);
+
+        assert_eq!(ephemeris.solar_position(position(), time(-1)), None);
+        assert_eq!(ephemeris.solar_position(position(), time(11)), None);
+    }
+
+    #[cfg(feature = "solar")]
+    #[test]
+    fn spa_matches_recorded_replay() {
+        let recorded = Spa.solar_position(position(), time(0)).unwrap();
+        let replay = RecordedEphemeris::new([(time(0), recorded)]);
+
+        assert_eq!(replay.solar_position(position(), time(0)), Some(recorded));
+        assert!(recorded.elevation().get::<degree>() > 0.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_sorts_samples() {
+        let deg = Angle::new::<degree>;
+        let ephemeris = RecordedEphemeris::new([
+            (time(0), SolarPosition::new(deg(356.0), deg(30.0))),
+            (time(10), SolarPosition::new(deg(2.0), deg(40.0))),
+        ]);
+        let mut value = serde_json::to_value(&ephemeris).unwrap();
+        value["samples"].as_array_mut().unwrap().reverse();
+
+        let loaded: RecordedEphemeris = serde_json::from_value(value).unwrap();
+        assert_eq!(loaded, ephemeris);
+    }
+}
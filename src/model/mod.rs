@@ -0,0 +1,574 @@
+pub mod ephemeris;
+pub mod refraction;
+pub mod validate;
+
+use crate::light::dop::Dop;
+use crate::{light::aop::Aop, ray::GlobalFrame};
+use chrono::prelude::*;
+use ephemeris::SolarEphemeris;
+use refraction::Refraction;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sguaba::Bearing;
+use sguaba::{
+    CoordinateSystem,
+    systems::{EnuLike, Wgs84},
+};
+use std::{
+    f64::consts::{FRAC_PI_2, TAU},
+    sync::OnceLock,
+};
+use thiserror::Error;
+use uom::{
+    ConstZero,
+    si::{
+        angle::{degree, radian},
+        f64::Angle,
+        ratio::ratio,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error(
+        "solar elevation of {} degrees is below the minimum of {} degrees",
+        elevation.get::<degree>(),
+        min_elevation.get::<degree>()
+    )]
+    LowSun {
+        elevation: Angle,
+        min_elevation: Angle,
+    },
+}
+
+/// Selects what happens when the sun is below the minimum elevation of an [`ElevationGate`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TwilightPolicy {
+    /// Reports [`SolarCondition::LowSun`] and leaves it to the caller to discount the frame.
+    Warn,
+
+    /// Fails with [`ModelError::LowSun`].
+    #[default]
+    Reject,
+}
+
+/// The state of the sun relative to an [`ElevationGate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SolarCondition {
+    /// The sun is at or above the minimum elevation.
+    Daylight,
+
+    /// The sun is below the minimum elevation and the single scattering pattern is unreliable.
+    LowSun,
+}
+
+/// Gates a [`SkyModel`] on the elevation of the sun.
+///
+/// Near sunset the Rayleigh pattern degrades as multiple scattering grows, and once the sun sets
+/// its elevation goes negative.
+/// Estimates from such frames are meaningless, so check the model before using it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ElevationGate {
+    min_elevation: Angle,
+    policy: TwilightPolicy,
+}
+
+impl ElevationGate {
+    /// Creates a new `ElevationGate` that rejects suns below `min_elevation`.
+    #[must_use]
+    pub fn new(min_elevation: Angle) -> Self {
+        Self {
+            min_elevation,
+            policy: TwilightPolicy::default(),
+        }
+    }
+
+    /// Sets what happens when the sun is below the minimum elevation.
+    #[must_use]
+    pub fn with_policy(mut self, policy: TwilightPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    #[must_use]
+    pub fn min_elevation(&self) -> Angle {
+        self.min_elevation
+    }
+
+    #[must_use]
+    pub fn policy(&self) -> TwilightPolicy {
+        self.policy
+    }
+
+    /// Classifies a sun at `elevation`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the sun is below the minimum elevation and the policy is
+    /// [`TwilightPolicy::Reject`].
+    pub fn check(&self, elevation: Angle) -> Result<SolarCondition, ModelError> {
+        if elevation >= self.min_elevation {
+            return Ok(SolarCondition::Daylight);
+        }
+
+        match self.policy {
+            TwilightPolicy::Warn => Ok(SolarCondition::LowSun),
+            TwilightPolicy::Reject => Err(ModelError::LowSun {
+                elevation,
+                min_elevation: self.min_elevation,
+            }),
+        }
+    }
+}
+
+impl Default for ElevationGate {
+    /// Rejects suns below the horizon.
+    fn default() -> Self {
+        Self::new(Angle::ZERO)
+    }
+}
+
+/// Number of intervals in the sine table used by [`Trig::Table`].
+const TABLE_SIZE: usize = 4096;
+
+/// Selects how a [`SkyModel`] evaluates sines and cosines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Trig {
+    /// Uses the standard library.
+    #[default]
+    Exact,
+
+    /// Linearly interpolates a precomputed table of 4096 intervals over a full turn.
+    ///
+    /// Sines and cosines have an absolute error below 3e-7.
+    /// Away from the sun and the anti-solar point, this bounds the error of the [`Aop`] to
+    /// about 1e-4 degrees and the error of the [`Dop`] to about 1e-6.
+    /// Inverse trigonometric functions are always exact.
+    Table,
+}
+
+impl Trig {
+    fn sin_cos(self, angle: Angle) -> (f64, f64) {
+        match self {
+            Trig::Exact => (angle.sin().get::<ratio>(), angle.cos().get::<ratio>()),
+            Trig::Table => {
+                let radians = angle.get::<radian>();
+                (table_sin(radians), table_sin(radians + FRAC_PI_2))
+            }
+        }
+    }
+}
+
+fn table_sin(radians: f64) -> f64 {
+    static TABLE: OnceLock<Vec<f64>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        #[allow(clippy::cast_precision_loss)]
+        (0..=TABLE_SIZE)
+            .map(|i| (i as f64 * TAU / TABLE_SIZE as f64).sin())
+            .collect()
+    });
+
+    #[allow(clippy::cast_precision_loss)]
+    let position = radians.rem_euclid(TAU) * TABLE_SIZE as f64 / TAU;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let index = (position as usize).min(TABLE_SIZE - 1);
+
+    #[allow(clippy::cast_precision_loss)]
+    let t = position - index as f64;
+    table[index] + t * (table[index + 1] - table[index])
+}
+
+/// Describes the skylight polarization pattern for a given earth centered
+/// (`Wgs84`) position and a UTC timepoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SkyModel<In> {
+    /// The location of the sun's center for an observer on the ground.
+    solar_bearing: Bearing<In>,
+
+    /// How sines and cosines are evaluated.
+    #[cfg_attr(feature = "serde", serde(default))]
+    trig: Trig,
+
+    /// Corrects observed bearings for atmospheric refraction.
+    #[cfg_attr(feature = "serde", serde(default))]
+    refraction: Option<Refraction>,
+}
+
+impl<In> SkyModel<In> {
+    /// Create a `SkyModel` from a `solar_bearing`.
+    #[must_use]
+    pub fn from_solar_bearing(solar_bearing: Bearing<In>) -> Self {
+        Self {
+            solar_bearing,
+            trig: Trig::Exact,
+            refraction: None,
+        }
+    }
+
+    /// Evaluates sines and cosines with `trig`.
+    ///
+    /// [`Trig::Table`] trades a small bounded error for speed on targets where trigonometry
+    /// dominates the cost of each ray.
+    #[must_use]
+    pub fn with_trig(mut self, trig: Trig) -> Self {
+        self.trig = trig;
+        self
+    }
+
+    #[must_use]
+    pub fn trig(&self) -> Trig {
+        self.trig
+    }
+
+    /// Treats bearings passed to [`SkyModel::aop`] and [`SkyModel::dop`] as apparent directions
+    /// bent by `refraction`.
+    ///
+    /// Each bearing is lowered to its true elevation before the pattern is evaluated.
+    /// The solar bearing stays the true position of the sun, see
+    /// [`SkyModel::apparent_solar_bearing`] for where it appears.
+    /// Near the horizon the correction is about half a degree.
+    #[must_use]
+    pub fn with_refraction(mut self, refraction: Refraction) -> Self {
+        self.refraction = Some(refraction);
+        self
+    }
+
+    #[must_use]
+    pub fn refraction(&self) -> Option<Refraction> {
+        self.refraction
+    }
+
+    /// Create a new [`SkyModel`] from a position and a time.
+    ///
+    /// The solar bearing is computed with the solar position algorithm, see
+    /// [`ephemeris::Spa`].
+    ///
+    /// # Safety
+    /// This function only produces a valid [`SkyModel`] if the origin of `In` is coincident with
+    /// `position`. Otherwise, the model will interpret the solar bearing from `position`, but
+    /// return results that interpret bearings from the origin of `In`.
+    ///
+    /// # Panics
+    /// Will panic if the latitude and longitude provided by `position` are not valid.
+    /// Since Wgs84 enforces valid `position`s this should not be a concern.
+    #[cfg(feature = "solar")]
+    pub unsafe fn from_position_and_time(
+        position: impl Into<Wgs84>,
+        time: impl Into<DateTime<Utc>>,
+    ) -> Self
+    where
+        In: CoordinateSystem<Convention = EnuLike>,
+    {
+        // SAFETY: The caller upholds the same contract.
+        unsafe { Self::from_ephemeris(&ephemeris::Spa, position, time) }
+            // Using `Wgs84` should enforce this.
+            .expect("latitude and longitude are valid")
+    }
+
+    /// Create a new [`SkyModel`] from a position and a time with the solar bearing taken from
+    /// `ephemeris`.
+    ///
+    /// Use an [`ephemeris::RecordedEphemeris`] to replay the solar bearings of a previous run
+    /// exactly.
+    /// Returns `None` if `ephemeris` does not know the position of the sun at `time`.
+    ///
+    /// # Safety
+    /// This function only produces a valid [`SkyModel`] if the origin of `In` is coincident with
+    /// `position`. Otherwise, the model will interpret the solar bearing from `position`, but
+    /// return results that interpret bearings from the origin of `In`.
+    ///
+    /// # Panics
+    /// Will panic if `ephemeris` returns an elevation outside of -90 to 90 degrees.
+    pub unsafe fn from_ephemeris(
+        ephemeris: &impl SolarEphemeris,
+        position: impl Into<Wgs84>,
+        time: impl Into<DateTime<Utc>>,
+    ) -> Option<Self>
+    where
+        In: CoordinateSystem<Convention = EnuLike>,
+    {
+        let solar_position = ephemeris.solar_position(position.into(), time.into())?;
+
+        Some(Self::from_solar_bearing(
+            Bearing::<In>::builder()
+                .azimuth(solar_position.azimuth())
+                // The elevation is taken from the XY plane towards positive Z.
+                .elevation(solar_position.elevation())
+                .expect("solar elevation should be on the range -90 to 90")
+                .build(),
+        ))
+    }
+
+    /// Returns the [`Bearing`] towards the sun.
+    #[must_use]
+    pub fn solar_bearing(&self) -> Bearing<In> {
+        self.solar_bearing
+    }
+
+    /// Returns the [`Bearing`] at which the sun appears to an observer on the ground.
+    ///
+    /// This is the solar bearing raised by the refraction of the model, if any.
+    #[must_use]
+    pub fn apparent_solar_bearing(&self) -> Bearing<In>
+    where
+        In: CoordinateSystem,
+    {
+        self.refraction.map_or(self.solar_bearing, |refraction| {
+            refraction.apparent_bearing(self.solar_bearing)
+        })
+    }
+
+    /// Returns the true direction of an observed `bearing`.
+    fn true_bearing(&self, bearing: Bearing<In>) -> Bearing<In>
+    where
+        In: CoordinateSystem,
+    {
+        self.refraction
+            .map_or(bearing, |refraction| refraction.true_bearing(bearing))
+    }
+
+    /// Checks the elevation of the sun against `gate`.
+    ///
+    /// # Errors
+    /// Will return `Err` if `gate` rejects the elevation of the sun.
+    pub fn check_solar_elevation(
+        &self,
+        gate: &ElevationGate,
+    ) -> Result<SolarCondition, ModelError> {
+        gate.check(self.solar_bearing.elevation())
+    }
+
+    /// Use the [`SkyModel`] to compute an [`Aop`] in the [`GlobalFrame`] at `bearing`.
+    ///
+    /// Returns `None` if `bearing` is below the horizon ie it has elevation
+    /// less than zero.
+    #[must_use]
+    pub fn aop(&self, bearing: Bearing<In>) -> Option<Aop<GlobalFrame>>
+    where
+        In: CoordinateSystem,
+    {
+        if bearing.elevation() < Angle::ZERO {
+            return None;
+        }
+        let bearing = self.true_bearing(bearing);
+
+        let solar_azimuth = self.solar_bearing.azimuth();
+        let solar_zenith = Angle::HALF_TURN / 2. - self.solar_bearing.elevation();
+        let azimuth = bearing.azimuth();
+        let zenith = Angle::HALF_TURN / 2. - bearing.elevation();
+
+        let (sin_zenith, cos_zenith) = self.trig.sin_cos(zenith);
+        let (sin_solar_zenith, cos_solar_zenith) = self.trig.sin_cos(solar_zenith);
+        let (sin_relative, cos_relative) = self.trig.sin_cos(azimuth - solar_azimuth);
+        let angle = Angle::new::<radian>(
+            (sin_zenith * cos_solar_zenith - cos_zenith * cos_relative * sin_solar_zenith)
+                .atan2(sin_relative * sin_solar_zenith),
+        );
+
+        Some(Aop::from_angle_wrapped(angle))
+    }
+
+    /// Use the `SkyModel` to compute a `Dop` at `bearing`.
+    ///
+    /// Returns `None` if `bearing` is below the horizon ie it has elevation
+    /// less than zero.
+    ///
+    /// # Panics
+    /// Will panic if the calculated [`Dop`] is out-of-bounds.
+    /// If the model is correct, this should never happen.
+    #[must_use]
+    pub fn dop(&self, bearing: Bearing<In>) -> Option<Dop>
+    where
+        In: CoordinateSystem,
+    {
+        if bearing.elevation() < Angle::ZERO {
+            return None;
+        }
+        let bearing = self.true_bearing(bearing);
+
+        let max_dop = 1.0;
+        let solar_azimuth = self.solar_bearing.azimuth();
+        let solar_zenith = Angle::HALF_TURN / 2. - self.solar_bearing.elevation();
+        let azimuth = bearing.azimuth();
+        let zenith = Angle::HALF_TURN / 2. - bearing.elevation();
+
+        let (sin_zenith, cos_zenith) = self.trig.sin_cos(zenith);
+        let (sin_solar_zenith, cos_solar_zenith) = self.trig.sin_cos(solar_zenith);
+        let (_, cos_relative) = self.trig.sin_cos(azimuth - solar_azimuth);
+        let scattering_angle = Angle::new::<radian>(
+            (cos_zenith * cos_solar_zenith + sin_zenith * sin_solar_zenith * cos_relative).acos(),
+        );
+
+        let (sin_scattering, cos_scattering) = self.trig.sin_cos(scattering_angle);
+        let deg = max_dop * sin_scattering.powf(2.0) / (1.0 + cos_scattering.powf(2.0));
+
+        Some(Dop::try_new(deg).unwrap())
+    }
+}
+
+/// A pattern of skylight polarization that can be sampled at any bearing.
+///
+/// Implement this for custom sky models to check them with [`validate::Validator`].
+pub trait SkyPattern<In> {
+    /// Returns the [`Bearing`] towards the sun.
+    fn solar_bearing(&self) -> Bearing<In>;
+
+    /// Returns the [`Aop`] at `bearing` relative to the local meridian or `None` if the
+    /// pattern is not defined there.
+    fn aop(&self, bearing: Bearing<In>) -> Option<Aop<GlobalFrame>>;
+
+    /// Returns the [`Dop`] at `bearing` or `None` if the pattern is not defined there.
+    fn dop(&self, bearing: Bearing<In>) -> Option<Dop>;
+}
+
+impl<In: CoordinateSystem> SkyPattern<In> for SkyModel<In> {
+    fn solar_bearing(&self) -> Bearing<In> {
+        SkyModel::solar_bearing(self)
+    }
+
+    fn aop(&self, bearing: Bearing<In>) -> Option<Aop<GlobalFrame>> {
+        SkyModel::aop(self, bearing)
+    }
+
+    fn dop(&self, bearing: Bearing<In>) -> Option<Dop> {
+        SkyModel::dop(self, bearing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::relative_eq;
+    use quickcheck::quickcheck;
+    use sguaba::system;
+
+    system!(struct ModelEnu using ENU);
+
+    quickcheck! {
+        fn solar_meridian_ortho_aop(flip_azimuth: bool, elevation_seed: u8) -> bool {
+            let elevation = Angle::new::<degree>(f64::from(elevation_seed) * 90. / f64::from(u8::MAX));
+            let azimuth = if flip_azimuth {
+                Angle::new::<degree>(180.0)
+            } else {
+                Angle::new::<degree>(0.0)
+            };
+
+            relative_eq!(
+                Into::<Angle>::into(SkyModel::from_solar_bearing(
+                    Bearing::<ModelEnu>::builder()
+                        .azimuth(Angle::new::<degree>(0.0))
+                        .elevation(Angle::new::<degree>(45.0))
+                        .expect("solar elevation should be on the range -90 to 90")
+                        .build(),
+                )
+                .aop(
+                    Bearing::<ModelEnu>::builder()
+                        .azimuth(azimuth)
+                        .elevation(elevation)
+                        .expect("elevation should be on the range -90 to 90")
+                        .build(),
+                )
+                .expect("bearing is above the horizon"))
+                .get::<degree>()
+                .abs(),
+                90.0
+            )
+        }
+    }
+
+    #[test]
+    fn gate_classifies_low_sun() {
+        let model = |elevation: f64| {
+            SkyModel::from_solar_bearing(
+                Bearing::<ModelEnu>::builder()
+                    .azimuth(Angle::new::<degree>(270.0))
+                    .elevation(Angle::new::<degree>(elevation))
+                    .expect("elevation should be on the range -90 to 90")
+                    .build(),
+            )
+        };
+        let gate = ElevationGate::new(Angle::new::<degree>(5.0));
+
+        assert_eq!(
+            model(20.0).check_solar_elevation(&gate).unwrap(),
+            SolarCondition::Daylight
+        );
+        assert!(matches!(
+            model(2.0).check_solar_elevation(&gate),
+            Err(ModelError::LowSun { .. })
+        ));
+        assert_eq!(
+            model(2.0)
+                .check_solar_elevation(&gate.with_policy(TwilightPolicy::Warn))
+                .unwrap(),
+            SolarCondition::LowSun
+        );
+        assert!(
+            model(-3.0)
+                .check_solar_elevation(&ElevationGate::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn refraction_lowers_observed_bearings() {
+        let bearing = |azimuth: f64, elevation: f64| {
+            Bearing::<ModelEnu>::builder()
+                .azimuth(Angle::new::<degree>(azimuth))
+                .elevation(Angle::new::<degree>(elevation))
+                .expect("elevation should be on the range -90 to 90")
+                .build()
+        };
+        let refraction = Refraction::standard();
+        let model = SkyModel::from_solar_bearing(bearing(30.0, 5.0));
+        let refracted = model.with_refraction(refraction);
+
+        assert_eq!(refracted.solar_bearing(), model.solar_bearing());
+        assert!(refracted.apparent_solar_bearing().elevation() > model.solar_bearing().elevation());
+        assert_eq!(
+            refracted.dop(bearing(120.0, 90.0)),
+            model.dop(bearing(120.0, 90.0))
+        );
+
+        let observed = bearing(120.0, 2.0);
+        assert_eq!(
+            refracted.dop(observed),
+            model.dop(refraction.true_bearing(observed))
+        );
+        assert_ne!(refracted.aop(observed), model.aop(observed));
+    }
+
+    #[test]
+    fn table_trig_error_is_bounded() {
+        let bearing = |azimuth: f64, elevation: f64| {
+            Bearing::<ModelEnu>::builder()
+                .azimuth(Angle::new::<degree>(azimuth))
+                .elevation(Angle::new::<degree>(elevation))
+                .expect("elevation should be on the range -90 to 90")
+                .build()
+        };
+        let exact = SkyModel::from_solar_bearing(bearing(30.0, 40.0));
+        let table = exact.with_trig(Trig::Table);
+
+        for (azimuth, elevation) in (0..72).flat_map(|a| (0..18).map(move |e| (a * 5, e * 5))) {
+            let bearing = bearing(f64::from(azimuth), f64::from(elevation));
+            let aop_error = exact
+                .aop(bearing)
+                .unwrap()
+                .angular_distance(&table.aop(bearing).unwrap());
+            let dop_error =
+                f64::from(exact.dop(bearing).unwrap()) - f64::from(table.dop(bearing).unwrap());
+
+            // The angle of polarization is undefined at the sun.
+            if (azimuth, elevation) != (30, 40) {
+                assert!(aop_error.get::<degree>() < 1e-4, "{azimuth} {elevation}");
+            }
+            assert!(dop_error.abs() < 1e-6, "{azimuth} {elevation}");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Atmospheric refraction near the horizon.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sguaba::{Bearing, CoordinateSystem};
+use uom::si::{
+    angle::{degree, minute},
+    f64::{Angle, Pressure, ThermodynamicTemperature},
+    pressure::kilopascal,
+    thermodynamic_temperature::degree_celsius,
+};
+
+/// The lowest elevation in degrees at which refraction is evaluated.
+///
+/// Both formulas diverge a few degrees below the horizon, so lower elevations are refracted as if
+/// they were at this elevation.
+const MIN_ELEVATION: f64 = -1.0;
+
+/// Bends bearings by the refraction of a standard atmosphere.
+///
+/// Light is refracted towards the zenith as it enters the atmosphere, so the sun and the sky
+/// appear higher than they are.
+/// The shift is about half a degree at the horizon and vanishes at the zenith.
+/// True elevations are refracted with Saemundsson's formula and apparent elevations with
+/// Bennett's formula, both scaled by the pressure and temperature of the air at the observer.
+/// The two formulas agree to within 0.1 arcminutes above a few degrees of elevation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Refraction {
+    pressure: Pressure,
+    temperature: ThermodynamicTemperature,
+}
+
+impl Refraction {
+    /// Creates a new `Refraction` for air at `pressure` and `temperature`.
+    #[must_use]
+    pub fn new(pressure: Pressure, temperature: ThermodynamicTemperature) -> Self {
+        Self {
+            pressure,
+            temperature,
+        }
+    }
+
+    /// Creates a new `Refraction` for air at 101 kPa and 10 degrees Celsius.
+    #[must_use]
+    pub fn standard() -> Self {
+        Self::new(
+            Pressure::new::<kilopascal>(101.0),
+            ThermodynamicTemperature::new::<degree_celsius>(10.0),
+        )
+    }
+
+    #[must_use]
+    pub fn pressure(&self) -> Pressure {
+        self.pressure
+    }
+
+    #[must_use]
+    pub fn temperature(&self) -> ThermodynamicTemperature {
+        self.temperature
+    }
+
+    /// Returns the elevation at which a direction with true `elevation` appears.
+    #[must_use]
+    pub fn apparent_elevation(&self, elevation: Angle) -> Angle {
+        let h = elevation.get::<degree>().max(MIN_ELEVATION);
+        let shift = 1.02 / (h + 10.3 / (h + 5.11)).to_radians().tan();
+        (elevation + self.scale(shift)).min(Angle::HALF_TURN / 2.0)
+    }
+
+    /// Returns the true elevation of a direction that appears at `elevation`.
+    #[must_use]
+    pub fn true_elevation(&self, elevation: Angle) -> Angle {
+        let h = elevation.get::<degree>().max(MIN_ELEVATION);
+        let shift = 1.0 / (h + 7.31 / (h + 4.4)).to_radians().tan();
+        (elevation - self.scale(shift)).max(-Angle::HALF_TURN / 2.0)
+    }
+
+    /// Returns the bearing at which a direction with true `bearing` appears.
+    #[must_use]
+    pub fn apparent_bearing<In: CoordinateSystem>(&self, bearing: Bearing<In>) -> Bearing<In> {
+        Self::with_elevation(bearing, self.apparent_elevation(bearing.elevation()))
+    }
+
+    /// Returns the true bearing of a direction that appears at `bearing`.
+    #[must_use]
+    pub fn true_bearing<In: CoordinateSystem>(&self, bearing: Bearing<In>) -> Bearing<In> {
+        Self::with_elevation(bearing, self.true_elevation(bearing.elevation()))
+    }
+
+    /// Scales a shift in arcminutes at standard conditions to the conditions of the air.
+    ///
+    /// The shift is never negative, which removes the small error of both formulas near the
+    /// zenith.
+    fn scale(&self, shift: f64) -> Angle {
+        let pressure = self.pressure.get::<kilopascal>() / 101.0;
+        let temperature = 283.0 / (273.0 + self.temperature.get::<degree_celsius>());
+        Angle::new::<minute>(shift.max(0.0) * pressure * temperature)
+    }
+
+    fn with_elevation<In: CoordinateSystem>(bearing: Bearing<In>, elevation: Angle) -> Bearing<In> {
+        let quarter_turn = Angle::HALF_TURN / 2.0;
+        Bearing::<In>::builder()
+            .azimuth(bearing.azimuth())
+            .elevation(elevation.max(-quarter_turn).min(quarter_turn))
+            .expect("elevation is clamped to the range -90 to 90")
+            .build()
+    }
+}
+
+impl Default for Refraction {
+    /// Returns the [`Refraction::standard`] atmosphere.
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn refraction_vanishes_towards_zenith() {
+        let refraction = Refraction::standard();
+        let shift = |elevation: f64| {
+            let elevation = Angle::new::<degree>(elevation);
+            (refraction.apparent_elevation(elevation) - elevation).get::<minute>()
+        };
+
+        // Roughly half a degree at the horizon and one arcminute at 45 degrees.
+        assert_relative_eq!(shift(0.0), 29.0, epsilon = 0.5);
+        assert_relative_eq!(shift(45.0), 1.0, epsilon = 0.05);
+        assert_relative_eq!(shift(90.0), 0.0);
+
+        let thin = Refraction::new(
+            Pressure::new::<kilopascal>(50.5),
+            ThermodynamicTemperature::new::<degree_celsius>(10.0),
+        );
+        let elevation = Angle::new::<degree>(10.0);
+        assert_relative_eq!(
+            (thin.apparent_elevation(elevation) - elevation).get::<minute>(),
+            shift(10.0) / 2.0,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn true_elevation_inverts_apparent_elevation() {
+        let refraction = Refraction::standard();
+        for elevation in [5.0, 15.0, 30.0, 60.0, 85.0] {
+            let elevation = Angle::new::<degree>(elevation);
+            let roundtrip = refraction.true_elevation(refraction.apparent_elevation(elevation));
+            assert_relative_eq!(
+                roundtrip.get::<minute>(),
+                elevation.get::<minute>(),
+                epsilon = 0.1
+            );
+        }
+    }
+
+    #[test]
+    fn refracted_bearings_stay_above_nadir() {
+        sguaba::system!(struct RefractionEnu using ENU);
+
+        let refraction = Refraction::standard();
+        for elevation in [-90.0, -89.5] {
+            let bearing = Bearing::<RefractionEnu>::builder()
+                .azimuth(Angle::new::<degree>(30.0))
+                .elevation(Angle::new::<degree>(elevation))
+                .unwrap()
+                .build();
+            let refracted = refraction.true_bearing(bearing);
+            assert!(refracted.elevation() >= Angle::new::<degree>(-90.0));
+            assert!(refraction.true_elevation(bearing.elevation()) >= Angle::new::<degree>(-90.0));
+        }
+        assert_relative_eq!(
+            refraction
+                .true_elevation(Angle::new::<degree>(-90.0))
+                .get::<degree>(),
+            -90.0
+        );
+    }
+}
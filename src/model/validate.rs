@@ -0,0 +1,353 @@
+//! Checks a [`SkyPattern`] against invariants of single scattering.
+
+use super::SkyPattern;
+use crate::{azimuth::AzimuthConvention, light::aop::Aop};
+use sguaba::Bearing;
+use uom::{
+    ConstZero,
+    si::{
+        angle::{degree, radian},
+        f64::Angle,
+    },
+};
+
+/// A property that every single scattering sky pattern should satisfy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Invariant {
+    /// The [`Aop`](crate::light::aop::Aop) is perpendicular to the plane containing the sun,
+    /// the observer, and the bearing.
+    PerpendicularToScatteringPlane,
+
+    /// The [`Aop`](crate::light::aop::Aop) is 90 degrees from the local meridian along the
+    /// solar and anti-solar meridians.
+    SolarMeridian,
+
+    /// The [`Dop`](crate::light::dop::Dop) is largest at a scattering angle of 90 degrees.
+    MaxDopAtRightAngle,
+}
+
+/// A bearing where a [`SkyPattern`] does not satisfy an [`Invariant`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    invariant: Invariant,
+    azimuth: Angle,
+    elevation: Angle,
+    error: f64,
+}
+
+impl Violation {
+    /// The convention of [`Violation::azimuth`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    #[must_use]
+    pub fn invariant(&self) -> Invariant {
+        self.invariant
+    }
+
+    #[must_use]
+    pub fn azimuth(&self) -> Angle {
+        self.azimuth
+    }
+
+    #[must_use]
+    pub fn elevation(&self) -> Angle {
+        self.elevation
+    }
+
+    /// The size of the violation in degrees for angle invariants and in degree of polarization
+    /// for [`Invariant::MaxDopAtRightAngle`].
+    #[must_use]
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+}
+
+/// The violations found by a [`Validator`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    checked: usize,
+    violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// The number of bearings on the grid where the pattern was defined.
+    #[must_use]
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    #[must_use]
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Returns `true` if no invariant was violated.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Samples a [`SkyPattern`] over a grid of bearings above the horizon and reports every
+/// [`Invariant`] it violates.
+///
+/// Expected angles are computed from the geometry of the sun and the bearing independently of
+/// the pattern.
+/// Bearings within a step of the sun or the anti-solar point are skipped for angle invariants
+/// since the [`Aop`](crate::light::aop::Aop) is undefined there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Validator {
+    step: Angle,
+    aop_tolerance: Angle,
+    dop_tolerance: f64,
+}
+
+impl Validator {
+    /// Creates a new `Validator`.
+    ///
+    /// By default, the grid has a step of 5 degrees, angles are compared within 1e-3 degrees,
+    /// and degrees of polarization within 1e-6.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            step: Angle::new::<degree>(5.0),
+            aop_tolerance: Angle::new::<degree>(1e-3),
+            dop_tolerance: 1e-6,
+        }
+    }
+
+    /// Sets the spacing of the grid in azimuth and elevation.
+    ///
+    /// # Panics
+    /// Will panic if `step` is not positive.
+    #[must_use]
+    pub fn with_step(mut self, step: Angle) -> Self {
+        assert!(step > Angle::ZERO, "step must be positive");
+        self.step = step;
+        self
+    }
+
+    /// Sets the largest difference between an expected and a sampled angle of polarization.
+    #[must_use]
+    pub fn with_aop_tolerance(mut self, aop_tolerance: Angle) -> Self {
+        self.aop_tolerance = aop_tolerance;
+        self
+    }
+
+    /// Sets the amount a degree of polarization may exceed the maximum at a right angle.
+    #[must_use]
+    pub fn with_dop_tolerance(mut self, dop_tolerance: f64) -> Self {
+        self.dop_tolerance = dop_tolerance;
+        self
+    }
+
+    /// Checks `pattern` against every [`Invariant`].
+    ///
+    /// # Panics
+    /// Will panic if the grid produces an invalid elevation.
+    /// This should never occur.
+    #[must_use]
+    pub fn validate<In>(&self, pattern: &impl SkyPattern<In>) -> ValidationReport {
+        let solar = pattern.solar_bearing();
+        let solar_zenith = Angle::HALF_TURN / 2.0 - solar.elevation();
+        let step = self.step.get::<degree>();
+        let sample = |azimuth: Angle, elevation: Angle| {
+            let bearing = Bearing::<In>::builder()
+                .azimuth(azimuth)
+                .elevation(elevation)
+                .expect("elevation is on the range 0 to 90")
+                .build();
+            let geometry = Geometry::new(
+                Angle::HALF_TURN / 2.0 - elevation,
+                solar_zenith,
+                azimuth - solar.azimuth(),
+            );
+            Some((pattern.aop(bearing)?, pattern.dop(bearing)?, geometry))
+        };
+        // The angle of polarization is undefined near the sun and the anti-solar point.
+        let defined = |geometry: &Geometry| {
+            geometry.scattering_angle() > self.step
+                && geometry.scattering_angle() < Angle::HALF_TURN - self.step
+        };
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (azimuths, elevations) = ((360.0 / step).ceil() as u32, (90.0 / step).floor() as u32);
+        let elevation = |j: u32| Angle::new::<degree>(f64::from(j) * step);
+
+        let mut samples = Vec::new();
+        let mut violations = Vec::new();
+        for i in 0..azimuths {
+            for j in 0..=elevations {
+                let (azimuth, elevation) =
+                    (Angle::new::<degree>(f64::from(i) * step), elevation(j));
+                let Some((aop, dop, geometry)) = sample(azimuth, elevation) else {
+                    continue;
+                };
+                let violation = move |invariant, error| Violation {
+                    invariant,
+                    azimuth,
+                    elevation,
+                    error,
+                };
+
+                if defined(&geometry) {
+                    let error = aop
+                        .angular_distance(&Aop::from_angle_wrapped(geometry.e_vector()))
+                        .get::<degree>();
+                    if error > self.aop_tolerance.get::<degree>() {
+                        violations
+                            .push(violation(Invariant::PerpendicularToScatteringPlane, error));
+                    }
+                }
+
+                samples.push((geometry.scattering_angle(), f64::from(dop), violation));
+            }
+        }
+
+        // The solar meridians are sampled separately since they rarely fall on the grid.
+        for azimuth in [solar.azimuth(), solar.azimuth() + Angle::HALF_TURN] {
+            for elevation in (0..=elevations).map(elevation) {
+                let Some((aop, _, geometry)) = sample(azimuth, elevation) else {
+                    continue;
+                };
+
+                let error = (90.0 - Angle::from(aop).get::<degree>().abs()).abs();
+                if defined(&geometry) && error > self.aop_tolerance.get::<degree>() {
+                    violations.push(Violation {
+                        invariant: Invariant::SolarMeridian,
+                        azimuth,
+                        elevation,
+                        error,
+                    });
+                }
+            }
+        }
+
+        // The largest degree of polarization near a right angle bounds every other bearing.
+        let near_right_angle =
+            |scattering: Angle| (scattering - Angle::HALF_TURN / 2.0).abs() <= self.step / 2.0;
+        let reference = samples
+            .iter()
+            .filter(|(scattering, ..)| near_right_angle(*scattering))
+            .map(|(_, dop, _)| *dop)
+            .reduce(f64::max);
+        if let Some(reference) = reference {
+            for (scattering, dop, violation) in &samples {
+                if !near_right_angle(*scattering) && *dop > reference + self.dop_tolerance {
+                    violations.push(violation(Invariant::MaxDopAtRightAngle, dop - reference));
+                }
+            }
+        }
+
+        ValidationReport {
+            checked: samples.len(),
+            violations,
+        }
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The scattering geometry of a bearing at `zenith` for a sun at `solar_zenith` separated by
+/// `relative_azimuth`.
+struct Geometry {
+    /// Components of the scattering plane normal along the local meridian (towards the horizon)
+    /// and the local parallel.
+    normal: (f64, f64),
+    cos_scattering: f64,
+}
+
+impl Geometry {
+    fn new(zenith: Angle, solar_zenith: Angle, relative_azimuth: Angle) -> Self {
+        let (sin_z, cos_z) = zenith.get::<radian>().sin_cos();
+        let (sin_s, cos_s) = solar_zenith.get::<radian>().sin_cos();
+        let (sin_a, cos_a) = relative_azimuth.get::<radian>().sin_cos();
+
+        Self {
+            normal: (sin_a * sin_s, cos_z * cos_a * sin_s - sin_z * cos_s),
+            cos_scattering: cos_z * cos_s + sin_z * sin_s * cos_a,
+        }
+    }
+
+    fn scattering_angle(&self) -> Angle {
+        Angle::new::<radian>(self.cos_scattering.clamp(-1.0, 1.0).acos())
+    }
+
+    /// The direction of the electric field, which is along the scattering plane normal,
+    /// measured from the local meridian.
+    fn e_vector(&self) -> Angle {
+        let (meridian, parallel) = self.normal;
+        Angle::new::<radian>((-parallel).atan2(meridian))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::dop::Dop,
+        model::{SkyModel, Trig},
+        ray::GlobalFrame,
+    };
+    use rstest::rstest;
+    use sguaba::system;
+
+    system!(struct ValidateEnu using ENU);
+
+    fn solar_bearing() -> Bearing<ValidateEnu> {
+        Bearing::<ValidateEnu>::builder()
+            .azimuth(Angle::new::<degree>(30.0))
+            .elevation(Angle::new::<degree>(40.0))
+            .expect("elevation should be on the range -90 to 90")
+            .build()
+    }
+
+    #[rstest]
+    #[case(Trig::Exact)]
+    #[case(Trig::Table)]
+    fn sky_model_is_valid(#[case] trig: Trig) {
+        let model = SkyModel::from_solar_bearing(solar_bearing()).with_trig(trig);
+        let report = Validator::new().validate(&model);
+
+        assert!(report.is_valid(), "{:?}", report.violations());
+        assert_eq!(report.checked(), 72 * 19);
+    }
+
+    /// A pattern that is polarized along the local meridian everywhere.
+    struct Meridional;
+
+    impl SkyPattern<ValidateEnu> for Meridional {
+        fn solar_bearing(&self) -> Bearing<ValidateEnu> {
+            solar_bearing()
+        }
+
+        fn aop(&self, _: Bearing<ValidateEnu>) -> Option<Aop<GlobalFrame>> {
+            Some(Aop::from_angle_wrapped(Angle::ZERO))
+        }
+
+        fn dop(&self, bearing: Bearing<ValidateEnu>) -> Option<Dop> {
+            // Most polarized at the zenith rather than at a right angle to the sun.
+            Dop::try_new(bearing.elevation().get::<degree>() / 90.0).ok()
+        }
+    }
+
+    #[test]
+    fn meridional_pattern_is_invalid() {
+        let report = Validator::new().validate(&Meridional);
+        let count = |invariant| {
+            report
+                .violations()
+                .iter()
+                .filter(|violation| violation.invariant() == invariant)
+                .count()
+        };
+
+        assert!(count(Invariant::PerpendicularToScatteringPlane) > 0);
+        assert!(count(Invariant::SolarMeridian) > 0);
+        assert!(count(Invariant::MaxDopAtRightAngle) > 0);
+    }
+}
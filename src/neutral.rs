@@ -0,0 +1,267 @@
+//! Neutral points and lines of a measured polarization pattern.
+
+use crate::{image::RayImage, light::dop::Dop, optic::PixelCoordinate};
+use uom::si::{angle::degree, f64::Angle};
+
+/// Locates the neutral lines of a [`RayImage`] and the neutral points along them.
+///
+/// The sky is unpolarized at the sun, the anti-solar point, and a few neutral points nearby.
+/// Around these points the [`Dop`] dips towards zero and the [`Aop`] rotates rapidly between
+/// neighboring pixels.
+/// A pixel is on a neutral line if its [`Dop`] is at most the maximum [`Dop`] and the [`Aop`]
+/// of some 4-connected neighbor differs by at least the minimum rotation.
+/// Each connected region of neutral line pixels is summarized by a [`NeutralPoint`].
+///
+/// [`Aop`]: crate::light::aop::Aop
+/// [`Dop`]: crate::light::dop::Dop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NeutralDetector {
+    max_dop: f64,
+    min_rotation: Angle,
+    min_size: usize,
+}
+
+impl NeutralDetector {
+    /// Creates a new `NeutralDetector`.
+    ///
+    /// By default, neutral line pixels have a [`Dop`](crate::light::dop::Dop) of at most 0.1
+    /// and rotate by at least 10 degrees, and every region is reported.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_dop: 0.1,
+            min_rotation: Angle::new::<degree>(10.0),
+            min_size: 1,
+        }
+    }
+
+    /// Sets the largest degree of polarization of a neutral line pixel.
+    #[must_use]
+    pub fn with_dop_threshold(mut self, max_dop: Dop) -> Self {
+        self.max_dop = max_dop.into();
+        self
+    }
+
+    /// Sets the largest degree of polarization of a neutral line pixel.
+    #[must_use]
+    #[deprecated(note = "use `NeutralDetector::with_dop_threshold` with a `Dop`")]
+    pub fn with_max_dop(self, max_dop: f64) -> Self {
+        self.with_dop_threshold(Dop::clamped(max_dop))
+    }
+
+    /// Sets the smallest rotation of the angle of polarization to a neighbor of a neutral line
+    /// pixel.
+    #[must_use]
+    pub fn with_min_rotation(mut self, min_rotation: Angle) -> Self {
+        self.min_rotation = min_rotation;
+        self
+    }
+
+    /// Sets the fewest pixels in a region reported as a [`NeutralPoint`].
+    ///
+    /// Larger regions reject isolated noisy pixels.
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Finds the neutral lines and points of `image`.
+    #[must_use]
+    pub fn detect<Frame: Copy>(&self, image: &RayImage<Frame>) -> NeutralFeatures {
+        let (rows, cols) = (image.rows(), image.cols());
+        let neighbors = |row: usize, col: usize| {
+            [
+                (row > 0).then(|| (row - 1, col)),
+                (row + 1 < rows).then(|| (row + 1, col)),
+                (col > 0).then(|| (row, col - 1)),
+                (col + 1 < cols).then(|| (row, col + 1)),
+            ]
+            .into_iter()
+            .flatten()
+        };
+
+        let neutral: Vec<bool> = (0..rows * cols)
+            .map(|index| {
+                let (row, col) = (index / cols, index % cols);
+                let Some(ray) = image.ray(row, col) else {
+                    return false;
+                };
+
+                f64::from(ray.dop()) <= self.max_dop
+                    && neighbors(row, col).any(|(r, c)| {
+                        image.ray(r, c).is_some_and(|other| {
+                            ray.aop().angular_distance(&other.aop()) >= self.min_rotation
+                        })
+                    })
+            })
+            .collect();
+
+        let mut visited = vec![false; neutral.len()];
+        let mut points = Vec::new();
+        for seed in (0..neutral.len()).filter(|index| neutral[*index]) {
+            if visited[seed] {
+                continue;
+            }
+
+            visited[seed] = true;
+            let mut stack = vec![seed];
+            let mut size = 0;
+            let mut best: Option<(f64, usize)> = None;
+            while let Some(index) = stack.pop() {
+                let (row, col) = (index / cols, index % cols);
+                let dop = image
+                    .ray(row, col)
+                    .map_or(f64::NAN, |ray| f64::from(ray.dop()));
+                size += 1;
+
+                // Ties are broken by index so the result is deterministic.
+                if best.is_none_or(|(best_dop, best_index)| {
+                    dop < best_dop || (dop == best_dop && index < best_index)
+                }) {
+                    best = Some((dop, index));
+                }
+
+                for (r, c) in neighbors(row, col) {
+                    let neighbor = r * cols + c;
+                    if neutral[neighbor] && !visited[neighbor] {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            if let Some((dop, index)) = best.filter(|_| size >= self.min_size) {
+                points.push(NeutralPoint {
+                    coordinate: PixelCoordinate::new(index / cols, index % cols),
+                    dop,
+                    size,
+                });
+            }
+        }
+
+        points.sort_by(|a, b| a.dop.total_cmp(&b.dop));
+
+        NeutralFeatures {
+            line: (0..neutral.len())
+                .filter(|index| neutral[*index])
+                .map(|index| PixelCoordinate::new(index / cols, index % cols))
+                .collect(),
+            points,
+        }
+    }
+}
+
+impl Default for NeutralDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The neutral lines and points found by a [`NeutralDetector`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NeutralFeatures {
+    line: Vec<PixelCoordinate>,
+    points: Vec<NeutralPoint>,
+}
+
+impl NeutralFeatures {
+    /// Returns the pixels on neutral lines in row-major order.
+    #[must_use]
+    pub fn line(&self) -> &[PixelCoordinate] {
+        &self.line
+    }
+
+    /// Returns one point for each connected region of neutral line pixels in order of
+    /// increasing [`Dop`](crate::light::dop::Dop).
+    ///
+    /// For a sky image without the sun, the first point is usually the anti-solar point.
+    #[must_use]
+    pub fn points(&self) -> &[NeutralPoint] {
+        &self.points
+    }
+}
+
+/// A connected region of neutral line pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NeutralPoint {
+    coordinate: PixelCoordinate,
+    dop: f64,
+    size: usize,
+}
+
+impl NeutralPoint {
+    /// The least polarized pixel of the region.
+    #[must_use]
+    pub fn coordinate(&self) -> PixelCoordinate {
+        self.coordinate
+    }
+
+    /// The degree of polarization at [`NeutralPoint::coordinate`].
+    #[must_use]
+    pub fn dop(&self) -> f64 {
+        self.dop
+    }
+
+    /// The number of pixels in the region.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::aop::Aop,
+        ray::{Ray, SensorFrame},
+    };
+    use uom::si::angle::radian;
+
+    /// A vortex whose angle of polarization turns around `center` and whose degree of
+    /// polarization grows with the distance from `center`.
+    fn vortex(rows: usize, cols: usize, center: (f64, f64)) -> RayImage<SensorFrame> {
+        let rays = (0..rows).flat_map(|row| {
+            (0..cols).map(move |col| {
+                let (dy, dx) = (row as f64 - center.0, col as f64 - center.1);
+                Some(Ray::new(
+                    Aop::from_angle_wrapped(Angle::new::<radian>(dy.atan2(dx) / 2.0)),
+                    Dop::clamped(0.05 * dy.hypot(dx)),
+                ))
+            })
+        });
+
+        RayImage::from_rays(rays, rows, cols).unwrap()
+    }
+
+    #[test]
+    fn detects_vortex() {
+        let features = NeutralDetector::new().detect(&vortex(11, 11, (5.0, 5.0)));
+
+        assert_eq!(features.points().len(), 1);
+        assert_eq!(
+            features.points()[0].coordinate(),
+            PixelCoordinate::new(5, 5)
+        );
+        assert_eq!(features.points()[0].dop(), 0.0);
+        assert!(features.line().contains(&PixelCoordinate::new(5, 6)));
+        assert!(!features.line().contains(&PixelCoordinate::new(0, 0)));
+    }
+
+    #[test]
+    fn uniform_image_has_no_features() {
+        let rays = (0..16).map(|_| {
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(0.0)),
+                Dop::zero(),
+            ))
+        });
+        let image = RayImage::<SensorFrame>::from_rays(rays, 4, 4).unwrap();
+
+        assert_eq!(
+            NeutralDetector::new().detect(&image),
+            NeutralFeatures::default()
+        );
+    }
+}
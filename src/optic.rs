@@ -1,8 +1,14 @@
+use crate::azimuth::AzimuthConvention;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use sguaba::{
+    Coordinate, CoordinateSystem,
+    systems::{FrdComponents, FrdLike},
+};
 use uom::{
     ConstZero,
     si::{
+        angle::radian,
         f64::{Angle, Length},
         length::meter,
         ratio::ratio,
@@ -60,6 +66,48 @@ impl SensorCoordinate {
     }
 }
 
+impl SensorCoordinate {
+    /// Converts the coordinate into a point on the image plane of a camera body frame `In`.
+    ///
+    /// The image plane lies `focal_length` in front of the optical center, so the point is
+    /// along the [`RayDirection`] traced from this coordinate by a [`PinholeOptic`] with the same
+    /// `focal_length`.
+    /// Front is along the optical axis, right is along the sensor X axis, and down is opposite to
+    /// the sensor Y axis.
+    #[must_use]
+    pub fn to_frd<In>(&self, focal_length: Length) -> Coordinate<In>
+    where
+        In: CoordinateSystem<Convention = FrdLike>,
+    {
+        Coordinate::<In>::build(FrdComponents {
+            front: focal_length,
+            right: self.x,
+            down: -self.y,
+        })
+    }
+
+    /// Projects a point in a camera body frame `In` onto the image plane.
+    ///
+    /// This is the inverse of [`SensorCoordinate::to_frd`] along each line through the optical
+    /// center.
+    /// Returns `None` if `point` is not in front of the camera.
+    #[must_use]
+    pub fn from_frd<In>(point: &Coordinate<In>, focal_length: Length) -> Option<Self>
+    where
+        In: CoordinateSystem<Convention = FrdLike>,
+    {
+        if point.frd_front() <= Length::ZERO {
+            return None;
+        }
+
+        let scale = (focal_length / point.frd_front()).get::<ratio>();
+        Some(Self::new(
+            point.frd_right() * scale,
+            -point.frd_down() * scale,
+        ))
+    }
+}
+
 impl AsRef<SensorCoordinate> for SensorCoordinate {
     fn as_ref(&self) -> &SensorCoordinate {
         self
@@ -103,24 +151,97 @@ impl AsRef<PixelCoordinate> for PixelCoordinate {
 
 /// Describes an image sensor including its physical dimensions and pixel size.
 /// This type allows conversion between a [`SensorCoordinate`] and a [`PixelCoordinate`].
+///
+/// Pixels may be rectangular with a different pitch along rows and columns.
+/// Serialized sensors that predate rectangular pixels give a single `pixel_size` that is used for
+/// both pitches.
 #[derive(Clone, Copy, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "ImageSensorFields")
+)]
 pub struct ImageSensor {
-    pixel_size: Length,
+    pixel_width: Length,
+    pixel_height: Length,
     rows: usize,
     cols: usize,
 }
 
+/// Accepts either the pitches of an [`ImageSensor`] or the `pixel_size` of square pixels.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImageSensorFields {
+    Pitch {
+        pixel_width: Length,
+        pixel_height: Length,
+        rows: usize,
+        cols: usize,
+    },
+    Square {
+        pixel_size: Length,
+        rows: usize,
+        cols: usize,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl From<ImageSensorFields> for ImageSensor {
+    fn from(fields: ImageSensorFields) -> Self {
+        match fields {
+            ImageSensorFields::Pitch {
+                pixel_width,
+                pixel_height,
+                rows,
+                cols,
+            } => Self::from_pixel_pitch(pixel_width, pixel_height, rows, cols),
+            ImageSensorFields::Square {
+                pixel_size,
+                rows,
+                cols,
+            } => Self::new(pixel_size, rows, cols),
+        }
+    }
+}
+
 impl ImageSensor {
+    /// Creates a new `ImageSensor` with square pixels of `pixel_size`.
     #[must_use]
     pub fn new(pixel_size: Length, rows: usize, cols: usize) -> Self {
+        Self::from_pixel_pitch(pixel_size, pixel_size, rows, cols)
+    }
+
+    /// Creates a new `ImageSensor` with rectangular pixels.
+    ///
+    /// `pixel_width` is the pitch between columns and `pixel_height` is the pitch between rows.
+    #[must_use]
+    pub fn from_pixel_pitch(
+        pixel_width: Length,
+        pixel_height: Length,
+        rows: usize,
+        cols: usize,
+    ) -> Self {
         Self {
-            pixel_size,
+            pixel_width,
+            pixel_height,
             rows,
             cols,
         }
     }
 
+    /// Returns the pitch between columns.
+    #[must_use]
+    pub fn pixel_width(&self) -> Length {
+        self.pixel_width
+    }
+
+    /// Returns the pitch between rows.
+    #[must_use]
+    pub fn pixel_height(&self) -> Length {
+        self.pixel_height
+    }
+
     #[must_use]
     pub fn pixel_count(&self) -> usize {
         self.cols * self.rows
@@ -149,10 +270,10 @@ impl ImageSensor {
         coord: impl AsRef<SensorCoordinate>,
     ) -> Option<PixelCoordinate> {
         let result = PixelCoordinate::new(
-            ((-coord.as_ref().y() / self.pixel_size).get::<ratio>()
+            ((-coord.as_ref().y() / self.pixel_height).get::<ratio>()
                 + self.rows.checked_sub(1)? as f64 / 2.0)
                 .round() as usize,
-            ((coord.as_ref().x() / self.pixel_size).get::<ratio>()
+            ((coord.as_ref().x() / self.pixel_width).get::<ratio>()
                 + self.cols.checked_sub(1)? as f64 / 2.0)
                 .round() as usize,
         );
@@ -171,16 +292,219 @@ impl ImageSensor {
     ) -> Option<SensorCoordinate> {
         if self.contains_pixel(&pixel) {
             Some(SensorCoordinate::new(
-                self.pixel_size * (pixel.as_ref().col() as f64 - (self.cols - 1) as f64 / 2.0),
-                -self.pixel_size * (pixel.as_ref().row() as f64 - (self.rows - 1) as f64 / 2.0),
+                self.pixel_width * (pixel.as_ref().col() as f64 - (self.cols - 1) as f64 / 2.0),
+                -self.pixel_height * (pixel.as_ref().row() as f64 - (self.rows - 1) as f64 / 2.0),
             ))
         } else {
             None
         }
     }
 
-    fn pixels(&self) -> impl Iterator<Item = PixelCoordinate> {
-        (0..self.rows).flat_map(|row| (0..self.cols).map(move |col| PixelCoordinate::new(row, col)))
+    /// Converts the center of `pixel` into a point on the image plane of a camera body frame.
+    ///
+    /// Returns `None` if `pixel` is not on the sensor.
+    /// See [`SensorCoordinate::to_frd`].
+    #[must_use]
+    pub fn frd_from_pixel<In>(
+        &self,
+        pixel: impl AsRef<PixelCoordinate>,
+        focal_length: Length,
+    ) -> Option<Coordinate<In>>
+    where
+        In: CoordinateSystem<Convention = FrdLike>,
+    {
+        Some(self.sensor_from_pixel(pixel)?.to_frd(focal_length))
+    }
+
+    /// Returns the pixel that images a point in a camera body frame.
+    ///
+    /// Returns `None` if `point` is behind the camera or does not project onto the sensor.
+    /// See [`SensorCoordinate::from_frd`].
+    #[must_use]
+    pub fn pixel_from_frd<In>(
+        &self,
+        point: &Coordinate<In>,
+        focal_length: Length,
+    ) -> Option<PixelCoordinate>
+    where
+        In: CoordinateSystem<Convention = FrdLike>,
+    {
+        self.pixel_from_sensor(SensorCoordinate::from_frd(point, focal_length)?)
+    }
+
+    /// Returns an iterator over every [`PixelCoordinate`] on the sensor in row-major order.
+    pub fn pixels(&self) -> impl Iterator<Item = PixelCoordinate> + use<> {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |row| (0..cols).map(move |col| PixelCoordinate::new(row, col)))
+    }
+
+    /// Returns an iterator over the [`SensorCoordinate`] of every pixel center in row-major order.
+    pub fn coords(&self) -> impl Iterator<Item = SensorCoordinate> + use<> {
+        let sensor = *self;
+        self.pixels().map(move |pixel| {
+            sensor
+                .sensor_from_pixel(pixel)
+                .expect("pixels are on the sensor")
+        })
+    }
+
+    /// Returns the [`SensorCoordinate`] of every pixel center in row-major order.
+    ///
+    /// Prefer this over [`ImageSensor::coords`] when the coordinates are traversed many times.
+    #[must_use]
+    pub fn coord_buffer(&self) -> Vec<SensorCoordinate> {
+        self.coords().collect()
+    }
+
+    /// Returns an [`ImageSensor`] whose pixels are the metapixels of this sensor.
+    ///
+    /// A metapixel is a 2x2 block of pixels under the micro-polarizer array.
+    /// The pixel centers of the returned sensor are the centers of each metapixel.
+    /// A trailing odd row or column is dropped, matching [`crate::image::IntensityImage`].
+    #[must_use]
+    pub fn metapixel_sensor(&self) -> Self {
+        self.binned(2, 2)
+    }
+
+    /// Returns an [`ImageSensor`] whose pixels are the metapixels of an image read out with
+    /// `layout`.
+    ///
+    /// This sensor describes the physical pixels, so any readout binning of `layout` is applied
+    /// before the metapixels are formed.
+    #[must_use]
+    pub fn metapixel_sensor_with_layout(&self, layout: MetapixelLayout) -> Self {
+        self.binned(layout.binning(), layout.binning())
+            .binned(layout.span(), layout.span())
+    }
+
+    /// Returns an [`ImageSensor`] that combines blocks of `row_factor` by `col_factor` pixels.
+    ///
+    /// The pixel centers of the returned sensor are the centers of each block.
+    /// Trailing rows and columns that do not fill a block are dropped.
+    ///
+    /// # Panics
+    /// Will panic if `row_factor` or `col_factor` is zero.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn binned(&self, row_factor: usize, col_factor: usize) -> Self {
+        assert!(
+            row_factor > 0 && col_factor > 0,
+            "binning factors must be non-zero"
+        );
+
+        Self::from_pixel_pitch(
+            self.pixel_width * col_factor as f64,
+            self.pixel_height * row_factor as f64,
+            self.rows / row_factor,
+            self.cols / col_factor,
+        )
+    }
+}
+
+/// Describes how the micro-polarizer pattern repeats in an image read out from a sensor.
+///
+/// In the standard layout, each polarizer covers one pixel and a metapixel is a 2x2 block.
+/// Some cameras run in modes where each polarizer covers a `cell` by `cell` block of pixels, e.g.,
+/// a 4x4 superpixel with a `cell` of two.
+/// The readout may also bin `binning` by `binning` physical pixels into each image pixel, which
+/// scales the effective pixel pitch.
+///
+/// Both the cell and the binning are non-zero, which [`MetapixelLayout::new`],
+/// [`MetapixelLayout::with_binning`], and deserialization enforce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "MetapixelLayoutFields")
+)]
+pub struct MetapixelLayout {
+    cell: usize,
+    binning: usize,
+}
+
+/// Holds the fields of a [`MetapixelLayout`] before they are validated.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct MetapixelLayoutFields {
+    cell: usize,
+    binning: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<MetapixelLayoutFields> for MetapixelLayout {
+    type Error = &'static str;
+
+    fn try_from(fields: MetapixelLayoutFields) -> Result<Self, Self::Error> {
+        if fields.cell == 0 {
+            return Err("cell must be non-zero");
+        }
+        if fields.binning == 0 {
+            return Err("binning must be non-zero");
+        }
+
+        Ok(Self::new(fields.cell).with_binning(fields.binning))
+    }
+}
+
+impl MetapixelLayout {
+    /// The standard 2x2 metapixel without binning.
+    pub const STANDARD: Self = Self {
+        cell: 1,
+        binning: 1,
+    };
+
+    /// A 4x4 superpixel where each polarizer covers a 2x2 block of pixels.
+    pub const SUPERPIXEL_4X4: Self = Self {
+        cell: 2,
+        binning: 1,
+    };
+
+    /// Creates a new `MetapixelLayout` where each polarizer covers `cell` by `cell` pixels.
+    ///
+    /// # Panics
+    /// Will panic if `cell` is zero.
+    #[must_use]
+    pub fn new(cell: usize) -> Self {
+        assert!(cell > 0, "cell must be non-zero");
+
+        Self { cell, binning: 1 }
+    }
+
+    /// Sets the number of physical pixels along each axis that the readout bins into each image
+    /// pixel.
+    ///
+    /// # Panics
+    /// Will panic if `binning` is zero.
+    #[must_use]
+    pub fn with_binning(mut self, binning: usize) -> Self {
+        assert!(binning > 0, "binning must be non-zero");
+
+        self.binning = binning;
+        self
+    }
+
+    /// The number of image pixels along each axis covered by one polarizer.
+    #[must_use]
+    pub fn cell(&self) -> usize {
+        self.cell
+    }
+
+    /// The number of physical pixels along each axis binned into each image pixel.
+    #[must_use]
+    pub fn binning(&self) -> usize {
+        self.binning
+    }
+
+    /// The number of image pixels along each axis spanned by a metapixel.
+    #[must_use]
+    pub fn span(&self) -> usize {
+        2 * self.cell
+    }
+}
+
+impl Default for MetapixelLayout {
+    fn default() -> Self {
+        Self::STANDARD
     }
 }
 
@@ -215,6 +539,9 @@ pub struct RayDirection {
 }
 
 impl RayDirection {
+    /// The convention of [`RayDirection::azimuth`] about the [`SensorCoordinate`] axes.
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::EastCounterclockwise;
+
     #[must_use]
     pub fn from_angles(polar: Angle, azimuth: Angle) -> Self {
         Self { polar, azimuth }
@@ -260,11 +587,17 @@ impl PinholeOptic {
 
         Self { focal_length }
     }
+
+    #[must_use]
+    pub fn focal_length(&self) -> Length {
+        self.focal_length
+    }
 }
 
 impl Optic for PinholeOptic {
     fn trace_backward(&self, coord: &SensorCoordinate) -> RayDirection {
-        let azimuth = coord.y().atan2(coord.x());
+        let azimuth = RayDirection::AZIMUTH
+            .from_components(coord.x().get::<meter>(), coord.y().get::<meter>());
         let ray_length_xy = Length::new::<meter>(
             (coord.x().get::<meter>().powf(2.0) + coord.y().get::<meter>().powf(2.0)).sqrt(),
         );
@@ -276,33 +609,113 @@ impl Optic for PinholeOptic {
 
     fn trace_forward(&self, bearing: &RayDirection) -> SensorCoordinate {
         let ray_length_xy = -self.focal_length * bearing.polar().tan();
-        let azimuth = bearing.azimuth();
-        let x = ray_length_xy * azimuth.cos();
-        let y = ray_length_xy * azimuth.sin();
+        let (x, y) = RayDirection::AZIMUTH.components(bearing.azimuth());
+        let (x, y) = (ray_length_xy * x, ray_length_xy * y);
 
         SensorCoordinate::new(x, y)
     }
 }
 
+/// Describes the relative illumination of a lens, which darkens the image away from its center.
+///
+/// The illumination at a field angle `θ` from the optical axis relative to the center is modeled
+/// as the even polynomial:
+/// ```text
+/// V(θ) = 1 + k_1 * θ^2 + k_2 * θ^4 + k_3 * θ^6
+/// ```
+/// with `θ` in radians.
+/// Vignetting scales all four channels of a metapixel equally, so it leaves the AoP unchanged but
+/// biases the total intensity `S_0` and every step that thresholds it.
+/// The default has no vignetting.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Vignetting {
+    coefficients: [f64; 3],
+}
+
+impl Vignetting {
+    /// Creates a new `Vignetting` from the coefficients of `θ^2`, `θ^4`, and `θ^6`.
+    #[must_use]
+    pub fn new(coefficients: [f64; 3]) -> Self {
+        Self { coefficients }
+    }
+
+    #[must_use]
+    pub fn coefficients(&self) -> [f64; 3] {
+        self.coefficients
+    }
+
+    /// Returns the illumination at `field_angle` relative to the optical axis.
+    ///
+    /// The result is clamped to be non-negative.
+    #[must_use]
+    pub fn relative_illumination(&self, field_angle: Angle) -> f64 {
+        let theta_sq = field_angle.get::<radian>().powi(2);
+        let polynomial = self
+            .coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, coefficient| (acc + coefficient) * theta_sq);
+        (1.0 + polynomial).max(0.0)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Camera<O> {
     optic: O,
     sensor: ImageSensor,
+    #[cfg_attr(feature = "serde", serde(default))]
+    vignetting: Vignetting,
 }
 
 impl<O> Camera<O> {
     pub fn new(optic: O, pixel_size: Length, rows: usize, cols: usize) -> Self {
+        Self::from_sensor(optic, ImageSensor::new(pixel_size, rows, cols))
+    }
+
+    /// Creates a new `Camera` from an `optic` and an image `sensor`.
+    ///
+    /// Use this to build cameras with rectangular or binned pixels.
+    pub fn from_sensor(optic: O, sensor: ImageSensor) -> Self {
         Self {
             optic,
-            sensor: ImageSensor::new(pixel_size, rows, cols),
+            sensor,
+            vignetting: Vignetting::default(),
         }
     }
 
-    pub fn pixels(&self) -> impl Iterator<Item = PixelCoordinate> {
+    /// Sets the [`Vignetting`] of the lens.
+    #[must_use]
+    pub fn with_vignetting(mut self, vignetting: Vignetting) -> Self {
+        self.vignetting = vignetting;
+        self
+    }
+
+    #[must_use]
+    pub fn vignetting(&self) -> &Vignetting {
+        &self.vignetting
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = PixelCoordinate> + use<O> {
         self.sensor.pixels()
     }
 
+    /// Returns an iterator over the [`SensorCoordinate`] of every pixel center in row-major order.
+    pub fn coords(&self) -> impl Iterator<Item = SensorCoordinate> + use<O> {
+        self.sensor.coords()
+    }
+
+    #[must_use]
+    pub fn optic(&self) -> &O {
+        &self.optic
+    }
+
+    #[must_use]
+    pub fn sensor(&self) -> &ImageSensor {
+        &self.sensor
+    }
+
     pub fn trace_from_pixel(&self, pixel: impl AsRef<PixelCoordinate>) -> Option<RayDirection>
     where
         O: Optic,
@@ -321,6 +734,31 @@ impl<O> Camera<O> {
         self.sensor.pixel_from_sensor(sensor_coord)
     }
 
+    /// Returns the angle between the optical axis and the direction `pixel` is traced to.
+    ///
+    /// Returns `None` if `pixel` is outside of the sensor.
+    pub fn field_angle(&self, pixel: impl AsRef<PixelCoordinate>) -> Option<Angle>
+    where
+        O: Optic,
+    {
+        // Scene points are traced towards negative Z, so the field angle is measured from it.
+        let direction = self.trace_from_pixel(pixel)?;
+        Some(Angle::HALF_TURN - direction.polar())
+    }
+
+    /// Returns the [`Vignetting::relative_illumination`] of `pixel`.
+    ///
+    /// Returns `None` if `pixel` is outside of the sensor.
+    pub fn relative_illumination(&self, pixel: impl AsRef<PixelCoordinate>) -> Option<f64>
+    where
+        O: Optic,
+    {
+        Some(
+            self.vignetting
+                .relative_illumination(self.field_angle(pixel)?),
+        )
+    }
+
     pub fn rows(&self) -> usize {
         self.sensor.rows()
     }
@@ -333,14 +771,17 @@ impl<O> Camera<O> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use approx::AbsDiffEq;
+    use approx::{AbsDiffEq, assert_relative_eq};
     use quickcheck::quickcheck;
     use rstest::rstest;
+    use sguaba::system;
     use uom::si::{
         angle::degree,
         length::{meter, micron, millimeter},
     };
 
+    system!(struct CameraFrd using FRD);
+
     impl AbsDiffEq for SensorCoordinate {
         type Epsilon = f64;
 
@@ -444,4 +885,270 @@ mod tests {
                 > Length::ZERO
         );
     }
+
+    #[test]
+    fn coords_match_pixels() {
+        let sensor = ImageSensor::new(Length::new::<micron>(3.45), 4, 6);
+        let coords = sensor.coord_buffer();
+
+        assert_eq!(coords.len(), sensor.pixel_count());
+        for (pixel, coord) in sensor.pixels().zip(coords) {
+            assert_eq!(sensor.sensor_from_pixel(pixel), Some(coord));
+        }
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(3, 5)]
+    #[case(7, 2)]
+    fn anisotropic_pixel_to_coord(#[case] row: usize, #[case] col: usize) {
+        let sensor = ImageSensor::from_pixel_pitch(
+            Length::new::<micron>(2.0),
+            Length::new::<micron>(5.0),
+            8,
+            6,
+        );
+        let px = PixelCoordinate::new(row, col);
+        let coord = sensor.sensor_from_pixel(px).unwrap();
+
+        assert_relative_eq!(
+            coord.x().get::<micron>(),
+            2.0 * (col as f64 - 2.5),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            coord.y().get::<micron>(),
+            -5.0 * (row as f64 - 3.5),
+            epsilon = 1e-9
+        );
+        assert_eq!(sensor.pixel_from_sensor(coord), Some(px));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_pixel_size() {
+        use serde::de::{
+            IntoDeserializer,
+            value::{Error, MapDeserializer},
+        };
+
+        let sensor = |fields: &[(&'static str, u32)]| {
+            ImageSensor::deserialize(MapDeserializer::<_, Error>::new(
+                fields.iter().map(|(k, v)| (*k, v.into_deserializer())),
+            ))
+        };
+
+        assert_eq!(
+            sensor(&[("pixel_size", 2), ("rows", 4), ("cols", 6)]).unwrap(),
+            ImageSensor::new(Length::new::<meter>(2.0), 4, 6)
+        );
+        assert_eq!(
+            sensor(&[
+                ("pixel_width", 2),
+                ("pixel_height", 3),
+                ("rows", 4),
+                ("cols", 6)
+            ])
+            .unwrap(),
+            ImageSensor::from_pixel_pitch(
+                Length::new::<meter>(2.0),
+                Length::new::<meter>(3.0),
+                4,
+                6
+            )
+        );
+        assert!(sensor(&[("pixel_width", 2), ("rows", 4), ("cols", 6)]).is_err());
+    }
+
+    #[test]
+    fn binning_scales_pitch() {
+        let sensor = ImageSensor::from_pixel_pitch(
+            Length::new::<micron>(2.0),
+            Length::new::<micron>(3.0),
+            9,
+            10,
+        );
+        let binned = sensor.binned(3, 2);
+
+        assert_eq!((binned.rows(), binned.cols()), (3, 5));
+        assert_relative_eq!(binned.pixel_width().get::<micron>(), 4.0);
+        assert_relative_eq!(binned.pixel_height().get::<micron>(), 9.0);
+        assert_eq!(sensor.binned(2, 2), sensor.metapixel_sensor());
+    }
+
+    #[test]
+    fn layout_scales_metapixel_pitch() {
+        let sensor = ImageSensor::new(Length::new::<micron>(3.45), 2048, 2448);
+
+        assert_eq!(
+            sensor.metapixel_sensor_with_layout(MetapixelLayout::STANDARD),
+            sensor.metapixel_sensor()
+        );
+        let superpixel = sensor.metapixel_sensor_with_layout(MetapixelLayout::SUPERPIXEL_4X4);
+        assert_eq!((superpixel.rows(), superpixel.cols()), (512, 612));
+        assert_relative_eq!(
+            superpixel.pixel_width().get::<micron>(),
+            13.8,
+            epsilon = 1e-9
+        );
+
+        let binned = sensor.metapixel_sensor_with_layout(MetapixelLayout::new(1).with_binning(2));
+        assert_eq!(binned, superpixel);
+    }
+
+    #[test]
+    fn layout_rejects_empty_cells() {
+        assert!(std::panic::catch_unwind(|| MetapixelLayout::new(0)).is_err());
+        assert!(std::panic::catch_unwind(|| MetapixelLayout::new(1).with_binning(0)).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_layout() {
+        use serde::de::{
+            IntoDeserializer,
+            value::{Error, MapDeserializer},
+        };
+
+        let layout = |cell: u32, binning: u32| {
+            MetapixelLayout::deserialize(MapDeserializer::<_, Error>::new(
+                [("cell", cell), ("binning", binning)]
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into_deserializer())),
+            ))
+        };
+
+        assert_eq!(layout(2, 1).unwrap(), MetapixelLayout::SUPERPIXEL_4X4);
+        assert!(layout(0, 1).is_err());
+        assert!(layout(1, 0).is_err());
+    }
+
+    #[test]
+    fn metapixel_centers() {
+        let sensor = ImageSensor::new(Length::new::<micron>(3.45), 4, 6);
+        let metasensor = sensor.metapixel_sensor();
+
+        assert_eq!(metasensor.rows(), 2);
+        assert_eq!(metasensor.cols(), 3);
+        for metapixel in metasensor.pixels() {
+            let (row, col) = (metapixel.row() * 2, metapixel.col() * 2);
+            let corners = [
+                (row, col),
+                (row, col + 1),
+                (row + 1, col),
+                (row + 1, col + 1),
+            ]
+            .map(|(row, col)| {
+                sensor
+                    .sensor_from_pixel(PixelCoordinate::new(row, col))
+                    .unwrap()
+            });
+            let center = SensorCoordinate::new(
+                corners.iter().map(SensorCoordinate::x).sum::<Length>() / 4.0,
+                corners.iter().map(SensorCoordinate::y).sum::<Length>() / 4.0,
+            );
+
+            assert!(
+                metasensor
+                    .sensor_from_pixel(metapixel)
+                    .unwrap()
+                    .abs_diff_eq(&center, 1e-12)
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(512, 612)]
+    #[case(1023, 17)]
+    fn pixel_to_frd_roundtrip(#[case] row: usize, #[case] col: usize) {
+        let sensor = ImageSensor::new(Length::new::<micron>(3.45 * 2.), 1024, 1224);
+        let focal_length = Length::new::<millimeter>(3.0);
+        let px = PixelCoordinate::new(row, col);
+
+        let point: Coordinate<CameraFrd> = sensor.frd_from_pixel(px, focal_length).unwrap();
+        assert_eq!(sensor.pixel_from_frd(&point, focal_length), Some(px));
+
+        // Every point along the line of sight is imaged by the same pixel.
+        let farther = Coordinate::<CameraFrd>::build(FrdComponents {
+            front: point.frd_front() * 250.0,
+            right: point.frd_right() * 250.0,
+            down: point.frd_down() * 250.0,
+        });
+        assert_eq!(sensor.pixel_from_frd(&farther, focal_length), Some(px));
+    }
+
+    #[test]
+    fn frd_matches_pinhole_trace() {
+        let focal_length = Length::new::<millimeter>(3.0);
+        let coord = SensorCoordinate::new(
+            Length::new::<millimeter>(1.2),
+            Length::new::<millimeter>(-0.7),
+        );
+        let point: Coordinate<CameraFrd> = coord.to_frd(focal_length);
+        let direction = PinholeOptic::from_focal_length(focal_length).trace_backward(&coord);
+
+        // The optic traces rays towards negative Z in a frame where X and Y match the sensor.
+        let (polar, azimuth) = (direction.polar(), direction.azimuth());
+        let xyz = [
+            (polar.sin() * azimuth.cos()).value,
+            (polar.sin() * azimuth.sin()).value,
+            polar.cos().value,
+        ];
+        let frd =
+            [point.frd_front(), point.frd_right(), point.frd_down()].map(|c| c.get::<meter>());
+        let norm = frd.iter().map(|c| c * c).sum::<f64>().sqrt();
+        assert_relative_eq!(frd[0] / norm, -xyz[2], epsilon = 1e-12);
+        assert_relative_eq!(frd[1] / norm, xyz[0], epsilon = 1e-12);
+        assert_relative_eq!(frd[2] / norm, -xyz[1], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn frd_behind_camera_is_not_imaged() {
+        let point = Coordinate::<CameraFrd>::build(FrdComponents {
+            front: Length::new::<meter>(-1.0),
+            right: Length::ZERO,
+            down: Length::ZERO,
+        });
+        let sensor = ImageSensor::new(Length::new::<micron>(3.45), 4, 6);
+
+        assert_eq!(
+            sensor.pixel_from_frd(&point, Length::new::<millimeter>(3.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn vignetting_darkens_field() {
+        let vignetting = Vignetting::new([-0.5, 0.1, 0.0]);
+        assert_relative_eq!(vignetting.relative_illumination(Angle::ZERO), 1.0);
+        assert_relative_eq!(
+            vignetting.relative_illumination(Angle::new::<radian>(0.5)),
+            1.0 - 0.5 * 0.25 + 0.1 * 0.0625
+        );
+        assert_relative_eq!(
+            Vignetting::new([-2.0, 0.0, 0.0]).relative_illumination(Angle::new::<radian>(1.0)),
+            0.0
+        );
+
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+            Length::new::<micron>(3.45),
+            5,
+            5,
+        )
+        .with_vignetting(vignetting);
+        let center = camera
+            .relative_illumination(PixelCoordinate::new(2, 2))
+            .unwrap();
+        let corner = camera
+            .relative_illumination(PixelCoordinate::new(0, 0))
+            .unwrap();
+        assert_relative_eq!(center, 1.0);
+        assert!(corner < center);
+        assert_eq!(
+            camera.relative_illumination(PixelCoordinate::new(5, 0)),
+            None
+        );
+    }
 }
@@ -0,0 +1,359 @@
+//! Records of how results were produced.
+
+use crate::rng::Fnv1a;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Write as _},
+    io::{self, Write},
+};
+
+/// The version of rumpus that produced a result.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Stamps a result with the version of rumpus, the algorithm and its hyperparameters, hashes of
+/// its inputs, and the seed of any random sampling.
+///
+/// Write it alongside exported results so experiments remain traceable once the code has moved
+/// on.
+/// Inputs are hashed with the 64 bit Fowler-Noll-Vo hash, whose output does not change between
+/// releases.
+/// With the `serde` feature, a `Provenance` (de)serializes as the object of
+/// [`Provenance::to_json`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Provenance {
+    version: String,
+    algorithm: String,
+    #[cfg_attr(feature = "serde", serde(with = "entries"))]
+    parameters: Vec<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(with = "entries"))]
+    inputs: Vec<(String, InputHash)>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    seed: Option<u64>,
+}
+
+impl Provenance {
+    /// Creates a new `Provenance` for a result of `algorithm` produced by this version of rumpus.
+    #[must_use]
+    pub fn new(algorithm: impl Into<String>) -> Self {
+        Self {
+            version: VERSION.to_owned(),
+            algorithm: algorithm.into(),
+            parameters: Vec::new(),
+            inputs: Vec::new(),
+            seed: None,
+        }
+    }
+
+    /// Records the hyperparameter `name` with the formatted `value`.
+    ///
+    /// Recording `name` again replaces its value in place.
+    #[must_use]
+    pub fn with_parameter(mut self, name: impl Into<String>, value: impl fmt::Display) -> Self {
+        insert(&mut self.parameters, name.into(), value.to_string());
+        self
+    }
+
+    /// Records the input `name` by the hash of its `bytes`.
+    ///
+    /// Recording `name` again replaces its hash in place.
+    #[must_use]
+    pub fn with_input(mut self, name: impl Into<String>, bytes: &[u8]) -> Self {
+        insert(&mut self.inputs, name.into(), InputHash::of(bytes));
+        self
+    }
+
+    /// Records the seed of the random sampling used to produce the result.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the version of rumpus that produced the result.
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    #[must_use]
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    /// Returns the hyperparameters in the order they were recorded.
+    #[must_use]
+    pub fn parameters(&self) -> &[(String, String)] {
+        &self.parameters
+    }
+
+    /// Returns the hashes of the inputs in the order they were recorded.
+    #[must_use]
+    pub fn inputs(&self) -> &[(String, InputHash)] {
+        &self.inputs
+    }
+
+    #[must_use]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Writes one `# key: value` line for each field.
+    ///
+    /// [`Evaluation::write_csv`](crate::metrics::dataset::Evaluation::write_csv) and
+    /// [`write_grid_with_provenance`](crate::io::grid::write_grid_with_provenance) put these at
+    /// the top of their output.
+    ///
+    /// # Errors
+    /// Will return `Err` if writing to `writer` fails.
+    pub fn write_comments(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "# version: {}", self.version)?;
+        writeln!(writer, "# algorithm: {}", self.algorithm)?;
+        for (name, value) in &self.parameters {
+            writeln!(writer, "# parameter {name}: {value}")?;
+        }
+        for (name, hash) in &self.inputs {
+            writeln!(writer, "# input {name}: {hash}")?;
+        }
+        if let Some(seed) = self.seed {
+            writeln!(writer, "# seed: {seed}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the record as a JSON object with the same keys as the fields of `Provenance`.
+    ///
+    /// Parameters and inputs are written as objects of strings in the order they were recorded.
+    /// [`SequenceWriter::write_provenance`](crate::io::sequence::SequenceWriter::write_provenance)
+    /// stores this object in a sequence.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            r#"{{"version":{},"algorithm":{},"parameters":{},"inputs":{}"#,
+            quote(&self.version),
+            quote(&self.algorithm),
+            object(
+                self.parameters
+                    .iter()
+                    .map(|(name, value)| (name, value.clone()))
+            ),
+            object(
+                self.inputs
+                    .iter()
+                    .map(|(name, hash)| (name, hash.to_string()))
+            ),
+        );
+        if let Some(seed) = self.seed {
+            let _ = write!(json, r#","seed":{seed}"#);
+        }
+        json.push('}');
+        json
+    }
+}
+
+/// Replaces the value of `name` in `entries` or appends it, so names stay unique.
+fn insert<T>(entries: &mut Vec<(String, T)>, name: String, value: T) {
+    match entries.iter_mut().find(|(existing, _)| *existing == name) {
+        Some((_, existing)) => *existing = value,
+        None => entries.push((name, value)),
+    }
+}
+
+/// (De)serializes named entries as a map in the order they were recorded.
+#[cfg(feature = "serde")]
+mod entries {
+    use serde::{
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{self, MapAccess, Visitor},
+        ser::SerializeMap,
+    };
+    use std::{fmt, marker::PhantomData};
+
+    pub fn serialize<S, T>(entries: &[(String, T)], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (name, value) in entries {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<(String, T)>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        struct EntriesVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for EntriesVisitor<T> {
+            type Value = Vec<(String, T)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of uniquely named entries")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries: Vec<(String, T)> = Vec::new();
+                while let Some((name, value)) = map.next_entry::<String, T>()? {
+                    if entries.iter().any(|(existing, _)| *existing == name) {
+                        return Err(de::Error::custom(format!("duplicate entry {name}")));
+                    }
+                    entries.push((name, value));
+                }
+                Ok(entries)
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
+
+/// The hash of an input recorded in a [`Provenance`].
+///
+/// With the `serde` feature, it (de)serializes as the hexadecimal string of its
+/// [`Display`](fmt::Display) implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InputHash(u64);
+
+impl InputHash {
+    /// Hashes `bytes`.
+    #[must_use]
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut hash = Fnv1a::default();
+        hash.write(bytes);
+        Self(hash.finish())
+    }
+}
+
+impl fmt::Display for InputHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for InputHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for InputHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        u64::from_str_radix(&hex, 16)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Writes `entries` as a JSON object of strings.
+fn object<'a>(entries: impl Iterator<Item = (&'a String, String)>) -> String {
+    let fields: Vec<_> = entries
+        .map(|(name, value)| format!("{}:{}", quote(name), quote(&value)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Quotes `text` as a JSON string.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str(r#"\""#),
+            '\\' => quoted.push_str(r"\\"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provenance() -> Provenance {
+        Provenance::new("symmetry-axis")
+            .with_parameter("dop_weighted", true)
+            .with_parameter("note", "say \"hi\"")
+            .with_input("frame.raw", b"abc")
+            .with_seed(7)
+    }
+
+    #[test]
+    fn hashes_are_stable() {
+        // The published FNV-1a test vector.
+        assert_eq!(InputHash::of(b"a").to_string(), "af63dc4c8601ec8c");
+        assert_ne!(InputHash::of(b"abc"), InputHash::of(b"abd"));
+    }
+
+    #[test]
+    fn writes_comments() {
+        let mut bytes = Vec::new();
+        provenance().write_comments(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+
+        assert!(text.starts_with(&format!(
+            "# version: {VERSION}\n# algorithm: symmetry-axis\n"
+        )));
+        assert!(text.contains("# parameter dop_weighted: true\n"));
+        assert!(text.contains(&format!("# input frame.raw: {}\n", InputHash::of(b"abc"))));
+        assert!(text.ends_with("# seed: 7\n"));
+        assert!(text.lines().all(|line| line.starts_with('#')));
+    }
+
+    #[test]
+    fn writes_json() {
+        let json = provenance().to_json();
+
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"version":"{VERSION}","algorithm":"symmetry-axis","parameters":{{"dop_weighted":"true","note":"say \"hi\""}},"inputs":{{"frame.raw":"{}"}},"seed":7}}"#,
+                InputHash::of(b"abc")
+            )
+        );
+        assert!(!Provenance::new("x").to_json().contains("seed"));
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let provenance = provenance().with_parameter("dop_weighted", false);
+        assert_eq!(
+            provenance.parameters()[0],
+            ("dop_weighted".to_owned(), "false".to_owned())
+        );
+        assert_eq!(provenance.parameters().len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_matches_json() {
+        for provenance in [provenance(), Provenance::new("x")] {
+            let json = provenance.to_json();
+            assert_eq!(serde_json::to_string(&provenance).unwrap(), json);
+            assert_eq!(
+                serde_json::from_str::<Provenance>(&json).unwrap(),
+                provenance
+            );
+        }
+
+        let duplicate = format!(
+            r#"{{"version":"{VERSION}","algorithm":"x","parameters":{{"a":"1","a":"2"}},"inputs":{{}}}}"#
+        );
+        assert!(serde_json::from_str::<Provenance>(&duplicate).is_err());
+    }
+}
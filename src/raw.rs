@@ -0,0 +1,383 @@
+//! Decoding of raw sensor dumps described by a [`RawFormat`].
+
+use crate::image::{ImageError, IntensityImage};
+use rayon::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RawError {
+    #[error("bit depth must be between 1 and 16: found {0}")]
+    InvalidBitDepth(u8),
+
+    #[error("stride of {stride} bytes is shorter than a row of {row} bytes")]
+    StrideTooShort { stride: usize, row: usize },
+
+    #[error("raw data is too short: expected {expected} bytes found {len}")]
+    TooShort { expected: usize, len: usize },
+
+    #[cfg(feature = "mmap")]
+    #[error("failed to map raw file")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "mmap")]
+    #[error("frame {index} is out of range: found {frames} frames")]
+    FrameOutOfRange { index: usize, frames: usize },
+
+    #[error(transparent)]
+    Image(#[from] ImageError),
+}
+
+/// Describes the layout of a raw frame from a polarized sensor.
+///
+/// Samples of 8 bits or fewer take one byte and deeper samples take two little endian bytes.
+/// Bits above the bit depth are ignored, e.g., the padding of 12 bit samples in 16 bit words.
+/// Rows start `stride` bytes apart after `offset` bytes of header.
+/// See [`IntensityImage::from_bytes`] for the layout of the micro-polarizer array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawFormat {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    offset: usize,
+    stride: Option<usize>,
+}
+
+impl RawFormat {
+    /// Creates a new `RawFormat` for tightly packed 8 bit frames of `width` by `height` pixels.
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            bit_depth: 8,
+            offset: 0,
+            stride: None,
+        }
+    }
+
+    /// Sets the number of significant bits in each sample.
+    ///
+    /// # Errors
+    /// Will return `Err` if `bit_depth` is not between 1 and 16 or a stride set before is shorter
+    /// than a row of the deeper samples.
+    pub fn with_bit_depth(mut self, bit_depth: u8) -> Result<Self, RawError> {
+        if !(1..=16).contains(&bit_depth) {
+            return Err(RawError::InvalidBitDepth(bit_depth));
+        }
+
+        self.bit_depth = bit_depth;
+        match self.stride {
+            Some(stride) => self.with_stride(stride),
+            None => Ok(self),
+        }
+    }
+
+    /// Sets the number of header bytes before the first row.
+    #[must_use]
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the number of bytes between the starts of consecutive rows.
+    ///
+    /// # Errors
+    /// Will return `Err` if `stride` is shorter than a row of samples.
+    pub fn with_stride(mut self, stride: usize) -> Result<Self, RawError> {
+        let row = self.width * self.sample_bytes();
+        if stride < row {
+            return Err(RawError::StrideTooShort { stride, row });
+        }
+
+        self.stride = Some(stride);
+        Ok(self)
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of bytes between the starts of consecutive rows.
+    ///
+    /// Defaults to the length of a row of samples.
+    #[must_use]
+    pub fn stride(&self) -> usize {
+        self.stride.unwrap_or(self.width * self.sample_bytes())
+    }
+
+    /// Returns the largest intensity a sample can hold, e.g., 4095 for 12 bit samples.
+    #[must_use]
+    pub fn saturation_level(&self) -> f64 {
+        f64::from((1u32 << self.bit_depth) - 1)
+    }
+
+    /// Returns the number of bytes spanned by a frame including its header.
+    #[must_use]
+    pub fn frame_len(&self) -> usize {
+        match self.height {
+            0 => self.offset,
+            height => self.offset + (height - 1) * self.stride() + self.width * self.sample_bytes(),
+        }
+    }
+
+    /// Returns the number of bytes between the starts of consecutive frames in a file.
+    ///
+    /// Unlike [`RawFormat::frame_len`], this includes the padding after the last row, so strided
+    /// frames stay aligned when they are stored back to back.
+    #[must_use]
+    pub fn frame_pitch(&self) -> usize {
+        self.offset + self.height * self.stride()
+    }
+
+    /// Decodes the metapixels of a frame directly from `bytes` without an intermediate copy.
+    ///
+    /// Intensities are raw sample values, so compare them against
+    /// [`RawFormat::saturation_level`].
+    /// Trailing rows and columns that do not fill a metapixel are dropped.
+    ///
+    /// # Errors
+    /// Will return `Err` if `bytes` is shorter than [`RawFormat::frame_len`].
+    pub fn decode(&self, bytes: &[u8]) -> Result<IntensityImage, RawError> {
+        let expected = self.frame_len();
+        if bytes.len() < expected {
+            return Err(RawError::TooShort {
+                expected,
+                len: bytes.len(),
+            });
+        }
+
+        let (meta_width, meta_height) = (self.width / 2, self.height / 2);
+        let intensities: Vec<[f64; 4]> = (0..meta_width * meta_height)
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index % meta_width, index / meta_width);
+                let sample = |row: usize, col: usize| self.sample(bytes, row, col);
+                [
+                    sample(y * 2 + 1, x * 2 + 1),
+                    sample(y * 2 + 1, x * 2),
+                    sample(y * 2, x * 2),
+                    sample(y * 2, x * 2 + 1),
+                ]
+            })
+            .collect();
+
+        Ok(IntensityImage::from_intensities(
+            meta_width,
+            meta_height,
+            intensities,
+        )?)
+    }
+
+    fn sample_bytes(&self) -> usize {
+        if self.bit_depth > 8 { 2 } else { 1 }
+    }
+
+    fn sample(&self, bytes: &[u8], row: usize, col: usize) -> f64 {
+        let start = self.offset + row * self.stride() + col * self.sample_bytes();
+        let word = if self.sample_bytes() == 2 {
+            u16::from_le_bytes([bytes[start], bytes[start + 1]])
+        } else {
+            u16::from(bytes[start])
+        };
+
+        let mask = u16::MAX >> (16 - self.bit_depth);
+        f64::from(word & mask)
+    }
+}
+
+/// A raw file mapped into memory.
+///
+/// Pages are read from disk when they are first touched, so large batches of frames can be
+/// decoded without copying whole files into memory.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedRaw {
+    map: memmap2::Mmap,
+    format: RawFormat,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedRaw {
+    /// Maps the file at `path` that holds frames laid out by `format`.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped.
+    /// Otherwise, decoding reads undefined data or the process is terminated by the operating
+    /// system.
+    ///
+    /// # Errors
+    /// Will return `Err` if the file cannot be opened or mapped.
+    pub unsafe fn open(
+        path: impl AsRef<std::path::Path>,
+        format: RawFormat,
+    ) -> Result<Self, RawError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: The caller upholds the same contract.
+        let map = unsafe { memmap2::Mmap::map(&file) }?;
+        Ok(Self { map, format })
+    }
+
+    #[must_use]
+    pub fn format(&self) -> &RawFormat {
+        &self.format
+    }
+
+    /// Returns the mapped bytes.
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Returns the number of whole frames in the file.
+    ///
+    /// Frames are stored back to back, each with its own header, and start
+    /// [`RawFormat::frame_pitch`] bytes apart.
+    /// The padding after the last row of the final frame may be missing.
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        let (len, frame_len) = (self.map.len(), self.format.frame_len());
+        if len < frame_len {
+            return 0;
+        }
+
+        (len - frame_len)
+            .checked_div(self.format.frame_pitch())
+            .map_or(0, |frames| frames + 1)
+    }
+
+    /// Decodes the frame at `index`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the file ends before the frame.
+    pub fn decode(&self, index: usize) -> Result<IntensityImage, RawError> {
+        let start = index
+            .checked_mul(self.format.frame_pitch())
+            .filter(|start| *start <= self.map.len())
+            .ok_or(RawError::FrameOutOfRange {
+                index,
+                frames: self.frames(),
+            })?;
+        self.format.decode(&self.map[start..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_padded_rows() {
+        let format = RawFormat::new(2, 2).with_offset(3).with_stride(4).unwrap();
+        // The 90 and 135 degree row, then the 45 and 0 degree row.
+        let bytes = [9, 9, 9, 90, 135, 0, 0, 45, 1, 0, 0];
+
+        assert_eq!(format.frame_len(), 9);
+        let image = format.decode(&bytes).unwrap();
+        assert_eq!(
+            image.intensities().collect::<Vec<_>>(),
+            vec![[1.0, 45.0, 90.0, 135.0]]
+        );
+        assert!(matches!(
+            format.decode(&bytes[..8]),
+            Err(RawError::TooShort {
+                expected: 9,
+                len: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn masks_deep_samples() {
+        let format = RawFormat::new(2, 2).with_bit_depth(12).unwrap();
+        let words: [u16; 4] = [0xf123, 4095, 7, 2048];
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+        let image = format.decode(&bytes).unwrap();
+        assert_eq!(
+            image.intensities().next(),
+            Some([2048.0, 7.0, 291.0, 4095.0])
+        );
+        assert_eq!(format.saturation_level(), 4095.0);
+        assert!(matches!(
+            RawFormat::new(2, 2).with_bit_depth(17),
+            Err(RawError::InvalidBitDepth(17))
+        ));
+        assert!(matches!(
+            format.with_stride(3),
+            Err(RawError::StrideTooShort { stride: 3, row: 4 })
+        ));
+        assert!(matches!(
+            RawFormat::new(2, 2)
+                .with_stride(3)
+                .unwrap()
+                .with_bit_depth(12),
+            Err(RawError::StrideTooShort { stride: 3, row: 4 })
+        ));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn maps_frames() {
+        let path = std::env::temp_dir().join(format!("rumpus-raw-{}", std::process::id()));
+        let format = RawFormat::new(2, 2).with_offset(1);
+        std::fs::write(&path, [0, 90, 135, 45, 0, 0, 91, 136, 46, 1, 0]).unwrap();
+
+        // SAFETY: The file is not modified while it is mapped.
+        let raw = unsafe { MappedRaw::open(&path, format) }.unwrap();
+        assert_eq!(raw.frames(), 2);
+        assert_eq!(
+            raw.decode(1).unwrap().intensities().next(),
+            Some([1.0, 46.0, 91.0, 136.0])
+        );
+        assert!(matches!(raw.decode(2), Err(RawError::TooShort { .. })));
+        assert!(matches!(
+            raw.decode(usize::MAX),
+            Err(RawError::FrameOutOfRange { frames: 2, .. })
+        ));
+
+        drop(raw);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn maps_strided_frames() {
+        let path = std::env::temp_dir().join(format!("rumpus-raw-strided-{}", std::process::id()));
+        let format = RawFormat::new(2, 2).with_offset(1).with_stride(4).unwrap();
+        assert_eq!((format.frame_len(), format.frame_pitch()), (7, 9));
+        // The second frame omits the padding after its last row.
+        let bytes = [0, 90, 135, 9, 9, 45, 1, 9, 9, 0, 91, 136, 9, 9, 46, 2];
+        std::fs::write(&path, bytes).unwrap();
+
+        // SAFETY: The file is not modified while it is mapped.
+        let raw = unsafe { MappedRaw::open(&path, format) }.unwrap();
+        assert_eq!(raw.frames(), 2);
+        assert_eq!(
+            raw.decode(0).unwrap().intensities().next(),
+            Some([1.0, 45.0, 90.0, 135.0])
+        );
+        assert_eq!(
+            raw.decode(1).unwrap().intensities().next(),
+            Some([2.0, 46.0, 91.0, 136.0])
+        );
+
+        drop(raw);
+        std::fs::remove_file(path).unwrap();
+    }
+}
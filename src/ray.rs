@@ -2,7 +2,7 @@ use crate::light::{LightError, aop::Aop, dop::Dop, stokes::StokesVec};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use uom::si::f64::Angle;
+use uom::si::{angle::radian, f64::Angle};
 
 #[derive(Debug, Error)]
 pub enum RayError {
@@ -18,6 +18,59 @@ pub struct GlobalFrame;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SensorFrame;
 
+/// Converts polarization angles from `From` into `To`.
+///
+/// Implement this trait to convert [`Ray`]s, [`RayImage`]s, and [`StokesVec`]s between
+/// user-defined frames e.g., a vehicle body or a gimbal.
+/// Frames are zero-sized marker types like [`SensorFrame`] and [`GlobalFrame`] so conversions are
+/// checked at compile time.
+///
+/// [`RayImage`]: crate::image::RayImage
+pub trait FrameTransform<From, To> {
+    /// Returns the angle of the reference axis of `To` relative to the reference axis of `From`.
+    ///
+    /// An [`Aop`] in `From` is expressed in `To` by subtracting this angle.
+    fn shift(&self) -> Angle;
+}
+
+/// A fixed rotation of the reference axis from `From` into `To`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FrameShift<From, To> {
+    angle: Angle,
+    _phan: std::marker::PhantomData<(From, To)>,
+}
+
+impl<From, To> FrameShift<From, To> {
+    /// Creates a new `FrameShift` where the reference axis of `To` is rotated by `angle` from the
+    /// reference axis of `From`.
+    #[must_use]
+    pub fn new(angle: Angle) -> Self {
+        Self {
+            angle,
+            _phan: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns the `FrameShift` from `To` back into `From`.
+    #[must_use]
+    pub fn inverse(&self) -> FrameShift<To, From> {
+        FrameShift::new(-self.angle)
+    }
+
+    /// Composes this `FrameShift` with a `FrameShift` from `To` into `Next`.
+    #[must_use]
+    pub fn then<Next>(&self, next: &FrameShift<To, Next>) -> FrameShift<From, Next> {
+        FrameShift::new(self.angle + next.angle)
+    }
+}
+
+impl<From, To> FrameTransform<From, To> for FrameShift<From, To> {
+    fn shift(&self) -> Angle {
+        self.angle
+    }
+}
+
 /// Describes the angle and degree of polarization for a single ray.
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -56,6 +109,50 @@ impl<Frame> Ray<Frame> {
     pub fn dop(&self) -> Dop {
         self.degree
     }
+
+    /// Averages `rays` as partially polarized light of equal intensity.
+    ///
+    /// The [`Aop`] is axial, so averaging angles directly is wrong across the wrap at ±90
+    /// degrees.
+    /// Each ray instead contributes its doubled AoP as a vector scaled by its [`Dop`], i.e., its
+    /// normalized linear Stokes vector, and the mean vector gives the AoP and DoP of the result.
+    /// Rays that disagree cancel, so the mean is less polarized than its parts.
+    /// Use this whenever rays are reduced, e.g., when downsampling or tiling an image.
+    ///
+    /// Returns `None` if `rays` is empty.
+    #[must_use]
+    pub fn mean<'a>(rays: impl IntoIterator<Item = &'a Ray<Frame>>) -> Option<Self>
+    where
+        Frame: Copy + 'a,
+    {
+        let (mut cos_sum, mut sin_sum, mut count) = (0.0, 0.0, 0usize);
+        for ray in rays {
+            let dop = f64::from(ray.dop());
+            let (sin, cos) = (2.0 * Angle::from(ray.aop()).get::<radian>()).sin_cos();
+            cos_sum += dop * cos;
+            sin_sum += dop * sin;
+            count += 1;
+        }
+
+        if count == 0 {
+            return None;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let count = count as f64;
+        Some(Self::new(
+            Aop::from_angle_wrapped(Angle::new::<radian>(sin_sum.atan2(cos_sum) / 2.0)),
+            Dop::clamped(cos_sum.hypot(sin_sum) / count),
+        ))
+    }
+}
+
+impl<From> Ray<From> {
+    /// Transforms the `Ray` from `From` into `To`.
+    #[must_use]
+    pub fn transform<To>(self, transform: &impl FrameTransform<From, To>) -> Ray<To> {
+        Ray::new(self.angle.transform(transform), self.degree)
+    }
 }
 
 impl Ray<GlobalFrame> {
@@ -74,6 +171,154 @@ impl Ray<SensorFrame> {
     }
 }
 
+/// Quality flags raised on a [`WeightedRay`] by earlier stages of a pipeline.
+///
+/// Any raised flag marks the ray as invalid so estimators skip it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RayFlags(u8);
+
+impl RayFlags {
+    /// No flags are raised.
+    pub const NONE: Self = Self(0);
+
+    /// At least one channel of the metapixel was saturated.
+    pub const SATURATED: Self = Self(1);
+
+    /// The pixel is likely covered by cloud.
+    pub const CLOUD: Self = Self(1 << 1);
+
+    /// The pixel is covered by a fixed occlusion, e.g., from a
+    /// [`PixelMask`](crate::mask::PixelMask).
+    pub const OCCLUDED: Self = Self(1 << 2);
+
+    /// The pixel failed a calibration check, e.g., a dead or hot pixel.
+    pub const UNCALIBRATED: Self = Self(1 << 3);
+
+    /// The [`Dop`] of the ray is below the minimum of a
+    /// [`DopFilter`](crate::filter::DopFilter).
+    pub const LOW_DOP: Self = Self(1 << 4);
+
+    /// The [`Dop`] of the ray is above the maximum of a
+    /// [`DopFilter`](crate::filter::DopFilter).
+    pub const HIGH_DOP: Self = Self(1 << 5);
+
+    /// The weight given to the ray was not finite.
+    pub const NON_FINITE: Self = Self(1 << 6);
+
+    /// Returns `true` if every flag raised in `other` is raised in `self`.
+    #[must_use]
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if no flags are raised.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for RayFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for RayFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A [`Ray`] with a weight and quality flags for estimators.
+///
+/// Filters and calibration stages can lower the weight of a ray, e.g., by a cloud probability,
+/// or raise [`RayFlags`] on it, so that information computed early in the pipeline reaches the
+/// estimator without bookkeeping arrays kept alongside the rays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WeightedRay<Frame> {
+    ray: Ray<Frame>,
+    weight: f64,
+    flags: RayFlags,
+}
+
+impl<Frame> WeightedRay<Frame> {
+    /// Creates a new `WeightedRay` with unit weight and no flags.
+    #[must_use]
+    pub fn new(ray: Ray<Frame>) -> Self {
+        Self {
+            ray,
+            weight: 1.0,
+            flags: RayFlags::NONE,
+        }
+    }
+
+    /// Sets the weight of the ray.
+    ///
+    /// Negative weights are treated as zero.
+    /// Non-finite weights, e.g., from a cloud probability computed on missing pixels, are treated
+    /// as zero and raise [`RayFlags::NON_FINITE`].
+    #[must_use]
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        if !weight.is_finite() {
+            self.flags |= RayFlags::NON_FINITE;
+        }
+        self.weight = if weight.is_finite() && weight > 0.0 {
+            weight
+        } else {
+            0.0
+        };
+        self
+    }
+
+    /// Raises `flags` in addition to the flags already raised.
+    #[must_use]
+    pub fn with_flags(mut self, flags: RayFlags) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    #[must_use]
+    pub fn ray(&self) -> Ray<Frame>
+    where
+        Frame: Copy,
+    {
+        self.ray
+    }
+
+    #[must_use]
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    #[must_use]
+    pub fn flags(&self) -> RayFlags {
+        self.flags
+    }
+
+    /// Returns `true` if the ray carries weight and no flags are raised.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.weight > 0.0 && self.flags.is_empty()
+    }
+
+    /// Returns the weight of the ray, or zero if it is not [`WeightedRay::is_valid`].
+    #[must_use]
+    pub fn effective_weight(&self) -> f64 {
+        if self.is_valid() { self.weight } else { 0.0 }
+    }
+}
+
+impl<Frame> From<Ray<Frame>> for WeightedRay<Frame> {
+    fn from(ray: Ray<Frame>) -> Self {
+        Self::new(ray)
+    }
+}
+
 impl<Frame> TryFrom<StokesVec<Frame>> for Ray<Frame> {
     type Error = RayError;
 
@@ -81,3 +326,114 @@ impl<Frame> TryFrom<StokesVec<Frame>> for Ray<Frame> {
         Ok(Self::new(stokes.aop()?, stokes.dop()?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use uom::si::angle::degree;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct VehicleFrame;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct GimbalFrame;
+
+    fn ray<Frame>(aop: f64) -> Ray<Frame> {
+        Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+            Dop::clamped(0.5),
+        )
+    }
+
+    #[test]
+    fn mean_is_axial() {
+        // Naively averaging the angles would point the mean at zero.
+        let mean = Ray::<SensorFrame>::mean(&[ray(85.0), ray(-85.0)]).unwrap();
+        assert_relative_eq!(
+            Angle::from(mean.aop()).get::<degree>().abs(),
+            90.0,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            f64::from(mean.dop()),
+            0.5 * 10f64.to_radians().cos(),
+            epsilon = 1e-9
+        );
+
+        // Perpendicular rays cancel in proportion to their degree of polarization.
+        let strong = Ray::<SensorFrame>::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(0.0)),
+            Dop::clamped(0.8),
+        );
+        let weak = Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(90.0)),
+            Dop::clamped(0.2),
+        );
+        let mean = Ray::mean(&[strong, weak]).unwrap();
+        assert_relative_eq!(Angle::from(mean.aop()).get::<degree>(), 0.0, epsilon = 1e-9);
+        assert_relative_eq!(f64::from(mean.dop()), 0.3, epsilon = 1e-9);
+
+        assert_eq!(Ray::<SensorFrame>::mean(&[]), None);
+    }
+
+    #[test]
+    fn transform_matches_global_frame() {
+        let shift = Angle::new::<degree>(25.0);
+        let transform = FrameShift::<SensorFrame, GlobalFrame>::new(shift);
+
+        assert_eq!(
+            ray::<SensorFrame>(10.0).transform(&transform),
+            ray::<SensorFrame>(10.0).into_global_frame(shift)
+        );
+    }
+
+    #[test]
+    fn custom_frames_compose() {
+        let sensor_to_gimbal =
+            FrameShift::<SensorFrame, GimbalFrame>::new(Angle::new::<degree>(30.0));
+        let gimbal_to_vehicle =
+            FrameShift::<GimbalFrame, VehicleFrame>::new(Angle::new::<degree>(-100.0));
+        let sensor_to_vehicle = sensor_to_gimbal.then(&gimbal_to_vehicle);
+
+        let result: Ray<VehicleFrame> = ray::<SensorFrame>(40.0).transform(&sensor_to_vehicle);
+        assert_relative_eq!(
+            Angle::from(result.aop()).get::<degree>(),
+            -70.0,
+            epsilon = 1e-9
+        );
+
+        let roundtrip: Ray<SensorFrame> = result.transform(&sensor_to_vehicle.inverse());
+        assert_relative_eq!(
+            Angle::from(roundtrip.aop()).get::<degree>(),
+            40.0,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn flags_invalidate_rays() {
+        let weighted = WeightedRay::new(ray::<SensorFrame>(10.0)).with_weight(0.25);
+        assert!(weighted.is_valid());
+        assert_relative_eq!(weighted.effective_weight(), 0.25);
+
+        let flagged = weighted
+            .with_flags(RayFlags::CLOUD)
+            .with_flags(RayFlags::SATURATED);
+        assert!(
+            flagged
+                .flags()
+                .contains(RayFlags::CLOUD | RayFlags::SATURATED)
+        );
+        assert!(!flagged.flags().contains(RayFlags::OCCLUDED));
+        assert!(!flagged.is_valid());
+        assert_relative_eq!(flagged.effective_weight(), 0.0);
+
+        for weight in [f64::NAN, f64::INFINITY] {
+            let unweighted = weighted.with_weight(weight);
+            assert!(unweighted.flags().contains(RayFlags::NON_FINITE));
+            assert_relative_eq!(unweighted.effective_weight(), 0.0);
+        }
+        assert!(weighted.with_weight(-1.0).flags().is_empty());
+    }
+}
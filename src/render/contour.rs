@@ -0,0 +1,394 @@
+use super::overlay::Canvas;
+use crate::{
+    image::RayImage,
+    light::{aop::Aop, dop::Dop},
+    optic::PixelCoordinate,
+    ray::Ray,
+};
+use std::{collections::HashMap, fmt::Write};
+use uom::si::{angle::degree, f64::Angle};
+
+/// The value traced by a [`Contour`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContourLevel {
+    /// An iso-AoP line in the frame of the traced image.
+    Aop(Angle),
+
+    /// An iso-DoP line.
+    Dop(f64),
+}
+
+/// A polyline along which the AoP or DoP of a [`RayImage`] is constant.
+///
+/// Points are continuous `(row, col)` positions where integers are pixel centers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contour {
+    level: ContourLevel,
+    points: Vec<(f64, f64)>,
+    closed: bool,
+}
+
+impl Contour {
+    #[must_use]
+    pub fn level(&self) -> ContourLevel {
+        self.level
+    }
+
+    #[must_use]
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// Returns `true` if the last point connects back to the first.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Draws the contour onto a row-major image of `rows` by `cols` pixels in `color`.
+    ///
+    /// The number of channels per pixel is the length of `color`.
+    ///
+    /// # Panics
+    /// Will panic if the length of `bytes` does not match `rows` by `cols` pixels.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn draw(&self, bytes: &mut [u8], rows: usize, cols: usize, color: &[u8]) {
+        assert_eq!(
+            bytes.len(),
+            rows * cols * color.len(),
+            "image bytes do not match extents"
+        );
+
+        let mut canvas = Canvas {
+            bytes,
+            rows,
+            cols,
+            color,
+        };
+        let pixel = |(row, col): (f64, f64)| {
+            PixelCoordinate::new(row.round() as usize, col.round() as usize)
+        };
+        let closing = self
+            .closed
+            .then(|| [self.points[self.points.len() - 1], self.points[0]]);
+        for segment in self
+            .points
+            .windows(2)
+            .chain(closing.as_ref().map(<[_; 2]>::as_slice))
+        {
+            canvas.line(pixel(segment[0]), pixel(segment[1]));
+        }
+    }
+}
+
+/// Writes `contours` as a GeoJSON `FeatureCollection` of `LineString`s.
+///
+/// Coordinates are `[col, row]` so that `x` points right and `y` points down the image.
+/// Each feature carries the kind of contour and its level, in degrees for the AoP, as
+/// properties.
+#[must_use]
+pub fn to_geojson(contours: &[Contour]) -> String {
+    let mut json = String::from(r#"{"type":"FeatureCollection","features":["#);
+    for (i, contour) in contours.iter().enumerate() {
+        let (kind, level) = match contour.level {
+            ContourLevel::Aop(angle) => ("aop", angle.get::<degree>()),
+            ContourLevel::Dop(dop) => ("dop", dop),
+        };
+        let mut points: Vec<_> = contour.points.clone();
+        if contour.closed {
+            points.push(points[0]);
+        }
+
+        if i > 0 {
+            json.push(',');
+        }
+        let _ = write!(
+            json,
+            r#"{{"type":"Feature","properties":{{"kind":"{kind}","level":{level}}},"geometry":{{"type":"LineString","coordinates":["#
+        );
+        for (j, (row, col)) in points.iter().enumerate() {
+            if j > 0 {
+                json.push(',');
+            }
+            let _ = write!(json, "[{col},{row}]");
+        }
+        json.push_str("]}}");
+    }
+    json.push_str("]}");
+    json
+}
+
+/// Extracts iso-AoP and iso-DoP contours from a [`RayImage`] with marching squares.
+///
+/// Pixels without a ray or with a [`crate::light::dop::Dop`] below the minimum are invalid and no
+/// contour passes through a cell that touches one.
+/// The AoP is axial, so iso-AoP lines are traced on the zero crossings of `sin(2 (aop - level))`,
+/// which is continuous across the +/- 90 degree boundary, and crossings of the perpendicular
+/// level are discarded.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ContourExtractor {
+    min_dop: f64,
+}
+
+impl ContourExtractor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the smallest degree of polarization of a valid pixel.
+    #[must_use]
+    pub fn with_dop_threshold(mut self, min_dop: Dop) -> Self {
+        self.min_dop = min_dop.into();
+        self
+    }
+
+    /// Sets the smallest degree of polarization of a valid pixel.
+    #[must_use]
+    #[deprecated(note = "use `ContourExtractor::with_dop_threshold` with a `Dop`")]
+    pub fn with_min_dop(self, min_dop: f64) -> Self {
+        self.with_dop_threshold(Dop::clamped(min_dop))
+    }
+
+    /// Traces the lines of `image` along which the AoP is `level`.
+    #[must_use]
+    pub fn aop<Frame: Copy>(&self, image: &RayImage<Frame>, level: Aop<Frame>) -> Vec<Contour> {
+        let level = Angle::from(level);
+        self.trace(image, ContourLevel::Aop(level), |ray| {
+            let difference = 2.0 * (Angle::from(ray.aop()) - level);
+            let (sin, cos) = (difference.sin().value, difference.cos().value);
+            // The perpendicular level is also a zero crossing, so only keep the near branch.
+            (cos > 0.0).then_some(sin)
+        })
+    }
+
+    /// Traces the lines of `image` along which the DoP is `level`.
+    #[must_use]
+    pub fn iso_dop<Frame: Copy>(&self, image: &RayImage<Frame>, level: Dop) -> Vec<Contour> {
+        let level = f64::from(level);
+        self.trace(image, ContourLevel::Dop(level), |ray| {
+            Some(f64::from(ray.dop()) - level)
+        })
+    }
+
+    /// Traces the lines of `image` along which the DoP is `level`.
+    #[must_use]
+    #[deprecated(note = "use `ContourExtractor::iso_dop` with a `Dop`")]
+    pub fn dop<Frame: Copy>(&self, image: &RayImage<Frame>, level: f64) -> Vec<Contour> {
+        self.iso_dop(image, Dop::clamped(level))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn trace<Frame: Copy>(
+        &self,
+        image: &RayImage<Frame>,
+        level: ContourLevel,
+        field: impl Fn(&Ray<Frame>) -> Option<f64>,
+    ) -> Vec<Contour> {
+        let (rows, cols) = (image.rows(), image.cols());
+        let values: Vec<Option<f64>> = (0..rows * cols)
+            .map(|index| {
+                let ray = image.ray(index / cols, index % cols)?;
+                if f64::from(ray.dop()) < self.min_dop {
+                    return None;
+                }
+                field(ray)
+            })
+            .collect();
+        let value = |row: usize, col: usize| values[row * cols + col];
+
+        let mut points: HashMap<Edge, (f64, f64)> = HashMap::new();
+        let mut segments = Vec::new();
+        for row in 0..rows.saturating_sub(1) {
+            for col in 0..cols.saturating_sub(1) {
+                let corners = [
+                    (row, col),
+                    (row, col + 1),
+                    (row + 1, col + 1),
+                    (row + 1, col),
+                ];
+                let Some(cell) = corners
+                    .iter()
+                    .map(|(r, c)| value(*r, *c))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    continue;
+                };
+
+                // Edges run clockwise from the top and join corners `i` and `i + 1`.
+                let edges = [
+                    Edge::Horizontal(row, col),
+                    Edge::Vertical(row, col + 1),
+                    Edge::Horizontal(row + 1, col),
+                    Edge::Vertical(row, col),
+                ];
+                let crossings: Vec<usize> = (0..4)
+                    .filter(|i| (cell[*i] > 0.0) != (cell[(i + 1) % 4] > 0.0))
+                    .collect();
+                for i in &crossings {
+                    let (a, b) = (*i, (i + 1) % 4);
+                    let t = cell[a] / (cell[a] - cell[b]);
+                    let (from, to) = (corners[a], corners[b]);
+                    points.entry(edges[*i]).or_insert((
+                        from.0 as f64 + t * (to.0 as f64 - from.0 as f64),
+                        from.1 as f64 + t * (to.1 as f64 - from.1 as f64),
+                    ));
+                }
+
+                match crossings.as_slice() {
+                    [a, b] => segments.push((edges[*a], edges[*b])),
+                    [_, _, _, _] => {
+                        // A saddle joins the corners that share the sign of the center.
+                        let center = cell.iter().sum::<f64>() / 4.0;
+                        if (center > 0.0) == (cell[0] > 0.0) {
+                            segments.push((edges[0], edges[1]));
+                            segments.push((edges[2], edges[3]));
+                        } else {
+                            segments.push((edges[3], edges[0]));
+                            segments.push((edges[1], edges[2]));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        chain(&segments)
+            .into_iter()
+            .map(|(edges, closed)| Contour {
+                level,
+                points: edges.iter().map(|edge| points[edge]).collect(),
+                closed,
+            })
+            .collect()
+    }
+}
+
+/// An edge between two adjacent pixel centers identified by its top or left end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Edge {
+    Horizontal(usize, usize),
+    Vertical(usize, usize),
+}
+
+/// Joins segments that share an edge into polylines.
+fn chain(segments: &[(Edge, Edge)]) -> Vec<(Vec<Edge>, bool)> {
+    let mut incident: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        incident.entry(*a).or_default().push(i);
+        incident.entry(*b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let next = |edge: Edge, used: &mut [bool]| {
+        let i = *incident[&edge].iter().find(|i| !used[**i])?;
+        used[i] = true;
+        let (a, b) = segments[i];
+        Some(if a == edge { b } else { a })
+    };
+
+    let mut lines = Vec::new();
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+
+        let (first, second) = segments[start];
+        let mut forward = vec![first, second];
+        while let Some(edge) = next(forward[forward.len() - 1], &mut used) {
+            forward.push(edge);
+        }
+
+        let closed = forward.len() > 2 && forward[0] == forward[forward.len() - 1];
+        if closed {
+            forward.pop();
+        } else {
+            let mut backward = Vec::new();
+            while let Some(edge) = next(*backward.last().unwrap_or(&first), &mut used) {
+                backward.push(edge);
+            }
+            backward.reverse();
+            backward.extend(forward);
+            forward = backward;
+        }
+
+        lines.push((forward, closed));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::SensorFrame;
+    use approx::assert_relative_eq;
+
+    /// An image whose DoP falls off with the distance from its center and whose AoP follows
+    /// the column from -90 to 90 degrees.
+    fn image() -> RayImage<SensorFrame> {
+        let size = 21;
+        let rays = (0..size * size).map(|index| {
+            let (row, col) = (f64::from(index / size), f64::from(index % size));
+            let radius = (row - 10.0).hypot(col - 10.0);
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(col * 9.0 - 90.0)),
+                Dop::clamped(1.0 - radius / 20.0),
+            ))
+        });
+        RayImage::from_rays(rays, 21, 21).unwrap()
+    }
+
+    #[test]
+    fn dop_contour_is_a_closed_circle() {
+        let contours = ContourExtractor::new().iso_dop(&image(), Dop::clamped(0.75));
+
+        assert_eq!(contours.len(), 1);
+        assert!(contours[0].is_closed());
+        for (row, col) in contours[0].points() {
+            assert_relative_eq!((row - 10.0).hypot(col - 10.0), 5.0, epsilon = 0.1);
+        }
+    }
+
+    #[test]
+    fn aop_contour_ignores_perpendicular_branch() {
+        let level = Aop::from_angle_wrapped(Angle::new::<degree>(4.5));
+        let contours = ContourExtractor::new().aop(&image(), level);
+
+        // The perpendicular level at -85.5 degrees is not traced.
+        assert_eq!(contours.len(), 1);
+        assert!(!contours[0].is_closed());
+        assert_eq!(contours[0].points().len(), 21);
+        for (_, col) in contours[0].points() {
+            assert_relative_eq!(*col, 10.5, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn masks_low_dop() {
+        let level = Aop::from_angle_wrapped(Angle::new::<degree>(4.5));
+        let contours = ContourExtractor::new()
+            .with_dop_threshold(Dop::clamped(0.6))
+            .aop(&image(), level);
+
+        assert!(!contours.is_empty());
+        for (row, col) in contours.iter().flat_map(Contour::points) {
+            assert!((row - 10.0).hypot(col - 10.0) < 8.5);
+        }
+    }
+
+    #[test]
+    fn exports_geojson() {
+        let contours = ContourExtractor::new().iso_dop(&image(), Dop::clamped(0.75));
+        let json = to_geojson(&contours);
+
+        assert!(json.starts_with(r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":{"kind":"dop","level":0.75}"#));
+        assert!(json.ends_with("]}}]}"));
+
+        let mut bytes = vec![0u8; 21 * 21];
+        contours[0].draw(&mut bytes, 21, 21, &[255]);
+        assert_eq!(bytes[10 * 21 + 15], 255);
+        assert_eq!(bytes[10 * 21 + 10], 0);
+    }
+}
@@ -0,0 +1,54 @@
+//! A minimal 3x5 bitmap font for labelling rendered images.
+
+/// The width of a glyph in font pixels.
+pub(crate) const GLYPH_WIDTH: usize = 3;
+
+/// The height of a glyph in font pixels.
+pub(crate) const GLYPH_HEIGHT: usize = 5;
+
+/// Returns the rows of the glyph for `c`, most significant bit on the left.
+///
+/// Characters without a glyph are rendered as blank space.
+pub(crate) fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        'a' => [0b000, 0b011, 0b101, 0b101, 0b011],
+        'd' => [0b001, 0b001, 0b111, 0b101, 0b111],
+        'e' => [0b000, 0b111, 0b111, 0b100, 0b111],
+        'g' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        'o' => [0b000, 0b111, 0b101, 0b101, 0b111],
+        'r' => [0b000, 0b111, 0b100, 0b100, 0b100],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'P' => [0b111, 0b101, 0b111, 0b100, 0b100],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+/// Calls `plot` with the position of every set font pixel in `text`, each scaled by `scale`.
+pub(crate) fn rasterize(text: &str, scale: usize, mut plot: impl FnMut(usize, usize)) {
+    for (i, c) in text.chars().enumerate() {
+        let left = i * (GLYPH_WIDTH + 1);
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            plot(row * scale + dy, (left + col) * scale + dx);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use super::font;
+use crate::image::RayMap;
+
+/// Describes a color bar with ticks and labels composited to the right of a rendered image.
+///
+/// The legend is drawn in black on a white background, so it is intended for color maps that
+/// produce one byte per channel such as [`crate::image::Jet`] and [`crate::image::Gray`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Legend {
+    ticks: usize,
+    scale: usize,
+}
+
+impl Legend {
+    /// Creates a new `Legend` with five ticks and labels drawn at twice the font size.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { ticks: 5, scale: 2 }
+    }
+
+    /// Draw `ticks` evenly spaced ticks including both ends of the color bar.
+    #[must_use]
+    pub fn with_ticks(mut self, ticks: usize) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    /// Scale the bar and labels by `scale` pixels per font pixel.
+    ///
+    /// # Panics
+    /// Will panic if `scale` is zero.
+    #[must_use]
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        assert!(scale > 0, "legend scale must be greater than zero");
+        self.scale = scale;
+        self
+    }
+
+    /// The number of columns the legend adds to the right of an image.
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        26 * self.scale
+    }
+
+    /// Composites a legend for values on `min` to `max` in `unit` to the right of `bytes`.
+    ///
+    /// `bytes` is a row-major image of `rows` by `cols` pixels rendered with `color_map`.
+    /// The result has `rows` by `cols + self.cols()` pixels, so an image without columns yields the
+    /// legend alone.
+    ///
+    /// # Panics
+    /// Will panic if the length of `bytes` does not match `rows` by `cols` pixels.
+    #[must_use]
+    pub fn compose<M>(
+        &self,
+        bytes: &[u8],
+        rows: usize,
+        cols: usize,
+        color_map: &M,
+        (min, max): (f64, f64),
+        unit: &str,
+    ) -> Vec<u8>
+    where
+        M: RayMap,
+        M::Output: AsRef<[u8]>,
+    {
+        let channels = color_map.map(min, min, max).as_ref().len();
+        assert_eq!(
+            bytes.len(),
+            rows * cols * channels,
+            "image bytes do not match extents"
+        );
+
+        let panel = self.panel(rows, channels, color_map, (min, max), unit);
+        if cols == 0 {
+            return panel;
+        }
+
+        let panel_cols = self.cols();
+        bytes
+            .chunks_exact(cols * channels)
+            .zip(panel.chunks_exact(panel_cols * channels))
+            .flat_map(|(image_row, panel_row)| image_row.iter().chain(panel_row).copied())
+            .collect()
+    }
+
+    /// The first row of the color bar, leaving room for the unit and the top tick label.
+    fn bar_top(&self) -> usize {
+        (font::GLYPH_HEIGHT + 2 + font::GLYPH_HEIGHT.div_ceil(2)) * self.scale
+    }
+
+    /// Renders the legend panel as a row-major image of `rows` by `self.cols()` pixels.
+    fn panel<M>(
+        &self,
+        rows: usize,
+        channels: usize,
+        color_map: &M,
+        (min, max): (f64, f64),
+        unit: &str,
+    ) -> Vec<u8>
+    where
+        M: RayMap,
+        M::Output: AsRef<[u8]>,
+    {
+        let scale = self.scale;
+        let cols = self.cols();
+        let mut panel = vec![u8::MAX; rows * cols * channels];
+        let mut ink = |row: usize, col: usize| {
+            if row < rows && col < cols {
+                let start = (row * cols + col) * channels;
+                panel[start..start + channels].fill(0);
+            }
+        };
+
+        let bar_left = 2 * scale;
+        let bar_right = bar_left + 4 * scale;
+        let top = self.bar_top();
+        let bottom = rows.saturating_sub(3 * scale);
+        if bottom <= top + 1 {
+            return panel;
+        }
+
+        font::rasterize(unit, scale, |row, col| ink(scale + row, bar_left + col));
+
+        // Ticks and labels are drawn first so the bar itself stays clean.
+        let span = (bottom - 1 - top) as f64;
+        let label_left = bar_right + 3 * scale;
+        for tick in 0..self.ticks {
+            let t = if self.ticks > 1 {
+                tick as f64 / (self.ticks - 1) as f64
+            } else {
+                0.0
+            };
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let row = top + (t * span).round() as usize;
+            for col in bar_right..bar_right + 2 * scale {
+                for dy in 0..scale.div_ceil(2) {
+                    ink(row + dy, col);
+                }
+            }
+
+            let label = label(max - t * (max - min), max - min);
+            let label_top = row.saturating_sub(font::GLYPH_HEIGHT * scale / 2);
+            font::rasterize(&label, scale, |r, c| ink(label_top + r, label_left + c));
+        }
+
+        for row in top..bottom {
+            let value = max - (row - top) as f64 / span * (max - min);
+            let color = color_map.map(value, min, max);
+            for col in bar_left..bar_right {
+                let start = (row * cols + col) * channels;
+                panel[start..start + channels].copy_from_slice(color.as_ref());
+            }
+        }
+
+        panel
+    }
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats a tick label with precision suited to the `range` of the color bar.
+fn label(value: f64, range: f64) -> String {
+    let label = if range.abs() >= 10.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.1}")
+    };
+
+    // Avoid labels like "-0".
+    if label
+        .trim_start_matches('-')
+        .chars()
+        .all(|c| c == '0' || c == '.')
+    {
+        label.trim_start_matches('-').to_string()
+    } else {
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::{Gray, Jet};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(-90.0, 180.0, "-90")]
+    #[case(-0.2, 180.0, "0")]
+    #[case(0.25, 1.0, "0.2")]
+    #[case(-0.01, 1.0, "0.0")]
+    fn tick_labels(#[case] value: f64, #[case] range: f64, #[case] expected: &str) {
+        assert_eq!(label(value, range), expected);
+    }
+
+    #[test]
+    fn compose_keeps_image() {
+        let (rows, cols) = (64, 10);
+        let bytes: Vec<u8> = (0..rows * cols * 3).map(|i| (i % 251) as u8).collect();
+        let legend = Legend::new();
+        let result = legend.compose(&bytes, rows, cols, &Jet, (-90.0, 90.0), "deg");
+
+        let stride = (cols + legend.cols()) * 3;
+        assert_eq!(result.len(), rows * stride);
+        for (row, image_row) in bytes.chunks_exact(cols * 3).enumerate() {
+            assert_eq!(&result[row * stride..][..cols * 3], image_row);
+        }
+    }
+
+    #[test]
+    fn compose_empty_image() {
+        let legend = Legend::new();
+        let result = legend.compose(&[], 64, 0, &Jet, (-90.0, 90.0), "deg");
+        assert_eq!(result, legend.panel(64, 3, &Jet, (-90.0, 90.0), "deg"));
+    }
+
+    #[test]
+    fn bar_spans_color_map() {
+        let legend = Legend::new().with_scale(1);
+        let rows = 100;
+        let panel = legend.panel(rows, 1, &Gray, (0.0, 1.0), "DoP");
+        let bar_col = 3;
+
+        // The top of the bar is the maximum value and the bottom is the minimum.
+        let top = legend.bar_top();
+        let bottom = rows - 3;
+        assert_eq!(panel[top * legend.cols() + bar_col], 255);
+        assert_eq!(panel[(bottom - 1) * legend.cols() + bar_col], 0);
+    }
+}
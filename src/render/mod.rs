@@ -0,0 +1,6 @@
+//! Utilities for annotating rendered images.
+
+pub mod contour;
+mod font;
+pub mod legend;
+pub mod overlay;
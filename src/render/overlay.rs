@@ -0,0 +1,202 @@
+use crate::optic::PixelCoordinate;
+
+/// The pixel locations of features of the sky used to validate an orientation visually.
+///
+/// See [`crate::simulation::Simulation::sky_features`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkyFeatures {
+    pub(crate) sun: Option<PixelCoordinate>,
+    pub(crate) zenith: Option<PixelCoordinate>,
+    pub(crate) solar_meridian: Vec<Option<PixelCoordinate>>,
+    pub(crate) anti_solar_meridian: Vec<Option<PixelCoordinate>>,
+}
+
+impl SkyFeatures {
+    /// The pixel at the center of the solar disk if it is on the sensor.
+    #[must_use]
+    pub fn sun(&self) -> Option<PixelCoordinate> {
+        self.sun
+    }
+
+    /// The pixel looking towards the zenith if it is on the sensor.
+    #[must_use]
+    pub fn zenith(&self) -> Option<PixelCoordinate> {
+        self.zenith
+    }
+
+    /// Samples along the solar meridian from the horizon to the zenith.
+    ///
+    /// Samples that are not on the sensor are `None`.
+    #[must_use]
+    pub fn solar_meridian(&self) -> &[Option<PixelCoordinate>] {
+        &self.solar_meridian
+    }
+
+    /// Samples along the anti-solar meridian from the horizon to the zenith.
+    ///
+    /// Samples that are not on the sensor are `None`.
+    #[must_use]
+    pub fn anti_solar_meridian(&self) -> &[Option<PixelCoordinate>] {
+        &self.anti_solar_meridian
+    }
+
+    /// Draws the features onto a row-major image of `rows` by `cols` pixels in `color`.
+    ///
+    /// The sun is drawn as a circle, the zenith as a cross, the solar meridian as a solid line,
+    /// and the anti-solar meridian as a dashed line.
+    /// The number of channels per pixel is the length of `color`.
+    ///
+    /// # Panics
+    /// Will panic if the length of `bytes` does not match `rows` by `cols` pixels.
+    pub fn draw(&self, bytes: &mut [u8], rows: usize, cols: usize, color: &[u8]) {
+        assert_eq!(
+            bytes.len(),
+            rows * cols * color.len(),
+            "image bytes do not match extents"
+        );
+
+        let mut canvas = Canvas {
+            bytes,
+            rows,
+            cols,
+            color,
+        };
+        let radius = (rows.min(cols) / 50).max(3);
+
+        for segment in self.solar_meridian.windows(2) {
+            if let [Some(from), Some(to)] = segment {
+                canvas.line(*from, *to);
+            }
+        }
+
+        for (i, segment) in self.anti_solar_meridian.windows(2).enumerate() {
+            if let ([Some(from), Some(to)], true) = (segment, i % 2 == 0) {
+                canvas.line(*from, *to);
+            }
+        }
+
+        if let Some(sun) = self.sun {
+            canvas.circle(sun, radius);
+        }
+
+        if let Some(zenith) = self.zenith {
+            canvas.cross(zenith, radius);
+        }
+    }
+}
+
+pub(super) struct Canvas<'a> {
+    pub(super) bytes: &'a mut [u8],
+    pub(super) rows: usize,
+    pub(super) cols: usize,
+    pub(super) color: &'a [u8],
+}
+
+impl Canvas<'_> {
+    fn plot(&mut self, row: isize, col: isize) {
+        let (Ok(row), Ok(col)) = (usize::try_from(row), usize::try_from(col)) else {
+            return;
+        };
+
+        if row < self.rows && col < self.cols {
+            let start = (row * self.cols + col) * self.color.len();
+            self.bytes[start..start + self.color.len()].copy_from_slice(self.color);
+        }
+    }
+
+    /// Draws a line with Bresenham's algorithm.
+    #[allow(clippy::cast_possible_wrap)]
+    pub(super) fn line(&mut self, from: PixelCoordinate, to: PixelCoordinate) {
+        let (mut row, mut col) = (from.row() as isize, from.col() as isize);
+        let (to_row, to_col) = (to.row() as isize, to.col() as isize);
+        let d_col = (to_col - col).abs();
+        let d_row = -(to_row - row).abs();
+        let step_col = if col < to_col { 1 } else { -1 };
+        let step_row = if row < to_row { 1 } else { -1 };
+        let mut error = d_col + d_row;
+
+        loop {
+            self.plot(row, col);
+            if row == to_row && col == to_col {
+                break;
+            }
+
+            let doubled = 2 * error;
+            if doubled >= d_row {
+                error += d_row;
+                col += step_col;
+            }
+            if doubled <= d_col {
+                error += d_col;
+                row += step_row;
+            }
+        }
+    }
+
+    /// Draws a circle outline with the midpoint algorithm.
+    #[allow(clippy::cast_possible_wrap)]
+    fn circle(&mut self, center: PixelCoordinate, radius: usize) {
+        let (row, col) = (center.row() as isize, center.col() as isize);
+        let (mut x, mut y) = (radius as isize, 0);
+        let mut error = 1 - x;
+
+        while x >= y {
+            for (dr, dc) in [(y, x), (x, y), (x, -y), (y, -x)] {
+                self.plot(row + dr, col + dc);
+                self.plot(row - dr, col - dc);
+            }
+
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn cross(&mut self, center: PixelCoordinate, radius: usize) {
+        let (row, col) = (center.row() as isize, center.col() as isize);
+        let radius = radius as isize;
+
+        for offset in -radius..=radius {
+            self.plot(row + offset, col);
+            self.plot(row, col + offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_features() {
+        let (rows, cols) = (100, 120);
+        let features = SkyFeatures {
+            sun: Some(PixelCoordinate::new(20, 30)),
+            zenith: Some(PixelCoordinate::new(50, 60)),
+            solar_meridian: vec![
+                Some(PixelCoordinate::new(90, 60)),
+                Some(PixelCoordinate::new(50, 60)),
+            ],
+            anti_solar_meridian: vec![None, Some(PixelCoordinate::new(10, 60))],
+        };
+
+        let mut bytes = vec![0u8; rows * cols];
+        features.draw(&mut bytes, rows, cols, &[255]);
+        let at = |row: usize, col: usize| bytes[row * cols + col];
+
+        // Zenith cross and solar meridian.
+        assert_eq!(at(50, 60), 255);
+        assert_eq!(at(50, 63), 255);
+        assert_eq!(at(70, 60), 255);
+        // Sun circle outline but not its center.
+        assert_eq!(at(20, 33), 255);
+        assert_eq!(at(20, 30), 0);
+        // The anti-solar meridian has no segment on the sensor.
+        assert_eq!(at(20, 60), 0);
+    }
+}
@@ -0,0 +1,81 @@
+//! Deterministic pseudo-random numbers and hashes for reproducible sampling.
+
+/// Mixes `z` with the SplitMix64 finalizer.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Hashes a seed and a lattice point.
+#[cfg(feature = "simulation")]
+pub(crate) fn hash(seed: u64, x: u64, y: u64) -> u64 {
+    mix(mix(mix(seed) ^ x) ^ y)
+}
+
+/// Maps a hash onto [0, 1).
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn unit(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A SplitMix64 generator.
+#[derive(Clone, Debug)]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+
+    /// Returns a number on [0, 1).
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        unit(self.next_u64())
+    }
+
+    /// Returns an index on [0, `len`).
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn next_index(&mut self, len: usize) -> usize {
+        ((u128::from(self.next_u64()) * len as u128) >> 64) as usize
+    }
+
+    /// Returns a standard normal number with the Box-Muller transform.
+    #[cfg(feature = "simulation")]
+    pub(crate) fn next_normal(&mut self) -> f64 {
+        // Keep the radius finite by drawing from (0, 1].
+        let radius = (-2.0 * (1.0 - self.next_f64()).ln()).sqrt();
+        radius * (std::f64::consts::TAU * self.next_f64()).cos()
+    }
+}
+
+/// The 64 bit Fowler-Noll-Vo hash.
+///
+/// Unlike [`std::hash::DefaultHasher`], its output is stable across releases so hashes can be
+/// stored.
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,341 @@
+//! Frames from division of time polarimeters that capture the polarizer states in sequence.
+
+use crate::{
+    calibration::{CalibrationError, PolarizerCalibration, PolarizerChannel},
+    image::{ImageError, IntensityImage, RayImage},
+    meta::FrameMeta,
+    ray::SensorFrame,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use thiserror::Error;
+use uom::si::{
+    angle::radian,
+    angular_velocity::radian_per_second,
+    f64::{Angle, AngularVelocity},
+};
+
+#[derive(Debug, Error)]
+pub enum SequentialError {
+    #[error(transparent)]
+    Image(#[from] ImageError),
+
+    #[error(transparent)]
+    Calibration(#[from] CalibrationError),
+}
+
+/// A frame from a division of time polarimeter.
+///
+/// Such a polarimeter places each polarizer in front of the whole sensor in turn, e.g., with a
+/// filter wheel or a liquid crystal modulator, so every pixel measures all four states but at
+/// different times.
+/// Planes are row-major and given in 0, 45, 90, 135 order with the time each was captured.
+/// [`SequentialFrame::intensity_image`] combines them into an [`IntensityImage`] whose
+/// metapixels are the pixels of the sensor, so the rest of the pipeline is unchanged.
+///
+/// A camera that rolls between states smears the pattern of polarization.
+/// Given the rate of roll about the optical axis, each plane is resampled to the mean time of
+/// the frame about the center of the sensor and the orientation of its polarizer is turned by
+/// the same angle.
+/// The rate is measured counterclockwise in the [`SensorFrame`], i.e., from the right of the
+/// image towards the top.
+/// Rotations about the other axes shift the image and are not compensated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequentialFrame {
+    planes: [Vec<f64>; 4],
+    times: [DateTime<Utc>; 4],
+    width: usize,
+    height: usize,
+    calibration: PolarizerCalibration,
+    angular_rate: Option<AngularVelocity>,
+}
+
+impl SequentialFrame {
+    /// Creates a new `SequentialFrame` from four planes of `width` by `height` intensities and
+    /// the times they were captured.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of any plane does not match `width` by `height`.
+    pub fn new(
+        width: usize,
+        height: usize,
+        planes: [Vec<f64>; 4],
+        times: [DateTime<Utc>; 4],
+    ) -> Result<Self, SequentialError> {
+        if let Some(len) = planes
+            .iter()
+            .map(Vec::len)
+            .find(|len| *len != width * height)
+        {
+            return Err(ImageError::SizeMismatch {
+                rows: height,
+                cols: width,
+                len,
+            }
+            .into());
+        }
+
+        Ok(Self {
+            planes,
+            times,
+            width,
+            height,
+            calibration: PolarizerCalibration::ideal(),
+            angular_rate: None,
+        })
+    }
+
+    /// Use `calibration` to describe the polarizer of each state.
+    ///
+    /// By default, a [`SequentialFrame`] assumes ideal polarizers.
+    #[must_use]
+    pub fn with_calibration(mut self, calibration: PolarizerCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Compensates for a camera that rolls about its optical axis at `angular_rate`, e.g., as
+    /// measured by a gyroscope.
+    #[must_use]
+    pub fn with_angular_rate(mut self, angular_rate: AngularVelocity) -> Self {
+        self.angular_rate = Some(angular_rate);
+        self
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn times(&self) -> &[DateTime<Utc>; 4] {
+        &self.times
+    }
+
+    #[must_use]
+    pub fn calibration(&self) -> &PolarizerCalibration {
+        &self.calibration
+    }
+
+    #[must_use]
+    pub fn angular_rate(&self) -> Option<AngularVelocity> {
+        self.angular_rate
+    }
+
+    /// Returns the mean of the times the states were captured.
+    ///
+    /// Motion is compensated to this time.
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        let first = self.times[0];
+        let offset = self
+            .times
+            .iter()
+            .map(|time| *time - first)
+            .sum::<TimeDelta>()
+            / 4;
+        first + offset
+    }
+
+    /// Returns the roll of the camera at the capture of each state relative to the mean time.
+    #[allow(clippy::cast_precision_loss)]
+    fn rolls(&self) -> [Angle; 4] {
+        let rate = self
+            .angular_rate
+            .map_or(0.0, |rate| rate.get::<radian_per_second>());
+        let reference = self.time();
+        self.times.map(|time| {
+            let elapsed = (time - reference).num_microseconds().unwrap_or(0) as f64 / 1e6;
+            Angle::new::<radian>(rate * elapsed)
+        })
+    }
+
+    /// Combines the planes into an [`IntensityImage`] at [`SequentialFrame::time`].
+    ///
+    /// Pixels that roll off of the sensor in any plane have non-finite intensities and yield no
+    /// ray.
+    ///
+    /// # Errors
+    /// Will return `Err` if the polarizers turned by the roll of the camera do not constrain the
+    /// linear Stokes parameters.
+    pub fn intensity_image(&self) -> Result<IntensityImage, SequentialError> {
+        let rolls = self.rolls();
+        let channels = std::array::from_fn(|k| {
+            let channel = self.calibration.channels()[k];
+            PolarizerChannel::new(
+                channel.gain(),
+                channel.efficiency(),
+                channel.orientation() + rolls[k],
+            )
+        });
+        let calibration = if rolls.iter().all(|roll| roll.get::<radian>() == 0.0) {
+            self.calibration
+        } else {
+            PolarizerCalibration::try_new(channels)?
+        };
+
+        let planes: [Vec<f64>; 4] =
+            std::array::from_fn(|k| self.resample(&self.planes[k], rolls[k]));
+        let intensities =
+            (0..self.width * self.height).map(|index| planes.each_ref().map(|plane| plane[index]));
+
+        Ok(
+            IntensityImage::from_intensities(self.width, self.height, intensities)?
+                .with_calibration(calibration)
+                .with_meta(FrameMeta::new().with_time(self.time())),
+        )
+    }
+
+    /// Returns the rays of [`SequentialFrame::intensity_image`].
+    ///
+    /// # Errors
+    /// Will return `Err` if the polarizers turned by the roll of the camera do not constrain the
+    /// linear Stokes parameters.
+    pub fn ray_image(&self) -> Result<RayImage<SensorFrame>, SequentialError> {
+        Ok(self.intensity_image()?.stokes_image().ray_image())
+    }
+
+    /// Bilinearly samples `plane` where each pixel was seen by a sensor turned by `roll`.
+    #[allow(clippy::cast_precision_loss)]
+    fn resample(&self, plane: &[f64], roll: Angle) -> Vec<f64> {
+        if roll.get::<radian>() == 0.0 {
+            return plane.to_vec();
+        }
+
+        let (width, height) = (self.width, self.height);
+        let (center_x, center_y) = ((width as f64 - 1.0) / 2.0, (height as f64 - 1.0) / 2.0);
+        let (sin, cos) = roll.get::<radian>().sin_cos();
+        let at = |row: usize, col: usize| plane[col + row * width];
+
+        (0..width * height)
+            .map(|index| {
+                // Image coordinates with y up so that the roll is counterclockwise.
+                let x = (index % width) as f64 - center_x;
+                let y = center_y - (index / width) as f64;
+
+                // The scene turns against the sensor.
+                let (col, row) = (
+                    center_x + cos * x + sin * y,
+                    center_y - (-sin * x + cos * y),
+                );
+                if !(0.0..=(width - 1) as f64).contains(&col)
+                    || !(0.0..=(height - 1) as f64).contains(&row)
+                {
+                    return f64::NAN;
+                }
+
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let (col0, row0) = (col.floor() as usize, row.floor() as usize);
+                let (col1, row1) = ((col0 + 1).min(width - 1), (row0 + 1).min(height - 1));
+                let (dx, dy) = (col - col0 as f64, row - row0 as f64);
+                let top = at(row0, col0) * (1.0 - dx) + at(row0, col1) * dx;
+                let bottom = at(row1, col0) * (1.0 - dx) + at(row1, col1) * dx;
+                top * (1.0 - dy) + bottom * dy
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use chrono::TimeZone;
+    use uom::si::{angle::degree, angular_velocity::degree_per_second};
+
+    fn times() -> [DateTime<Utc>; 4] {
+        let start = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        [0, 1, 2, 3].map(|k| start + TimeDelta::milliseconds(100 * k))
+    }
+
+    /// Captures a uniform sky with `aop` in the [`SensorFrame`] at the mean time while the camera
+    /// rolls at `rate` degrees per second.
+    fn frame(aop: f64, rate: f64) -> SequentialFrame {
+        let (width, height) = (9, 7);
+        let reference =
+            Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap() + TimeDelta::milliseconds(150);
+        let planes = std::array::from_fn(|k| {
+            #[allow(clippy::cast_precision_loss)]
+            let elapsed = (times()[k] - reference).num_milliseconds() as f64 / 1e3;
+            // The sky turns against the sensor.
+            let aop = (aop - rate * elapsed).to_radians();
+            let polarizer = (45.0 * k as f64).to_radians();
+            vec![50.0 * (1.0 + 0.5 * (2.0 * (polarizer - aop)).cos()); width * height]
+        });
+        SequentialFrame::new(width, height, planes, times()).unwrap()
+    }
+
+    #[test]
+    fn combines_static_planes() {
+        let frame = frame(30.0, 0.0);
+        assert_eq!(frame.time(), times()[0] + TimeDelta::milliseconds(150));
+
+        let image = frame.intensity_image().unwrap();
+        assert_eq!((image.width(), image.height()), (9, 7));
+        assert_eq!(image.meta().time(), Some(frame.time()));
+        let ray = *frame.ray_image().unwrap().ray(3, 4).unwrap();
+        assert_relative_eq!(Angle::from(ray.aop()).get::<degree>(), 30.0, epsilon = 1e-9);
+        assert_relative_eq!(f64::from(ray.dop()), 0.5, epsilon = 1e-9);
+
+        assert!(matches!(
+            SequentialFrame::new(9, 6, frame.planes.clone(), times()),
+            Err(SequentialError::Image(ImageError::SizeMismatch {
+                len: 63,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn compensates_roll() {
+        // The camera turns 30 degrees over the frame.
+        let frame = frame(30.0, 100.0);
+        let aop = |frame: &SequentialFrame| {
+            let ray = *frame.ray_image().unwrap().ray(3, 4).unwrap();
+            Angle::from(ray.aop()).get::<degree>()
+        };
+        assert!((aop(&frame) - 30.0).abs() > 1.0);
+
+        let frame = frame.with_angular_rate(AngularVelocity::new::<degree_per_second>(100.0));
+        assert_relative_eq!(aop(&frame), 30.0, epsilon = 1e-9);
+
+        // Corners roll off of the sensor.
+        let rays = frame.ray_image().unwrap();
+        assert!(rays.ray(0, 0).is_none());
+        assert!(rays.ray(3, 4).is_some());
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn resamples_rolled_planes() {
+        // Brightness increases towards the right of the image at the mean time.
+        let brightness = |x: f64, _y: f64| 100.0 + 5.0 * x;
+        let (width, height) = (9, 7);
+        let coordinates =
+            |index: usize| ((index % width) as f64 - 4.0, 3.0 - (index / width) as f64);
+
+        // A quarter turn later, the right of the scene is at the bottom of the sensor.
+        let roll = Angle::new::<degree>(90.0);
+        let rolled: Vec<_> = (0..width * height)
+            .map(|index| {
+                let (x, y) = coordinates(index);
+                brightness(-y, x)
+            })
+            .collect();
+        let frame = frame(0.0, 0.0);
+        let resampled = frame.resample(&rolled, roll);
+
+        for (index, value) in resampled.iter().enumerate() {
+            let (x, y) = coordinates(index);
+            if x.abs() <= 3.0 {
+                assert_relative_eq!(*value, brightness(x, y), epsilon = 1e-9);
+            } else {
+                assert!(value.is_nan());
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Lookup of the sky bearing seen by each pixel.
+
+use super::Simulation;
+use crate::{
+    azimuth::AzimuthConvention,
+    mask::PixelMask,
+    optic::{Optic, PixelCoordinate},
+};
+use uom::{ConstZero, si::f64::Angle};
+
+/// The azimuth and elevation of the sky seen by every pixel of a [`Simulation`].
+///
+/// Tracing a pixel through the [`Camera`](crate::optic::Camera) and its pose is the expensive part
+/// of asking where a ray came from.
+/// A `BearingMap` traces every pixel once, so filters and statistics can cut a
+/// [`RayImage`](crate::image::RayImage) by bands of elevation or sectors of azimuth through a
+/// [`PixelMask`].
+///
+/// Azimuths are measured clockwise from north in `[0, 360)` degrees and elevations up from the
+/// horizon.
+/// Pixels below the horizon keep their negative elevation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BearingMap {
+    bearings: Vec<Option<(Angle, Angle)>>,
+    rows: usize,
+    cols: usize,
+}
+
+impl BearingMap {
+    /// The convention of the azimuths of a `BearingMap`, which are wrapped into `[0, 360)`.
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the azimuth and elevation seen by the pixel at `row` and `col`.
+    ///
+    /// Returns `None` if the pixel is outside of the sensor or the optic does not image it.
+    #[must_use]
+    pub fn bearing(&self, row: usize, col: usize) -> Option<(Angle, Angle)> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+
+        self.bearings[row * self.cols + col]
+    }
+
+    /// Keeps the pixels with an elevation from `min` up to but excluding `max`.
+    ///
+    /// # Panics
+    /// Panics if the number of bearings does not match the extents.
+    /// This should never occur.
+    #[must_use]
+    pub fn elevation_band(&self, min: Angle, max: Angle) -> PixelMask {
+        self.mask(|_, elevation| min <= elevation && elevation < max)
+    }
+
+    /// Keeps the pixels with an azimuth from `start` clockwise up to but excluding `end`.
+    ///
+    /// Both bounds are measured clockwise from north as in [`BearingMap::AZIMUTH`].
+    ///
+    /// Sectors may wrap through north, e.g., from 350 to 10 degrees.
+    ///
+    /// # Panics
+    /// Panics if the number of bearings does not match the extents.
+    /// This should never occur.
+    #[must_use]
+    pub fn azimuth_sector(&self, start: Angle, end: Angle) -> PixelMask {
+        let width = wrap(end - start);
+        self.mask(|azimuth, _| wrap(azimuth - start) < width)
+    }
+
+    fn mask(&self, keep: impl Fn(Angle, Angle) -> bool) -> PixelMask {
+        let keep = self
+            .bearings
+            .iter()
+            .map(|bearing| bearing.is_some_and(|(azimuth, elevation)| keep(azimuth, elevation)))
+            .collect();
+        PixelMask::new(keep, self.rows, self.cols).unwrap()
+    }
+}
+
+/// Wraps `angle` into `[0, 360)` degrees.
+fn wrap(angle: Angle) -> Angle {
+    let wrapped = angle % Angle::FULL_TURN;
+    if wrapped < Angle::ZERO {
+        wrapped + Angle::FULL_TURN
+    } else {
+        wrapped
+    }
+}
+
+impl<O: Optic> Simulation<O> {
+    /// Returns the azimuth and elevation of the sky seen by `pixel`.
+    ///
+    /// Like [`Simulation::ray`], this uses the pose at the time the row of `pixel` is exposed.
+    /// Returns `None` if the optic does not image `pixel`.
+    ///
+    /// # Panics
+    /// Panics if the [`crate::optic::RayDirection`] returned by the
+    /// [`Camera`](crate::optic::Camera) points behind the plane of the sensor.
+    #[must_use]
+    pub fn bearing(&self, pixel: impl AsRef<PixelCoordinate>) -> Option<(Angle, Angle)> {
+        let bearing = self.sky_bearing(pixel)?;
+        Some((wrap(bearing.azimuth()), bearing.elevation()))
+    }
+
+    /// Traces every pixel of the sensor into a [`BearingMap`].
+    ///
+    /// # Panics
+    /// Panics if the [`crate::optic::RayDirection`] returned by the
+    /// [`Camera`](crate::optic::Camera) points behind the plane of the sensor.
+    #[must_use]
+    pub fn bearing_map(&self) -> BearingMap {
+        BearingMap {
+            bearings: self.camera.pixels().map(|px| self.bearing(px)).collect(),
+            rows: self.camera.rows(),
+            cols: self.camera.cols(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uom::si::angle::degree;
+
+    fn map(bearings: &[(f64, f64)]) -> BearingMap {
+        BearingMap {
+            bearings: bearings
+                .iter()
+                .map(|&(azimuth, elevation)| {
+                    Some((
+                        Angle::new::<degree>(azimuth),
+                        Angle::new::<degree>(elevation),
+                    ))
+                })
+                .chain([None])
+                .collect(),
+            rows: 1,
+            cols: bearings.len() + 1,
+        }
+    }
+
+    #[test]
+    fn cuts_bands_and_sectors() {
+        let map = map(&[(0.0, -5.0), (90.0, 10.0), (355.0, 45.0), (180.0, 80.0)]);
+        let kept = |mask: PixelMask| {
+            (0..mask.cols())
+                .filter(|&col| mask.keeps(0, col))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kept(map.elevation_band(Angle::ZERO, Angle::new::<degree>(45.0))),
+            vec![1]
+        );
+        assert_eq!(
+            kept(map.azimuth_sector(Angle::new::<degree>(350.0), Angle::new::<degree>(95.0))),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            kept(map.azimuth_sector(Angle::new::<degree>(-10.0), Angle::new::<degree>(5.0))),
+            vec![0, 2]
+        );
+        assert_eq!(map.bearing(0, 4), None);
+        assert_eq!(map.bearing(1, 0), None);
+    }
+}
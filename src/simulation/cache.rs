@@ -0,0 +1,496 @@
+//! On-disk memoization of simulated [`RayImage`]s.
+//!
+//! Each entry is a single frame [`crate::io::sequence`] file named by a hash of the camera
+//! geometry, the settings of the simulation, the binned orientation of the camera, and the binned
+//! bearing of the sun.
+//! Configurations that fall in the same bins share an entry.
+
+use super::Simulation;
+use crate::{
+    image::RayImage,
+    io::sequence::{SequenceError, SequenceReader, SequenceWriter},
+    meta::FrameMeta,
+    model::Trig,
+    optic::{Optic, PinholeOptic},
+    ray::GlobalFrame,
+    rng::Fnv1a,
+};
+use chrono::DateTime;
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use thiserror::Error;
+use uom::si::{
+    angle::degree, angular_velocity::radian_per_second, f64::Angle, length::meter,
+    pressure::pascal, thermodynamic_temperature::kelvin, time::second,
+};
+
+const EXTENSION: &str = "rmps";
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("failed to access cache")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to read or write cache entry")]
+    Sequence(#[from] SequenceError),
+}
+
+/// An [`Optic`] that can describe its geometry for use in a [`RayImageCache`] key.
+pub trait CacheableOptic: Optic {
+    /// Returns the parameters that determine how the optic traces rays.
+    fn parameters(&self) -> Vec<f64>;
+}
+
+impl CacheableOptic for PinholeOptic {
+    fn parameters(&self) -> Vec<f64> {
+        vec![self.focal_length().get::<meter>()]
+    }
+}
+
+/// Identifies an entry in a [`RayImageCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey(u64);
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A directory of simulated [`RayImage`]s keyed by camera geometry, orientation, and solar
+/// bearing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RayImageCache {
+    dir: PathBuf,
+    orientation_step: Angle,
+    solar_step: Angle,
+    capacity: Option<usize>,
+}
+
+impl RayImageCache {
+    /// Creates a new `RayImageCache` in `dir`, creating the directory if it does not exist.
+    ///
+    /// Angles are binned to 0.1 degrees and the number of entries is unbounded by default.
+    ///
+    /// # Errors
+    /// Will return `Err` if `dir` cannot be created.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, CacheError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            orientation_step: Angle::new::<degree>(0.1),
+            solar_step: Angle::new::<degree>(0.1),
+            capacity: None,
+        })
+    }
+
+    /// Sets the width of the bins used to quantize the yaw, pitch, and roll of the camera.
+    #[must_use]
+    pub fn with_orientation_step(mut self, step: Angle) -> Self {
+        self.orientation_step = step;
+        self
+    }
+
+    /// Sets the width of the bins used to quantize the azimuth and elevation of the sun.
+    #[must_use]
+    pub fn with_solar_step(mut self, step: Angle) -> Self {
+        self.solar_step = step;
+        self
+    }
+
+    /// Limits the cache to `capacity` entries.
+    ///
+    /// The least recently used entries are evicted when an insert exceeds the capacity.
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Computes the key of the entry for `simulation`.
+    ///
+    /// Besides the camera, its orientation, and the sun, the key covers every setting that
+    /// changes the simulated rays, i.e., the [`Trig`] mode, the
+    /// [`Refraction`](crate::model::refraction::Refraction), and the
+    /// [`RollingShutter`](super::RollingShutter).
+    #[must_use]
+    pub fn key<O: CacheableOptic>(&self, simulation: &Simulation<O>) -> CacheKey {
+        let sensor = simulation.camera.sensor();
+        let (yaw, pitch, roll) = simulation.camera_pose.orientation().to_tait_bryan_angles();
+        let solar_bearing = simulation.model.solar_bearing();
+
+        // Absent settings are marked so they never collide with present ones.
+        let mut settings = vec![match simulation.model.trig() {
+            Trig::Exact => 0.0,
+            Trig::Table => 1.0,
+        }];
+        match simulation.model.refraction() {
+            Some(refraction) => settings.extend([
+                1.0,
+                refraction.pressure().get::<pascal>(),
+                refraction.temperature().get::<kelvin>(),
+            ]),
+            None => settings.push(0.0),
+        }
+        match simulation.rolling_shutter {
+            Some(rolling_shutter) => {
+                let (yaw_rate, pitch_rate, roll_rate) = rolling_shutter.rates();
+                settings.extend([
+                    1.0,
+                    rolling_shutter.line_time().get::<second>(),
+                    yaw_rate.get::<radian_per_second>(),
+                    pitch_rate.get::<radian_per_second>(),
+                    roll_rate.get::<radian_per_second>(),
+                ]);
+            }
+            None => settings.push(0.0),
+        }
+
+        self.key_from_parts(
+            &simulation.camera.optic().parameters(),
+            [
+                sensor.pixel_width().get::<meter>(),
+                sensor.pixel_height().get::<meter>(),
+                f64::from(u32::try_from(sensor.rows()).unwrap_or(u32::MAX)),
+                f64::from(u32::try_from(sensor.cols()).unwrap_or(u32::MAX)),
+            ],
+            &settings,
+            [yaw, pitch, roll],
+            [solar_bearing.azimuth(), solar_bearing.elevation()],
+        )
+    }
+
+    fn key_from_parts(
+        &self,
+        optic: &[f64],
+        sensor: [f64; 4],
+        settings: &[f64],
+        orientation: [Angle; 3],
+        solar: [Angle; 2],
+    ) -> CacheKey {
+        #[allow(clippy::cast_possible_truncation)]
+        let bin = |angle: Angle, step: Angle| (angle / step).value.round() as i64;
+        // Yaw, roll, and azimuth are periodic, so bins are wrapped onto one turn to place angles
+        // like -180 and 180 degrees in the same bin.
+        #[allow(clippy::cast_possible_truncation)]
+        let wrapped_bin = |angle: Angle, step: Angle| {
+            let turn = (Angle::HALF_TURN * 2.0 / step).value.round() as i64;
+            bin(angle, step).rem_euclid(turn.max(1))
+        };
+        let [yaw, pitch, roll] = orientation;
+        let [azimuth, elevation] = solar;
+
+        let mut hash = Fnv1a::default();
+        // Each part is prefixed by its length so values cannot shift between parts.
+        for part in [optic, &sensor, settings] {
+            hash.write(&(part.len() as u64).to_le_bytes());
+            part.iter()
+                .for_each(|value| hash.write(&value.to_bits().to_le_bytes()));
+        }
+        [
+            wrapped_bin(yaw, self.orientation_step),
+            bin(pitch, self.orientation_step),
+            wrapped_bin(roll, self.orientation_step),
+            wrapped_bin(azimuth, self.solar_step),
+            bin(elevation, self.solar_step),
+        ]
+        .into_iter()
+        .for_each(|bin| hash.write(&bin.to_le_bytes()));
+
+        CacheKey(hash.finish())
+    }
+
+    fn path(&self, key: CacheKey) -> PathBuf {
+        self.dir.join(format!("{key}.{EXTENSION}"))
+    }
+
+    /// Returns the entry for `key` if it exists.
+    ///
+    /// Reading an entry marks it as recently used.
+    ///
+    /// # Errors
+    /// Will return `Err` if the entry exists but cannot be read.
+    pub fn get(&self, key: CacheKey) -> Result<Option<RayImage<GlobalFrame>>, CacheError> {
+        let path = self.path(key);
+        let file = match File::options().read(true).append(true).open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        file.set_modified(SystemTime::now())?;
+
+        let mut reader = SequenceReader::new(BufReader::new(file))?;
+        // Cached images are keyed by simulation parameters, not by when they were written.
+        Ok(reader
+            .read_frame()?
+            .map(|frame| frame.into_image().with_meta(FrameMeta::default())))
+    }
+
+    /// Stores `image` as the entry for `key` and evicts entries beyond the capacity.
+    ///
+    /// # Errors
+    /// Will return `Err` if the entry cannot be written or old entries cannot be removed.
+    pub fn insert(&self, key: CacheKey, image: &RayImage<GlobalFrame>) -> Result<(), CacheError> {
+        // Write to a temporary file first so readers never observe partial entries.
+        let path = self.path(key);
+        let temp = path.with_extension("tmp");
+        let mut writer = SequenceWriter::new(
+            BufWriter::new(File::create(&temp)?),
+            image.rows(),
+            image.cols(),
+        )?;
+        writer.write_frame(DateTime::UNIX_EPOCH, image)?;
+        writer.into_inner()?;
+        fs::rename(&temp, &path)?;
+
+        self.evict()
+    }
+
+    fn entries(&self) -> Result<Vec<(SystemTime, PathBuf)>, CacheError> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                entries.push((fs::metadata(&path)?.modified()?, path));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn evict(&self) -> Result<(), CacheError> {
+        let Some(capacity) = self.capacity else {
+            return Ok(());
+        };
+
+        let mut entries = self.entries()?;
+        if entries.len() > capacity {
+            entries.sort_unstable();
+            for (_, path) in &entries[..entries.len() - capacity] {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes every entry from the cache.
+    ///
+    /// # Errors
+    /// Will return `Err` if an entry cannot be removed.
+    pub fn clear(&self) -> Result<(), CacheError> {
+        for (_, path) in self.entries()? {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<O: CacheableOptic + Send + Sync> Simulation<O> {
+    /// Returns the [`RayImage`] for this simulation from `cache`, simulating and storing it on a
+    /// miss.
+    ///
+    /// On a hit, the rays may have been simulated from a different orientation or solar bearing
+    /// that falls in the same bins, but the [`FrameMeta`] always describes this simulation.
+    ///
+    /// # Errors
+    /// Will return `Err` if the cache cannot be read or written.
+    pub fn cached_ray_image(
+        &self,
+        cache: &RayImageCache,
+    ) -> Result<RayImage<GlobalFrame>, CacheError> {
+        let key = cache.key(self);
+        if let Some(image) = cache.get(key)? {
+            return Ok(image.with_meta(self.meta()));
+        }
+
+        let image = self.par_ray_image();
+        cache.insert(key, &image)?;
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::{aop::Aop, dop::Dop},
+        model::refraction::Refraction,
+        optic::Camera,
+        ray::Ray,
+        simulation::RollingShutter,
+    };
+    use chrono::{TimeZone, Utc};
+    use sguaba::{
+        Coordinate,
+        engineering::{Orientation, Pose},
+        math::RigidBodyTransform,
+        system,
+        systems::Wgs84,
+    };
+    use uom::{
+        ConstZero,
+        si::{
+            f64::{Length, Time},
+            length::{micron, millimeter},
+            time::millisecond,
+        },
+    };
+
+    system!(struct CacheEnu using ENU);
+
+    fn cache(name: &str) -> RayImageCache {
+        let dir = std::env::temp_dir().join(format!("rumpus-cache-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        RayImageCache::new(dir).unwrap()
+    }
+
+    fn image(dop: f64) -> RayImage<GlobalFrame> {
+        let ray = Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(10.0)),
+            Dop::clamped(dop),
+        );
+        RayImage::from_rays(vec![Some(ray), None, Some(ray), Some(ray)], 2, 2).unwrap()
+    }
+
+    fn key(cache: &RayImageCache, yaw: f64, focal_length: f64) -> CacheKey {
+        let deg = Angle::new::<degree>;
+        cache.key_from_parts(
+            &[focal_length],
+            [6.9e-6, 6.9e-6, 1024.0, 1224.0],
+            &[0.0, 0.0, 0.0],
+            [deg(yaw), deg(0.0), deg(180.0)],
+            [deg(200.0), deg(60.0)],
+        )
+    }
+
+    #[test]
+    fn keys_are_quantized() {
+        let cache = cache("keys");
+        assert_eq!(key(&cache, 10.0, 3e-3), key(&cache, 10.04, 3e-3));
+        assert_ne!(key(&cache, 10.0, 3e-3), key(&cache, 10.1, 3e-3));
+        assert_ne!(key(&cache, 10.0, 3e-3), key(&cache, 10.0, 4e-3));
+
+        let coarse = cache
+            .clone()
+            .with_orientation_step(Angle::new::<degree>(1.0));
+        assert_eq!(key(&coarse, 10.0, 3e-3), key(&coarse, 10.4, 3e-3));
+
+        // Opposite ends of a turn are the same heading.
+        assert_eq!(key(&cache, 180.0, 3e-3), key(&cache, -180.0, 3e-3));
+        assert_eq!(key(&cache, 0.0, 3e-3), key(&cache, 360.0, 3e-3));
+        assert_eq!(key(&coarse, 179.8, 3e-3), key(&coarse, -179.9, 3e-3));
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    fn simulation() -> Simulation<PinholeOptic> {
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+            Length::new::<micron>(100.0),
+            8,
+            10,
+        );
+        let position = Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.5))
+            .altitude(Length::ZERO)
+            .build();
+        // Look straight up.
+        let pose = Pose::new(
+            Coordinate::origin(),
+            Orientation::<CacheEnu>::tait_bryan_builder()
+                .yaw(Angle::new::<degree>(0.0))
+                .pitch(Angle::new::<degree>(0.0))
+                .roll(Angle::new::<degree>(180.0))
+                .build(),
+        );
+        // SAFETY: The origin of CacheEnu is the position of the camera.
+        let enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&position) }.inverse();
+        let time = Utc.with_ymd_and_hms(2025, 6, 1, 16, 0, 0).unwrap();
+        Simulation::new(camera, enu_to_ecef.transform(pose), time)
+    }
+
+    #[test]
+    fn settings_change_keys() {
+        let cache = cache("settings");
+        let base = simulation();
+        cache.insert(cache.key(&base), &image(0.5)).unwrap();
+        assert!(cache.get(cache.key(&base)).unwrap().is_some());
+
+        let rolling_shutter =
+            base.with_rolling_shutter(RollingShutter::new(Time::new::<millisecond>(0.01)));
+        let refraction = simulation().with_refraction(Refraction::standard());
+        let mut table = simulation();
+        table.model = table.model.with_trig(Trig::Table);
+        for changed in [&rolling_shutter, &refraction, &table] {
+            assert_ne!(cache.key(changed), cache.key(&simulation()));
+            assert_eq!(cache.get(cache.key(changed)).unwrap(), None);
+        }
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn hits_carry_meta_of_simulation() {
+        let cache = cache("meta");
+        let simulation = simulation();
+        cache.insert(cache.key(&simulation), &image(0.5)).unwrap();
+
+        let image = simulation.cached_ray_image(&cache).unwrap();
+        assert_eq!(image.meta(), &simulation.meta());
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn roundtrip() {
+        let cache = cache("roundtrip");
+        let key = CacheKey(42);
+        assert_eq!(cache.get(key).unwrap(), None);
+
+        cache.insert(key, &image(0.5)).unwrap();
+        assert_eq!(cache.get(key).unwrap(), Some(image(0.5)));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.get(key).unwrap(), None);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = cache("evict").with_capacity(2);
+        let old = SystemTime::UNIX_EPOCH;
+
+        cache.insert(CacheKey(1), &image(0.1)).unwrap();
+        cache.insert(CacheKey(2), &image(0.2)).unwrap();
+        for key in [CacheKey(1), CacheKey(2)] {
+            File::options()
+                .append(true)
+                .open(cache.path(key))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+
+        // Reading the first entry makes the second the least recently used.
+        cache.get(CacheKey(1)).unwrap();
+        cache.insert(CacheKey(3), &image(0.3)).unwrap();
+
+        assert!(cache.get(CacheKey(1)).unwrap().is_some());
+        assert!(cache.get(CacheKey(2)).unwrap().is_none());
+        assert!(cache.get(CacheKey(3)).unwrap().is_some());
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
@@ -0,0 +1,373 @@
+use crate::{
+    image::{ImageError, RayImage},
+    light::{aop::Aop, dop::Dop},
+    ray::Ray,
+    rng::{hash, unit},
+};
+use std::f64::consts::TAU;
+use thiserror::Error;
+use uom::si::f64::Angle;
+
+#[derive(Debug, Error)]
+pub enum CloudError {
+    #[error("length of densities does not match size of extents: expected {} found {len}", rows * cols)]
+    SizeMismatch {
+        rows: usize,
+        cols: usize,
+        len: usize,
+    },
+
+    #[error("cloud density must be on the range 0 to 1: found {density}")]
+    InvalidDensity { density: f64 },
+
+    #[error(
+        "cloud mask extents do not match image: expected {expected_rows}x{expected_cols} found {rows}x{cols}"
+    )]
+    ExtentsMismatch {
+        expected_rows: usize,
+        expected_cols: usize,
+        rows: usize,
+        cols: usize,
+    },
+
+    #[error(transparent)]
+    Image(#[from] ImageError),
+}
+
+/// Describes the density of clouds over the pixels of an image.
+///
+/// A density of zero is clear sky and a density of one is completely overcast.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CloudMask {
+    inner: MaskKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum MaskKind {
+    Perlin {
+        scale: f64,
+        coverage: f64,
+        seed: u64,
+    },
+    Densities {
+        densities: Vec<f64>,
+        rows: usize,
+        cols: usize,
+    },
+}
+
+impl CloudMask {
+    /// Creates a procedural `CloudMask` from fractal Perlin noise.
+    ///
+    /// `scale` is the approximate size of a cloud in pixels and `coverage` is the approximate
+    /// fraction of the image covered by clouds.
+    /// A `coverage` of one is overcast at every pixel and a `coverage` of zero is clear.
+    /// The same `seed` always generates the same mask.
+    ///
+    /// # Panics
+    /// Will panic if `scale` is not positive.
+    #[must_use]
+    pub fn perlin(scale: f64, coverage: f64, seed: u64) -> Self {
+        assert!(scale > 0.0, "cloud scale must be positive");
+        Self {
+            inner: MaskKind::Perlin {
+                scale,
+                coverage: coverage.clamp(0.0, 1.0),
+                seed,
+            },
+        }
+    }
+
+    /// Creates a `CloudMask` from row-major `densities` supplied by the user.
+    ///
+    /// # Errors
+    /// Will return `Err` if the length of `densities` does not match `rows` by `cols` or if any
+    /// density is outside of [0, 1].
+    pub fn from_densities(
+        densities: Vec<f64>,
+        rows: usize,
+        cols: usize,
+    ) -> Result<Self, CloudError> {
+        let len = densities.len();
+        if rows * cols != len {
+            return Err(CloudError::SizeMismatch { rows, cols, len });
+        }
+
+        if let Some(&density) = densities.iter().find(|d| !(0.0..=1.0).contains(*d)) {
+            return Err(CloudError::InvalidDensity { density });
+        }
+
+        Ok(Self {
+            inner: MaskKind::Densities {
+                densities,
+                rows,
+                cols,
+            },
+        })
+    }
+
+    /// Returns the extents of the mask if it was supplied by the user.
+    #[must_use]
+    pub fn extents(&self) -> Option<(usize, usize)> {
+        match self.inner {
+            MaskKind::Perlin { .. } => None,
+            MaskKind::Densities { rows, cols, .. } => Some((rows, cols)),
+        }
+    }
+
+    /// Returns the cloud density at a pixel.
+    #[must_use]
+    pub fn density(&self, row: usize, col: usize) -> f64 {
+        match &self.inner {
+            MaskKind::Perlin {
+                scale,
+                coverage,
+                seed,
+            } => {
+                // The noise only roughly spans [-1, 1], so the extremes are exact by definition.
+                if *coverage >= 1.0 {
+                    return 1.0;
+                }
+                if *coverage <= 0.0 {
+                    return 0.0;
+                }
+
+                #[allow(clippy::cast_precision_loss)]
+                let value = fractal_noise(row as f64 / scale, col as f64 / scale, *seed);
+
+                // Noise is roughly symmetric about zero so coverage selects a threshold.
+                // Densities ramp up over a narrow band to soften the edges of clouds.
+                let threshold = 1.0 - 2.0 * coverage;
+                ((value - threshold) / EDGE_WIDTH).clamp(0.0, 1.0)
+            }
+            MaskKind::Densities {
+                densities, cols, ..
+            } => densities[row * cols + col],
+        }
+    }
+}
+
+/// Width of the band of noise values over which clouds fade in.
+const EDGE_WIDTH: f64 = 0.2;
+
+/// Describes a layer of clouds that obscures the polarization pattern of the sky.
+///
+/// Inside a cloud, the degree of polarization is attenuated and the angle of polarization is
+/// randomized in proportion to the density of the [`CloudMask`].
+/// This yields synthetic images with partial occlusion while keeping the ground truth pose of
+/// the [`crate::simulation::Simulation`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CloudLayer {
+    mask: CloudMask,
+    attenuation: f64,
+    seed: u64,
+}
+
+impl CloudLayer {
+    /// Creates a new `CloudLayer` that completely depolarizes light at full density.
+    #[must_use]
+    pub fn new(mask: CloudMask) -> Self {
+        Self {
+            mask,
+            attenuation: 1.0,
+            seed: 0,
+        }
+    }
+
+    /// Sets the fraction of the degree of polarization removed at full density.
+    #[must_use]
+    pub fn with_attenuation(mut self, attenuation: f64) -> Self {
+        self.attenuation = attenuation.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the seed used to randomize the angle of polarization.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    #[must_use]
+    pub fn mask(&self) -> &CloudMask {
+        &self.mask
+    }
+
+    /// Obscures a `ray` imaged at a pixel.
+    #[must_use]
+    pub fn obscure<Frame: Copy>(&self, ray: Ray<Frame>, row: usize, col: usize) -> Ray<Frame> {
+        let density = self.mask.density(row, col);
+        if density <= 0.0 {
+            return ray;
+        }
+
+        let degree = Dop::clamped(f64::from(ray.dop()) * (1.0 - self.attenuation * density));
+
+        // Uniform on [-90, 90) degrees at full density.
+        let noise = unit(hash(self.seed, row as u64, col as u64)) - 0.5;
+        let offset = Angle::HALF_TURN * noise * density;
+        let angle = Aop::from_angle_wrapped(Angle::from(ray.aop()) + offset);
+
+        Ray::new(angle, degree)
+    }
+
+    /// Obscures every ray in `image`.
+    ///
+    /// # Errors
+    /// Will return `Err` if the [`CloudMask`] was supplied with extents that do not match `image`.
+    pub fn apply<Frame: Copy>(
+        &self,
+        image: &RayImage<Frame>,
+    ) -> Result<RayImage<Frame>, CloudError> {
+        let (rows, cols) = (image.rows(), image.cols());
+        if let Some((expected_rows, expected_cols)) = self.mask.extents()
+            && (expected_rows, expected_cols) != (rows, cols)
+        {
+            return Err(CloudError::ExtentsMismatch {
+                expected_rows,
+                expected_cols,
+                rows,
+                cols,
+            });
+        }
+
+        let rays = image
+            .rays()
+            .enumerate()
+            .map(|(index, ray)| ray.map(|ray| self.obscure(*ray, index / cols, index % cols)));
+
+        Ok(RayImage::from_rays(rays, rows, cols)?.with_meta(*image.meta()))
+    }
+}
+
+/// Sums octaves of Perlin noise.
+fn fractal_noise(x: f64, y: f64, seed: u64) -> f64 {
+    const OCTAVES: u32 = 4;
+
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut norm = 0.0;
+    for octave in 0..OCTAVES {
+        let frequency = f64::from(1 << octave);
+        total += amplitude
+            * perlin(
+                x * frequency,
+                y * frequency,
+                seed.wrapping_add(octave.into()),
+            );
+        norm += amplitude;
+        amplitude /= 2.0;
+    }
+
+    total / norm
+}
+
+/// Computes two dimensional gradient noise on roughly [-1, 1].
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn perlin(x: f64, y: f64, seed: u64) -> f64 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (dx, dy) = (x - x0, y - y0);
+
+    let gradient = |ix: f64, iy: f64, dx: f64, dy: f64| {
+        let theta = unit(hash(seed, ix as i64 as u64, iy as i64 as u64)) * TAU;
+        theta.cos() * dx + theta.sin() * dy
+    };
+    let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+
+    let (u, v) = (fade(dx), fade(dy));
+    let top = lerp(
+        gradient(x0, y0, dx, dy),
+        gradient(x0 + 1.0, y0, dx - 1.0, dy),
+        u,
+    );
+    let bottom = lerp(
+        gradient(x0, y0 + 1.0, dx, dy - 1.0),
+        gradient(x0 + 1.0, y0 + 1.0, dx - 1.0, dy - 1.0),
+        u,
+    );
+
+    lerp(top, bottom, v) * std::f64::consts::SQRT_2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::GlobalFrame;
+    use approx::assert_relative_eq;
+    use rstest::rstest;
+    use uom::si::angle::degree;
+
+    fn ray(aop: f64, dop: f64) -> Ray<GlobalFrame> {
+        Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+            Dop::clamped(dop),
+        )
+    }
+
+    #[rstest]
+    #[case(0.0, 0.0)]
+    #[case(1.0, 1.0)]
+    fn perlin_coverage_extremes(#[case] coverage: f64, #[case] expected: f64) {
+        let mask = CloudMask::perlin(16.0, coverage, 7);
+        for (row, col) in (0..32).flat_map(|row| (0..32).map(move |col| (row, col))) {
+            assert_eq!(mask.density(row, col), expected);
+        }
+    }
+
+    #[test]
+    fn full_coverage_is_overcast() {
+        for seed in 0..8 {
+            let mask = CloudMask::perlin(4.0, 1.0, seed);
+            for (row, col) in (0..128).flat_map(|row| (0..128).map(move |col| (row, col))) {
+                assert_eq!(mask.density(row, col), 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn perlin_is_deterministic() {
+        let mask = CloudMask::perlin(8.0, 0.5, 3);
+        let densities: Vec<_> = (0..64).map(|i| mask.density(i / 8, i % 8)).collect();
+        let again: Vec<_> = (0..64).map(|i| mask.density(i / 8, i % 8)).collect();
+        assert_eq!(densities, again);
+        assert!(densities.iter().all(|d| (0.0..=1.0).contains(d)));
+    }
+
+    #[test]
+    fn clear_sky_is_unchanged() {
+        let layer = CloudLayer::new(CloudMask::from_densities(vec![0.0; 4], 2, 2).unwrap());
+        let ray = ray(30.0, 0.6);
+        assert_eq!(layer.obscure(ray, 1, 1), ray);
+    }
+
+    #[test]
+    fn dense_cloud_attenuates_dop() {
+        let layer = CloudLayer::new(CloudMask::from_densities(vec![1.0; 4], 2, 2).unwrap())
+            .with_attenuation(0.75);
+        let result = layer.obscure(ray(30.0, 0.8), 0, 1);
+        assert_relative_eq!(f64::from(result.dop()), 0.2, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn densities_are_validated() {
+        assert!(matches!(
+            CloudMask::from_densities(vec![0.0; 3], 2, 2),
+            Err(CloudError::SizeMismatch { .. })
+        ));
+        assert!(matches!(
+            CloudMask::from_densities(vec![0.0, 0.5, 1.5, 0.0], 2, 2),
+            Err(CloudError::InvalidDensity { .. })
+        ));
+    }
+
+    #[test]
+    fn apply_checks_extents() {
+        let layer = CloudLayer::new(CloudMask::from_densities(vec![0.5; 4], 2, 2).unwrap());
+        let image = RayImage::from_rays(vec![Some(ray(10.0, 0.5)); 6], 2, 3).unwrap();
+        assert!(matches!(
+            layer.apply(&image),
+            Err(CloudError::ExtentsMismatch { .. })
+        ));
+    }
+}
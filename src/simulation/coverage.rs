@@ -0,0 +1,383 @@
+//! Planning of the orientations that capture the whole sky in several shots.
+
+use crate::{
+    azimuth::AzimuthConvention,
+    optic::{Camera, Optic, PixelCoordinate},
+};
+use sguaba::engineering::Orientation;
+use uom::si::{angle::radian, f64::Angle};
+
+/// The direction of the optical axis of a camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pointing {
+    azimuth: Angle,
+    elevation: Angle,
+}
+
+impl Pointing {
+    /// The convention of [`Pointing::azimuth`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    #[must_use]
+    pub fn new(azimuth: Angle, elevation: Angle) -> Self {
+        Self { azimuth, elevation }
+    }
+
+    /// The azimuth of the optical axis, e.g., for the pan axis of a gimbal.
+    #[must_use]
+    pub fn azimuth(&self) -> Angle {
+        self.azimuth
+    }
+
+    /// The elevation of the optical axis, e.g., for the tilt axis of a gimbal.
+    #[must_use]
+    pub fn elevation(&self) -> Angle {
+        self.elevation
+    }
+
+    /// Returns the orientation of a [`Camera`] that looks along this pointing with the top of
+    /// the image towards the zenith.
+    ///
+    /// `In` should be an east, north, up system, e.g., of a [`Pose`](sguaba::engineering::Pose)
+    /// passed to [`Simulation::new`](super::Simulation::new).
+    #[must_use]
+    pub fn orientation<In>(&self) -> Orientation<In> {
+        Orientation::<In>::tait_bryan_builder()
+            .yaw(-self.azimuth)
+            .pitch(Angle::new::<radian>(0.0))
+            .roll(Angle::HALF_TURN / 2.0 + self.elevation)
+            .build()
+    }
+}
+
+/// Plans the pointings of a camera that together capture the sky above a minimum elevation.
+///
+/// The footprint of each shot is taken as a circle around its optical axis.
+/// The first shot looks at the zenith and the rest are spread evenly around rings of constant
+/// elevation, working down towards the horizon.
+/// Each ring reaches the bottom of the ring above it and uses the number of shots that covers
+/// the most sky per shot, or the fewest shots that reach the minimum elevation.
+/// The plan is not guaranteed to be globally minimal, but is close for the fields of view of
+/// typical lenses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoveragePlanner {
+    field_of_view: Angle,
+    min_elevation: Angle,
+    overlap: Angle,
+}
+
+impl CoveragePlanner {
+    /// Creates a new `CoveragePlanner` for shots with a circular `field_of_view` that covers the
+    /// sky down to the horizon without overlap.
+    ///
+    /// # Panics
+    /// Will panic if `field_of_view` is not between 0 and 360 degrees.
+    #[must_use]
+    pub fn new(field_of_view: Angle) -> Self {
+        assert!(
+            Angle::new::<radian>(0.0) < field_of_view && field_of_view < Angle::FULL_TURN,
+            "field of view must be between 0 and 360 degrees: {field_of_view:#?}"
+        );
+
+        Self {
+            field_of_view,
+            min_elevation: Angle::new::<radian>(0.0),
+            overlap: Angle::new::<radian>(0.0),
+        }
+    }
+
+    /// Creates a new `CoveragePlanner` for the circle inscribed in the sensor of `camera`.
+    ///
+    /// Returns `None` if the optic does not image the middle of every edge of the sensor.
+    #[must_use]
+    pub fn from_camera<O: Optic>(camera: &Camera<O>) -> Option<Self> {
+        let (rows, cols) = (camera.rows(), camera.cols());
+        let edges = [
+            (0, cols / 2),
+            (rows.saturating_sub(1), cols / 2),
+            (rows / 2, 0),
+            (rows / 2, cols.saturating_sub(1)),
+        ];
+
+        let mut half = Angle::HALF_TURN;
+        for (row, col) in edges {
+            let direction = camera.trace_from_pixel(PixelCoordinate::new(row, col))?;
+            half = half.min(Angle::HALF_TURN - direction.polar());
+        }
+
+        Some(Self::new(2.0 * half))
+    }
+
+    /// Sets the lowest elevation that must be captured.
+    #[must_use]
+    pub fn with_min_elevation(mut self, min_elevation: Angle) -> Self {
+        self.min_elevation = min_elevation;
+        self
+    }
+
+    /// Sets the angle by which neighboring footprints must overlap, e.g., to register the shots
+    /// into a mosaic.
+    #[must_use]
+    pub fn with_overlap(mut self, overlap: Angle) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    #[must_use]
+    pub fn field_of_view(&self) -> Angle {
+        self.field_of_view
+    }
+
+    #[must_use]
+    pub fn min_elevation(&self) -> Angle {
+        self.min_elevation
+    }
+
+    #[must_use]
+    pub fn overlap(&self) -> Angle {
+        self.overlap
+    }
+
+    /// Returns the pointings of the plan starting with the zenith.
+    ///
+    /// Capture them in order to sweep the sky from the top down.
+    ///
+    /// # Panics
+    /// Will panic if the overlap is not smaller than the field of view.
+    #[must_use]
+    pub fn plan(&self) -> Vec<Pointing> {
+        assert!(
+            self.overlap < self.field_of_view,
+            "overlap must be smaller than the field of view: {:#?}",
+            self.overlap
+        );
+
+        let radius = ((self.field_of_view - self.overlap) / 2.0).get::<radian>();
+        let min_elevation = self.min_elevation.get::<radian>();
+        let mut plan = vec![Pointing::new(
+            Angle::new::<radian>(0.0),
+            Angle::HALF_TURN / 2.0,
+        )];
+
+        // The bottom of the sky covered so far.
+        let mut top = std::f64::consts::FRAC_PI_2 - radius;
+        let mut ring = 0;
+        while top > min_elevation && top > -std::f64::consts::FRAC_PI_2 {
+            let (count, elevation, bottom) = next_ring(top, radius, min_elevation);
+
+            // Stagger alternate rings to spread the overlap.
+            let offset = if ring % 2 == 0 { 0.0 } else { 0.5 };
+            plan.extend((0..count).map(|k| {
+                Pointing::new(
+                    Angle::FULL_TURN * ((f64::from(k) + offset) / f64::from(count)),
+                    Angle::new::<radian>(elevation),
+                )
+            }));
+
+            top = bottom;
+            ring += 1;
+        }
+
+        plan
+    }
+}
+
+/// Returns the half width in azimuth of the part of a footprint of `radius` centered at
+/// `center` elevation that lies at `elevation`, all in radians.
+fn half_width(center: f64, elevation: f64, radius: f64) -> f64 {
+    let scale = center.cos() * elevation.cos();
+    if scale <= 0.0 {
+        return 0.0;
+    }
+
+    let cos = (radius.cos() - center.sin() * elevation.sin()) / scale;
+    if cos > 1.0 { 0.0 } else { cos.max(-1.0).acos() }
+}
+
+/// Returns the boundary of `holds` between `fails` and `passes` by bisection.
+fn bisect(mut fails: f64, mut passes: f64, holds: impl Fn(f64) -> bool) -> f64 {
+    for _ in 0..64 {
+        let middle = (fails + passes) / 2.0;
+        if holds(middle) {
+            passes = middle;
+        } else {
+            fails = middle;
+        }
+    }
+    passes
+}
+
+/// Plans the ring of shots below the covered sky down to `top` and returns the number of shots,
+/// their elevation, and the bottom of the sky they cover, all in radians.
+fn next_ring(top: f64, radius: f64, min_elevation: f64) -> (u32, f64, f64) {
+    // A ring of `count` shots covers an elevation if each shot covers the gap to its neighbor.
+    let ring = |count: u32| {
+        let gap = std::f64::consts::PI / f64::from(count);
+        if half_width(top, top, radius) < gap {
+            return None;
+        }
+
+        // Lower the ring as far as it still reaches the top, then find how far down it reaches.
+        let elevation = bisect(top - radius, top, |center| {
+            half_width(center, top, radius) >= gap
+        });
+        let bottom = bisect(elevation - radius, elevation, |lower| {
+            half_width(elevation, lower, radius) >= gap
+        });
+        Some((count, elevation, bottom))
+    };
+
+    // The fewest shots that reach the top.
+    let fewest = (std::f64::consts::PI / half_width(top, top, radius).max(f64::EPSILON)).ceil();
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let fewest = fewest.clamp(1.0, f64::from(u32::MAX / 4)) as u32;
+
+    let candidates: Vec<_> = (fewest..fewest.saturating_mul(4))
+        .filter_map(ring)
+        .collect();
+    if let Some((count, lowest, _)) = candidates
+        .iter()
+        .find(|(_, _, bottom)| *bottom <= min_elevation)
+    {
+        // The last ring may be raised as long as it still reaches the minimum elevation, so
+        // center it between its lowest and highest positions to share the slack.
+        let gap = std::f64::consts::PI / f64::from(*count);
+        let reaches = |center: f64| half_width(center, min_elevation, radius) >= gap;
+        let highest = if reaches(top) {
+            top
+        } else {
+            bisect(top, *lowest, reaches)
+        };
+        return (*count, (lowest + highest) / 2.0, min_elevation);
+    }
+
+    candidates
+        .into_iter()
+        .min_by(|(lhs, _, lhs_bottom), (rhs, _, rhs_bottom)| {
+            let cost = |count: u32, bottom: f64| f64::from(count) / (top.sin() - bottom.sin());
+            cost(*lhs, *lhs_bottom).total_cmp(&cost(*rhs, *rhs_bottom))
+        })
+        .expect("the fewest shots that reach the top form a ring")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        optic::PinholeOptic,
+        simulation::{Simulation, SimulationEnu},
+    };
+    use chrono::{TimeZone, Utc};
+    use sguaba::{Coordinate, engineering::Pose, math::RigidBodyTransform, system, systems::Wgs84};
+    use uom::{
+        ConstZero,
+        si::{
+            angle::degree,
+            f64::Length,
+            length::{meter, micron, millimeter},
+        },
+    };
+
+    system!(struct CoverageEnu using ENU);
+
+    /// Returns the angle between two bearings in radians.
+    fn separation(lhs: (f64, f64), rhs: (f64, f64)) -> f64 {
+        let cos = lhs.1.sin() * rhs.1.sin() + lhs.1.cos() * rhs.1.cos() * (lhs.0 - rhs.0).cos();
+        cos.clamp(-1.0, 1.0).acos()
+    }
+
+    #[test]
+    fn covers_the_sky() {
+        for field_of_view in [40.0, 60.0, 90.0, 120.0] {
+            let planner = CoveragePlanner::new(Angle::new::<degree>(field_of_view))
+                .with_min_elevation(Angle::new::<degree>(-5.0));
+            let plan = planner.plan();
+            let radius = (field_of_view / 2.0).to_radians() + 1e-9;
+
+            for azimuth in (0..360).step_by(2) {
+                for elevation in -5..=90 {
+                    let at = (
+                        f64::from(azimuth).to_radians(),
+                        f64::from(elevation).to_radians(),
+                    );
+                    assert!(
+                        plan.iter().any(|pointing| separation(
+                            at,
+                            (
+                                pointing.azimuth().get::<radian>(),
+                                pointing.elevation().get::<radian>()
+                            )
+                        ) <= radius),
+                        "{field_of_view} misses {azimuth} {elevation} with {} shots",
+                        plan.len()
+                    );
+                }
+            }
+        }
+
+        assert_eq!(
+            CoveragePlanner::new(Angle::new::<degree>(200.0))
+                .plan()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn overlap_adds_shots() {
+        let planner = CoveragePlanner::new(Angle::new::<degree>(90.0));
+        let overlapping = planner.with_overlap(Angle::new::<degree>(10.0));
+        assert!(overlapping.plan().len() > planner.plan().len());
+    }
+
+    #[test]
+    fn orientation_looks_along_pointing() {
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+            Length::new::<micron>(20.0),
+            101,
+            101,
+        );
+        let planner = CoveragePlanner::from_camera(&camera).unwrap();
+        assert!(planner.field_of_view().get::<degree>() > 35.0);
+
+        let position = Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.5))
+            .altitude(Length::ZERO)
+            .build();
+        // SAFETY: The origin of CoverageEnu is the position of the camera.
+        let enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&position) }.inverse();
+        let time = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+
+        for pointing in planner.plan() {
+            let pose = Pose::new(Coordinate::origin(), pointing.orientation::<CoverageEnu>());
+            let simulation = Simulation::new(camera, enu_to_ecef.transform(pose), time);
+
+            let at = |azimuth: Angle, elevation: Angle| {
+                let (east, north) = Pointing::AZIMUTH.components(azimuth);
+                let meters = |value: f64| Length::new::<meter>(value);
+                simulation.pixel_from_enu(
+                    &Coordinate::<SimulationEnu>::builder()
+                        .enu_east(meters(east * elevation.cos().value))
+                        .enu_north(meters(north * elevation.cos().value))
+                        .enu_up(meters(elevation.sin().value))
+                        .build(),
+                )
+            };
+
+            let center = at(pointing.azimuth(), pointing.elevation()).unwrap();
+            assert!(center.row().abs_diff(50) <= 1 && center.col().abs_diff(50) <= 1);
+            if pointing.elevation() < Angle::HALF_TURN / 2.0 {
+                // The zenith is towards the top of the image.
+                let above = at(
+                    pointing.azimuth(),
+                    pointing.elevation() + Angle::new::<degree>(5.0),
+                )
+                .unwrap();
+                assert!(above.row() < center.row());
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Simulated long exposures during which the source of skylight moves.
+
+use super::Simulation;
+use crate::{
+    azimuth::AzimuthConvention,
+    image::RayImage,
+    meta::FrameMeta,
+    model::ephemeris::{SolarEphemeris, SolarPosition},
+    optic::{Camera, Optic},
+    ray::{GlobalFrame, Ray},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use sguaba::{engineering::Pose, systems::Ecef};
+use uom::si::{
+    angle::radian,
+    f64::{Angle, Time},
+    time::second,
+};
+
+/// Simulates a [`Camera`] with a fixed [`Pose`] over an exposure window.
+///
+/// Twilight and night images need exposures of seconds to minutes, during which the sun or moon
+/// moves enough to smear the pattern of polarization.
+/// The window from `start` is divided into equal intervals and the sky is evaluated at the
+/// middle of each one, so every sample carries the same weight in time.
+/// Rays of a pixel are combined with [`Ray::mean`].
+///
+/// The [`SkyModel`](crate::model::SkyModel) scatters light from whichever source the ephemeris
+/// places in the sky, so pass a lunar ephemeris, e.g., a
+/// [`RecordedEphemeris`](crate::model::ephemeris::RecordedEphemeris) of the moon, to simulate
+/// moonlit skies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LongExposure<O> {
+    camera: Camera<O>,
+    pose: Pose<Ecef>,
+    start: DateTime<Utc>,
+    duration: TimeDelta,
+    samples: usize,
+}
+
+impl<O> LongExposure<O> {
+    /// Creates a new `LongExposure` that samples the window eight times.
+    ///
+    /// # Panics
+    /// Will panic if `duration` is negative.
+    pub fn new(
+        camera: Camera<O>,
+        pose: Pose<Ecef>,
+        start: DateTime<Utc>,
+        duration: TimeDelta,
+    ) -> Self {
+        assert!(
+            duration >= TimeDelta::zero(),
+            "duration must not be negative"
+        );
+        Self {
+            camera,
+            pose,
+            start,
+            duration,
+            samples: 8,
+        }
+    }
+
+    /// Sets the number of times the window is sampled.
+    ///
+    /// The number is clamped between one and [`u32::MAX`].
+    #[must_use]
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.clamp(1, u32::MAX as usize);
+        self
+    }
+
+    #[must_use]
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns the middle of the exposure window.
+    #[must_use]
+    pub fn midpoint(&self) -> DateTime<Utc> {
+        self.start + self.duration / 2
+    }
+
+    /// Returns an iterator over the time of each sample.
+    ///
+    /// Times are rounded down to the nanosecond.
+    pub fn times(&self) -> impl Iterator<Item = DateTime<Utc>> + use<O> {
+        let start = self.start;
+        let nanos = i128::from(self.duration.num_seconds()) * 1_000_000_000
+            + i128::from(self.duration.subsec_nanos());
+        // The sample count is validated to fit in a `u32`, so the products below cannot overflow.
+        let samples = u32::try_from(self.samples).expect("samples fit in a u32");
+        let intervals = 2 * i128::from(samples);
+        (0..samples).map(move |k| {
+            let offset = nanos * (2 * i128::from(k) + 1) / intervals;
+            // The offset is within the duration, so both parts fit in a `TimeDelta`.
+            let seconds = i64::try_from(offset / 1_000_000_000).expect("offset is within duration");
+            let subsec = i64::try_from(offset % 1_000_000_000).expect("remainder is below 1e9");
+            start + TimeDelta::seconds(seconds) + TimeDelta::nanoseconds(subsec)
+        })
+    }
+
+    fn simulations(&self, ephemeris: &impl SolarEphemeris) -> Option<Vec<Simulation<O>>>
+    where
+        O: Clone,
+    {
+        self.times()
+            .map(|time| Simulation::from_ephemeris(self.camera.clone(), self.pose, time, ephemeris))
+            .collect()
+    }
+
+    /// Returns the time-weighted mean azimuth and elevation of the source over the window.
+    ///
+    /// Estimators fit to a long exposure recover this bearing rather than the bearing at any one
+    /// instant.
+    /// The azimuth follows [`AzimuthConvention::NorthClockwise`].
+    /// Returns `None` if `ephemeris` does not know the position of the source at every sample.
+    #[must_use]
+    pub fn mean_bearing(&self, ephemeris: &impl SolarEphemeris) -> Option<(Angle, Angle)>
+    where
+        O: Clone,
+    {
+        let mean = Self::mean_position(&self.simulations(ephemeris)?);
+        Some((mean.azimuth(), mean.elevation()))
+    }
+
+    fn mean_position(simulations: &[Simulation<O>]) -> SolarPosition {
+        let mut sum = [0.0; 3];
+        for simulation in simulations {
+            let bearing = simulation.model.solar_bearing();
+            let (sin_az, cos_az) = (bearing.azimuth().sin().value, bearing.azimuth().cos().value);
+            let (sin_el, cos_el) = (
+                bearing.elevation().sin().value,
+                bearing.elevation().cos().value,
+            );
+            for (total, component) in sum
+                .iter_mut()
+                .zip([sin_az * cos_el, cos_az * cos_el, sin_el])
+            {
+                *total += component;
+            }
+        }
+
+        let [east, north, up] = sum;
+        SolarPosition::new(
+            AzimuthConvention::NorthClockwise.from_components(east, north),
+            Angle::new::<radian>(up.atan2(east.hypot(north))),
+        )
+    }
+
+    /// Simulates the image integrated over the exposure window.
+    ///
+    /// The image carries a [`FrameMeta`] with the middle of the window, its duration, the
+    /// position of the camera, and the [`LongExposure::mean_bearing`] of the source.
+    /// Returns `None` if `ephemeris` does not know the position of the source at every sample.
+    #[must_use]
+    pub fn ray_image(&self, ephemeris: &impl SolarEphemeris) -> Option<RayImage<GlobalFrame>>
+    where
+        O: Optic + Clone + Send + Sync,
+    {
+        let simulations = self.simulations(ephemeris)?;
+        let images: Vec<_> = simulations.iter().map(Simulation::par_ray_image).collect();
+
+        let mut rays: Vec<_> = images.iter().map(RayImage::rays).collect();
+        let means: Vec<_> = (0..self.camera.rows() * self.camera.cols())
+            .map(|_| {
+                let samples: Vec<_> = rays
+                    .iter_mut()
+                    .filter_map(|rays| rays.next().flatten())
+                    .collect();
+                Ray::mean(samples)
+            })
+            .collect();
+
+        #[allow(clippy::cast_precision_loss)]
+        let exposure =
+            Time::new::<second>(self.duration.num_microseconds().unwrap_or(i64::MAX) as f64 / 1e6);
+        let meta = FrameMeta::new()
+            .with_time(self.midpoint())
+            .with_exposure(exposure)
+            .with_position(self.pose.position().into())
+            .with_solar_position(Self::mean_position(&simulations));
+        Some(
+            RayImage::from_rays(means, self.camera.rows(), self.camera.cols())
+                .expect("one mean is computed for every pixel")
+                .with_meta(meta),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{model::ephemeris::RecordedEphemeris, optic::PinholeOptic};
+    use approx::assert_relative_eq;
+    use chrono::TimeZone;
+    use sguaba::{
+        Coordinate, engineering::Orientation, math::RigidBodyTransform, system, systems::Wgs84,
+    };
+    use uom::{
+        ConstZero,
+        si::{
+            angle::degree,
+            f64::Length,
+            length::{micron, millimeter},
+        },
+    };
+
+    system!(struct IntegrationEnu using ENU);
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, 20, 0, 0).unwrap()
+    }
+
+    /// A source that moves 40 degrees in azimuth over 40 minutes at a fixed elevation.
+    fn ephemeris() -> RecordedEphemeris {
+        let position =
+            |azimuth| SolarPosition::new(Angle::new::<degree>(azimuth), Angle::new::<degree>(30.0));
+        RecordedEphemeris::new([
+            (start(), position(100.0)),
+            (start() + TimeDelta::minutes(40), position(140.0)),
+        ])
+    }
+
+    fn exposure(duration: TimeDelta) -> LongExposure<PinholeOptic> {
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+            Length::new::<micron>(100.0),
+            8,
+            10,
+        );
+        let position = Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.5))
+            .altitude(Length::ZERO)
+            .build();
+        // Look straight up.
+        let pose = Pose::new(
+            Coordinate::origin(),
+            Orientation::<IntegrationEnu>::tait_bryan_builder()
+                .yaw(Angle::new::<degree>(0.0))
+                .pitch(Angle::new::<degree>(0.0))
+                .roll(Angle::new::<degree>(180.0))
+                .build(),
+        );
+        // SAFETY: The origin of IntegrationEnu is the position of the camera.
+        let enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&position) }.inverse();
+        LongExposure::new(camera, enu_to_ecef.transform(pose), start(), duration).with_samples(4)
+    }
+
+    #[test]
+    fn averages_source_over_window() {
+        let exposure = exposure(TimeDelta::minutes(40));
+        let times: Vec<_> = exposure.times().collect();
+        assert_eq!(times[0], start() + TimeDelta::minutes(5));
+        assert_eq!(times[3], start() + TimeDelta::minutes(35));
+
+        let (azimuth, elevation) = exposure.mean_bearing(&ephemeris()).unwrap();
+        assert_relative_eq!(azimuth.get::<degree>(), 120.0, epsilon = 1e-6);
+        assert!(elevation.get::<degree>() >= 30.0);
+
+        let image = exposure.ray_image(&ephemeris()).unwrap();
+        assert_eq!(image.meta().time(), Some(start() + TimeDelta::minutes(20)));
+        assert_relative_eq!(image.meta().exposure().unwrap().get::<second>(), 2400.0);
+        assert!(image.rays().all(|ray| ray.is_some()));
+        let solar = image.meta().solar_position().unwrap();
+        assert_eq!((solar.azimuth(), solar.elevation()), (azimuth, elevation));
+
+        // The window must lie within the ephemeris.
+        let late = LongExposure {
+            start: start() + TimeDelta::minutes(30),
+            ..exposure
+        };
+        assert_eq!(late.ray_image(&ephemeris()), None);
+    }
+
+    #[test]
+    fn samples_long_windows_exactly() {
+        // A window longer than `i32::MAX` milliseconds with an odd split.
+        let exposure = exposure(TimeDelta::days(30)).with_samples(7);
+        let times: Vec<_> = exposure.times().collect();
+        assert_eq!(times.len(), 7);
+        assert_eq!(times[3], exposure.midpoint());
+        assert_eq!(
+            times[0] - start(),
+            TimeDelta::nanoseconds(30 * 86_400 * 1_000_000_000 / 14)
+        );
+
+        // The sample count is never zero.
+        assert_eq!(exposure.with_samples(0).times().count(), 1);
+    }
+
+    #[test]
+    fn instant_exposure_matches_snapshot() {
+        let exposure = exposure(TimeDelta::zero());
+        let image = exposure.ray_image(&ephemeris()).unwrap();
+        let snapshot =
+            Simulation::from_ephemeris(exposure.camera, exposure.pose, start(), &ephemeris())
+                .unwrap()
+                .par_ray_image();
+
+        for (lhs, rhs) in image.rays().zip(snapshot.rays()) {
+            let (lhs, rhs) = (lhs.unwrap(), rhs.unwrap());
+            assert_relative_eq!(
+                Angle::from(lhs.aop()).get::<degree>(),
+                Angle::from(rhs.aop()).get::<degree>(),
+                epsilon = 1e-9
+            );
+            assert_relative_eq!(f64::from(lhs.dop()), f64::from(rhs.dop()), epsilon = 1e-9);
+        }
+    }
+}
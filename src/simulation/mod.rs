@@ -0,0 +1,645 @@
+pub mod bearings;
+pub mod cache;
+pub mod cloud;
+pub mod coverage;
+pub mod integration;
+pub mod randomize;
+pub mod skymap;
+pub mod targets;
+pub mod transit;
+
+use crate::{
+    image::RayImage,
+    iter::RayIterator,
+    meta::FrameMeta,
+    model::{
+        ElevationGate, ModelError, SkyModel, SolarCondition,
+        ephemeris::{SolarEphemeris, SolarPosition, Spa},
+        refraction::Refraction,
+    },
+    optic::{Camera, Optic, PixelCoordinate, RayDirection},
+    ray::{GlobalFrame, Ray},
+    render::overlay::SkyFeatures,
+};
+use chrono::{DateTime, Utc};
+use rayon::{
+    ThreadPool,
+    iter::{IntoParallelIterator, ParallelIterator},
+};
+use sguaba::{
+    Bearing, Vector,
+    engineering::{Orientation, Pose},
+    math::{RigidBodyTransform, Rotation},
+    system,
+    systems::{BearingDefined, Ecef},
+};
+use uom::{
+    ConstZero,
+    si::{
+        angle::radian,
+        angular_velocity::radian_per_second,
+        f64::{Angle, AngularVelocity, Time},
+        time::second,
+    },
+};
+
+system!(
+    /// Global frame of the simulation.
+    ///
+    /// Axes are aligned with east, north, and up.
+    /// The origin is the position of the [`Pose`] given to the simulation and the orientation of
+    /// the camera is defined in this frame.
+    pub struct SimulationEnu using ENU
+);
+
+system!(
+    /// Body frame of the camera.
+    ///
+    /// X points towards the right of the image.
+    /// Y points towards the top of the image.
+    /// Z points towards the viewer (away from the sky).
+    pub struct CameraXyz using right-handed XYZ
+);
+
+/// This type describes a [`Camera`] with a [`Pose`] viewing a [`SkyModel`].
+/// It is responsible for mapping [`PixelCoordinate`]s from the [`Camera`] onto [`Ray`]s from
+/// incident skylight.
+/// [`Ray`]s encode the polarization state (i.e., the angle and degree of polarization) for
+/// different regions of the sky.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Simulation<O> {
+    camera: Camera<O>,
+    camera_pose: Pose<SimulationEnu>,
+    ecef_to_enu: RigidBodyTransform<Ecef, SimulationEnu>,
+    lever_arm: Vector<CameraXyz>,
+    model: SkyModel<SimulationEnu>,
+    rolling_shutter: Option<RollingShutter>,
+}
+
+impl<O> Simulation<O> {
+    /// Construct a simulation from a [`Camera`] with a [`Pose`] in [`Ecef`] and at a
+    /// [`DateTime<Utc>`].
+    ///
+    /// The time is used to construct a [`SkyModel`] which requires knowing the position of the sun
+    /// in the sky.
+    /// This is determined with the time provided and the position of the camera taken from its pose.
+    ///
+    /// # Panics
+    /// Will panic if the solar position algorithm rejects the position of the camera.
+    /// Since [`Ecef`] positions are always valid this should not be a concern.
+    pub fn new(camera: Camera<O>, camera_pose: Pose<Ecef>, time: DateTime<Utc>) -> Self {
+        Self::from_ephemeris(camera, camera_pose, time, &Spa)
+            .expect("solar position algorithm covers every position")
+    }
+
+    /// Construct a simulation with the position of the sun taken from `ephemeris`.
+    ///
+    /// Use an [`RecordedEphemeris`](crate::model::ephemeris::RecordedEphemeris) to replay a
+    /// previous run exactly or to test without depending on the precision of the solar position
+    /// algorithm.
+    /// Returns `None` if `ephemeris` does not know the position of the sun at `time`.
+    pub fn from_ephemeris(
+        camera: Camera<O>,
+        camera_pose: Pose<Ecef>,
+        time: DateTime<Utc>,
+        ephemeris: &impl SolarEphemeris,
+    ) -> Option<Self> {
+        // SAFETY: The origin of SimulationEnu is the position of the pose.
+        let model = unsafe { SkyModel::from_ephemeris(ephemeris, camera_pose.position(), time) }?;
+        let ecef_to_enu =
+            unsafe { RigidBodyTransform::ecef_to_enu_at(&camera_pose.position().into()) };
+        Some(Self {
+            camera,
+            camera_pose: ecef_to_enu.transform(camera_pose),
+            ecef_to_enu,
+            lever_arm: Vector::zero(),
+            model,
+            rolling_shutter: None,
+        })
+    }
+
+    /// Exposes each row of the sensor at a different time while the camera rotates.
+    ///
+    /// The [`Pose`] of the simulation is the pose of the camera when the first row is exposed.
+    /// Rows are exposed in order from the top of the image.
+    /// Only [`Simulation::ray`] and the images built from it use the row times.
+    /// [`Simulation::sky_features`] and [`Simulation::project`] use the pose of the first row.
+    #[must_use]
+    pub fn with_rolling_shutter(mut self, rolling_shutter: RollingShutter) -> Self {
+        self.rolling_shutter = Some(rolling_shutter);
+        self
+    }
+
+    /// Corrects the simulated sky for atmospheric `refraction`.
+    ///
+    /// Each pixel sees the sky at an apparent bearing, which is lowered to its true elevation
+    /// before the [`SkyModel`] is evaluated.
+    /// [`Simulation::sky_features`] places the sun at its apparent bearing.
+    /// See [`SkyModel::with_refraction`].
+    #[must_use]
+    pub fn with_refraction(mut self, refraction: Refraction) -> Self {
+        self.model = self.model.with_refraction(refraction);
+        self
+    }
+
+    /// Places the camera at `lever_arm` from the position of its [`Pose`].
+    ///
+    /// Use it when the position comes from another sensor on the same body, e.g., a GNSS antenna.
+    /// The lever arm only moves the viewpoint of [`Simulation::pixel_from_enu`],
+    /// [`Simulation::pixel_from_wgs84`], and [`Simulation::enu_from_pixel`] since the sky is at
+    /// infinity.
+    #[must_use]
+    pub fn with_lever_arm(mut self, lever_arm: Vector<CameraXyz>) -> Self {
+        self.lever_arm = lever_arm;
+        self
+    }
+
+    #[must_use]
+    pub fn lever_arm(&self) -> Vector<CameraXyz> {
+        self.lever_arm
+    }
+
+    /// Checks the elevation of the sun at the time of the simulation against `gate`.
+    ///
+    /// The simulation evaluates the single scattering model at any solar elevation, so check it
+    /// before comparing simulated rays with measurements near twilight.
+    ///
+    /// # Errors
+    /// Will return `Err` if `gate` rejects the elevation of the sun.
+    pub fn check_solar_elevation(
+        &self,
+        gate: &ElevationGate,
+    ) -> Result<SolarCondition, ModelError> {
+        self.model.check_solar_elevation(gate)
+    }
+
+    /// Returns the true position of the sun assumed by the simulation.
+    #[must_use]
+    pub fn solar_position(&self) -> SolarPosition {
+        let bearing = self.model.solar_bearing();
+        SolarPosition::new(bearing.azimuth(), bearing.elevation())
+    }
+
+    /// Returns the position at which the sun appears after [`Simulation::with_refraction`].
+    #[must_use]
+    pub fn apparent_solar_position(&self) -> SolarPosition {
+        let bearing = self.model.apparent_solar_bearing();
+        SolarPosition::new(bearing.azimuth(), bearing.elevation())
+    }
+
+    /// Returns the orientation of the camera when `row` is exposed.
+    fn orientation(&self, row: usize) -> Orientation<SimulationEnu> {
+        let orientation = self.camera_pose.orientation();
+        let Some(rolling_shutter) = self.rolling_shutter else {
+            return orientation;
+        };
+
+        let elapsed = rolling_shutter.elapsed(row);
+        let turned =
+            |rate: AngularVelocity| Angle::new::<radian>(rate.get::<radian_per_second>() * elapsed);
+
+        let (yaw, pitch, roll) = orientation.to_tait_bryan_angles();
+        let (yaw_rate, pitch_rate, roll_rate) = rolling_shutter.rates();
+        Orientation::<SimulationEnu>::tait_bryan_builder()
+            .yaw(yaw + turned(yaw_rate))
+            .pitch(pitch + turned(pitch_rate))
+            .roll(roll + turned(roll_rate))
+            .build()
+    }
+
+    /// # Panics
+    /// Panics if the [`crate::optic::RayDirection`] returned by the [`Camera`] points behind the
+    /// plane of the sensor.
+    /// This would represent a field of view larger than 180 degrees.
+    pub fn ray(&self, pixel: impl AsRef<PixelCoordinate>) -> Option<Ray<GlobalFrame>>
+    where
+        O: Optic,
+    {
+        let bearing_sim = self.sky_bearing(pixel)?;
+
+        Some(Ray::new(
+            self.model.aop(bearing_sim)?,
+            self.model.dop(bearing_sim)?,
+        ))
+    }
+
+    /// Traces `pixel` into the sky at the time its row is exposed.
+    ///
+    /// # Panics
+    /// Panics if the [`crate::optic::RayDirection`] returned by the [`Camera`] points behind the
+    /// plane of the sensor.
+    fn sky_bearing(&self, pixel: impl AsRef<PixelCoordinate>) -> Option<Bearing<SimulationEnu>>
+    where
+        O: Optic,
+    {
+        let row = pixel.as_ref().row();
+
+        // Defined in the body frame of the camera.
+        let ray_direction = self.camera.trace_from_pixel(pixel)?;
+
+        // FIXME: This should probably be allowed... what happens if a lens has a FOV > 180?
+        let bearing_cam =
+            CameraXyz::spherical_to_bearing(ray_direction.polar(), ray_direction.azimuth())
+                .unwrap();
+
+        // SAFETY: The position of camera_pose lies at the origin of CameraXyz.
+        let cam_to_sim: Rotation<CameraXyz, SimulationEnu> =
+            unsafe { self.orientation(row).map_as_zero_in::<CameraXyz>() }.inverse();
+        Some(cam_to_sim.transform(bearing_cam))
+    }
+
+    /// Returns the pixel that images `bearing` if it is in front of the sensor and on it.
+    fn pixel_from_bearing(&self, bearing: Bearing<SimulationEnu>) -> Option<PixelCoordinate>
+    where
+        O: Optic,
+    {
+        match self.trace_bearing(bearing) {
+            Projection::Projected(pixel) => Some(pixel),
+            _ => None,
+        }
+    }
+
+    /// Traces `bearing` onto the sensor without considering the horizon.
+    fn trace_bearing(&self, bearing: Bearing<SimulationEnu>) -> Projection
+    where
+        O: Optic,
+    {
+        // SAFETY: The position of camera_pose lies at the origin of CameraXyz.
+        let sim_to_cam: Rotation<SimulationEnu, CameraXyz> =
+            unsafe { self.camera_pose.orientation().map_as_zero_in::<CameraXyz>() };
+        let (polar, azimuth) = CameraXyz::bearing_to_spherical(sim_to_cam.transform(bearing));
+
+        // Bearings in front of the sensor point towards negative Z.
+        if polar <= Angle::HALF_TURN / 2.0 {
+            return Projection::BehindSensor;
+        }
+
+        self.camera
+            .trace_from_bearing(RayDirection::from_angles(polar, azimuth))
+            .map_or(Projection::OffSensor, Projection::Projected)
+    }
+
+    /// Projects sky directions onto the [`Camera`]'s image sensor and reports what happened to
+    /// each one.
+    ///
+    /// Each item of `bearings` is an azimuth and an elevation in the local east, north, up frame
+    /// of the camera.
+    /// Directions below the horizon are never projected even if the camera could image them.
+    /// Elevations above 90 degrees are reported as [`Projection::InvalidElevation`].
+    pub fn project(&self, bearings: impl IntoIterator<Item = (Angle, Angle)>) -> ProjectionReport
+    where
+        O: Optic,
+    {
+        let projections = bearings
+            .into_iter()
+            .map(|(azimuth, elevation)| {
+                if elevation < Angle::ZERO {
+                    return Projection::BelowHorizon;
+                }
+
+                Bearing::<SimulationEnu>::builder()
+                    .azimuth(azimuth)
+                    .elevation(elevation)
+                    .map_or(Projection::InvalidElevation, |bearing| {
+                        self.trace_bearing(bearing.build())
+                    })
+            })
+            .collect();
+
+        ProjectionReport { projections }
+    }
+
+    /// Locates the sun, the zenith, and the solar and anti-solar meridians on the [`Camera`]'s
+    /// image sensor.
+    ///
+    /// Each meridian is sampled at `samples` evenly spaced elevations from the horizon to the
+    /// zenith.
+    /// Use [`SkyFeatures::draw`] to overlay them on images rendered from the same sensor.
+    ///
+    /// # Panics
+    /// Panics if the solar bearing of the [`SkyModel`] is invalid.
+    /// This should never occur.
+    pub fn sky_features(&self, samples: usize) -> SkyFeatures
+    where
+        O: Optic,
+    {
+        let bearing = |azimuth: Angle, elevation: Angle| {
+            Bearing::<SimulationEnu>::builder()
+                .azimuth(azimuth)
+                .elevation(elevation)
+                .expect("elevation is on the range -90 to 90")
+                .build()
+        };
+        let solar_azimuth = self.model.solar_bearing().azimuth();
+        let meridian = |azimuth: Angle| {
+            (0..samples)
+                .map(|i| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let t = i as f64 / samples.saturating_sub(1).max(1) as f64;
+                    self.pixel_from_bearing(bearing(azimuth, t * Angle::HALF_TURN / 2.0))
+                })
+                .collect()
+        };
+
+        SkyFeatures {
+            sun: self.pixel_from_bearing(self.model.apparent_solar_bearing()),
+            zenith: self.pixel_from_bearing(bearing(Angle::ZERO, Angle::HALF_TURN / 2.0)),
+            solar_meridian: meridian(solar_azimuth),
+            anti_solar_meridian: meridian(solar_azimuth + Angle::HALF_TURN),
+        }
+    }
+
+    /// The image carries a [`FrameMeta`] with the [`Simulation::solar_position`].
+    ///
+    /// # Panics
+    /// Panics if the dimensions of the [`Camera`]'s image sensor do not match the results returned
+    /// by [`Camera::pixels`].
+    /// This should never occur.
+    pub fn ray_image(&self) -> RayImage<GlobalFrame>
+    where
+        O: Optic,
+    {
+        RayImage::from_rays(
+            self.camera.pixels().map(|px| self.ray(px)),
+            self.camera.rows(),
+            self.camera.cols(),
+        )
+        .unwrap()
+        .with_meta(self.meta())
+    }
+
+    /// Returns the metadata of the images simulated by this simulation.
+    fn meta(&self) -> FrameMeta {
+        FrameMeta::new().with_solar_position(self.solar_position())
+    }
+
+    /// Returns a lazy iterator over the simulated rays in row-major order.
+    ///
+    /// Each ray is only simulated when it is reached, so filters, decimation, and estimators can
+    /// consume a subset of the rays without building a whole [`RayImage`].
+    /// Pixels that do not see the sky are skipped.
+    pub fn rays(&self) -> SimulatedRays<'_, O> {
+        SimulatedRays {
+            simulation: self,
+            index: 0,
+        }
+    }
+
+    /// Simulates every pixel in parallel on the current rayon thread pool.
+    ///
+    /// The result is identical to [`Simulation::ray_image`] regardless of the number of threads.
+    /// Rays are always stored in row-major order because they are collected from an indexed
+    /// parallel iterator.
+    ///
+    /// # Panics
+    /// Panics if the dimensions of the [`Camera`]'s image sensor do not match the results returned
+    /// by [`Camera::pixels`].
+    /// This should never occur.
+    pub fn par_ray_image(&self) -> RayImage<GlobalFrame>
+    where
+        O: Optic + Send + Sync,
+    {
+        let pixels: Vec<_> = self.camera.pixels().collect();
+        let rays: Vec<_> = pixels.into_par_iter().map(|px| self.ray(px)).collect();
+        RayImage::from_rays(rays, self.camera.rows(), self.camera.cols())
+            .unwrap()
+            .with_meta(self.meta())
+    }
+
+    /// Simulates every pixel in parallel on `pool`.
+    ///
+    /// Use this to bound the threads used by rumpus when it is embedded in a larger rayon
+    /// program.
+    /// See [`Simulation::par_ray_image`].
+    pub fn par_ray_image_in(&self, pool: &ThreadPool) -> RayImage<GlobalFrame>
+    where
+        O: Optic + Send + Sync,
+    {
+        pool.install(|| self.par_ray_image())
+    }
+}
+
+/// A lazy iterator over the rays of a [`Simulation`].
+///
+/// See [`Simulation::rays`].
+pub struct SimulatedRays<'a, O> {
+    simulation: &'a Simulation<O>,
+    index: usize,
+}
+
+impl<O: Optic> Iterator for SimulatedRays<'_, O> {
+    type Item = Ray<GlobalFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cols = self.simulation.camera.cols();
+        let len = self.simulation.camera.rows() * cols;
+        while self.index < len {
+            let pixel = PixelCoordinate::new(self.index / cols, self.index % cols);
+            self.index += 1;
+            if let Some(ray) = self.simulation.ray(pixel) {
+                return Some(ray);
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.simulation.camera.rows() * self.simulation.camera.cols();
+        (0, Some(len - self.index))
+    }
+}
+
+// All of RayIterator's functions are defined using Iterator.
+impl<O: Optic> RayIterator<GlobalFrame> for SimulatedRays<'_, O> {}
+
+// Used to convert from the polar angle convention to the elevation angle convention.
+// The elevation angle is taken from the horizontal plane positive towards Z.
+// Bearings from the camera should have a negative elevation angle.
+impl BearingDefined for CameraXyz {
+    fn bearing_to_spherical(bearing: Bearing<Self>) -> (Angle, Angle) {
+        let polar = Angle::HALF_TURN / 2.0 - bearing.elevation();
+        let azimuth = bearing.azimuth();
+        (polar, azimuth)
+    }
+
+    fn spherical_to_bearing(
+        polar: impl Into<Angle>,
+        azimuth: impl Into<Angle>,
+    ) -> Option<Bearing<Self>> {
+        let elevation = Angle::HALF_TURN / 2.0 - polar.into();
+        let azimuth = azimuth.into();
+
+        Some(
+            Bearing::builder()
+                .azimuth(azimuth)
+                .elevation(elevation)?
+                .build(),
+        )
+    }
+}
+
+/// The outcome of projecting a sky direction onto an image sensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// The direction is imaged by this pixel.
+    Projected(PixelCoordinate),
+
+    /// The direction is below the horizon.
+    BelowHorizon,
+
+    /// The direction points behind the plane of the sensor.
+    BehindSensor,
+
+    /// The direction is in front of the sensor but outside of its field of view.
+    OffSensor,
+
+    /// The elevation of the direction is above 90 degrees or not a number.
+    InvalidElevation,
+}
+
+impl Projection {
+    /// Returns the pixel if the direction was projected.
+    #[must_use]
+    pub fn pixel(&self) -> Option<PixelCoordinate> {
+        match self {
+            Projection::Projected(pixel) => Some(*pixel),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of projecting many sky directions.
+///
+/// See [`Simulation::project`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProjectionReport {
+    projections: Vec<Projection>,
+}
+
+impl ProjectionReport {
+    /// Returns the outcome for each direction in the order they were projected.
+    #[must_use]
+    pub fn projections(&self) -> &[Projection] {
+        &self.projections
+    }
+
+    /// Returns an iterator over the pixel of each direction if it was projected.
+    pub fn pixels(&self) -> impl Iterator<Item = Option<PixelCoordinate>> {
+        self.projections.iter().map(Projection::pixel)
+    }
+
+    /// The number of directions imaged by a pixel.
+    #[must_use]
+    pub fn projected(&self) -> usize {
+        self.count(|projection| matches!(projection, Projection::Projected(_)))
+    }
+
+    /// The number of directions dropped because they are below the horizon.
+    #[must_use]
+    pub fn below_horizon(&self) -> usize {
+        self.count(|projection| *projection == Projection::BelowHorizon)
+    }
+
+    /// The number of directions dropped because they point behind the sensor.
+    #[must_use]
+    pub fn behind_sensor(&self) -> usize {
+        self.count(|projection| *projection == Projection::BehindSensor)
+    }
+
+    /// The number of directions dropped because they are outside of the field of view.
+    #[must_use]
+    pub fn off_sensor(&self) -> usize {
+        self.count(|projection| *projection == Projection::OffSensor)
+    }
+
+    /// The number of directions dropped because their elevation is invalid.
+    #[must_use]
+    pub fn invalid_elevation(&self) -> usize {
+        self.count(|projection| *projection == Projection::InvalidElevation)
+    }
+
+    fn count(&self, predicate: impl Fn(&Projection) -> bool) -> usize {
+        self.projections.iter().filter(|p| predicate(p)).count()
+    }
+}
+
+/// Describes a rolling shutter sensor on a rotating platform.
+///
+/// The camera rotates at constant yaw, pitch, and roll rates while rows are exposed one
+/// `line_time` apart.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RollingShutter {
+    line_time: Time,
+    yaw_rate: AngularVelocity,
+    pitch_rate: AngularVelocity,
+    roll_rate: AngularVelocity,
+}
+
+impl RollingShutter {
+    /// Creates a new `RollingShutter` that exposes each row `line_time` after the previous one
+    /// on a platform that is not rotating.
+    #[must_use]
+    pub fn new(line_time: Time) -> Self {
+        Self {
+            line_time,
+            yaw_rate: AngularVelocity::ZERO,
+            pitch_rate: AngularVelocity::ZERO,
+            roll_rate: AngularVelocity::ZERO,
+        }
+    }
+
+    /// Sets the rates of change of the Tait-Bryan angles of the camera.
+    #[must_use]
+    pub fn with_rates(
+        mut self,
+        yaw_rate: AngularVelocity,
+        pitch_rate: AngularVelocity,
+        roll_rate: AngularVelocity,
+    ) -> Self {
+        self.yaw_rate = yaw_rate;
+        self.pitch_rate = pitch_rate;
+        self.roll_rate = roll_rate;
+        self
+    }
+
+    /// Returns the time between exposing consecutive rows.
+    #[must_use]
+    pub fn line_time(&self) -> Time {
+        self.line_time
+    }
+
+    /// Returns the yaw, pitch, and roll rates.
+    #[must_use]
+    pub fn rates(&self) -> (AngularVelocity, AngularVelocity, AngularVelocity) {
+        (self.yaw_rate, self.pitch_rate, self.roll_rate)
+    }
+
+    /// The time in seconds between exposing the first row and `row`.
+    #[allow(clippy::cast_precision_loss)]
+    fn elapsed(&self, row: usize) -> f64 {
+        row as f64 * self.line_time.get::<second>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Angle::HALF_TURN/2.0)]
+    #[case(Angle::HALF_TURN/4.0)]
+    fn bearing_cam_xyz_roundtrip(#[case] elevation: Angle) {
+        let bearing = Bearing::<CameraXyz>::builder()
+            .azimuth(Angle::ZERO)
+            .elevation(elevation)
+            .unwrap()
+            .build();
+
+        let (polar, azimuth) = CameraXyz::bearing_to_spherical(bearing);
+        let result = CameraXyz::spherical_to_bearing(polar, azimuth);
+
+        assert_eq!(result, Some(bearing));
+    }
+}
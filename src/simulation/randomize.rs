@@ -0,0 +1,538 @@
+//! Randomized simulations for generating training corpora.
+
+use super::{
+    Simulation,
+    cloud::{CloudLayer, CloudMask},
+};
+use crate::{
+    image::RayImage,
+    light::{aop::Aop, dop::Dop},
+    meta::FrameMeta,
+    model::ephemeris::SolarPosition,
+    optic::{Camera, Optic},
+    ray::{GlobalFrame, Ray},
+    rng::{SplitMix64, hash},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use sguaba::{
+    Coordinate,
+    engineering::{Orientation, Pose},
+    math::RigidBodyTransform,
+    system,
+    systems::Wgs84,
+};
+use std::ops::{Add, Mul, Sub};
+use uom::{
+    ConstZero,
+    si::{
+        f64::{Angle, Length, Time},
+        time::second,
+    },
+};
+
+// Local frame of a randomized camera.
+// Axes are aligned with east, north, and up.
+system!(struct RandomEnu using ENU);
+
+/// How many positions and times are drawn for a sample before giving up on daylight.
+const MAX_ATTEMPTS: usize = 64;
+
+/// A distribution that a [`Randomizer`] draws a parameter from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution<T> {
+    /// Always draws `T`.
+    Fixed(T),
+
+    /// Draws uniformly from `min` up to `max`.
+    Uniform { min: T, max: T },
+
+    /// Draws from a normal distribution.
+    Normal { mean: T, std_dev: T },
+}
+
+impl<T> Distribution<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+{
+    fn sample(&self, rng: &mut SplitMix64) -> T {
+        match *self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { min, max } => min + (max - min) * rng.next_f64(),
+            Distribution::Normal { mean, std_dev } => mean + std_dev * rng.next_normal(),
+        }
+    }
+}
+
+/// Generates randomized simulations with their ground truth for training learned estimators.
+///
+/// Each sample draws a time, a position, the orientation of the [`Camera`], the maximum degree
+/// of polarization, per-pixel noise, and a [`CloudMask`] from configurable [`Distribution`]s.
+/// Samples are drawn from a generator seeded by the seed of the `Randomizer` and the index of the
+/// sample, so any sample can be regenerated on its own and corpora can be split across machines.
+///
+/// Orientations are the Tait-Bryan angles of the camera in its local east, north, up frame, as
+/// for [`Simulation::new`].
+/// By default, the yaw is uniform over a full turn and the camera looks at the zenith.
+/// The maximum degree of polarization scales the single scattering pattern to stand in for the
+/// depolarization of a turbid atmosphere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Randomizer<O> {
+    camera: Camera<O>,
+    seed: u64,
+    start: DateTime<Utc>,
+    elapsed: Distribution<Time>,
+    latitude: Distribution<Angle>,
+    longitude: Distribution<Angle>,
+    yaw: Distribution<Angle>,
+    pitch: Distribution<Angle>,
+    roll: Distribution<Angle>,
+    min_solar_elevation: Angle,
+    max_dop: Distribution<f64>,
+    aop_noise: Distribution<Angle>,
+    dop_noise: Distribution<f64>,
+    cloud_coverage: Distribution<f64>,
+    cloud_scale: Distribution<f64>,
+}
+
+impl<O> Randomizer<O> {
+    /// Creates a new `Randomizer` for `camera` that draws times from `start` onwards.
+    ///
+    /// By default, every sample is captured at `start` on the equator at the prime meridian,
+    /// without noise or clouds.
+    #[must_use]
+    pub fn new(camera: Camera<O>, start: DateTime<Utc>, seed: u64) -> Self {
+        Self {
+            camera,
+            seed,
+            start,
+            elapsed: Distribution::Fixed(Time::ZERO),
+            latitude: Distribution::Fixed(Angle::ZERO),
+            longitude: Distribution::Fixed(Angle::ZERO),
+            yaw: Distribution::Uniform {
+                min: -Angle::HALF_TURN,
+                max: Angle::HALF_TURN,
+            },
+            pitch: Distribution::Fixed(Angle::ZERO),
+            roll: Distribution::Fixed(Angle::HALF_TURN),
+            min_solar_elevation: Angle::ZERO,
+            max_dop: Distribution::Fixed(1.0),
+            aop_noise: Distribution::Fixed(Angle::ZERO),
+            dop_noise: Distribution::Fixed(0.0),
+            cloud_coverage: Distribution::Fixed(0.0),
+            cloud_scale: Distribution::Fixed(16.0),
+        }
+    }
+
+    /// Sets the distribution of the time after the start at which each sample is captured.
+    #[must_use]
+    pub fn with_elapsed(mut self, elapsed: Distribution<Time>) -> Self {
+        self.elapsed = elapsed;
+        self
+    }
+
+    /// Sets the distributions of the latitude and longitude of the camera.
+    ///
+    /// Latitudes are clamped to the range -90 to 90 degrees.
+    #[must_use]
+    pub fn with_position(
+        mut self,
+        latitude: Distribution<Angle>,
+        longitude: Distribution<Angle>,
+    ) -> Self {
+        self.latitude = latitude;
+        self.longitude = longitude;
+        self
+    }
+
+    /// Sets the distributions of the orientation of the camera.
+    #[must_use]
+    pub fn with_orientation(
+        mut self,
+        yaw: Distribution<Angle>,
+        pitch: Distribution<Angle>,
+        roll: Distribution<Angle>,
+    ) -> Self {
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.roll = roll;
+        self
+    }
+
+    /// Sets the lowest elevation of the sun that a sample may be captured at.
+    ///
+    /// Times and positions are redrawn until the sun is high enough.
+    #[must_use]
+    pub fn with_min_solar_elevation(mut self, min_solar_elevation: Angle) -> Self {
+        self.min_solar_elevation = min_solar_elevation;
+        self
+    }
+
+    /// Sets the distribution of the largest degree of polarization in the sky.
+    #[must_use]
+    pub fn with_max_dop(mut self, max_dop: Distribution<f64>) -> Self {
+        self.max_dop = max_dop;
+        self
+    }
+
+    /// Sets the distributions of the standard deviations of the per-pixel noise added to the
+    /// AoP and DoP.
+    #[must_use]
+    pub fn with_noise(
+        mut self,
+        aop_noise: Distribution<Angle>,
+        dop_noise: Distribution<f64>,
+    ) -> Self {
+        self.aop_noise = aop_noise;
+        self.dop_noise = dop_noise;
+        self
+    }
+
+    /// Sets the distributions of the coverage and the size in pixels of Perlin noise clouds.
+    ///
+    /// See [`CloudMask::perlin`].
+    #[must_use]
+    pub fn with_clouds(mut self, coverage: Distribution<f64>, scale: Distribution<f64>) -> Self {
+        self.cloud_coverage = coverage;
+        self.cloud_scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub fn camera(&self) -> &Camera<O> {
+        &self.camera
+    }
+
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Simulates the sample at `index`.
+    ///
+    /// The image carries a [`FrameMeta`] with its time, index, and the position of the camera.
+    /// Returns `None` if no time and position with the sun above the minimum elevation was
+    /// drawn.
+    ///
+    /// # Panics
+    /// Will panic if the solar position algorithm rejects the position of the camera.
+    /// This should never occur.
+    #[must_use]
+    pub fn sample(&self, index: u64) -> Option<RandomSample>
+    where
+        O: Optic + Clone + Send + Sync,
+    {
+        let mut rng = SplitMix64::new(hash(self.seed, index, 0));
+
+        let (simulation, mut label) = (0..MAX_ATTEMPTS).find_map(|_| {
+            let label = self.draw(&mut rng, index);
+            let simulation = self.simulation(&label);
+            let sun = simulation.model.solar_bearing();
+            (sun.elevation() >= self.min_solar_elevation).then_some((
+                simulation,
+                SampleLabel {
+                    solar_azimuth: sun.azimuth(),
+                    solar_elevation: sun.elevation(),
+                    ..label
+                },
+            ))
+        })?;
+
+        label.max_dop = self.max_dop.sample(&mut rng).clamp(0.0, 1.0);
+        label.aop_noise = self.aop_noise.sample(&mut rng).abs();
+        label.dop_noise = self.dop_noise.sample(&mut rng).abs();
+        label.cloud_coverage = self.cloud_coverage.sample(&mut rng).clamp(0.0, 1.0);
+        let cloud_scale = self.cloud_scale.sample(&mut rng).max(f64::EPSILON);
+        let cloud_seed = rng.next_u64();
+
+        let mut noise = SplitMix64::new(hash(self.seed, index, 1));
+        let image = simulation.par_ray_image();
+        let rays = image.rays().map(|ray| {
+            ray.map(|ray| {
+                let aop = Angle::from(ray.aop()) + label.aop_noise * noise.next_normal();
+                let dop =
+                    f64::from(ray.dop()) * label.max_dop + label.dop_noise * noise.next_normal();
+                Ray::new(Aop::from_angle_wrapped(aop), Dop::clamped(dop))
+            })
+        });
+        let mut image =
+            RayImage::from_rays(rays, image.rows(), image.cols()).expect("extents are unchanged");
+
+        if label.cloud_coverage > 0.0 {
+            image = CloudLayer::new(CloudMask::perlin(
+                cloud_scale,
+                label.cloud_coverage,
+                cloud_seed,
+            ))
+            .with_seed(cloud_seed)
+            .apply(&image)
+            .expect("procedural masks cover any extents");
+        }
+
+        let meta = FrameMeta::new()
+            .with_time(label.time)
+            .with_sequence(index)
+            .with_position(label.position)
+            .with_solar_position(SolarPosition::new(
+                label.solar_azimuth,
+                label.solar_elevation,
+            ));
+        Some(RandomSample {
+            image: image.with_meta(meta),
+            label,
+        })
+    }
+
+    /// Returns an iterator that simulates the samples at indices from zero up to `count`.
+    ///
+    /// Samples without daylight are skipped.
+    pub fn samples(&self, count: u64) -> impl Iterator<Item = RandomSample> + '_
+    where
+        O: Optic + Clone + Send + Sync,
+    {
+        (0..count).filter_map(|index| self.sample(index))
+    }
+
+    /// Draws the time, position, and orientation of a sample.
+    fn draw(&self, rng: &mut SplitMix64, index: u64) -> SampleLabel {
+        #[allow(clippy::cast_possible_truncation)]
+        let elapsed =
+            TimeDelta::nanoseconds((self.elapsed.sample(rng).get::<second>() * 1e9) as i64);
+        let latitude = self
+            .latitude
+            .sample(rng)
+            .max(-Angle::HALF_TURN / 2.0)
+            .min(Angle::HALF_TURN / 2.0);
+        let position = Wgs84::builder()
+            .latitude(latitude)
+            .expect("latitude is clamped to the range -90 to 90")
+            .longitude(self.longitude.sample(rng))
+            .altitude(Length::ZERO)
+            .build();
+
+        SampleLabel {
+            index,
+            time: self.start + elapsed,
+            position,
+            yaw: self.yaw.sample(rng),
+            pitch: self.pitch.sample(rng),
+            roll: self.roll.sample(rng),
+            solar_azimuth: Angle::ZERO,
+            solar_elevation: Angle::ZERO,
+            max_dop: 1.0,
+            aop_noise: Angle::ZERO,
+            dop_noise: 0.0,
+            cloud_coverage: 0.0,
+        }
+    }
+
+    fn simulation(&self, label: &SampleLabel) -> Simulation<O>
+    where
+        O: Clone,
+    {
+        let pose = Pose::new(
+            Coordinate::origin(),
+            Orientation::<RandomEnu>::tait_bryan_builder()
+                .yaw(label.yaw)
+                .pitch(label.pitch)
+                .roll(label.roll)
+                .build(),
+        );
+        // SAFETY: The origin of RandomEnu is the position of the camera.
+        let enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&label.position) }.inverse();
+        Simulation::new(self.camera.clone(), enu_to_ecef.transform(pose), label.time)
+    }
+}
+
+/// A randomized simulation and its ground truth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomSample {
+    image: RayImage<GlobalFrame>,
+    label: SampleLabel,
+}
+
+impl RandomSample {
+    #[must_use]
+    pub fn image(&self) -> &RayImage<GlobalFrame> {
+        &self.image
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &SampleLabel {
+        &self.label
+    }
+
+    #[must_use]
+    pub fn into_parts(self) -> (RayImage<GlobalFrame>, SampleLabel) {
+        (self.image, self.label)
+    }
+}
+
+/// The parameters drawn for a [`RandomSample`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleLabel {
+    index: u64,
+    time: DateTime<Utc>,
+    position: Wgs84,
+    yaw: Angle,
+    pitch: Angle,
+    roll: Angle,
+    solar_azimuth: Angle,
+    solar_elevation: Angle,
+    max_dop: f64,
+    aop_noise: Angle,
+    dop_noise: f64,
+    cloud_coverage: f64,
+}
+
+impl SampleLabel {
+    #[must_use]
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    #[must_use]
+    pub fn position(&self) -> Wgs84 {
+        self.position
+    }
+
+    /// Returns the yaw, pitch, and roll of the camera in its local east, north, up frame.
+    #[must_use]
+    pub fn orientation(&self) -> (Angle, Angle, Angle) {
+        (self.yaw, self.pitch, self.roll)
+    }
+
+    /// Returns the azimuth and elevation of the sun in the local east, north, up frame.
+    #[must_use]
+    pub fn solar_bearing(&self) -> (Angle, Angle) {
+        (self.solar_azimuth, self.solar_elevation)
+    }
+
+    #[must_use]
+    pub fn max_dop(&self) -> f64 {
+        self.max_dop
+    }
+
+    /// Returns the standard deviations of the noise added to the AoP and DoP.
+    #[must_use]
+    pub fn noise(&self) -> (Angle, f64) {
+        (self.aop_noise, self.dop_noise)
+    }
+
+    #[must_use]
+    pub fn cloud_coverage(&self) -> f64 {
+        self.cloud_coverage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optic::PinholeOptic;
+    use uom::si::{
+        angle::degree,
+        length::{micron, millimeter},
+        time::hour,
+    };
+
+    fn randomizer(seed: u64) -> Randomizer<PinholeOptic> {
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+            Length::new::<micron>(50.0),
+            12,
+            16,
+        );
+        let start = "2025-06-13T00:00:00+00:00".parse().unwrap();
+
+        Randomizer::new(camera, start, seed)
+            .with_elapsed(Distribution::Uniform {
+                min: Time::ZERO,
+                max: Time::new::<hour>(24.0),
+            })
+            .with_position(
+                Distribution::Uniform {
+                    min: Angle::new::<degree>(-60.0),
+                    max: Angle::new::<degree>(60.0),
+                },
+                Distribution::Uniform {
+                    min: -Angle::HALF_TURN,
+                    max: Angle::HALF_TURN,
+                },
+            )
+            .with_min_solar_elevation(Angle::new::<degree>(10.0))
+            .with_max_dop(Distribution::Uniform { min: 0.5, max: 0.9 })
+            .with_noise(
+                Distribution::Fixed(Angle::new::<degree>(2.0)),
+                Distribution::Fixed(0.02),
+            )
+            .with_clouds(
+                Distribution::Uniform { min: 0.0, max: 0.5 },
+                Distribution::Fixed(4.0),
+            )
+    }
+
+    #[test]
+    fn samples_are_reproducible() {
+        let first: Vec<_> = randomizer(7).samples(3).collect();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, randomizer(7).samples(3).collect::<Vec<_>>());
+
+        // Samples do not depend on the samples before them.
+        assert_eq!(randomizer(7).sample(2).as_ref(), first.get(2));
+        assert_ne!(randomizer(8).sample(2).as_ref(), first.get(2));
+    }
+
+    #[test]
+    fn labels_follow_distributions() {
+        for sample in randomizer(3).samples(4) {
+            let label = sample.label();
+            assert!(label.solar_bearing().1 >= Angle::new::<degree>(10.0));
+            assert!(label.position().latitude().abs() <= Angle::new::<degree>(60.0));
+            assert!((0.5..=0.9).contains(&label.max_dop()));
+            assert!((0.0..=0.5).contains(&label.cloud_coverage()));
+            assert_eq!(sample.image().meta().sequence(), Some(label.index()));
+            assert_eq!(sample.image().meta().time(), Some(label.time()));
+            assert!(
+                sample
+                    .image()
+                    .rays()
+                    .flatten()
+                    .all(|ray| f64::from(ray.dop()) <= 1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn noiseless_sample_matches_simulation() {
+        let randomizer = randomizer(1)
+            .with_max_dop(Distribution::Fixed(1.0))
+            .with_noise(Distribution::Fixed(Angle::ZERO), Distribution::Fixed(0.0))
+            .with_clouds(Distribution::Fixed(0.0), Distribution::Fixed(4.0));
+        let sample = randomizer.sample(0).unwrap();
+        let simulation = randomizer.simulation(sample.label());
+
+        let expected = simulation.ray_image();
+        assert!(
+            sample
+                .image()
+                .rays()
+                .zip(expected.rays())
+                .all(|(lhs, rhs)| lhs == rhs)
+        );
+    }
+
+    #[test]
+    fn night_is_rejected() {
+        let randomizer = randomizer(1)
+            .with_elapsed(Distribution::Fixed(Time::ZERO))
+            .with_position(
+                Distribution::Fixed(Angle::ZERO),
+                Distribution::Fixed(Angle::ZERO),
+            );
+        assert_eq!(randomizer.sample(0), None);
+    }
+}
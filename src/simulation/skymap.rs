@@ -0,0 +1,189 @@
+//! Resampling of camera images onto maps of the sky.
+
+use super::{Simulation, SimulationEnu, bearings::BearingMap};
+use crate::{
+    azimuth::AzimuthConvention,
+    image::{ImageError, RayImage},
+    optic::Optic,
+    ray::GlobalFrame,
+};
+use sguaba::Bearing;
+use uom::{
+    ConstZero,
+    si::{angle::radian, f64::Angle, ratio::ratio},
+};
+
+/// A projection of the sky onto a rectangular map.
+///
+/// Azimuths are measured clockwise from north and elevations up from the horizon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkyProjection {
+    /// Maps azimuth linearly onto columns and elevation linearly onto rows.
+    ///
+    /// Columns span a full turn of azimuth from north and rows span elevation from the zenith
+    /// at the top down to `min_elevation` at the bottom.
+    Equirectangular { min_elevation: Angle },
+
+    /// Projects the sky onto the plane tangent to the sphere at `azimuth` and `elevation`.
+    ///
+    /// Great circles map to straight lines.
+    /// The map is `field_of_view` wide with square cells, increasing azimuth is to the right,
+    /// and the zenith is towards the top.
+    Gnomonic {
+        azimuth: Angle,
+        elevation: Angle,
+        field_of_view: Angle,
+    },
+}
+
+impl SkyProjection {
+    /// The convention of the azimuths of a `SkyProjection`.
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    /// Returns the azimuth and elevation at the center of the cell at `row` and `col` of a map
+    /// that is `rows` by `cols` cells.
+    #[allow(clippy::cast_precision_loss)]
+    fn bearing(&self, row: usize, col: usize, rows: usize, cols: usize) -> (Angle, Angle) {
+        let u = (col as f64 + 0.5) / cols as f64;
+        let v = (row as f64 + 0.5) / rows as f64;
+
+        match *self {
+            SkyProjection::Equirectangular { min_elevation } => (
+                Angle::FULL_TURN * u,
+                Angle::HALF_TURN / 2.0 - (Angle::HALF_TURN / 2.0 - min_elevation) * v,
+            ),
+            SkyProjection::Gnomonic {
+                azimuth,
+                elevation,
+                field_of_view,
+            } => {
+                let half_width = (field_of_view / 2.0).tan().get::<ratio>();
+                let x = (2.0 * u - 1.0) * half_width;
+                let y = (1.0 - 2.0 * v) * half_width * rows as f64 / cols as f64;
+
+                let (sin_az, cos_az) = (azimuth.sin().value, azimuth.cos().value);
+                let (sin_el, cos_el) = (elevation.sin().value, elevation.cos().value);
+                // The center and the tangent directions of increasing azimuth and elevation in
+                // east, north, up.
+                let center = [sin_az * cos_el, cos_az * cos_el, sin_el];
+                let east = [cos_az, -sin_az, 0.0];
+                let up = [-sin_az * sin_el, -cos_az * sin_el, cos_el];
+
+                let direction: [f64; 3] =
+                    std::array::from_fn(|i| center[i] + x * east[i] + y * up[i]);
+                let norm = direction.iter().map(|d| d * d).sum::<f64>().sqrt();
+                let [e, n, u] = direction.map(|d| d / norm);
+
+                (
+                    BearingMap::AZIMUTH.from_components(e, n),
+                    Angle::new::<radian>(u.clamp(-1.0, 1.0).asin()),
+                )
+            }
+        }
+    }
+}
+
+impl<O: Optic> Simulation<O> {
+    /// Resamples `image` captured by the [`Camera`](crate::optic::Camera) of this simulation onto
+    /// a `rows` by `cols` map of the sky.
+    ///
+    /// Each cell takes the ray of the pixel that images its center.
+    /// Cells below the horizon or outside of the field of view hold no ray.
+    /// The [`Aop`](crate::light::aop::Aop) stays relative to the local meridian, so maps from
+    /// different projections agree.
+    /// Like [`Simulation::sky_features`], this uses the pose of the first row.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of `image` do not match the sensor.
+    pub fn sky_map(
+        &self,
+        image: &RayImage<GlobalFrame>,
+        projection: SkyProjection,
+        rows: usize,
+        cols: usize,
+    ) -> Result<RayImage<GlobalFrame>, ImageError> {
+        if (image.rows(), image.cols()) != (self.camera.rows(), self.camera.cols()) {
+            return Err(ImageError::ExtentsMismatch {
+                expected_rows: self.camera.rows(),
+                expected_cols: self.camera.cols(),
+                rows: image.rows(),
+                cols: image.cols(),
+            });
+        }
+
+        let rays = (0..rows * cols).map(|index| {
+            let (azimuth, elevation) = projection.bearing(index / cols, index % cols, rows, cols);
+            if elevation < Angle::ZERO {
+                return None;
+            }
+
+            let bearing = Bearing::<SimulationEnu>::builder()
+                .azimuth(azimuth)
+                .elevation(elevation)?
+                .build();
+            let pixel = self.trace_bearing(bearing).pixel()?;
+            image.ray(pixel.row(), pixel.col()).copied()
+        });
+
+        Ok(RayImage::from_rays(rays, rows, cols)?.with_meta(*image.meta()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use uom::si::angle::degree;
+
+    fn degrees((azimuth, elevation): (Angle, Angle)) -> (f64, f64) {
+        (
+            azimuth.get::<degree>().rem_euclid(360.0),
+            elevation.get::<degree>(),
+        )
+    }
+
+    #[test]
+    fn equirectangular_spans_sky() {
+        let projection = SkyProjection::Equirectangular {
+            min_elevation: Angle::ZERO,
+        };
+
+        let (azimuth, elevation) = degrees(projection.bearing(0, 0, 9, 36));
+        assert_relative_eq!(azimuth, 5.0, epsilon = 1e-9);
+        assert_relative_eq!(elevation, 85.0, epsilon = 1e-9);
+
+        let (azimuth, elevation) = degrees(projection.bearing(8, 35, 9, 36));
+        assert_relative_eq!(azimuth, 355.0, epsilon = 1e-9);
+        assert_relative_eq!(elevation, 5.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn gnomonic_is_centered_and_oriented() {
+        let projection = SkyProjection::Gnomonic {
+            azimuth: Angle::new::<degree>(90.0),
+            elevation: Angle::new::<degree>(30.0),
+            field_of_view: Angle::new::<degree>(90.0),
+        };
+
+        // The center of an odd map is the tangent point.
+        let (azimuth, elevation) = degrees(projection.bearing(1, 1, 3, 3));
+        assert_relative_eq!(azimuth, 90.0, epsilon = 1e-9);
+        assert_relative_eq!(elevation, 30.0, epsilon = 1e-9);
+
+        // Right is towards increasing azimuth and up is towards the zenith.
+        let (right, _) = degrees(projection.bearing(1, 2, 3, 3));
+        let (_, top) = degrees(projection.bearing(0, 1, 3, 3));
+        assert!(right > 90.0);
+        assert!(top > 30.0);
+
+        // The edges of a wide map are half of the field of view from the center.
+        let projection = SkyProjection::Gnomonic {
+            azimuth: Angle::new::<degree>(90.0),
+            elevation: Angle::ZERO,
+            field_of_view: Angle::new::<degree>(90.0),
+        };
+        let (azimuth, elevation) = degrees(projection.bearing(0, 0, 1, 1_000_000));
+        assert_relative_eq!(azimuth, 45.0, epsilon = 1e-3);
+        assert_relative_eq!(elevation, 0.0, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,206 @@
+//! Projection of targets around the camera onto its pixels.
+
+use super::{CameraXyz, Simulation, SimulationEnu};
+use crate::optic::{Optic, PixelCoordinate};
+use sguaba::{Coordinate, Vector, math::Rotation, systems::Wgs84};
+use uom::{
+    ConstZero,
+    si::{f64::Length, length::meter},
+};
+
+impl<O: Optic> Simulation<O> {
+    /// Returns the pixel that images `target`.
+    ///
+    /// Use it for targets that are not at infinity, e.g., surveyed landmarks or the detections of
+    /// another sensor, so geometric cues share the camera model used for polarization.
+    /// The origin of [`SimulationEnu`] is the position of the pose and the target is seen from the
+    /// camera at [`Simulation::lever_arm`].
+    ///
+    /// Unlike [`Simulation::project`], targets below the horizon are projected, so this suits
+    /// targets on the ground.
+    /// Like [`Simulation::project`], this uses the pose at the start of the exposure.
+    /// Returns `None` if `target` is at the camera, behind the sensor, or off of it.
+    #[must_use]
+    pub fn pixel_from_enu(&self, target: &Coordinate<SimulationEnu>) -> Option<PixelCoordinate> {
+        let bearing = (*target - self.camera_position(0)).bearing_at_origin()?;
+        self.pixel_from_bearing(bearing)
+    }
+
+    /// Returns the pixel that images the geodetic `target`.
+    ///
+    /// See [`Simulation::pixel_from_enu`].
+    #[must_use]
+    pub fn pixel_from_wgs84(&self, target: &Wgs84) -> Option<PixelCoordinate> {
+        self.pixel_from_enu(&self.ecef_to_enu.transform(Coordinate::from_wgs84(target)))
+    }
+
+    /// Traces `pixel` to the point where it meets the horizontal plane `up` above the origin of
+    /// [`SimulationEnu`].
+    ///
+    /// Use a negative `up` for the ground below the pose.
+    /// Like [`Simulation::ray`], this uses the pose at the time the row of `pixel` is exposed.
+    /// Returns `None` if the optic does not image `pixel` or its ray does not reach the plane.
+    ///
+    /// # Panics
+    /// Panics if the [`crate::optic::RayDirection`] returned by the
+    /// [`Camera`](crate::optic::Camera) points behind the plane of the sensor.
+    #[must_use]
+    pub fn enu_from_pixel(
+        &self,
+        pixel: impl AsRef<PixelCoordinate>,
+        up: Length,
+    ) -> Option<Coordinate<SimulationEnu>> {
+        let camera = self.camera_position(pixel.as_ref().row());
+        let direction = Vector::<SimulationEnu>::from_bearing(
+            self.sky_bearing(pixel)?,
+            Length::new::<meter>(1.0),
+        );
+
+        // The distance along the ray to the plane.
+        let distance = (up - camera.enu_up()) / direction.enu_up().get::<meter>();
+        if !distance.is_finite() || distance <= Length::ZERO {
+            return None;
+        }
+
+        Some(camera + direction * distance.get::<meter>())
+    }
+
+    /// Returns the position of the camera when `row` is exposed.
+    fn camera_position(&self, row: usize) -> Coordinate<SimulationEnu> {
+        // SAFETY: The position of camera_pose lies at the origin of CameraXyz.
+        let cam_to_sim: Rotation<CameraXyz, SimulationEnu> =
+            unsafe { self.orientation(row).map_as_zero_in::<CameraXyz>() }.inverse();
+        Coordinate::origin() + cam_to_sim.transform(self.lever_arm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optic::{Camera, PinholeOptic};
+    use approx::assert_relative_eq;
+    use chrono::{TimeZone, Utc};
+    use sguaba::{
+        engineering::{Orientation, Pose},
+        math::RigidBodyTransform,
+        system,
+    };
+    use uom::si::{
+        angle::degree,
+        f64::Angle,
+        length::{micron, millimeter},
+    };
+
+    system!(struct TargetEnu using ENU);
+
+    fn position() -> Wgs84 {
+        Wgs84::builder()
+            .latitude(Angle::new::<degree>(44.2))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(-76.5))
+            .altitude(Length::ZERO)
+            .build()
+    }
+
+    fn simulation() -> Simulation<PinholeOptic> {
+        let camera = Camera::new(
+            PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+            Length::new::<micron>(100.0),
+            9,
+            11,
+        );
+        // Look straight up.
+        let pose = Pose::new(
+            Coordinate::origin(),
+            Orientation::<TargetEnu>::tait_bryan_builder()
+                .yaw(Angle::new::<degree>(0.0))
+                .pitch(Angle::new::<degree>(0.0))
+                .roll(Angle::new::<degree>(180.0))
+                .build(),
+        );
+        // SAFETY: The origin of TargetEnu is the position of the camera.
+        let enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&position()) }.inverse();
+        Simulation::new(
+            camera,
+            enu_to_ecef.transform(pose),
+            Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn projects_geodetic_targets() {
+        let simulation = simulation();
+        let pixel = PixelCoordinate::new(2, 7);
+        let point = simulation
+            .enu_from_pixel(pixel, Length::new::<meter>(100.0))
+            .unwrap();
+        let target = simulation.ecef_to_enu.inverse().transform(point).to_wgs84();
+        assert_eq!(simulation.pixel_from_wgs84(&target), Some(pixel));
+
+        let overhead = Wgs84::builder()
+            .latitude(position().latitude())
+            .expect("latitude is between -90 and 90")
+            .longitude(position().longitude())
+            .altitude(Length::new::<meter>(100.0))
+            .build();
+        assert_eq!(
+            simulation.pixel_from_wgs84(&overhead),
+            simulation.pixel_from_enu(
+                &Coordinate::<SimulationEnu>::builder()
+                    .enu_east(Length::ZERO)
+                    .enu_north(Length::ZERO)
+                    .enu_up(Length::new::<meter>(100.0))
+                    .build()
+            )
+        );
+    }
+
+    #[test]
+    fn lever_arm_moves_viewpoint() {
+        let lever_arm = Vector::<CameraXyz>::builder()
+            .x(Length::new::<meter>(3.0))
+            .y(Length::new::<meter>(4.0))
+            .z(Length::new::<meter>(-1.0))
+            .build();
+        let fixed = simulation();
+        let simulation = fixed.with_lever_arm(lever_arm);
+        assert_eq!(simulation.lever_arm(), lever_arm);
+
+        // The camera is 5 meters to the side of and 1 meter above the pose.
+        let camera = simulation.camera_position(0);
+        assert_relative_eq!(
+            camera.enu_east().hypot(camera.enu_north()).get::<meter>(),
+            5.0,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(camera.enu_up().get::<meter>(), 1.0, epsilon = 1e-9);
+
+        // Rays leave from the camera rather than the pose.
+        let above = |origin: Coordinate<SimulationEnu>| {
+            origin
+                + Vector::<SimulationEnu>::builder()
+                    .enu_east(Length::ZERO)
+                    .enu_north(Length::ZERO)
+                    .enu_up(Length::new::<meter>(100.0))
+                    .build()
+        };
+        assert_eq!(
+            simulation.pixel_from_enu(&above(camera)),
+            fixed.pixel_from_enu(&above(Coordinate::origin()))
+        );
+        assert_ne!(
+            simulation.pixel_from_enu(&above(Coordinate::origin())),
+            fixed.pixel_from_enu(&above(Coordinate::origin()))
+        );
+        assert_eq!(
+            simulation.enu_from_pixel(PixelCoordinate::new(4, 5), Length::new::<meter>(0.5)),
+            None
+        );
+
+        let pixel = PixelCoordinate::new(2, 7);
+        let point = simulation
+            .enu_from_pixel(pixel, Length::new::<meter>(100.0))
+            .unwrap();
+        assert_eq!(simulation.pixel_from_enu(&point), Some(pixel));
+    }
+}
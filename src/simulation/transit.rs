@@ -0,0 +1,182 @@
+//! Simulated sequences of skylight polarization over the course of a day.
+
+use super::Simulation;
+use crate::{
+    azimuth::AzimuthConvention,
+    image::RayImage,
+    io::sequence::{SequenceError, SequenceWriter},
+    meta::FrameMeta,
+    optic::{Camera, Optic},
+    ray::GlobalFrame,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use sguaba::{engineering::Pose, systems::Ecef};
+use std::io::Write;
+use uom::si::f64::Angle;
+
+/// The position of the sun in the local east, north, up frame of the camera at a point in time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolarSample {
+    time: DateTime<Utc>,
+    azimuth: Angle,
+    elevation: Angle,
+}
+
+impl SolarSample {
+    /// The convention of [`SolarSample::azimuth`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    #[must_use]
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    #[must_use]
+    pub fn azimuth(&self) -> Angle {
+        self.azimuth
+    }
+
+    #[must_use]
+    pub fn elevation(&self) -> Angle {
+        self.elevation
+    }
+}
+
+/// A simulated image with the position of the sun when it was captured.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransitFrame {
+    solar: SolarSample,
+    image: RayImage<GlobalFrame>,
+}
+
+impl TransitFrame {
+    #[must_use]
+    pub fn solar(&self) -> &SolarSample {
+        &self.solar
+    }
+
+    #[must_use]
+    pub fn image(&self) -> &RayImage<GlobalFrame> {
+        &self.image
+    }
+
+    #[must_use]
+    pub fn into_image(self) -> RayImage<GlobalFrame> {
+        self.image
+    }
+}
+
+/// Simulates a [`Camera`] with a fixed [`Pose`] as the sun moves across the sky.
+///
+/// Frames are simulated from `start` to `end` inclusive every `cadence`.
+/// The ground truth pose never changes so any drift in an estimator over the sequence is caused
+/// by the changing sky.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transit<O> {
+    camera: Camera<O>,
+    pose: Pose<Ecef>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    cadence: TimeDelta,
+}
+
+impl<O> Transit<O> {
+    /// # Panics
+    /// Will panic if `cadence` is not positive.
+    pub fn new(
+        camera: Camera<O>,
+        pose: Pose<Ecef>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        cadence: TimeDelta,
+    ) -> Self {
+        assert!(cadence > TimeDelta::zero(), "cadence must be positive");
+        Self {
+            camera,
+            pose,
+            start,
+            end,
+            cadence,
+        }
+    }
+
+    /// Returns an iterator over the time of each frame.
+    pub fn times(&self) -> impl Iterator<Item = DateTime<Utc>> + use<O> {
+        let (start, end, cadence) = (self.start, self.end, self.cadence);
+        std::iter::successors(Some(start), move |time| time.checked_add_signed(cadence))
+            .take_while(move |time| *time <= end)
+    }
+
+    fn simulation(&self, time: DateTime<Utc>) -> Simulation<O>
+    where
+        O: Clone,
+    {
+        Simulation::new(self.camera.clone(), self.pose, time)
+    }
+
+    fn solar(simulation: &Simulation<O>, time: DateTime<Utc>) -> SolarSample {
+        let bearing = simulation.model.solar_bearing();
+        SolarSample {
+            time,
+            azimuth: bearing.azimuth(),
+            elevation: bearing.elevation(),
+        }
+    }
+
+    /// Returns the position of the sun for each frame without simulating any images.
+    #[must_use]
+    pub fn trajectory(&self) -> Vec<SolarSample>
+    where
+        O: Clone,
+    {
+        self.times()
+            .map(|time| Self::solar(&self.simulation(time), time))
+            .collect()
+    }
+
+    /// Returns an iterator that simulates each frame.
+    ///
+    /// Each image carries a [`FrameMeta`] with its time, index, and the position of the camera.
+    ///
+    /// The [`crate::model::SkyModel`] does not account for the sun being below the horizon.
+    /// Use [`SolarSample::elevation`] to discard frames captured at night.
+    pub fn frames(&self) -> impl Iterator<Item = TransitFrame>
+    where
+        O: Optic + Clone + Send + Sync,
+    {
+        let position = self.pose.position().into();
+        self.times().zip(0..).map(move |(time, sequence)| {
+            let simulation = self.simulation(time);
+            let meta = FrameMeta::new()
+                .with_time(time)
+                .with_sequence(sequence)
+                .with_position(position)
+                .with_solar_position(simulation.solar_position());
+            TransitFrame {
+                solar: Self::solar(&simulation, time),
+                image: simulation.par_ray_image().with_meta(meta),
+            }
+        })
+    }
+
+    /// Simulates each frame and writes it to a sequence in `writer`.
+    ///
+    /// Returns the position of the sun for each frame written.
+    ///
+    /// # Errors
+    /// Will return `Err` if the sequence cannot be written.
+    pub fn write_sequence<W: Write>(&self, writer: W) -> Result<Vec<SolarSample>, SequenceError>
+    where
+        O: Optic + Clone + Send + Sync,
+    {
+        let mut writer = SequenceWriter::new(writer, self.camera.rows(), self.camera.cols())?;
+        let mut trajectory = Vec::new();
+        for frame in self.frames() {
+            writer.write_frame(frame.solar.time, &frame.image)?;
+            trajectory.push(frame.solar);
+        }
+        writer.into_inner()?;
+
+        Ok(trajectory)
+    }
+}
@@ -0,0 +1,440 @@
+//! Filters over sequences of registered images.
+
+use crate::{
+    image::{ImageError, RayImage},
+    light::{aop::Aop, dop::Dop},
+    mask::PixelMask,
+    ray::Ray,
+};
+use std::collections::VecDeque;
+use thiserror::Error;
+use uom::si::{angle::degree, f64::Angle};
+
+#[derive(Debug, Error)]
+pub enum TemporalError {
+    #[error(
+        "frame extents do not match window: expected {expected_rows}x{expected_cols} found {rows}x{cols}"
+    )]
+    ExtentsMismatch {
+        expected_rows: usize,
+        expected_cols: usize,
+        rows: usize,
+        cols: usize,
+    },
+
+    #[error(transparent)]
+    Image(#[from] ImageError),
+}
+
+/// A per-pixel median over a sliding window of registered [`RayImage`]s.
+///
+/// Moving clouds corrupt the polarization of a pixel for a few frames at a time.
+/// The median rejects these transients as long as the pixel is clear in most of the window.
+/// The [`Aop`](crate::light::aop::Aop) is axial, so its median is the sample closest to all of
+/// the others in angular distance.
+/// The [`Dop`] median is the usual median of the samples.
+///
+/// Images must be registered, e.g., from a camera that does not rotate between frames, so each
+/// pixel sees the same part of the sky.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemporalMedian<Frame> {
+    window: usize,
+    min_count: usize,
+    frames: VecDeque<RayImage<Frame>>,
+}
+
+impl<Frame: Copy> TemporalMedian<Frame> {
+    /// Creates a new `TemporalMedian` over the last `window` frames.
+    ///
+    /// # Panics
+    /// Will panic if `window` is zero.
+    #[must_use]
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "window must be non-zero");
+
+        Self {
+            window,
+            min_count: 1,
+            frames: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Sets the fewest frames in the window that must hold a ray at a pixel for the output to
+    /// hold a ray there.
+    #[must_use]
+    pub fn with_min_count(mut self, min_count: usize) -> Self {
+        self.min_count = min_count;
+        self
+    }
+
+    #[must_use]
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Returns the number of frames currently in the window.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns `true` if no frames have been pushed since the window was created or cleared.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Removes every frame from the window, e.g., after the camera moves.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Adds `image` to the window and returns the median of the frames in the window.
+    ///
+    /// The output carries the [`FrameMeta`](crate::meta::FrameMeta) of `image`.
+    /// Until the window fills, the median is taken over the frames pushed so far.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of `image` do not match the frames in the window.
+    pub fn push(&mut self, image: RayImage<Frame>) -> Result<RayImage<Frame>, TemporalError> {
+        let (rows, cols) = (image.rows(), image.cols());
+        if let Some(front) = self.frames.front()
+            && (front.rows(), front.cols()) != (rows, cols)
+        {
+            return Err(TemporalError::ExtentsMismatch {
+                expected_rows: front.rows(),
+                expected_cols: front.cols(),
+                rows,
+                cols,
+            });
+        }
+
+        let meta = *image.meta();
+        if self.frames.len() == self.window {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(image);
+
+        let mut samples = Vec::with_capacity(self.frames.len());
+        let rays: Vec<_> = (0..rows * cols)
+            .map(|index| {
+                samples.clear();
+                samples.extend(
+                    self.frames
+                        .iter()
+                        .filter_map(|frame| frame.ray(index / cols, index % cols).copied()),
+                );
+
+                if samples.is_empty() || samples.len() < self.min_count {
+                    None
+                } else {
+                    Some(median(&samples))
+                }
+            })
+            .collect();
+
+        Ok(RayImage::from_rays(rays, rows, cols)?.with_meta(meta))
+    }
+}
+
+/// Flags pixels whose polarization changes between consecutive registered [`RayImage`]s by more
+/// than the sky explains.
+///
+/// Over a long deployment, moving occluders such as birds, aircraft, or clouds and faults of the
+/// sensor show up as sudden local changes of the [`Aop`] or the [`Dop`].
+/// The sky itself changes slowly as the sun moves, so the change predicted by a model, e.g.,
+/// images simulated at the times of both frames, is subtracted before comparing against the
+/// tolerances.
+/// The AoP is axial, so its changes are wrapped into [-90, 90).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChangeDetector {
+    aop_tolerance: Angle,
+    dop_tolerance: f64,
+}
+
+impl ChangeDetector {
+    /// Creates a new `ChangeDetector` that tolerates 5 degrees of AoP and 0.1 of DoP change.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            aop_tolerance: Angle::new::<degree>(5.0),
+            dop_tolerance: 0.1,
+        }
+    }
+
+    /// Sets the largest unexplained change of the AoP that is not an anomaly.
+    #[must_use]
+    pub fn with_aop_tolerance(mut self, aop_tolerance: Angle) -> Self {
+        self.aop_tolerance = aop_tolerance;
+        self
+    }
+
+    /// Sets the largest unexplained change of the DoP that is not an anomaly.
+    #[must_use]
+    pub fn with_dop_tolerance(mut self, dop_tolerance: f64) -> Self {
+        self.dop_tolerance = dop_tolerance;
+        self
+    }
+
+    #[must_use]
+    pub fn aop_tolerance(&self) -> Angle {
+        self.aop_tolerance
+    }
+
+    #[must_use]
+    pub fn dop_tolerance(&self) -> f64 {
+        self.dop_tolerance
+    }
+
+    /// Compares `current` with `previous` assuming the sky did not change between them.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the images do not match.
+    pub fn detect<Frame: Copy>(
+        &self,
+        previous: &RayImage<Frame>,
+        current: &RayImage<Frame>,
+    ) -> Result<ChangeReport, TemporalError> {
+        self.detect_predicted(previous, current, previous, previous)
+    }
+
+    /// Compares `current` with `previous` after removing the change from `predicted_previous`
+    /// to `predicted_current`.
+    ///
+    /// Only pixels with a ray in all four images are compared.
+    ///
+    /// # Errors
+    /// Will return `Err` if the extents of the images do not match.
+    pub fn detect_predicted<Frame: Copy>(
+        &self,
+        previous: &RayImage<Frame>,
+        current: &RayImage<Frame>,
+        predicted_previous: &RayImage<Frame>,
+        predicted_current: &RayImage<Frame>,
+    ) -> Result<ChangeReport, TemporalError> {
+        let (rows, cols) = (previous.rows(), previous.cols());
+        for image in [current, predicted_previous, predicted_current] {
+            if (image.rows(), image.cols()) != (rows, cols) {
+                return Err(TemporalError::ExtentsMismatch {
+                    expected_rows: rows,
+                    expected_cols: cols,
+                    rows: image.rows(),
+                    cols: image.cols(),
+                });
+            }
+        }
+
+        let mut compared = 0;
+        let anomalies = (0..rows * cols)
+            .map(|index| {
+                let (row, col) = (index / cols, index % cols);
+                let (Some(before), Some(after), Some(expected_before), Some(expected_after)) = (
+                    previous.ray(row, col),
+                    current.ray(row, col),
+                    predicted_previous.ray(row, col),
+                    predicted_current.ray(row, col),
+                ) else {
+                    return false;
+                };
+                compared += 1;
+
+                let aop_change = after.aop().wrapped_difference(&before.aop());
+                let expected_aop_change = expected_after
+                    .aop()
+                    .wrapped_difference(&expected_before.aop());
+                let aop_residual =
+                    Aop::<Frame>::from_angle_wrapped(aop_change - expected_aop_change);
+
+                let dop_change = f64::from(after.dop()) - f64::from(before.dop());
+                let expected_dop_change =
+                    f64::from(expected_after.dop()) - f64::from(expected_before.dop());
+
+                Angle::from(aop_residual).abs() > self.aop_tolerance
+                    || (dop_change - expected_dop_change).abs() > self.dop_tolerance
+            })
+            .collect();
+
+        Ok(ChangeReport {
+            anomalies: PixelMask::new(anomalies, rows, cols)
+                .expect("one flag is produced for each pixel"),
+            compared,
+        })
+    }
+}
+
+impl Default for ChangeDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The result of [`ChangeDetector::detect`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeReport {
+    anomalies: PixelMask,
+    compared: usize,
+}
+
+impl ChangeReport {
+    /// Returns a mask that keeps the anomalous pixels.
+    ///
+    /// Use [`PixelMask::patches`] to group them into regions.
+    #[must_use]
+    pub fn anomalies(&self) -> &PixelMask {
+        &self.anomalies
+    }
+
+    /// The number of pixels that held a ray in every compared image.
+    #[must_use]
+    pub fn compared(&self) -> usize {
+        self.compared
+    }
+
+    /// The number of anomalous pixels.
+    #[must_use]
+    pub fn anomalous(&self) -> usize {
+        self.anomalies.kept()
+    }
+
+    /// The fraction of compared pixels that are anomalous or zero if no pixels were compared.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn anomalous_fraction(&self) -> f64 {
+        if self.compared == 0 {
+            return 0.0;
+        }
+
+        self.anomalous() as f64 / self.compared as f64
+    }
+}
+
+/// Computes the axial median of the AoP and the median of the DoP of non-empty `samples`.
+fn median<Frame: Copy>(samples: &[Ray<Frame>]) -> Ray<Frame> {
+    // Ties are broken by the earliest sample so the result is deterministic.
+    let aop = samples
+        .iter()
+        .map(|ray| {
+            let cost: Angle = samples
+                .iter()
+                .map(|other| ray.aop().angular_distance(&other.aop()))
+                .sum();
+            (cost, ray.aop())
+        })
+        .reduce(|best, next| if next.0 < best.0 { next } else { best })
+        .map(|(_, aop)| aop)
+        .expect("samples is not empty");
+
+    let mut dops: Vec<f64> = samples.iter().map(|ray| f64::from(ray.dop())).collect();
+    dops.sort_by(f64::total_cmp);
+    let middle = dops.len() / 2;
+    let dop = if dops.len().is_multiple_of(2) {
+        f64::midpoint(dops[middle - 1], dops[middle])
+    } else {
+        dops[middle]
+    };
+
+    Ray::new(aop, Dop::clamped(dop))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{meta::FrameMeta, ray::SensorFrame};
+    use approx::assert_relative_eq;
+    use uom::si::angle::degree;
+
+    fn frame(aop: f64, dop: f64) -> RayImage<SensorFrame> {
+        let ray = Ray::new(
+            Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+            Dop::clamped(dop),
+        );
+        RayImage::from_rays([Some(ray), Some(ray), None, Some(ray)], 2, 2).unwrap()
+    }
+
+    #[test]
+    fn rejects_transient() {
+        let mut median = TemporalMedian::new(3);
+        median.push(frame(88.0, 0.5)).unwrap();
+        median.push(frame(-40.0, 0.05)).unwrap();
+        let output = median
+            .push(frame(-88.0, 0.6).with_meta(FrameMeta::new().with_sequence(2)))
+            .unwrap();
+
+        // The median wraps across the +/- 90 degree boundary rather than averaging to zero.
+        let ray = output.ray(0, 0).unwrap();
+        assert!(Angle::from(ray.aop()).get::<degree>().abs() >= 88.0);
+        assert_relative_eq!(f64::from(ray.dop()), 0.5);
+        assert!(output.ray(1, 0).is_none());
+        assert_eq!(output.meta().sequence(), Some(2));
+    }
+
+    #[test]
+    fn window_slides() {
+        let mut median = TemporalMedian::new(2);
+        for aop in [10.0, 20.0, 30.0] {
+            median.push(frame(aop, 0.5)).unwrap();
+        }
+
+        assert_eq!(median.len(), 2);
+        let output = median.push(frame(40.0, 0.5)).unwrap();
+        // Both samples are equally central so the earlier one is kept.
+        let aop = Angle::from(output.ray(0, 0).unwrap().aop()).get::<degree>();
+        assert_relative_eq!(aop, 30.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn min_count_drops_sparse_pixels() {
+        let mut median = TemporalMedian::new(3).with_min_count(2);
+        let output = median.push(frame(10.0, 0.5)).unwrap();
+
+        assert_eq!(output.rays().flatten().count(), 0);
+        assert!(matches!(
+            median.push(RayImage::from_rays([None], 1, 1).unwrap()),
+            Err(TemporalError::ExtentsMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn flags_unexplained_change() {
+        let ray = |aop: f64, dop: f64| {
+            Some(Ray::new(
+                Aop::from_angle_wrapped(Angle::new::<degree>(aop)),
+                Dop::clamped(dop),
+            ))
+        };
+        let image = |rays| RayImage::<SensorFrame>::from_rays(rays, 1, 4).unwrap();
+
+        // The sky rotates by 10 degrees between frames.
+        let predicted_previous = image([ray(85.0, 0.5), ray(0.0, 0.5), ray(0.0, 0.5), None]);
+        let predicted_current = image([ray(-85.0, 0.5), ray(10.0, 0.5), ray(10.0, 0.5), None]);
+        // The first pixel follows the sky across the wrap, the second is occluded, and the third
+        // loses its polarization.
+        let previous = image([ray(86.0, 0.5), ray(0.0, 0.5), ray(1.0, 0.5), ray(0.0, 0.5)]);
+        let current = image([
+            ray(-84.0, 0.5),
+            ray(-40.0, 0.5),
+            ray(11.0, 0.1),
+            ray(0.0, 0.5),
+        ]);
+
+        let detector = ChangeDetector::new();
+        let report = detector
+            .detect_predicted(&previous, &current, &predicted_previous, &predicted_current)
+            .unwrap();
+        assert_eq!(report.compared(), 3);
+        assert_eq!(report.anomalous(), 2);
+        assert!(!report.anomalies().keeps(0, 0));
+        assert!(report.anomalies().keeps(0, 1));
+        assert!(report.anomalies().keeps(0, 2));
+        assert_eq!(report.anomalies().patches(1).len(), 1);
+
+        // Without the prediction, the rotation of the sky is an anomaly at every pixel.
+        let report = detector.detect(&previous, &current).unwrap();
+        assert_eq!(report.compared(), 4);
+        assert_eq!(report.anomalous(), 3);
+        assert!(matches!(
+            detector.detect(&previous, &frame(0.0, 0.5)),
+            Err(TemporalError::ExtentsMismatch { .. })
+        ));
+    }
+}
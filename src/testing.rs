@@ -0,0 +1,337 @@
+//! Canonical fixtures for regression tests in downstream crates.
+//!
+//! The values are computed once and shipped as constants so that a change to the numerics of
+//! rumpus shows up as a failing test rather than a silently moving reference.
+//! Angles of polarization are axial, so compare them with
+//! [`Aop::angular_distance`](crate::light::aop::Aop::angular_distance) rather than by value.
+
+use crate::{azimuth::AzimuthConvention, image::IntensityImage};
+#[cfg(feature = "estimation")]
+use crate::{
+    estimate::symmetry::SymmetryAxisFit,
+    filter::DopFilter,
+    iter::RayIterator,
+    light::{aop::Aop, dop::Dop},
+    ray::SensorFrame,
+};
+use uom::si::{angle::degree, f64::Angle};
+
+/// A reference value of a [`SkyModel`](crate::model::SkyModel) at one bearing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModelFixture {
+    azimuth: Angle,
+    elevation: Angle,
+    aop: Angle,
+    dop: f64,
+}
+
+impl ModelFixture {
+    /// The convention of [`ModelFixture::azimuth`] and of the azimuth of [`solar_bearing`].
+    pub const AZIMUTH: AzimuthConvention = AzimuthConvention::NorthClockwise;
+
+    #[must_use]
+    pub fn azimuth(&self) -> Angle {
+        self.azimuth
+    }
+
+    #[must_use]
+    pub fn elevation(&self) -> Angle {
+        self.elevation
+    }
+
+    /// The expected angle of polarization in the [`GlobalFrame`](crate::ray::GlobalFrame).
+    #[must_use]
+    pub fn aop(&self) -> Angle {
+        self.aop
+    }
+
+    /// The expected degree of polarization.
+    #[must_use]
+    pub fn dop(&self) -> f64 {
+        self.dop
+    }
+}
+
+/// Returns the azimuth and elevation of the sun used by [`model_fixtures`].
+///
+/// The azimuth is clockwise from north as in [`ModelFixture::AZIMUTH`].
+#[must_use]
+pub fn solar_bearing() -> (Angle, Angle) {
+    (Angle::new::<degree>(135.0), Angle::new::<degree>(30.0))
+}
+
+/// Returns reference values of a [`SkyModel`](crate::model::SkyModel) with the sun at
+/// [`solar_bearing`].
+///
+/// The first two bearings lie on the solar and anti-solar meridians.
+#[must_use]
+pub fn model_fixtures() -> Vec<ModelFixture> {
+    [
+        (135.0, 60.0, -90.0, 0.142_857_142_857_142_85),
+        (315.0, 60.0, 90.0, 1.0),
+        (45.0, 30.0, -26.565_051_177_077_965, 0.882_352_941_176_470_7),
+        (225.0, 45.0, 22.207_654_298_596_477, 0.777_777_777_777_777_8),
+        (90.0, 10.0, -32.229_134_756_366_22, 0.355_057_026_798_869_86),
+        (180.0, 75.0, -37.038_239_393_968_76, 0.416_965_949_937_987_1),
+        (270.0, 20.0, 47.965_689_945_564_02, 0.718_855_309_878_966_6),
+    ]
+    .into_iter()
+    .map(|(azimuth, elevation, aop, dop)| ModelFixture {
+        azimuth: Angle::new::<degree>(azimuth),
+        elevation: Angle::new::<degree>(elevation),
+        aop: Angle::new::<degree>(aop),
+        dop,
+    })
+    .collect()
+}
+
+/// A reference ray of a metapixel of [`intensity_image`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelFixture {
+    row: usize,
+    col: usize,
+    aop: Angle,
+    dop: f64,
+}
+
+impl PixelFixture {
+    /// The row of the metapixel.
+    #[must_use]
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// The column of the metapixel.
+    #[must_use]
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// The expected angle of polarization in the [`SensorFrame`](crate::ray::SensorFrame).
+    #[must_use]
+    pub fn aop(&self) -> Angle {
+        self.aop
+    }
+
+    /// The expected degree of polarization.
+    #[must_use]
+    pub fn dop(&self) -> f64 {
+        self.dop
+    }
+}
+
+/// The raw bytes of [`intensity_image`].
+///
+/// Every metapixel has a total intensity of 200 with an ideal polarizer.
+#[rustfmt::skip]
+const INTENSITY_BYTES: [u8; 16] = [
+      0, 100, 100,  50,
+    100, 200, 150, 100,
+    100, 150, 130,  60,
+     50, 100, 140,  70,
+];
+
+/// Returns a 4 by 4 pixel [`IntensityImage`] with 2 by 2 metapixels.
+///
+/// The expected rays with the default calibration are given by [`intensity_fixtures`].
+///
+/// # Panics
+/// Will panic if the fixture is malformed.
+/// This should never occur.
+#[must_use]
+pub fn intensity_image() -> IntensityImage {
+    IntensityImage::from_bytes(4, 4, &INTENSITY_BYTES).expect("fixture has valid extents")
+}
+
+/// Returns the expected ray of each metapixel of [`intensity_image`] in row-major order.
+#[must_use]
+pub fn intensity_fixtures() -> [PixelFixture; 4] {
+    let fixture = |row, col, aop, dop| PixelFixture {
+        row,
+        col,
+        aop: Angle::new::<degree>(aop),
+        dop,
+    };
+
+    [
+        fixture(0, 0, 0.0, 1.0),
+        fixture(0, 1, 45.0, 0.5),
+        fixture(1, 0, -45.0, 0.5),
+        fixture(1, 1, 63.434_948_822_922_01, 0.5),
+    ]
+}
+
+/// A labeled frame for end-to-end regression tests of the estimation pipeline.
+///
+/// [`MeridianCase::run`] computes the rays of the frame, drops rays below a minimum
+/// [`Dop`](crate::light::dop::Dop), and fits the solar meridian with a DoP weighted
+/// [`SymmetryAxisFit`].
+/// Unit tests cover each of these steps on its own, while a case guards the seams between them.
+/// Build cases from your own frames with a surveyed meridian to check a camera and calibration
+/// end to end.
+#[cfg(feature = "estimation")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeridianCase {
+    image: IntensityImage,
+    expected: Angle,
+    tolerance: Angle,
+    min_dop: Dop,
+}
+
+#[cfg(feature = "estimation")]
+impl MeridianCase {
+    /// Creates a new `MeridianCase` that expects the solar meridian of `image` at `expected` in
+    /// the [`SensorFrame`] within `tolerance`.
+    ///
+    /// By default, rays with a [`Dop`](crate::light::dop::Dop) below 0.1 are dropped.
+    #[must_use]
+    pub fn new(image: IntensityImage, expected: Angle, tolerance: Angle) -> Self {
+        Self {
+            image,
+            expected,
+            tolerance,
+            min_dop: Dop::clamped(0.1),
+        }
+    }
+
+    /// Sets the minimum [`Dop`](crate::light::dop::Dop) of rays passed to the estimator.
+    #[must_use]
+    pub fn with_min_dop(mut self, min_dop: Dop) -> Self {
+        self.min_dop = min_dop;
+        self
+    }
+
+    #[must_use]
+    pub fn expected(&self) -> Angle {
+        self.expected
+    }
+
+    #[must_use]
+    pub fn tolerance(&self) -> Angle {
+        self.tolerance
+    }
+
+    #[must_use]
+    pub fn min_dop(&self) -> Dop {
+        self.min_dop
+    }
+
+    /// Runs the pipeline on the frame and compares the recovered meridian to the label.
+    #[must_use]
+    pub fn run(&self) -> MeridianOutcome {
+        let estimated = SymmetryAxisFit::new()
+            .dop_weighted(true)
+            .estimate(
+                self.image
+                    .rays()
+                    .ray_filter(DopFilter::from_min(self.min_dop)),
+            )
+            .map(|estimate| estimate.azimuth());
+
+        // The meridian is a line, so compare axially.
+        let error = estimated.map(|estimated| {
+            Aop::<SensorFrame>::from_angle_wrapped(estimated)
+                .angular_distance(&Aop::from_angle_wrapped(self.expected))
+        });
+
+        MeridianOutcome {
+            estimated,
+            error,
+            tolerance: self.tolerance,
+        }
+    }
+}
+
+/// The result of a [`MeridianCase`].
+#[cfg(feature = "estimation")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeridianOutcome {
+    estimated: Option<Angle>,
+    error: Option<Angle>,
+    tolerance: Angle,
+}
+
+#[cfg(feature = "estimation")]
+impl MeridianOutcome {
+    /// The azimuth of the recovered meridian in the [`SensorFrame`] on [-90, 90].
+    ///
+    /// The azimuth is counterclockwise from the right of the image as in
+    /// [`MeridianEstimate::AZIMUTH`](crate::estimate::symmetry::MeridianEstimate::AZIMUTH).
+    /// Returns `None` if no ray survived the filter.
+    #[must_use]
+    pub fn estimated(&self) -> Option<Angle> {
+        self.estimated
+    }
+
+    /// The axial distance between the recovered and expected meridians on [0, 90].
+    #[must_use]
+    pub fn error(&self) -> Option<Angle> {
+        self.error
+    }
+
+    /// Returns true if a meridian was recovered within the tolerance of the case.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.error.is_some_and(|error| error <= self.tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::aop::Aop,
+        model::SkyModel,
+        ray::{GlobalFrame, SensorFrame},
+    };
+    use approx::assert_relative_eq;
+    use sguaba::{Bearing, system};
+
+    system!(struct FixtureEnu using ENU);
+
+    fn bearing(azimuth: Angle, elevation: Angle) -> Bearing<FixtureEnu> {
+        Bearing::<FixtureEnu>::builder()
+            .azimuth(azimuth)
+            .elevation(elevation)
+            .expect("elevation is on the range -90 to 90")
+            .build()
+    }
+
+    #[test]
+    fn model_matches_fixtures() {
+        let (azimuth, elevation) = solar_bearing();
+        let model = SkyModel::from_solar_bearing(bearing(azimuth, elevation));
+
+        for fixture in model_fixtures() {
+            let at = bearing(fixture.azimuth(), fixture.elevation());
+            let aop = model.aop(at).unwrap();
+            let expected = Aop::<GlobalFrame>::from_angle_wrapped(fixture.aop());
+            assert_relative_eq!(
+                aop.angular_distance(&expected).get::<degree>(),
+                0.0,
+                epsilon = 1e-9
+            );
+            assert_relative_eq!(
+                f64::from(model.dop(at).unwrap()),
+                fixture.dop(),
+                epsilon = 1e-12
+            );
+        }
+    }
+
+    #[test]
+    fn intensity_image_matches_fixtures() {
+        let image = intensity_image().stokes_image().ray_image();
+
+        for fixture in intensity_fixtures() {
+            let ray = image.ray(fixture.row(), fixture.col()).unwrap();
+            let expected = Aop::<SensorFrame>::from_angle_wrapped(fixture.aop());
+            assert_relative_eq!(
+                ray.aop().angular_distance(&expected).get::<degree>(),
+                0.0,
+                epsilon = 1e-9
+            );
+            assert_relative_eq!(f64::from(ray.dop()), fixture.dop(), epsilon = 1e-12);
+        }
+    }
+}
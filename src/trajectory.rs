@@ -0,0 +1,195 @@
+//! Time-indexed positions of a moving platform.
+
+use crate::meta::FrameMeta;
+use chrono::{DateTime, Utc};
+use sguaba::systems::Wgs84;
+use thiserror::Error;
+use uom::si::{angle::degree, f64::Angle};
+
+#[derive(Debug, Error)]
+pub enum TrajectoryError {
+    #[error("trajectory has no samples")]
+    Empty,
+
+    #[error("sample at {0} is not after the previous sample")]
+    NotIncreasing(DateTime<Utc>),
+}
+
+/// A track of positions, e.g., from a GNSS receiver, that can be sampled at any time it spans.
+///
+/// Positions between samples are linearly interpolated in latitude, longitude, and altitude.
+/// Longitude is interpolated along the shorter arc so tracks may cross the antimeridian.
+/// Samples are usually close enough together that the curvature of the earth is negligible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trajectory {
+    samples: Vec<(DateTime<Utc>, Wgs84)>,
+}
+
+impl Trajectory {
+    /// Creates a new `Trajectory` from `samples` in increasing order of time.
+    ///
+    /// # Errors
+    /// Will return `Err` if `samples` is empty or its times are not strictly increasing.
+    pub fn new(
+        samples: impl IntoIterator<Item = (DateTime<Utc>, Wgs84)>,
+    ) -> Result<Self, TrajectoryError> {
+        let samples: Vec<_> = samples.into_iter().collect();
+        if samples.is_empty() {
+            return Err(TrajectoryError::Empty);
+        }
+
+        if let Some(pair) = samples.windows(2).find(|pair| pair[1].0 <= pair[0].0) {
+            return Err(TrajectoryError::NotIncreasing(pair[1].0));
+        }
+
+        Ok(Self { samples })
+    }
+
+    /// Returns the samples in increasing order of time.
+    #[must_use]
+    pub fn samples(&self) -> &[(DateTime<Utc>, Wgs84)] {
+        &self.samples
+    }
+
+    /// The time of the first sample.
+    #[must_use]
+    pub fn start(&self) -> DateTime<Utc> {
+        self.samples[0].0
+    }
+
+    /// The time of the last sample.
+    #[must_use]
+    pub fn end(&self) -> DateTime<Utc> {
+        self.samples[self.samples.len() - 1].0
+    }
+
+    /// Interpolates the position at `time`.
+    ///
+    /// Returns `None` if `time` is outside of the trajectory since positions are never
+    /// extrapolated.
+    ///
+    /// # Panics
+    /// Will panic if an interpolated latitude is outside of -90 to 90 degrees.
+    /// This should never occur.
+    #[must_use]
+    pub fn position_at(&self, time: DateTime<Utc>) -> Option<Wgs84> {
+        if time < self.start() || time > self.end() {
+            return None;
+        }
+
+        let after = self.samples.partition_point(|(t, _)| *t < time);
+        let (t1, p1) = self.samples[after];
+        if after == 0 || t1 == time {
+            return Some(p1);
+        }
+
+        let (t0, p0) = self.samples[after - 1];
+        let span = (t1 - t0).num_nanoseconds()?;
+        let elapsed = (time - t0).num_nanoseconds()?;
+
+        #[allow(clippy::cast_precision_loss)]
+        let t = elapsed as f64 / span as f64;
+
+        let longitude = (p1.longitude() - p0.longitude()).get::<degree>();
+        let longitude = (longitude + 180.0).rem_euclid(360.0) - 180.0;
+        Some(
+            Wgs84::builder()
+                .latitude(p0.latitude() + (p1.latitude() - p0.latitude()) * t)
+                .expect("latitude between two valid latitudes is valid")
+                .longitude(p0.longitude() + Angle::new::<degree>(longitude * t))
+                .altitude(p0.altitude() + (p1.altitude() - p0.altitude()) * t)
+                .build(),
+        )
+    }
+
+    /// Sets the position of `meta` from the time it was captured.
+    ///
+    /// `meta` is returned unchanged if it has no time or its time is outside of the trajectory.
+    #[must_use]
+    pub fn annotate(&self, meta: FrameMeta) -> FrameMeta {
+        match meta.time().and_then(|time| self.position_at(time)) {
+            Some(position) => meta.with_position(position),
+            None => meta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use chrono::TimeDelta;
+    use uom::si::{f64::Length, length::meter};
+
+    fn position(latitude: f64, longitude: f64, altitude: f64) -> Wgs84 {
+        Wgs84::builder()
+            .latitude(Angle::new::<degree>(latitude))
+            .expect("latitude is between -90 and 90")
+            .longitude(Angle::new::<degree>(longitude))
+            .altitude(Length::new::<meter>(altitude))
+            .build()
+    }
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        "2025-06-13T16:26:47+00:00"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            + TimeDelta::seconds(seconds)
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        let trajectory = Trajectory::new([
+            (time(0), position(44.0, -76.0, 100.0)),
+            (time(10), position(45.0, -75.0, 200.0)),
+        ])
+        .unwrap();
+
+        let position = trajectory.position_at(time(4)).unwrap();
+        assert_relative_eq!(position.latitude().get::<degree>(), 44.4, epsilon = 1e-9);
+        assert_relative_eq!(position.longitude().get::<degree>(), -75.6, epsilon = 1e-9);
+        assert_relative_eq!(position.altitude().get::<meter>(), 140.0, epsilon = 1e-9);
+
+        assert_eq!(
+            trajectory.position_at(time(10)),
+            Some(trajectory.samples()[1].1)
+        );
+        assert_eq!(trajectory.position_at(time(-1)), None);
+        assert_eq!(trajectory.position_at(time(11)), None);
+    }
+
+    #[test]
+    fn crosses_antimeridian() {
+        let trajectory = Trajectory::new([
+            (time(0), position(0.0, 179.0, 0.0)),
+            (time(2), position(0.0, -179.0, 0.0)),
+        ])
+        .unwrap();
+
+        let longitude = trajectory.position_at(time(1)).unwrap().longitude();
+        assert_relative_eq!(longitude.get::<degree>().abs(), 180.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn annotates_meta() {
+        let trajectory = Trajectory::new([
+            (time(0), position(44.0, -76.0, 0.0)),
+            (time(10), position(45.0, -75.0, 0.0)),
+        ])
+        .unwrap();
+
+        let meta = trajectory.annotate(FrameMeta::new().with_time(time(5)));
+        assert_eq!(meta.position(), trajectory.position_at(time(5)));
+        assert_eq!(trajectory.annotate(FrameMeta::new()), FrameMeta::new());
+    }
+
+    #[test]
+    fn rejects_unordered_samples() {
+        let sample = (time(0), position(44.0, -76.0, 0.0));
+        assert!(matches!(
+            Trajectory::new([sample, sample]),
+            Err(TrajectoryError::NotIncreasing(_))
+        ));
+        assert!(matches!(Trajectory::new([]), Err(TrajectoryError::Empty)));
+    }
+}
@@ -1,6 +1,7 @@
 use rumpus::{
     image::{Binary, Gray, Jet},
     prelude::*,
+    render::legend::Legend,
 };
 use std::{
     io::Cursor,
@@ -26,8 +27,7 @@ fn ray_image<P: AsRef<Path>>(path: P) -> RayImage<SensorFrame> {
         IntensityImage::from_bytes(width as usize, height as usize, &raw_image.into_raw())
             .expect("image dimensions are even");
 
-    let rays: Vec<_> = intensity_image.rays().map(|ray| Some(ray)).collect();
-    RayImage::from_rays(rays, intensity_image.height(), intensity_image.width()).unwrap()
+    intensity_image.ray_image()
 }
 
 #[test]
@@ -107,3 +107,58 @@ fn gray_works() {
 
     insta::assert_binary_snapshot!("gray_dop.png", png_bytes);
 }
+
+#[test]
+#[allow(clippy::cast_possible_truncation)]
+fn legend_works() {
+    let input_path = fixture_path("intensity.png");
+    let ray_image = ray_image(&input_path);
+    let legend = Legend::new();
+
+    let mut png_bytes: Vec<u8> = Vec::new();
+    image::write_buffer_with_format(
+        &mut Cursor::new(&mut png_bytes),
+        &ray_image.aop_bytes_with_legend(&Jet, AopConvention::SignedDegrees, &legend),
+        (ray_image.cols() + legend.cols()) as u32,
+        ray_image.rows() as u32,
+        image::ExtendedColorType::Rgb8,
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+
+    insta::assert_binary_snapshot!("legend_aop.png", png_bytes);
+}
+
+#[test]
+#[allow(clippy::cast_possible_truncation)]
+fn s0_works() {
+    let raw_image = image::ImageReader::open(fixture_path("intensity.png"))
+        .unwrap()
+        .decode()
+        .unwrap()
+        .into_luma8();
+    let (width, height) = raw_image.dimensions();
+    let intensity_image =
+        IntensityImage::from_bytes(width as usize, height as usize, &raw_image.into_raw())
+            .expect("image dimensions are even");
+    let s0_image = intensity_image.s0_image();
+
+    // The total intensity of a metapixel is at most twice the maximum intensity.
+    let samples: Vec<u8> = s0_image
+        .gray16(2.0 * f64::from(u8::MAX))
+        .into_iter()
+        .flat_map(u16::to_ne_bytes)
+        .collect();
+
+    let mut png_bytes: Vec<u8> = Vec::new();
+    image::write_buffer_with_format(
+        &mut Cursor::new(&mut png_bytes),
+        &samples,
+        s0_image.cols() as u32,
+        s0_image.rows() as u32,
+        image::ExtendedColorType::L16,
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+    insta::assert_binary_snapshot!("s0.png", png_bytes);
+}
@@ -0,0 +1,155 @@
+use chrono::prelude::*;
+use rumpus::{optic::RayDirection, prelude::*, testing::MeridianCase};
+use sguaba::{
+    Coordinate,
+    engineering::{Orientation, Pose},
+    math::RigidBodyTransform,
+    system,
+    systems::Wgs84,
+};
+use uom::{
+    ConstZero,
+    si::{
+        angle::{degree, radian},
+        f64::{Angle, Length},
+        length::{micron, millimeter},
+    },
+};
+
+system!(struct PipelineEnu using ENU);
+
+/// Side of the simulated sensor in metapixels.
+const SIZE: usize = 48;
+
+/// Simulates a frame from a camera that looks at the zenith with a heading of `yaw` degrees and
+/// encodes it as the bytes of a micro-polarizer array.
+///
+/// Returns the encoded frame and the azimuth of the sun clockwise from north.
+fn frame(yaw: f64) -> (Vec<u8>, Angle) {
+    let camera = Camera::new(
+        PinholeOptic::from_focal_length(Length::new::<millimeter>(3.0)),
+        Length::new::<micron>(50.0),
+        SIZE,
+        SIZE,
+    );
+    let position = Wgs84::builder()
+        .latitude(Angle::new::<degree>(44.2))
+        .expect("latitude is between -90 and 90")
+        .longitude(Angle::new::<degree>(-76.5))
+        .altitude(Length::ZERO)
+        .build();
+    let pose = Pose::new(
+        Coordinate::origin(),
+        Orientation::<PipelineEnu>::tait_bryan_builder()
+            .yaw(Angle::new::<degree>(yaw))
+            .pitch(Angle::ZERO)
+            .roll(Angle::new::<degree>(180.0))
+            .build(),
+    );
+    // SAFETY: The origin of PipelineEnu is the position of the camera.
+    let enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&position) }.inverse();
+    let time = Utc.with_ymd_and_hms(2025, 6, 1, 14, 0, 0).unwrap();
+    let simulation = Simulation::new(camera, enu_to_ecef.transform(pose), time);
+    let rays = simulation.par_ray_image();
+
+    // The model measures the AoP from the local meridian, which the zenith camera images as the
+    // line through the center of the sensor and the pixel.
+    let center = (SIZE as f64 - 1.0) / 2.0;
+    let mut bytes = vec![0; 4 * SIZE * SIZE];
+    for row in 0..SIZE {
+        for col in 0..SIZE {
+            let ray = rays.ray(row, col).expect("every pixel sees the sky");
+            let meridian = RayDirection::AZIMUTH
+                .from_components(col as f64 - center, center - row as f64)
+                .get::<radian>();
+            let aop = meridian - Angle::from(ray.aop()).get::<radian>();
+            let dop = f64::from(ray.dop());
+            let intensity = |polarizer: f64| {
+                let value = 100.0 * (1.0 + dop * (2.0 * (polarizer.to_radians() - aop)).cos());
+                value.round() as u8
+            };
+
+            let at = |y: usize, x: usize| (2 * row + y) * 2 * SIZE + 2 * col + x;
+            bytes[at(0, 0)] = intensity(90.0);
+            bytes[at(0, 1)] = intensity(135.0);
+            bytes[at(1, 0)] = intensity(45.0);
+            bytes[at(1, 1)] = intensity(0.0);
+        }
+    }
+
+    (bytes, simulation.solar_position().azimuth())
+}
+
+/// Decodes a frame from [`frame`].
+fn intensity_image(bytes: &[u8]) -> IntensityImage {
+    IntensityImage::from_bytes(2 * SIZE, 2 * SIZE, bytes).expect("image dimensions are even")
+}
+
+/// Returns the solar meridian in the image of a zenith camera at `yaw`.
+///
+/// The yaw turns counterclockwise about up, so the camera images a compass azimuth `a` at
+/// `a + yaw - 90` degrees counterclockwise from the right of the image.
+fn meridian_from_yaw(yaw: Angle, solar_azimuth: Angle) -> Angle {
+    solar_azimuth + yaw - Angle::new::<degree>(90.0)
+}
+
+/// Returns the yaw of a zenith camera whose image holds the solar meridian at `meridian`.
+///
+/// The meridian is a line, so the yaw is only known up to a half turn.
+fn yaw_from_meridian(meridian: Angle, solar_azimuth: Angle) -> Angle {
+    meridian - solar_azimuth + Angle::new::<degree>(90.0)
+}
+
+/// Returns the distance between two headings that are only known up to a half turn.
+fn axial_distance(lhs: Angle, rhs: Angle) -> f64 {
+    let distance = (lhs - rhs).get::<degree>().rem_euclid(180.0);
+    distance.min(180.0 - distance)
+}
+
+#[test]
+fn recovers_simulated_yaw() {
+    for yaw in [0.0, 35.0, -60.0, 110.0] {
+        let (bytes, solar_azimuth) = frame(yaw);
+        let expected = Angle::new::<degree>(yaw);
+        let case = MeridianCase::new(
+            intensity_image(&bytes),
+            meridian_from_yaw(expected, solar_azimuth),
+            Angle::new::<degree>(1.0),
+        );
+
+        for min_dop in [0.0, 0.1, 0.3] {
+            let outcome = case.clone().with_min_dop(Dop::clamped(min_dop)).run();
+            assert!(outcome.passed(), "{yaw} {min_dop}: {outcome:?}");
+
+            let recovered = yaw_from_meridian(outcome.estimated().unwrap(), solar_azimuth);
+            assert!(
+                axial_distance(recovered, expected) < 1.0,
+                "{yaw} {min_dop}: {}",
+                recovered.get::<degree>()
+            );
+        }
+    }
+}
+
+#[test]
+fn rejects_mislabeled_frame() {
+    let (bytes, solar_azimuth) = frame(20.0);
+    let meridian = meridian_from_yaw(Angle::new::<degree>(20.0), solar_azimuth);
+
+    // The meridian is axial, so a label a half turn away still passes.
+    let flipped = MeridianCase::new(
+        intensity_image(&bytes),
+        meridian + Angle::new::<degree>(180.0),
+        Angle::new::<degree>(1.0),
+    );
+    assert!(flipped.run().passed());
+
+    let outcome = MeridianCase::new(
+        intensity_image(&bytes),
+        meridian + Angle::new::<degree>(90.0),
+        Angle::new::<degree>(1.0),
+    )
+    .run();
+    assert!(!outcome.passed());
+    assert!(outcome.error().unwrap().get::<degree>() > 88.0);
+}
@@ -1,33 +1,45 @@
 use std::io::Cursor;
 
+use chrono::TimeDelta;
 use chrono::prelude::*;
 use rumpus::image::Jet;
-use rumpus::image::RayImage;
-use rumpus::optic::Camera;
-use rumpus::optic::PinholeOptic;
-use rumpus::ray::GlobalFrame;
-use rumpus::simulation::Simulation;
+use rumpus::io::sequence::SequenceReader;
+use rumpus::model::ephemeris::Spa;
+use rumpus::prelude::*;
+use rumpus::simulation::Projection;
+use rumpus::simulation::RollingShutter;
+use rumpus::simulation::SimulationEnu;
+use rumpus::simulation::skymap::SkyProjection;
+use rumpus::simulation::transit::Transit;
 use sguaba::Coordinate;
 use sguaba::engineering::Orientation;
 use sguaba::engineering::Pose;
 use sguaba::math::RigidBodyTransform;
 use sguaba::system;
+use sguaba::systems::Ecef;
 use sguaba::systems::Wgs84;
 use uom::ConstZero;
 use uom::si::f64::Angle;
+use uom::si::f64::AngularVelocity;
 use uom::si::f64::Length;
+use uom::si::f64::Time;
 use uom::si::{
     angle::degree,
-    length::{micron, millimeter},
+    angular_velocity::degree_per_second,
+    length::{meter, micron, millimeter},
+    time::millisecond,
 };
 
 system!(struct CameraBody using right-handed XYZ);
 system!(struct CameraEnu using ENU);
 
-fn ray_image() -> RayImage<GlobalFrame> {
-    let pixel_size = Length::new::<micron>(3.45 * 2.);
-    let image_rows = 1024;
-    let image_cols = 1224;
+fn simulation() -> Simulation<PinholeOptic> {
+    simulation_with_extents(1024, 1224)
+}
+
+fn camera_and_pose(image_rows: usize, image_cols: usize) -> (Camera<PinholeOptic>, Pose<Ecef>) {
+    // Scale the pixels to keep the field of view of the full resolution sensor.
+    let pixel_size = Length::new::<micron>(3.45 * 2. * 1224. / image_cols as f64);
     // Use a small focal length to see more of the sky.
     let focal_length = Length::new::<millimeter>(3.0);
     let latitude = Angle::new::<degree>(44.2187);
@@ -38,7 +50,6 @@ fn ray_image() -> RayImage<GlobalFrame> {
         .longitude(longitude)
         .altitude(Length::ZERO)
         .build();
-    let time = "2025-06-13T16:26:47+00:00";
 
     let camera_pose_enu = Pose::new(
         Coordinate::origin(),
@@ -54,7 +65,7 @@ fn ray_image() -> RayImage<GlobalFrame> {
     let camera_enu_to_ecef = unsafe { RigidBodyTransform::ecef_to_enu_at(&position) }.inverse();
     let camera_pose_ecef = camera_enu_to_ecef.transform(camera_pose_enu);
 
-    Simulation::new(
+    (
         Camera::new(
             PinholeOptic::from_focal_length(focal_length),
             pixel_size,
@@ -62,10 +73,22 @@ fn ray_image() -> RayImage<GlobalFrame> {
             image_cols,
         ),
         camera_pose_ecef,
-        time.parse::<DateTime<Utc>>()
-            .expect("valid datetime string"),
     )
-    .par_ray_image()
+}
+
+fn time() -> DateTime<Utc> {
+    "2025-06-13T16:26:47+00:00"
+        .parse::<DateTime<Utc>>()
+        .expect("valid datetime string")
+}
+
+fn simulation_with_extents(image_rows: usize, image_cols: usize) -> Simulation<PinholeOptic> {
+    let (camera, pose) = camera_and_pose(image_rows, image_cols);
+    Simulation::new(camera, pose, time())
+}
+
+fn ray_image() -> RayImage<GlobalFrame> {
+    simulation().par_ray_image()
 }
 
 #[test]
@@ -105,3 +128,273 @@ fn dop_works() {
     .unwrap();
     insta::assert_binary_snapshot!(".png", png_bytes);
 }
+
+#[test]
+fn sky_features_work() {
+    let features = simulation().sky_features(91);
+
+    // The camera looks straight up so the zenith is imaged at the center of the sensor.
+    let zenith = features.zenith().expect("zenith is on the sensor");
+    assert!(zenith.row().abs_diff(512) <= 1);
+    assert!(zenith.col().abs_diff(612) <= 1);
+
+    // The sun is high in the sky near solar noon.
+    assert!(features.sun().is_some());
+
+    // Both meridians end at the zenith.
+    assert_eq!(features.solar_meridian().len(), 91);
+    assert_eq!(features.solar_meridian().last(), Some(&Some(zenith)));
+    assert_eq!(features.anti_solar_meridian().last(), Some(&Some(zenith)));
+}
+
+#[test]
+fn sky_map_resamples_visible_sky() {
+    let simulation = simulation_with_extents(128, 153);
+    let ray_image = simulation.ray_image();
+
+    // The camera looks straight up so a narrow map around the zenith is fully covered.
+    let zenith = simulation
+        .sky_map(
+            &ray_image,
+            SkyProjection::Gnomonic {
+                azimuth: Angle::ZERO,
+                elevation: Angle::new::<degree>(90.0),
+                field_of_view: Angle::new::<degree>(40.0),
+            },
+            16,
+            16,
+        )
+        .unwrap();
+    assert!(zenith.rays().all(|ray| ray.is_some()));
+
+    // Cells below the horizon and beyond the field of view are empty.
+    let panorama = simulation
+        .sky_map(
+            &ray_image,
+            SkyProjection::Equirectangular {
+                min_elevation: Angle::new::<degree>(-30.0),
+            },
+            12,
+            36,
+        )
+        .unwrap();
+    assert!((0..36).all(|col| panorama.ray(0, col).is_some()));
+    assert!((0..36).all(|col| panorama.ray(11, col).is_none()));
+
+    assert!(
+        simulation
+            .sky_map(
+                &simulation_with_extents(64, 76).ray_image(),
+                SkyProjection::Equirectangular {
+                    min_elevation: Angle::ZERO,
+                },
+                1,
+                1
+            )
+            .is_err()
+    );
+}
+
+#[test]
+fn bearing_map_cuts_simulated_sky() {
+    let simulation = simulation_with_extents(64, 76);
+    let ray_image = simulation.ray_image();
+    let bearings = simulation.bearing_map();
+
+    // The camera looks straight up so the center of the sensor sees the zenith.
+    let (_, elevation) = bearings.bearing(32, 38).unwrap();
+    assert!(elevation > Angle::new::<degree>(85.0));
+
+    let high = bearings
+        .elevation_band(Angle::new::<degree>(60.0), Angle::new::<degree>(90.1))
+        .apply(&ray_image)
+        .unwrap();
+    assert!(high.rays().flatten().count() > 0);
+    assert!(high.rays().flatten().count() < ray_image.rays().flatten().count());
+    for row in 0..high.rows() {
+        for col in 0..high.cols() {
+            if high.ray(row, col).is_some() {
+                let (_, elevation) = bearings.bearing(row, col).unwrap();
+                assert!(elevation >= Angle::new::<degree>(60.0));
+            }
+        }
+    }
+}
+
+#[test]
+fn project_reports_dropped_bearings() {
+    let degrees = |azimuth: f64, elevation: f64| {
+        (
+            Angle::new::<degree>(azimuth),
+            Angle::new::<degree>(elevation),
+        )
+    };
+    let report = simulation().project([
+        degrees(0.0, 90.0),
+        degrees(30.0, -10.0),
+        degrees(120.0, 5.0),
+        degrees(200.0, 80.0),
+        degrees(0.0, 95.0),
+    ]);
+
+    assert_eq!(
+        report.projections()[..3],
+        [
+            Projection::Projected(simulation().sky_features(2).zenith().unwrap()),
+            Projection::BelowHorizon,
+            Projection::OffSensor,
+        ]
+    );
+    assert_eq!(report.projections()[4], Projection::InvalidElevation);
+    assert!(report.projections()[3].pixel().is_some());
+    assert_eq!(report.projected(), 2);
+    assert_eq!(report.below_horizon(), 1);
+    assert_eq!(report.behind_sensor(), 0);
+    assert_eq!(report.off_sensor(), 1);
+    assert_eq!(report.invalid_elevation(), 1);
+}
+
+#[test]
+fn rolling_shutter_rotates_later_rows() {
+    let nominal = simulation_with_extents(48, 64).ray_image();
+    let rolling_shutter = |yaw_rate: f64| {
+        simulation_with_extents(48, 64)
+            .with_rolling_shutter(
+                RollingShutter::new(Time::new::<millisecond>(1.0)).with_rates(
+                    AngularVelocity::new::<degree_per_second>(yaw_rate),
+                    AngularVelocity::ZERO,
+                    AngularVelocity::ZERO,
+                ),
+            )
+            .ray_image()
+    };
+    let max_distance = |image: &RayImage<GlobalFrame>, row: usize| {
+        (0..image.cols())
+            .filter_map(|col| Some((image.ray(row, col)?, nominal.ray(row, col)?)))
+            .map(|(a, b)| a.aop().angular_distance(&b.aop()).get::<degree>())
+            .fold(0.0, f64::max)
+    };
+
+    let still = rolling_shutter(0.0);
+    let turning = rolling_shutter(200.0);
+    for row in [0, 47] {
+        assert!(max_distance(&still, row) < 1e-9);
+    }
+    assert!(max_distance(&turning, 0) < 1e-9);
+    assert!(max_distance(&turning, 47) > 1.0);
+}
+
+#[test]
+fn rays_match_ray_image() {
+    let simulation = simulation_with_extents(48, 64);
+    let expected: Vec<_> = simulation.ray_image().rays().flatten().copied().collect();
+
+    assert_eq!(simulation.rays().collect::<Vec<_>>(), expected);
+    assert_eq!(
+        simulation.rays().ray_stride(7).collect::<Vec<_>>(),
+        expected.into_iter().step_by(7).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn recorded_ephemeris_replays_simulation() {
+    let (camera, pose) = camera_and_pose(48, 64);
+    let position = Wgs84::from(pose.position());
+    let recorded = Spa.solar_position(position, time()).unwrap();
+    let replay = RecordedEphemeris::new([(time(), recorded)]);
+
+    let expected = Simulation::new(camera, pose, time()).ray_image();
+    let replayed = Simulation::from_ephemeris(camera, pose, time(), &replay)
+        .unwrap()
+        .ray_image();
+    assert_eq!(replayed, expected);
+    assert!(
+        Simulation::from_ephemeris(camera, pose, time() + TimeDelta::seconds(1), &replay).is_none()
+    );
+}
+
+#[test]
+fn par_ray_image_is_deterministic() {
+    let simulation = simulation_with_extents(48, 64);
+    let expected = simulation.ray_image();
+
+    for threads in [1, 3, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        assert_eq!(simulation.par_ray_image_in(&pool), expected);
+    }
+}
+
+#[test]
+fn transit_works() {
+    let (camera, pose) = camera_and_pose(12, 16);
+    let start = time() - TimeDelta::hours(12);
+    let transit = Transit::new(
+        camera,
+        pose,
+        start,
+        time() + TimeDelta::hours(12),
+        TimeDelta::hours(1),
+    );
+
+    let trajectory = transit.trajectory();
+    assert_eq!(trajectory.len(), 25);
+    assert_eq!(trajectory[0].time(), start);
+
+    // The sun is highest near solar noon which is within an hour of the reference time.
+    let highest = trajectory
+        .iter()
+        .max_by(|a, b| a.elevation().value.total_cmp(&b.elevation().value))
+        .unwrap();
+    assert!((highest.time() - time()).abs() <= TimeDelta::hours(1));
+    assert!(trajectory[0].elevation() < Angle::ZERO);
+
+    let mut bytes = Vec::new();
+    assert_eq!(transit.write_sequence(&mut bytes).unwrap(), trajectory);
+
+    let frames: Vec<_> = SequenceReader::<_, GlobalFrame>::new(Cursor::new(bytes))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(frames.len(), 25);
+    assert!(
+        frames[12]
+            .image()
+            .rays()
+            .eq(Simulation::new(camera, pose, time()).par_ray_image().rays())
+    );
+    assert_eq!(frames[12].image().meta().time(), Some(time()));
+    assert_eq!(frames[12].image().meta().sequence(), Some(12));
+}
+
+#[test]
+fn enu_points_roundtrip_through_pixels() {
+    let simulation = simulation_with_extents(64, 76);
+    let enu = |east: f64, north: f64, up: f64| {
+        Coordinate::<SimulationEnu>::builder()
+            .enu_east(Length::new::<meter>(east))
+            .enu_north(Length::new::<meter>(north))
+            .enu_up(Length::new::<meter>(up))
+            .build()
+    };
+
+    // The camera looks straight up so a point overhead is imaged near the center.
+    let pixel = simulation.pixel_from_enu(&enu(0.0, 0.0, 100.0)).unwrap();
+    assert!(pixel.row().abs_diff(32) <= 1 && pixel.col().abs_diff(38) <= 1);
+
+    let pixel = PixelCoordinate::new(10, 50);
+    let point = simulation
+        .enu_from_pixel(pixel, Length::new::<meter>(250.0))
+        .unwrap();
+    assert!((point.enu_up().get::<meter>() - 250.0).abs() < 1e-9);
+    assert_eq!(simulation.pixel_from_enu(&point), Some(pixel));
+
+    // The camera cannot see the ground or anything at its own position.
+    assert_eq!(
+        simulation.enu_from_pixel(pixel, -Length::new::<meter>(2.0)),
+        None
+    );
+    assert_eq!(simulation.pixel_from_enu(&enu(0.0, 0.0, 0.0)), None);
+}